@@ -0,0 +1,305 @@
+use dioxus::fullstack::{AsStatusCode, HttpError, RequestError};
+use dioxus::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// A single invalid input of a request
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct FieldError {
+    /// Name of the argument of the server function, e.g. `email`
+    pub field: String,
+    pub message: String,
+}
+
+/// Error returned by all server functions in [`crate::routes`].
+///
+/// The error is serialized into the response on the server and decoded into the same variant on
+/// the client, so views can match on the cases they want to handle and display the message for
+/// everything else.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum ApiError {
+    /// 400: The request cannot be processed in the current state
+    BadRequest { message: String },
+    /// 401: The user is not logged in or supplied wrong credentials
+    Unauthorized { message: String },
+    /// 403: The user is not allowed to perform the action
+    Forbidden { message: String },
+    /// 404: The requested resource does not exist or is not visible to the user
+    NotFound { message: String },
+    /// 409: The request collides with existing data, e.g. an email that is already registered
+    Conflict { message: String },
+    /// 409: The object was changed by someone else since the client loaded it.
+    /// `current` holds the serialized current state, see [`ApiError::current_state`]
+    EditConflict {
+        message: String,
+        current: serde_json::Value,
+    },
+    /// 422: One or more inputs are invalid
+    Validation {
+        message: String,
+        fields: Vec<FieldError>,
+    },
+    /// 429: Too many attempts. The request may be retried after `retry_after_seconds`
+    RateLimited {
+        message: String,
+        retry_after_seconds: u64,
+    },
+    /// 500: An unexpected error occurred on the server
+    Internal { message: String },
+    /// The request did not reach the server or the response could not be read.
+    /// Only created on the client
+    Network { message: String },
+}
+
+impl ApiError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        ApiError::BadRequest {
+            message: message.into(),
+        }
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        ApiError::Unauthorized {
+            message: message.into(),
+        }
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        ApiError::Forbidden {
+            message: message.into(),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        ApiError::NotFound {
+            message: message.into(),
+        }
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        ApiError::Conflict {
+            message: message.into(),
+        }
+    }
+
+    /// Creates a [`ApiError::EditConflict`] for an update based on an outdated version of `current`
+    pub fn edit_conflict(message: impl Into<String>, current: &impl Serialize) -> Self {
+        ApiError::EditConflict {
+            message: message.into(),
+            current: serde_json::to_value(current).unwrap_or_default(),
+        }
+    }
+
+    /// Creates a [`ApiError::Validation`] for a single invalid field
+    ///
+    /// # Arguments
+    ///
+    /// * `field`: Name of the invalid argument
+    /// * `message`: Description of the problem, which is also used as the overall message
+    pub fn validation(field: impl Into<String>, message: impl Into<String>) -> Self {
+        let message = message.into();
+        ApiError::Validation {
+            message: message.clone(),
+            fields: vec![FieldError {
+                field: field.into(),
+                message,
+            }],
+        }
+    }
+
+    /// Creates a [`ApiError::RateLimited`] telling the client how long to wait
+    ///
+    /// # Arguments
+    ///
+    /// * `reason`: What the client did too often, e.g. "Too many failed login attempts"
+    /// * `retry_after_seconds`: Seconds until the next attempt is allowed
+    pub fn rate_limited(reason: &str, retry_after_seconds: u64) -> Self {
+        ApiError::RateLimited {
+            message: format!("{reason}. Try again in {retry_after_seconds} seconds"),
+            retry_after_seconds,
+        }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        ApiError::Internal {
+            message: message.into(),
+        }
+    }
+
+    /// Human readable description of the error
+    #[must_use]
+    pub fn message(&self) -> &str {
+        match self {
+            ApiError::BadRequest { message }
+            | ApiError::Unauthorized { message }
+            | ApiError::Forbidden { message }
+            | ApiError::NotFound { message }
+            | ApiError::Conflict { message }
+            | ApiError::EditConflict { message, .. }
+            | ApiError::Validation { message, .. }
+            | ApiError::RateLimited { message, .. }
+            | ApiError::Internal { message }
+            | ApiError::Network { message } => message,
+        }
+    }
+
+    /// Returns the errors of the supplied field. Empty for anything but [`ApiError::Validation`]
+    #[must_use]
+    pub fn field_errors(&self, field: &str) -> Vec<String> {
+        match self {
+            ApiError::Validation { fields, .. } => fields
+                .iter()
+                .filter(|error| error.field == field)
+                .map(|error| error.message.clone())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the state of the object sent with an [`ApiError::EditConflict`], so editors can
+    /// show it or base a new update on it
+    #[must_use]
+    pub fn current_state<T: DeserializeOwned>(&self) -> Option<T> {
+        match self {
+            ApiError::EditConflict { current, .. } => serde_json::from_value(current.clone()).ok(),
+            _ => None,
+        }
+    }
+
+    /// Maps an HTTP status code and message to the matching variant
+    fn from_status(code: u16, message: String) -> Self {
+        match code {
+            401 => ApiError::Unauthorized { message },
+            403 => ApiError::Forbidden { message },
+            404 => ApiError::NotFound { message },
+            409 => ApiError::Conflict { message },
+            422 => ApiError::Validation {
+                message,
+                fields: Vec::new(),
+            },
+            429 => ApiError::RateLimited {
+                message,
+                retry_after_seconds: 0,
+            },
+            400..500 => ApiError::BadRequest { message },
+            _ => ApiError::Internal { message },
+        }
+    }
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl AsStatusCode for ApiError {
+    fn as_status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest { .. } => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized { .. } => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden { .. } => StatusCode::FORBIDDEN,
+            ApiError::NotFound { .. } => StatusCode::NOT_FOUND,
+            ApiError::Conflict { .. } | ApiError::EditConflict { .. } => StatusCode::CONFLICT,
+            ApiError::Validation { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::Internal { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::Network { .. } => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+impl From<HttpError> for ApiError {
+    fn from(error: HttpError) -> Self {
+        let message = error.message.unwrap_or_else(|| {
+            error
+                .status
+                .canonical_reason()
+                .unwrap_or("Unknown error")
+                .to_string()
+        });
+        ApiError::from_status(error.status.as_u16(), message)
+    }
+}
+
+impl From<ServerFnError> for ApiError {
+    fn from(error: ServerFnError) -> Self {
+        match error {
+            ServerFnError::ServerError {
+                message,
+                code,
+                details,
+            } => details
+                .and_then(|details| serde_json::from_value(details).ok())
+                .unwrap_or_else(|| ApiError::from_status(code, message)),
+            ServerFnError::Request(RequestError::Status(message, code)) => {
+                ApiError::from_status(code, message)
+            }
+            ServerFnError::Request(error) => ApiError::Network {
+                message: error.to_string(),
+            },
+            error => ApiError::Internal {
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_codes_map_to_variants() {
+        let error: ApiError = HttpError::new(StatusCode::NOT_FOUND, "Event not found").into();
+        assert_eq!(error, ApiError::not_found("Event not found"));
+        assert_eq!(error.as_status_code(), StatusCode::NOT_FOUND);
+
+        let error: ApiError = ServerFnError::ServerError {
+            message: "Email already registered".to_string(),
+            code: 409,
+            details: None,
+        }
+        .into();
+        assert_eq!(error, ApiError::conflict("Email already registered"));
+    }
+
+    #[test]
+    fn details_are_decoded() {
+        let original = ApiError::validation("email", "Email must be a valid email");
+        let error: ApiError = ServerFnError::ServerError {
+            message: original.to_string(),
+            code: 422,
+            details: Some(serde_json::to_value(&original).unwrap()),
+        }
+        .into();
+
+        assert_eq!(error, original);
+        assert_eq!(
+            error.field_errors("email"),
+            vec!["Email must be a valid email".to_string()]
+        );
+        assert!(error.field_errors("password").is_empty());
+    }
+
+    #[test]
+    fn edit_conflicts_contain_the_current_state() {
+        let original = ApiError::edit_conflict("The Task was changed", &vec![1, 2, 3]);
+        let error: ApiError = ServerFnError::ServerError {
+            message: original.to_string(),
+            code: 409,
+            details: Some(serde_json::to_value(&original).unwrap()),
+        }
+        .into();
+
+        assert_eq!(error.as_status_code(), StatusCode::CONFLICT);
+        assert_eq!(error.current_state::<Vec<i32>>(), Some(vec![1, 2, 3]));
+        assert_eq!(
+            ApiError::conflict("Email").current_state::<Vec<i32>>(),
+            None
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use dioxus::prelude::*;
 
+pub mod error;
 pub mod routes;
 #[cfg(feature = "server")]
 pub mod server;
@@ -0,0 +1,83 @@
+use crate::error::ApiError;
+use crate::routes::users::UserInfo;
+#[cfg(feature = "server")]
+use crate::server;
+use dioxus::prelude::*;
+use entity::activity::ActivityKind;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use dioxus::server::axum::Extension;
+
+/// Number of activities per page of a feed
+pub const ACTIVITY_PAGE_SIZE: u64 = 20;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct ActivityEntry {
+    pub id: i32,
+    /// `None` if the user has since been deleted
+    pub actor: Option<UserInfo>,
+    pub kind: ActivityKind,
+    pub subject: String,
+    pub created_at: time::OffsetDateTime,
+}
+
+impl ActivityEntry {
+    /// Human readable description, e.g. `Alex Doe completed 'Buy milk'`
+    pub fn message(&self) -> String {
+        let actor = self
+            .actor
+            .as_ref()
+            .map(|actor| format!("{} {}", actor.first_name, actor.last_name))
+            .unwrap_or_else(|| "A former member".to_string());
+        self.kind.describe(&actor, &self.subject)
+    }
+}
+
+/// One page of an activity feed, newest first
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct ActivityPage {
+    pub items: Vec<ActivityEntry>,
+    /// Current page, starting at 1
+    pub page: u64,
+    pub total_pages: u64,
+}
+
+/// Lists the activities of a group the user is a member of
+#[get("/api/groups/{group_id}/activity?page", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn list_group_activity(
+    group_id: i32,
+    page: Option<u64>,
+) -> Result<ActivityPage, ApiError> {
+    use crate::server::activity::{Feed, load_activity_page};
+    use crate::server::events::is_user_in_group;
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+    is_user_in_group(&ext.database, group_id, user.id)
+        .await?
+        .or_forbidden("No permission to view the activities of this group")?;
+
+    Ok(load_activity_page(Feed::Group(group_id), page.unwrap_or(1), &ext.database).await?)
+}
+
+/// Lists the activities of a To-Do list the user is a member of
+#[get("/api/todolists/{todo_list_id}/activity?page", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn list_todo_list_activity(
+    todo_list_id: i32,
+    page: Option<u64>,
+) -> Result<ActivityPage, ApiError> {
+    use crate::server::activity::{Feed, load_activity_page};
+    use crate::server::todo_lists::get_todo_list_permission;
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+    get_todo_list_permission(todo_list_id, user.id, &ext.database)
+        .await?
+        .or_forbidden("You are not permitted to view the activities of this To-Do List")?;
+
+    Ok(load_activity_page(
+        Feed::TodoList(todo_list_id),
+        page.unwrap_or(1),
+        &ext.database,
+    )
+    .await?)
+}
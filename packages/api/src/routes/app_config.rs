@@ -1,27 +1,52 @@
+use crate::error::ApiError;
+#[cfg(feature = "server")]
 use crate::server;
 use dioxus::prelude::*;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "server")]
+use dioxus::server::axum::Extension;
+
+/// A configured OIDC provider users can log in with
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct OidcProviderInfo {
+    pub id: String,
+    /// Name displayed on the login button
+    pub name: String,
+    pub login_url: String,
+    /// Starts linking an account of the provider to the logged in user
+    pub link_url: String,
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct AppConfig {
-    pub oidc_enabled: bool,
-    pub oidc_provider_name: Option<String>,
+    pub oidc_providers: Vec<OidcProviderInfo>,
     pub signup_enabled: bool,
+    pub email_verification_required: bool,
 }
 
-#[allow(clippy::unused_async)]
-#[get("/api/app_config")]
-pub async fn get_app_config() -> Result<AppConfig, ServerFnError> {
-    use server::constants;
-    use server::utils::{convert_env_to_bool, get_env_or, parse_env_string};
+impl AppConfig {
+    #[must_use]
+    pub fn oidc_enabled(&self) -> bool {
+        !self.oidc_providers.is_empty()
+    }
+}
 
+#[allow(clippy::unused_async)]
+#[get("/api/app_config", ext: Extension<server::AppState>)]
+pub async fn get_app_config() -> Result<AppConfig, ApiError> {
     Ok(AppConfig {
-        oidc_enabled: get_env_or(constants::OIDC_ENABLED_ENV_VAR, false, convert_env_to_bool),
-        oidc_provider_name: get_env_or(
-            constants::OIDC_PROVIDER_NAME_ENV_VAR,
-            None,
-            parse_env_string,
-        ),
-        signup_enabled: get_env_or(constants::SIGNUP_ENABLED_ENV_VAR, true, convert_env_to_bool),
+        oidc_providers: ext
+            .oidc_providers
+            .iter()
+            .map(|provider| OidcProviderInfo {
+                id: provider.id.clone(),
+                name: provider.name.clone(),
+                login_url: format!("/api/oidc/{}/login", provider.id),
+                link_url: format!("/api/oidc/{}/link", provider.id),
+            })
+            .collect(),
+        signup_enabled: ext.settings.signup_enabled,
+        email_verification_required: ext.settings.email_verification_required,
     })
 }
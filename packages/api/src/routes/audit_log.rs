@@ -0,0 +1,79 @@
+use crate::error::ApiError;
+use crate::routes::users::UserInfo;
+#[cfg(feature = "server")]
+use crate::server;
+use dioxus::prelude::*;
+use entity::audit_log::{AuditAction, AuditTarget};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use dioxus::server::axum::Extension;
+
+/// Number of most recent entries returned by [`list_audit_log`]
+pub const AUDIT_LOG_LIMIT: u64 = 200;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct AuditLogEntry {
+    pub id: i32,
+    /// `None` for actions of the server or users that have since been deleted
+    pub actor: Option<UserInfo>,
+    pub action: AuditAction,
+    pub affected_user: Option<UserInfo>,
+    pub details: Option<String>,
+    pub created_at: time::OffsetDateTime,
+}
+
+/// Lists the most recent audit log entries of an object the user administers, newest first
+#[get("/api/audit-log?target_type&target_id", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn list_audit_log(
+    target_type: AuditTarget,
+    target_id: i32,
+) -> Result<Vec<AuditLogEntry>, ApiError> {
+    use crate::server::audit::can_view_audit_log;
+    use entity::audit_log::Column as AuditLogColumn;
+    use entity::prelude::{AuditLog, User};
+    use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+    use std::collections::HashMap;
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+    can_view_audit_log(user.id, target_type, target_id, &ext.database)
+        .await?
+        .or_forbidden("No permission to view the audit log")?;
+
+    let entries = AuditLog::find()
+        .filter(AuditLogColumn::TargetType.eq(target_type))
+        .filter(AuditLogColumn::TargetId.eq(target_id))
+        .order_by_desc(AuditLogColumn::Id)
+        .limit(AUDIT_LOG_LIMIT)
+        .all(&ext.database)
+        .await
+        .inspect_err(|e| error!("Error loading audit log: {e}"))
+        .or_internal_server_error("Error loading audit log")?;
+
+    let user_ids: Vec<i32> = entries
+        .iter()
+        .flat_map(|entry| [entry.actor_id, entry.affected_user_id])
+        .flatten()
+        .collect();
+    let users: HashMap<i32, UserInfo> = User::find()
+        .filter(entity::user::Column::Id.is_in(user_ids))
+        .all(&ext.database)
+        .await
+        .or_internal_server_error("Error loading users")?
+        .into_iter()
+        .map(|user| (user.id, UserInfo::from_user_model(user)))
+        .collect();
+    let find_user = |id: Option<i32>| id.and_then(|id| users.get(&id).cloned());
+
+    Ok(entries
+        .into_iter()
+        .map(|entry| AuditLogEntry {
+            id: entry.id,
+            actor: find_user(entry.actor_id),
+            action: entry.action,
+            affected_user: find_user(entry.affected_user_id),
+            details: entry.details,
+            created_at: entry.created_at,
+        })
+        .collect())
+}
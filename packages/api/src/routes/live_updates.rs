@@ -0,0 +1,54 @@
+use crate::error::ApiError;
+#[cfg(feature = "server")]
+use crate::server;
+use dioxus::fullstack::ServerEvents;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use dioxus::server::axum::Extension;
+
+/// Change pushed to the users who can access the changed object
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ChangeEvent {
+    /// A task was created or updated
+    TodoSaved(entity::todo::Model),
+    TodoDeleted {
+        todo_list_id: i32,
+        todo_id: i32,
+    },
+    /// The title, description or members of the To-Do list changed
+    TodoListChanged {
+        todo_list_id: i32,
+    },
+    /// The To-Do list was deleted or the user lost access to it
+    TodoListDeleted {
+        todo_list_id: i32,
+    },
+    /// The event was created, updated or shared
+    EventChanged {
+        event_id: i32,
+    },
+    EventDeleted {
+        event_id: i32,
+    },
+    /// The name, members or events of the group changed
+    GroupChanged {
+        group_id: i32,
+    },
+    /// The group was deleted or the user was removed from it
+    GroupDeleted {
+        group_id: i32,
+    },
+    /// Event or To-Do list invitations of the user changed
+    InvitationsChanged,
+    /// Changes were dropped because the client fell behind. All data should be reloaded
+    Resync,
+}
+
+/// Stream of the changes to everything the user can access, sent as server-sent events
+#[get("/api/live-updates", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn subscribe_to_changes() -> Result<ServerEvents<ChangeEvent>, ApiError> {
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+    Ok(ext.live_updates.subscribe(user.id, auth.session_id()))
+}
@@ -1,7 +1,11 @@
+pub mod activity;
 pub mod app_config;
+pub mod audit_log;
 pub mod events;
 pub mod groups;
+pub mod live_updates;
 pub mod oidc;
 pub mod todo_list;
 pub mod todos;
+pub mod trash;
 pub mod users;
@@ -1,3 +1,4 @@
+use crate::error::ApiError;
 #[cfg(feature = "server")]
 use crate::server;
 use dioxus::fullstack::Redirect;
@@ -8,15 +9,15 @@ use dioxus::server::axum::Extension;
 
 const OIDC_SESSION_KEY: &str = "oidc_metadata";
 #[allow(clippy::unused_async)]
-#[get("/api/oidc/login", state: Extension<server::AppState>,  session: Extension<tower_sessions::Session> )]
-pub async fn oauth_login() -> Result<Redirect, ServerFnError> {
+#[get("/api/oidc/{provider}/login", state: Extension<server::AppState>,  session: Extension<tower_sessions::Session> )]
+pub async fn oauth_login(provider: String) -> Result<Redirect, ApiError> {
     use crate::server::auth::oidc;
 
     let oidc_config = state
-        .oidc_config
-        .as_ref()
-        .or_internal_server_error("OAuth Client Missing or disabled!")?;
-    let metadata = oidc::create_oidc_challenge(&oidc_config.client);
+        .oidc_providers
+        .get(&provider)
+        .or_not_found("Unknown OIDC provider")?;
+    let metadata = oidc::create_oidc_challenge(oidc_config, false);
 
     let redirect_url = metadata.url.as_str().to_string();
     let oidc_session: oidc::OidcSession = metadata.into();
@@ -29,23 +30,131 @@ pub async fn oauth_login() -> Result<Redirect, ServerFnError> {
     Ok(Redirect::to(&redirect_url))
 }
 
+/// Starts linking an identity of the provider to the logged in user. The provider redirects back to
+/// [`oauth_redirect`], which links the identity instead of logging in
+#[allow(clippy::unused_async)]
+#[get("/api/oidc/{provider}/link",
+    state: Extension<server::AppState>,
+    auth: Extension<server::AuthenticationState>,
+    session: Extension<tower_sessions::Session>
+)]
+pub async fn oauth_link(provider: String) -> Result<Redirect, ApiError> {
+    use crate::server::auth::oidc;
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+    let oidc_config = state
+        .oidc_providers
+        .get(&provider)
+        .or_not_found("Unknown OIDC provider")?;
+    let metadata = oidc::create_oidc_challenge(oidc_config, false);
+
+    let redirect_url = metadata.url.as_str().to_string();
+    let oidc_session = oidc::OidcSession {
+        link_user_id: Some(user.id),
+        ..metadata.into()
+    };
+
+    session
+        .insert(OIDC_SESSION_KEY, oidc_session)
+        .await
+        .or_internal_server_error("Failed to create session")?;
+
+    Ok(Redirect::to(&redirect_url))
+}
+
+/// Asks the provider the user logged in with to authenticate them again, even if they are still logged in there.
+/// The provider redirects back to [`oauth_redirect`], which remembers the re-authentication in the session,
+/// so accounts without a password can be deleted
+#[allow(clippy::unused_async)]
+#[get("/api/oidc/reauthenticate",
+    state: Extension<server::AppState>,
+    auth: Extension<server::AuthenticationState>,
+    cookies: Extension<tower_cookies::Cookies>,
+    session: Extension<tower_sessions::Session>
+)]
+pub async fn oauth_reauthenticate() -> Result<Redirect, ApiError> {
+    use crate::server::auth::oidc;
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+    let oidc_config = state
+        .oidc_providers
+        .from_cookies(&cookies)
+        .or_bad_request("Not logged in with an OIDC provider")?;
+    let metadata = oidc::create_oidc_challenge(oidc_config, true);
+
+    let redirect_url = metadata.url.as_str().to_string();
+    let oidc_session = oidc::OidcSession {
+        reauthenticate_user_id: Some(user.id),
+        ..metadata.into()
+    };
+
+    session
+        .insert(OIDC_SESSION_KEY, oidc_session)
+        .await
+        .or_internal_server_error("Failed to create session")?;
+
+    Ok(Redirect::to(&redirect_url))
+}
+
+#[get("/api/oidc/{provider}/redirect?state&code",
+    ext: Extension<server::AppState>,
+    auth: Extension<server::AuthenticationState>,
+    cookies: Extension<tower_cookies::Cookies>,
+    session: Extension<tower_sessions::Session>
+)]
+pub async fn oauth_redirect(
+    provider: String,
+    state: String,
+    code: String,
+) -> Result<Redirect, ApiError> {
+    let oidc_config = ext
+        .oidc_providers
+        .get(&provider)
+        .or_not_found("Unknown OIDC provider")?;
+    complete_oidc_flow(oidc_config, state, code, &ext, &auth, &cookies, &session).await
+}
+
+/// Redirect of the provider configured by the unprefixed `OIDC_*` variables. Kept at the path used before multiple
+/// providers were supported, so existing client registrations keep working
 #[get("/api/oidc/redirect?state&code",
     ext: Extension<server::AppState>,
+    auth: Extension<server::AuthenticationState>,
     cookies: Extension<tower_cookies::Cookies>,
     session: Extension<tower_sessions::Session>
 )]
-pub async fn oauth_redirect(state: String, code: String) -> Result<Redirect, ServerFnError> {
+pub async fn oauth_redirect_default(state: String, code: String) -> Result<Redirect, ApiError> {
+    use crate::server::auth::oidc::DEFAULT_PROVIDER_ID;
+
+    let oidc_config = ext
+        .oidc_providers
+        .get(DEFAULT_PROVIDER_ID)
+        .or_not_found("Unknown OIDC provider")?;
+    complete_oidc_flow(oidc_config, state, code, &ext, &auth, &cookies, &session).await
+}
+
+/// Exchanges the authorization code of the provider and logs the user in or links the identity
+#[cfg(feature = "server")]
+async fn complete_oidc_flow(
+    oidc_config: &server::auth::oidc::OidcConfig,
+    state: String,
+    code: String,
+    ext: &server::AppState,
+    auth: &server::AuthenticationState,
+    cookies: &tower_cookies::Cookies,
+    session: &tower_sessions::Session,
+) -> Result<Redirect, ApiError> {
+    use crate::server::audit::record_login;
     use crate::server::auth::oidc;
     use crate::server::auth::oidc::add_oidc_cookies;
-    use entity::prelude::*;
+    use crate::server::auth::oidc_groups;
+    use crate::server::auth::oidc_identity::{self, OidcProfile};
+    use crate::server::auth::reauthentication::{
+        is_recent_authentication, record_reauthentication,
+    };
     use openidconnect::{AccessTokenHash, OAuth2TokenResponse, TokenResponse};
-    use sea_orm::prelude::*;
+    use serde_json::Value;
 
-    let oidc_client = ext
-        .oidc_config
-        .as_ref()
-        .map(|c| &c.client)
-        .or_internal_server_error("Oidc not initialized / enabled")?;
+    let oidc_client = &oidc_config.client;
 
     let oidc_session: oidc::OidcSession = session
         .get(OIDC_SESSION_KEY)
@@ -54,6 +163,7 @@ pub async fn oauth_redirect(state: String, code: String) -> Result<Redirect, Ser
         .or_bad_request("Failed to get session with required metadata")?;
 
     (*oidc_session.csrf_token.secret() == state).or_bad_request("CSRF Mismatch")?;
+    (oidc_session.provider_id == oidc_config.id).or_bad_request("OIDC provider mismatch")?;
 
     let token_response =
         oidc::verify_oidc_challenge(oidc_client, code, oidc_session.pkce_code_verifier)
@@ -84,70 +194,104 @@ pub async fn oauth_redirect(state: String, code: String) -> Result<Redirect, Ser
             .or_unauthorized("Invalid access token")?;
     }
 
+    let raw_claims = oidc_identity::decode_id_token_claims(&id_token.to_string())
+        .or_bad_request("Unable to read claims")?;
+    let profile = OidcProfile::from_claims(&raw_claims, &oidc_config.claim_mapping)
+        .or_bad_request("Unable to read claims")?;
+
     debug!(
-        "User {} with e-mail address {} has authenticated successfully",
-        claims.subject().as_str(),
-        claims
-            .email()
-            .map_or("<not provided>", |email| email.as_str())
+        "User {} of {} with e-mail address {} has authenticated successfully",
+        profile.subject,
+        profile.issuer,
+        profile.email.as_deref().unwrap_or("<not provided>")
     );
 
-    let email: &str = claims
-        .email()
-        .map(|email| email.as_str())
-        .or_bad_request("Email is required")?;
-    let first_name: &str = claims
-        .given_name()
-        .or_bad_request("Missing given name")?
-        .get(None)
-        .map(|n| n.as_str())
-        .or_bad_request("Missing given name")?;
-    let last_name: &str = claims
-        .family_name()
-        .or_bad_request("Missing family name")?
-        .get(None)
-        .map(|n| n.as_str())
-        .or_bad_request("Missing family name")?;
-
-    if User::find_by_email(email)
-        .count(&ext.database)
+    session
+        .remove::<oidc::OidcSession>(OIDC_SESSION_KEY)
+        .await
+        .or_internal_server_error("Failed to update session")?;
+
+    if let Some(reauthenticate_user_id) = oidc_session.reauthenticate_user_id {
+        let user = auth
+            .user
+            .as_ref()
+            .filter(|user| user.id == reauthenticate_user_id)
+            .or_unauthorized("Not authenticated")?;
+        let issued_at = raw_claims.get("iat").and_then(Value::as_i64);
+        let identity_user = oidc_identity::find_user_by_identity(
+            &profile.issuer,
+            &profile.subject,
+            issued_at,
+            &ext.database,
+        )
         .await
-        .or_internal_server_error("Failed to retrieve user")?
-        == 0
+        .or_internal_server_error("Error loading user from database")?;
+        identity_user
+            .is_some_and(|identity_user| identity_user.id == user.id)
+            .or_forbidden("The identity does not belong to your account")?;
+        // Without `auth_time` it is unknown whether the provider asked for the credentials again
+        let authenticated_at = raw_claims
+            .get("auth_time")
+            .and_then(Value::as_i64)
+            .or_forbidden("The provider did not report when you authenticated")?;
+        is_recent_authentication(
+            authenticated_at,
+            time::OffsetDateTime::now_utc().unix_timestamp(),
+        )
+        .or_forbidden("The provider did not authenticate you again")?;
+        record_reauthentication(session, user.id, authenticated_at).await?;
+        return Ok(Redirect::to("/profile"));
+    }
+
+    if let Some(link_user_id) = oidc_session.link_user_id {
+        let user = auth
+            .user
+            .as_ref()
+            .filter(|user| user.id == link_user_id)
+            .or_unauthorized("Not authenticated")?;
+        oidc_identity::link_identity(user, &profile, &ext.database).await?;
+        return Ok(Redirect::to("/profile"));
+    }
+
+    let user =
+        oidc_identity::login_with_identity(&profile, &oidc_config.provisioning, &ext.database)
+            .await?;
+    if let Some(groups) = &profile.groups
+        && let Err(e) =
+            oidc_groups::sync_groups(user.id, groups, &oidc_config.group_sync, &ext.database).await
     {
-        let new_user = entity::user::ActiveModel {
-            email: sea_orm::Set(email.to_string()),
-            first_name: sea_orm::Set(first_name.to_string()),
-            last_name: sea_orm::Set(last_name.to_string()),
-            password: sea_orm::Set(None),
-            is_oidc_user: sea_orm::Set(true),
-            ..Default::default()
-        };
-        new_user
-            .insert(&ext.database)
-            .await
-            .inspect_err(|e| error!("{e}"))
-            .or_internal_server_error("Failed to create user")?;
+        error!("Error syncing OIDC groups: {e}");
     }
 
-    add_oidc_cookies(&cookies, &token_response)
+    add_oidc_cookies(cookies, &token_response, oidc_config)
         .or_internal_server_error("Failed to add cookies")?;
+    record_login(
+        user.id,
+        &format!("OIDC ({})", oidc_config.id),
+        &ext.database,
+    )
+    .await;
 
     Ok(Redirect::to("/"))
 }
 
 #[post("/api/oidc/refresh", state: Extension<server::AppState>, cookies: Extension<tower_cookies::Cookies>)]
-pub async fn refresh_authorization_token() -> Result<(), ServerFnError> {
+pub async fn refresh_authorization_token() -> Result<(), ApiError> {
     use crate::server::auth::oidc::add_oidc_cookies;
     let refresh_token_cookie = cookies
         .get("refresh_token")
         .or_bad_request("Invalid refresh token")?;
     let refresh_token = refresh_token_cookie.value();
+    let provider = state
+        .oidc_providers
+        .from_cookies(&cookies)
+        .or_bad_request("Unknown OIDC provider")?;
 
-    let tokens = server::auth::oidc::refresh_authorization_token(refresh_token, &state)
+    let tokens = server::auth::oidc::refresh_authorization_token(refresh_token, provider)
         .await
         .or_internal_server_error("Failed to refresh token")?;
 
-    add_oidc_cookies(&cookies, &tokens).or_internal_server_error("Failed to set cookies")?;
+    add_oidc_cookies(&cookies, &tokens, provider)
+        .or_internal_server_error("Failed to set cookies")?;
     Ok(())
 }
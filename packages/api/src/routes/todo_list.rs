@@ -1,3 +1,4 @@
+use crate::error::ApiError;
 #[cfg(feature = "server")]
 use crate::server;
 use dioxus::fullstack::NoContent;
@@ -9,8 +10,7 @@ use entity::todo_list::{CreateTodoList, UpdateTodoList};
 pub mod invite;
 
 #[get("/api/todolists", state: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
-pub async fn list_todo_lists()
--> Result<Vec<entity::todo_list::TodoListWithPermission>, ServerFnError> {
+pub async fn list_todo_lists() -> Result<Vec<entity::todo_list::TodoListWithPermission>, ApiError> {
     use entity::todo_list::Column as TodoListColumn;
     use entity::todo_list::Entity as TodoList;
     use entity::todo_list_invitation::Column as InvitationColumn;
@@ -28,7 +28,8 @@ pub async fn list_todo_lists()
         .filter(
             Condition::all()
                 .add(InvitationColumn::ReceivingUserId.eq(user.id))
-                .add(InvitationColumn::IsAccepted.eq(true)),
+                .add(InvitationColumn::IsAccepted.eq(true))
+                .add(TodoListColumn::DeletedAt.is_null()),
         )
         .order_by_asc(TodoListColumn::Title)
         .into_partial_model()
@@ -43,7 +44,8 @@ pub async fn list_todo_lists()
 #[get("/api/todolists/{todo_list_id}", state: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
 pub async fn retrieve_todo_list(
     todo_list_id: i32,
-) -> Result<entity::todo_list::TodoListWithPermission, ServerFnError> {
+) -> Result<entity::todo_list::TodoListWithPermission, ApiError> {
+    use entity::todo_list::Column as TodoListColumn;
     use entity::todo_list::Entity as TodoList;
     use entity::todo_list_invitation::Column as InvitationColumn;
     use entity::todo_list_invitation::Entity as TodoListInvitation;
@@ -54,6 +56,7 @@ pub async fn retrieve_todo_list(
     let todo_list = TodoList::find_by_id(todo_list_id)
         .inner_join(TodoListInvitation)
         .filter(InvitationColumn::ReceivingUserId.eq(user.id))
+        .filter(TodoListColumn::DeletedAt.is_null())
         .into_partial_model()
         .one(&state.database)
         .await
@@ -67,7 +70,7 @@ pub async fn retrieve_todo_list(
 #[get("/api/todolists/{todo_list_id}/members", state: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
 pub async fn list_todo_list_members(
     todo_list_id: i32,
-) -> Result<Vec<entity::user::UserWithTodoListInvitation>, ServerFnError> {
+) -> Result<Vec<entity::user::UserWithTodoListInvitation>, ApiError> {
     use entity::user::Entity as User;
     use sea_orm::EntityTrait;
     use sea_orm::JoinType;
@@ -95,107 +98,28 @@ pub async fn list_todo_list_members(
 }
 
 #[post("/api/todolists", state: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
-pub async fn create_todo_list(
-    data: CreateTodoList,
-) -> Result<entity::todo_list::Model, ServerFnError> {
-    use sea_orm::TransactionError;
-    use sea_orm::TransactionTrait;
-    use sea_orm::{ActiveModelTrait, IntoActiveModel, TryIntoModel};
+pub async fn create_todo_list(data: CreateTodoList) -> Result<entity::todo_list::Model, ApiError> {
     let user_id = auth.user.as_ref().or_unauthorized("Not authenticated")?.id;
-
-    state
-        .database
-        .transaction::<_, entity::todo_list::Model, ServerFnError>(move |txn| {
-            Box::pin(async move {
-                let mut todo_list = data.into_active_model();
-                todo_list.created_by_id = sea_orm::Set(Some(user_id));
-
-                let todo_list = todo_list
-                    .save(txn)
-                    .await
-                    .inspect_err(|error| error!("{error}"))
-                    .or_internal_server_error("Error creating todo list")?;
-
-                let todo_list = todo_list
-                    .try_into_model()
-                    .or_internal_server_error("Error parsing todo list")?;
-
-                let invitation = entity::todo_list_invitation::ActiveModel {
-                    todo_list_id: sea_orm::Set(todo_list.id),
-                    receiving_user_id: sea_orm::Set(user_id),
-                    permission: sea_orm::Set(
-                        entity::todo_list_invitation::InvitationPermission::Admin,
-                    ),
-                    is_accepted: sea_orm::Set(true),
-                    is_favorite: sea_orm::Set(false),
-                    ..Default::default()
-                };
-                invitation
-                    .insert(txn)
-                    .await
-                    .inspect_err(|error| error!("{error}"))
-                    .or_internal_server_error("Error creating todo list invitation")?;
-
-                Ok(todo_list)
-            })
-        })
-        .await
-        .map_err(|error| {
-            error!("Creating To-Do List: {error}");
-            match error {
-                TransactionError::Connection(_) => ServerFnError::ServerError {
-                    message: String::from("Error creating group"),
-                    code: 500,
-                    details: None,
-                },
-                TransactionError::Transaction(error) => error,
-            }
-        })
+    server::todo_lists::create_todo_list(&state, user_id, data).await
 }
 
 #[patch("/api/todolists/{todo_list_id}", state: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
 pub async fn update_todo_list(
     todo_list_id: i32,
+    version: i32,
     data: UpdateTodoList,
-) -> Result<entity::todo_list::Model, ServerFnError> {
-    use sea_orm::{ActiveModelTrait, IntoActiveModel, TryIntoModel};
-    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
-
-    server::todo_lists::get_todo_list_permission(todo_list_id, user.id, &state.database)
+) -> Result<entity::todo_list::Model, ApiError> {
+    let user_id = auth.user.as_ref().or_unauthorized("Not authenticated")?.id;
+    server::todo_lists::update_todo_list(&state, user_id, todo_list_id, version, data)
         .await?
-        .or_forbidden("Unauthorized to update todo list")?
-        .can_write()
-        .or_forbidden("Unauthorized to update todo list")?;
-
-    let mut todo_list = data.into_active_model();
-    todo_list.id = sea_orm::Unchanged(todo_list_id);
-    let todo_list = todo_list
-        .save(&state.database)
-        .await
-        .or_internal_server_error("Failed to update todo list")?;
-    Ok(todo_list
-        .try_into_model()
-        .or_internal_server_error("Error parsing todo list")?)
+        .or_edit_conflict("The To-Do List was changed by someone else in the meantime")
 }
 
+/// Moves the `TodoList` to the trash, from where it can be restored until the retention period has passed
 #[delete("/api/todolists/{todo_list_id}", state: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
-pub async fn delete_todo_list(todo_list_id: i32) -> Result<NoContent, ServerFnError> {
-    use entity::todo_list::Entity as TodoList;
-    use sea_orm::EntityTrait;
-    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
-
-    server::todo_lists::get_todo_list_permission(todo_list_id, user.id, &state.database)
-        .await?
-        .or_forbidden("Unauthorized to update todo list")?
-        .can_admin()
-        .or_forbidden("Unauthorized to update todo list")?;
-
-    TodoList::delete_by_id(todo_list_id)
-        .exec(&state.database)
-        .await
-        .inspect_err(|e| error!("Error deleting todo list: {e}"))
-        .or_internal_server_error("Failed to delete todo list")?;
-
+pub async fn delete_todo_list(todo_list_id: i32) -> Result<NoContent, ApiError> {
+    let user_id = auth.user.as_ref().or_unauthorized("Not authenticated")?.id;
+    server::todo_lists::delete_todo_list(&state, user_id, todo_list_id).await?;
     Ok(NoContent)
 }
 
@@ -205,7 +129,7 @@ pub async fn delete_todo_list(todo_list_id: i32) -> Result<NoContent, ServerFnEr
 pub async fn remove_user_from_todo_list(
     todo_list_id: i32,
     user_id: i32,
-) -> Result<NoContent, ServerFnError> {
+) -> Result<NoContent, ApiError> {
     let request_user_id = auth.user.as_ref().or_unauthorized("Not authenticated")?.id;
     server::todo_lists::remove_user_from_todo_list(
         todo_list_id,
@@ -214,5 +138,6 @@ pub async fn remove_user_from_todo_list(
         &state.database,
     )
     .await?;
+    server::todo_lists::publish_member_removed(todo_list_id, user_id, &state).await;
     Ok(NoContent)
 }
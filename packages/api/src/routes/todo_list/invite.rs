@@ -1,3 +1,4 @@
+use crate::error::ApiError;
 #[cfg(feature = "server")]
 use crate::server;
 use dioxus::fullstack::NoContent;
@@ -19,8 +20,13 @@ pub struct InviteToTodoListData {
 pub async fn invite_to_todo_list(
     todo_list_id: i32,
     data: InviteToTodoListData,
-) -> Result<NoContent, ServerFnError> {
+) -> Result<NoContent, ApiError> {
+    use crate::routes::live_updates::ChangeEvent;
     use crate::routes::users::EMAIL_REGEX;
+    use crate::server::audit::AuditEntry;
+    use crate::server::auth::email_verification::addressable_by_email;
+    use crate::server::live_updates::Audience;
+    use entity::audit_log::{AuditAction, AuditTarget};
     use entity::todo_list::Entity as TodoList;
     use entity::todo_list_invitation::Entity as TodoListInvitation;
     use entity::user::Entity as User;
@@ -33,12 +39,15 @@ pub async fn invite_to_todo_list(
     let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
 
     let email_regex = Regex::new(EMAIL_REGEX).expect("EMAIL_REGEX must be valid");
-    email_regex
-        .is_match(&data.email)
-        .or_bad_request("email is not a valid email")?;
+    if !email_regex.is_match(&data.email) {
+        return Err(ApiError::validation("email", "email is not a valid email"));
+    }
 
     let to_user = User::find()
-        .filter(entity::user::Column::Email.eq(data.email))
+        .filter(addressable_by_email(
+            &data.email,
+            state.settings.email_verification_required,
+        ))
         .one(&state.database)
         .await
         .or_internal_server_error("Failed to load user")?
@@ -80,11 +89,40 @@ pub async fn invite_to_todo_list(
         .inspect_err(|e| error!("{e}"))
         .or_internal_server_error("Failed to invite user")?;
 
+    AuditEntry::new(
+        Some(user.id),
+        AuditAction::TodoListMemberInvited,
+        AuditTarget::TodoList,
+        todo_list_id,
+    )
+    .affected_user(to_user.id)
+    .details(data.permission.to_string())
+    .record(&state.database)
+    .await;
+    state
+        .live_updates
+        .publish(
+            Audience::TodoList(todo_list_id),
+            ChangeEvent::TodoListChanged { todo_list_id },
+            &state.database,
+        )
+        .await;
+    state
+        .live_updates
+        .publish(
+            Audience::Users(vec![to_user.id]),
+            ChangeEvent::InvitationsChanged,
+            &state.database,
+        )
+        .await;
+
     Ok(NoContent)
 }
 
 #[post("/api/todolists/{todo_list_id}/invite/accept", state: Extension<server::AppState>, auth: Extension<server::AuthenticationState> )]
-pub async fn accept_todo_list_invite(todo_list_id: i32) -> Result<NoContent, ServerFnError> {
+pub async fn accept_todo_list_invite(todo_list_id: i32) -> Result<NoContent, ApiError> {
+    use crate::routes::live_updates::ChangeEvent;
+    use crate::server::live_updates::Audience;
     use sea_orm::{ActiveModelTrait, IntoActiveModel};
     use server::todo_lists::find_todo_list_invitation;
     let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
@@ -102,11 +140,30 @@ pub async fn accept_todo_list_invite(todo_list_id: i32) -> Result<NoContent, Ser
         .inspect_err(|e| error!("{e}"))
         .or_internal_server_error("Failed to accept Invite")?;
 
+    state
+        .live_updates
+        .publish(
+            Audience::TodoList(todo_list_id),
+            ChangeEvent::TodoListChanged { todo_list_id },
+            &state.database,
+        )
+        .await;
+    state
+        .live_updates
+        .publish(
+            Audience::Users(vec![user.id]),
+            ChangeEvent::InvitationsChanged,
+            &state.database,
+        )
+        .await;
+
     Ok(NoContent)
 }
 
 #[delete("/api/todolists/{todo_list_id}/invite/decline", state: Extension<server::AppState>, auth: Extension<server::AuthenticationState> )]
-pub async fn decline_todo_list_invite(todo_list_id: i32) -> Result<NoContent, ServerFnError> {
+pub async fn decline_todo_list_invite(todo_list_id: i32) -> Result<NoContent, ApiError> {
+    use crate::routes::live_updates::ChangeEvent;
+    use crate::server::live_updates::Audience;
     use sea_orm::ModelTrait;
     use server::todo_lists::find_todo_list_invitation;
 
@@ -122,6 +179,23 @@ pub async fn decline_todo_list_invite(todo_list_id: i32) -> Result<NoContent, Se
         .await
         .or_internal_server_error("could not delete invite")?;
 
+    state
+        .live_updates
+        .publish(
+            Audience::TodoList(todo_list_id),
+            ChangeEvent::TodoListChanged { todo_list_id },
+            &state.database,
+        )
+        .await;
+    state
+        .live_updates
+        .publish(
+            Audience::Users(vec![user.id]),
+            ChangeEvent::InvitationsChanged,
+            &state.database,
+        )
+        .await;
+
     Ok(NoContent)
 }
 
@@ -129,7 +203,11 @@ pub async fn decline_todo_list_invite(todo_list_id: i32) -> Result<NoContent, Se
 /// If the user is the only member of the `TodoList`, then the `TodoList` will be deleted.
 /// If the user is an admin, they can only leave if there is at least one other admin remaining in the `TodoList` to avoid leaving the `TodoList` without any admins.
 #[post("/api/todolists/{todo_list_id}/invite/leave", state: Extension<server::AppState>, auth: Extension<server::AuthenticationState> )]
-pub async fn leave_todo_list(todo_list_id: i32) -> Result<NoContent, ServerFnError> {
+pub async fn leave_todo_list(todo_list_id: i32) -> Result<NoContent, ApiError> {
+    use crate::routes::live_updates::ChangeEvent;
+    use crate::server::audit::AuditEntry;
+    use crate::server::live_updates::Audience;
+    use entity::audit_log::{AuditAction, AuditTarget};
     use entity::todo_list::Entity as TodoList;
     use entity::todo_list_invitation::Column as InviteColum;
     use entity::todo_list_invitation::Entity as TodoListInvitation;
@@ -138,6 +216,7 @@ pub async fn leave_todo_list(todo_list_id: i32) -> Result<NoContent, ServerFnErr
     let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
 
     let todo_list = TodoList::find_by_id(todo_list_id)
+        .filter(entity::todo_list::Column::DeletedAt.is_null())
         .one(&state.database)
         .await
         .inspect_err(|e| error!("{e}"))
@@ -170,6 +249,23 @@ pub async fn leave_todo_list(todo_list_id: i32) -> Result<NoContent, ServerFnErr
             .inspect_err(|e| error!("{e}"))
             .or_internal_server_error("Failed to delete todo list")?;
 
+        AuditEntry::new(
+            Some(user.id),
+            AuditAction::TodoListDeleted,
+            AuditTarget::TodoList,
+            todo_list_id,
+        )
+        .record(&state.database)
+        .await;
+        state
+            .live_updates
+            .publish(
+                Audience::Users(vec![user.id]),
+                ChangeEvent::TodoListDeleted { todo_list_id },
+                &state.database,
+            )
+            .await;
+
         return Ok(NoContent);
     }
 
@@ -197,6 +293,17 @@ pub async fn leave_todo_list(todo_list_id: i32) -> Result<NoContent, ServerFnErr
         .await
         .or_internal_server_error("Failed to leave todo list")?;
 
+    AuditEntry::new(
+        Some(user.id),
+        AuditAction::TodoListMemberRemoved,
+        AuditTarget::TodoList,
+        todo_list_id,
+    )
+    .affected_user(user.id)
+    .record(&state.database)
+    .await;
+    server::todo_lists::publish_member_removed(todo_list_id, user.id, &state).await;
+
     Ok(NoContent)
 }
 
@@ -205,7 +312,11 @@ pub async fn update_todo_list_invitation(
     todo_list_id: i32,
     user_id: i32,
     data: UpdateTodoListInvitation,
-) -> Result<UserWithTodoListInvitation, ServerFnError> {
+) -> Result<UserWithTodoListInvitation, ApiError> {
+    use crate::routes::live_updates::ChangeEvent;
+    use crate::server::audit::AuditEntry;
+    use crate::server::live_updates::Audience;
+    use entity::audit_log::{AuditAction, AuditTarget};
     use entity::todo_list_invitation::Column as InviteColum;
     use entity::todo_list_invitation::Entity as TodoListInvitation;
     use entity::user::Entity as User;
@@ -245,7 +356,8 @@ pub async fn update_todo_list_invitation(
         .or_internal_server_error("Failed to retrieve Invite")?
         .or_not_found("Invite not found")?;
 
-    let permission = data.permission.unwrap_or(invitation.permission);
+    let previous_permission = invitation.permission;
+    let permission = data.permission.unwrap_or(previous_permission);
     let mut invitation = invitation.into_active_model();
     invitation.permission.set_if_not_equals(permission);
     invitation
@@ -254,6 +366,27 @@ pub async fn update_todo_list_invitation(
         .inspect_err(|e| error!("{e}"))
         .or_internal_server_error("Failed to update Invite")?;
 
+    if permission != previous_permission {
+        AuditEntry::new(
+            Some(user.id),
+            AuditAction::TodoListPermissionChanged,
+            AuditTarget::TodoList,
+            todo_list_id,
+        )
+        .affected_user(user_id)
+        .details(format!("{previous_permission} -> {permission}"))
+        .record(&state.database)
+        .await;
+        state
+            .live_updates
+            .publish(
+                Audience::TodoList(todo_list_id),
+                ChangeEvent::TodoListChanged { todo_list_id },
+                &state.database,
+            )
+            .await;
+    }
+
     let user = User::find_by_id(user_id)
         .join(
             sea_orm::JoinType::InnerJoin,
@@ -273,7 +406,7 @@ pub async fn update_todo_list_invitation(
 pub async fn update_my_todo_list_invitation(
     todo_list_id: i32,
     data: UpdateMyTodoListInvitation,
-) -> Result<UserWithTodoListInvitation, ServerFnError> {
+) -> Result<UserWithTodoListInvitation, ApiError> {
     use entity::todo_list_invitation::Column as InviteColum;
     use entity::todo_list_invitation::Entity as TodoListInvitation;
     use entity::user::Entity as User;
@@ -325,7 +458,7 @@ pub async fn update_my_todo_list_invitation(
 #[get("/api/todolists/invite?accepted", state: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
 pub async fn list_todo_invites(
     accepted: Option<bool>,
-) -> Result<Vec<entity::todo_list_invitation::Model>, ServerFnError> {
+) -> Result<Vec<entity::todo_list_invitation::Model>, ApiError> {
     use entity::todo_list_invitation::Column as InviteColum;
     use entity::todo_list_invitation::Entity as TodoListInvitation;
     use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryTrait};
@@ -333,7 +466,9 @@ pub async fn list_todo_invites(
     let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
 
     let invitations = TodoListInvitation::find()
+        .inner_join(entity::todo_list::Entity)
         .filter(InviteColum::ReceivingUserId.eq(user.id))
+        .filter(entity::todo_list::Column::DeletedAt.is_null())
         .filter(InviteColum::SenderUserId.ne(user.id))
         .apply_if(accepted, |query, v| {
             query.filter(InviteColum::IsAccepted.eq(v))
@@ -1,3 +1,4 @@
+use crate::error::ApiError;
 #[cfg(feature = "server")]
 use crate::server;
 use dioxus::fullstack::NoContent;
@@ -7,7 +8,7 @@ use dioxus::server::axum::Extension;
 use entity::todo::{CreateToDo, UpdateToDo};
 
 #[get("/api/todolists/{todo_list_id}/todos", state: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
-pub async fn list_todo(todo_list_id: i32) -> Result<Vec<entity::todo::Model>, ServerFnError> {
+pub async fn list_todo(todo_list_id: i32) -> Result<Vec<entity::todo::Model>, ApiError> {
     use entity::todo::Entity as Todo;
     use entity::todo_list::Entity as TodoList;
     use sea_orm::ColumnTrait;
@@ -42,7 +43,7 @@ pub async fn list_todo(todo_list_id: i32) -> Result<Vec<entity::todo::Model>, Se
 pub async fn list_todos(
     completed: Option<bool>,
     favorite: Option<bool>,
-) -> Result<Vec<entity::todo::TodoWithPermission>, ServerFnError> {
+) -> Result<Vec<entity::todo::TodoWithPermission>, ApiError> {
     use entity::todo::Entity as Todo;
     use sea_orm::ColumnTrait;
     use sea_orm::EntityTrait;
@@ -63,6 +64,7 @@ pub async fn list_todos(
         )
         .filter(entity::todo_list_invitation::Column::ReceivingUserId.eq(user.id))
         .filter(entity::todo_list_invitation::Column::IsAccepted.eq(true))
+        .filter(entity::todo_list::Column::DeletedAt.is_null())
         .apply_if(completed, |query, v| {
             query.filter(entity::todo::Column::Completed.eq(v))
         })
@@ -84,101 +86,26 @@ pub async fn list_todos(
 pub async fn create_todo(
     todo_list_id: i32,
     data: CreateToDo,
-) -> Result<entity::todo::Model, ServerFnError> {
-    use entity::todo_list::Entity as TodoList;
-    use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, Set};
-
-    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
-
-    let todo_list = TodoList::find_by_id(todo_list_id)
-        .one(&state.database)
-        .await
-        .or_internal_server_error("Failed to load To-Do List")?
-        .or_not_found("To-Do List not founds")?;
-
-    server::todo_lists::get_todo_list_permission(todo_list.id, user.id, &state.database)
-        .await?
-        .or_forbidden("You are not permitted to add Tasks in this To-Do List")?
-        .can_write()
-        .or_forbidden("You are not permitted to add Tasks to this To-Do List")?;
-
-    let mut todo = data.into_active_model();
-    todo.completed = Set(false);
-    todo.owner_id = Set(user.id);
-    todo.todo_list_id = Set(todo_list_id);
-
-    let todo = todo
-        .insert(&state.database)
-        .await
-        .inspect_err(|error| error!("{error:?}"))
-        .or_internal_server_error("Failed to create Task")?;
-
-    Ok(todo)
+) -> Result<entity::todo::Model, ApiError> {
+    let user_id = auth.user.as_ref().or_unauthorized("Not authenticated")?.id;
+    server::todo_lists::create_todo(&state, user_id, todo_list_id, data).await
 }
 
 #[patch("/api/todos/{todo_id}", state: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
 pub async fn update_todo(
     todo_id: i32,
+    version: i32,
     data: UpdateToDo,
-) -> Result<entity::todo::Model, ServerFnError> {
-    use entity::todo::Entity as Todo;
-    use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, TryIntoModel};
-    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
-    let todo = Todo::find_by_id(todo_id)
-        .one(&state.database)
-        .await
-        .or_internal_server_error("Failed to load To-do Task")?
-        .or_not_found("To-do Task not found")?;
-
-    server::todo_lists::get_todo_list_permission(todo.todo_list_id, user.id, &state.database)
+) -> Result<entity::todo::Model, ApiError> {
+    let user_id = auth.user.as_ref().or_unauthorized("Not authenticated")?.id;
+    server::todo_lists::update_todo(&state, user_id, todo_id, version, data)
         .await?
-        .or_forbidden("You are not permitted to update Tasks in this To-Do List")?
-        .can_write()
-        .or_forbidden("You are not permitted to update Tasks in this To-Do List")?;
-
-    let mut todo = todo.into_active_model();
-    if let Some(title) = data.title {
-        todo.title = sea_orm::Set(title);
-    }
-    if let Some(details) = data.details {
-        todo.details = sea_orm::Set(details);
-    }
-    if let Some(completed) = data.completed {
-        todo.completed = sea_orm::Set(completed);
-    }
-
-    let todo = todo
-        .save(&state.database)
-        .await
-        .or_internal_server_error("Failed to update Task")?;
-
-    Ok(todo
-        .try_into_model()
-        .or_internal_server_error("Failed to convert to Active Model")?)
+        .or_edit_conflict("The Task was changed by someone else in the meantime")
 }
 
 #[delete("/api/todos/{todo_id}", state: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
-pub async fn delete_todo(todo_id: i32) -> Result<NoContent, ServerFnError> {
-    use entity::todo::Entity as Todo;
-    use sea_orm::{EntityTrait, ModelTrait};
-
-    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
-
-    let todo = Todo::find_by_id(todo_id)
-        .one(&state.database)
-        .await
-        .or_internal_server_error("Failed to load To-do Task")?
-        .or_not_found("To-do Task not found")?;
-
-    server::todo_lists::get_todo_list_permission(todo.todo_list_id, user.id, &state.database)
-        .await?
-        .or_forbidden("You are not permitted to delete Tasks in this To-Do List")?
-        .can_write()
-        .or_forbidden("You are not permitted to delete Tasks in this To-Do List")?;
-
-    todo.delete(&state.database)
-        .await
-        .or_internal_server_error("Failed to delete Task")?;
-
+pub async fn delete_todo(todo_id: i32) -> Result<NoContent, ApiError> {
+    let user_id = auth.user.as_ref().or_unauthorized("Not authenticated")?.id;
+    server::todo_lists::delete_todo(&state, user_id, todo_id).await?;
     Ok(NoContent)
 }
@@ -0,0 +1,190 @@
+use crate::error::ApiError;
+#[cfg(feature = "server")]
+use crate::server;
+use dioxus::fullstack::NoContent;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use dioxus::server::axum::Extension;
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum TrashKind {
+    TodoList,
+    Group,
+}
+
+/// A deleted To-Do list or group which can still be restored
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct TrashItem {
+    pub kind: TrashKind,
+    pub id: i32,
+    /// Title of the To-Do list or name of the group
+    pub name: String,
+    pub deleted_at: time::OffsetDateTime,
+    /// When the item is deleted permanently
+    pub purge_at: time::OffsetDateTime,
+}
+
+/// Lists the To-Do lists the user administrates and the groups the user is a member of which are in the trash,
+/// most recently deleted first
+#[get("/api/trash", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn list_trash() -> Result<Vec<TrashItem>, ApiError> {
+    use crate::server::trash::retention;
+    use entity::group::Column as GroupColumn;
+    use entity::group::Entity as Group;
+    use entity::todo_list::Column as TodoListColumn;
+    use entity::todo_list::Entity as TodoList;
+    use entity::todo_list_invitation::Column as InvitationColumn;
+    use entity::todo_list_invitation::Entity as TodoListInvitation;
+    use entity::todo_list_invitation::InvitationPermission;
+    use sea_orm::{ColumnTrait, EntityTrait, ModelTrait, QueryFilter};
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+    let retention = retention(ext.settings.trash_retention_days);
+    let cutoff = time::OffsetDateTime::now_utc() - retention;
+
+    let todo_lists = TodoList::find()
+        .inner_join(TodoListInvitation)
+        .filter(InvitationColumn::ReceivingUserId.eq(user.id))
+        .filter(InvitationColumn::IsAccepted.eq(true))
+        .filter(InvitationColumn::Permission.eq(InvitationPermission::Admin))
+        .filter(TodoListColumn::DeletedAt.gt(cutoff))
+        .all(&ext.database)
+        .await
+        .inspect_err(|e| error!("{e}"))
+        .or_internal_server_error("Error loading the trash")?;
+
+    let groups = user
+        .find_related(Group)
+        .filter(GroupColumn::DeletedAt.gt(cutoff))
+        .all(&ext.database)
+        .await
+        .inspect_err(|e| error!("{e}"))
+        .or_internal_server_error("Error loading the trash")?;
+
+    let todo_lists = todo_lists.into_iter().filter_map(|todo_list| {
+        let deleted_at = todo_list.deleted_at?;
+        Some(TrashItem {
+            kind: TrashKind::TodoList,
+            id: todo_list.id,
+            name: todo_list.title,
+            deleted_at,
+            purge_at: deleted_at + retention,
+        })
+    });
+    let groups = groups.into_iter().filter_map(|group| {
+        let deleted_at = group.deleted_at?;
+        Some(TrashItem {
+            kind: TrashKind::Group,
+            id: group.id,
+            name: group.name,
+            deleted_at,
+            purge_at: deleted_at + retention,
+        })
+    });
+
+    let mut items: Vec<TrashItem> = todo_lists.chain(groups).collect();
+    items.sort_by_key(|item| std::cmp::Reverse(item.deleted_at));
+    Ok(items)
+}
+
+/// Takes a To-Do list out of the trash. Can only be performed by an admin of the `TodoList`
+#[post("/api/trash/todolists/{todo_list_id}/restore", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn restore_todo_list(todo_list_id: i32) -> Result<NoContent, ApiError> {
+    use crate::routes::live_updates::ChangeEvent;
+    use crate::server::audit::AuditEntry;
+    use crate::server::live_updates::Audience;
+    use crate::server::todo_lists::find_todo_list_invitation;
+    use crate::server::trash::retention;
+    use entity::audit_log::{AuditAction, AuditTarget};
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+
+    // The permission checks of `server::todo_lists` ignore lists in the trash
+    let invitation = find_todo_list_invitation(todo_list_id, user.id, &ext.database)
+        .await
+        .or_internal_server_error("Failed to load todo list invitation")?
+        .or_not_found("To-Do List not found")?;
+    (invitation.is_accepted && invitation.permission.can_admin())
+        .or_forbidden("Unauthorized to restore todo list")?;
+
+    server::trash::restore_todo_list(
+        todo_list_id,
+        retention(ext.settings.trash_retention_days),
+        &ext.database,
+    )
+    .await
+    .inspect_err(|e| error!("Error restoring todo list: {e}"))
+    .or_internal_server_error("Failed to restore todo list")?
+    .or_not_found("To-Do List is not in the trash")?;
+
+    AuditEntry::new(
+        Some(user.id),
+        AuditAction::TodoListRestored,
+        AuditTarget::TodoList,
+        todo_list_id,
+    )
+    .record(&ext.database)
+    .await;
+    ext.live_updates
+        .publish(
+            Audience::TodoList(todo_list_id),
+            ChangeEvent::TodoListChanged { todo_list_id },
+            &ext.database,
+        )
+        .await;
+
+    Ok(NoContent)
+}
+
+/// Takes a group out of the trash. Can be performed by every member of the group
+#[post("/api/trash/groups/{group_id}/restore", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn restore_group(group_id: i32) -> Result<NoContent, ApiError> {
+    use crate::routes::live_updates::ChangeEvent;
+    use crate::server::audit::AuditEntry;
+    use crate::server::live_updates::Audience;
+    use crate::server::trash::retention;
+    use entity::audit_log::{AuditAction, AuditTarget};
+    use entity::group::Column as GroupColumn;
+    use entity::group::Entity as Group;
+    use sea_orm::{ColumnTrait, ModelTrait, QueryFilter};
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+
+    // `is_user_in_group` ignores groups in the trash
+    user.find_related(Group)
+        .filter(GroupColumn::Id.eq(group_id))
+        .one(&ext.database)
+        .await
+        .or_internal_server_error("Error loading group")?
+        .or_not_found("Group not found")?;
+
+    server::trash::restore_group(
+        group_id,
+        retention(ext.settings.trash_retention_days),
+        &ext.database,
+    )
+    .await
+    .inspect_err(|e| error!("Error restoring group: {e}"))
+    .or_internal_server_error("Failed to restore group")?
+    .or_not_found("Group is not in the trash")?;
+
+    AuditEntry::new(
+        Some(user.id),
+        AuditAction::GroupRestored,
+        AuditTarget::Group,
+        group_id,
+    )
+    .record(&ext.database)
+    .await;
+    ext.live_updates
+        .publish(
+            Audience::Group(group_id),
+            ChangeEvent::GroupChanged { group_id },
+            &ext.database,
+        )
+        .await;
+
+    Ok(NoContent)
+}
@@ -1,4 +1,5 @@
 use crate::dioxus_fullstack::NoContent;
+use crate::error::ApiError;
 #[cfg(feature = "server")]
 use crate::server;
 use dioxus::prelude::*;
@@ -7,6 +8,15 @@ use dioxus::prelude::*;
 use dioxus::server::axum::Extension;
 use serde::{Deserialize, Serialize};
 
+pub mod account_deletion;
+pub mod api_tokens;
+pub mod email_verification;
+pub mod export;
+pub mod oidc_identities;
+pub mod passkeys;
+pub mod password_reset;
+pub mod totp;
+
 #[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
 pub struct UserInfo {
     pub id: i32,
@@ -14,6 +24,7 @@ pub struct UserInfo {
     pub first_name: String,
     pub last_name: String,
     pub is_oidc_user: bool,
+    pub email_verified: bool,
 }
 
 impl UserInfo {
@@ -24,12 +35,13 @@ impl UserInfo {
             first_name: user.first_name,
             last_name: user.last_name,
             is_oidc_user: user.is_oidc_user,
+            email_verified: user.email_verified,
         }
     }
 }
 
 #[get("/api/users/{user_id}", ext: Extension<server::AppState>)]
-pub async fn retrieve_user(user_id: i32) -> dioxus::Result<UserInfo, ServerFnError> {
+pub async fn retrieve_user(user_id: i32) -> dioxus::Result<UserInfo, ApiError> {
     use entity::user::Entity as User;
     use sea_orm::EntityTrait;
 
@@ -50,8 +62,9 @@ pub async fn sign_up(
     password: String,
     first_name: String,
     last_name: String,
-) -> Result<UserInfo, ServerFnError> {
+) -> Result<UserInfo, ApiError> {
     use crate::server::auth;
+    use crate::server::auth::email_verification::send_verification_email;
     use entity::user::Entity as User;
     use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
 
@@ -65,30 +78,43 @@ pub async fn sign_up(
         .or_internal_server_error("Error loading user from database")?;
 
     if user_check.is_some() {
-        Err(ServerFnError::ServerError {
-            message: ("Email already registered".to_string()),
-            code: (409),
-            details: None,
-        })
+        Err(ApiError::conflict("Email already registered"))
     } else {
         let user = auth::create_user(email, password, first_name, last_name, &ext.database).await?;
+        // The account exists at this point. A failed email can be resent, so signup still succeeds
+        if let Err(err) = send_verification_email(
+            &user,
+            &user.email,
+            &ext.mailer,
+            &ext.settings.server_url,
+            &ext.database,
+        )
+        .await
+        {
+            error!("Failed to send verification email: {err}");
+        }
         Ok(UserInfo::from_user_model(user))
     }
 }
 
+/// Result of the password step of a login
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum LoginResponse {
+    /// The session cookie has been set
+    LoggedIn(UserInfo),
+    /// The user has two-factor authentication enabled. The login has to be completed using
+    /// [`totp::complete_totp_login`] with the supplied challenge
+    TotpRequired { challenge: String },
+}
+
 #[post("/api/users/login", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>, cookies: Extension<tower_cookies::Cookies> )]
-pub async fn login(email: String, password: String) -> Result<UserInfo, ServerFnError> {
-    use crate::server::auth::{create_session, verify_user};
-    use crate::server::constants;
-    use tower_cookies::Cookie;
-    use tower_cookies::cookie::SameSite;
+pub async fn login(email: String, password: String) -> Result<LoginResponse, ApiError> {
+    use crate::server::audit::record_login;
+    use crate::server::auth::totp::{create_login_challenge, find_confirmed_credential};
+    use crate::server::auth::{add_session_cookie, create_session, verify_user};
 
     if auth.is_authenticated() {
-        return Err(ServerFnError::ServerError {
-            message: "Already logged in".to_string(),
-            code: 409,
-            details: None,
-        });
+        return Err(ApiError::conflict("Already logged in"));
     }
 
     let email = email.trim().to_lowercase();
@@ -97,56 +123,69 @@ pub async fn login(email: String, password: String) -> Result<UserInfo, ServerFn
         .await
         .or_unauthorized("Missing or incorrect Credentials")?;
 
+    (verified_user.email_verified || !ext.settings.email_verification_required)
+        .or_forbidden("Please verify your email address before logging in")?;
+
+    let totp_credential = find_confirmed_credential(verified_user.id, &ext.database)
+        .await
+        .or_internal_server_error("Error loading TOTP credential")?;
+    if totp_credential.is_some() {
+        let challenge = create_login_challenge(verified_user.id, &ext.database)
+            .await
+            .or_internal_server_error("Error creating login challenge")?;
+        return Ok(LoginResponse::TotpRequired { challenge });
+    }
+
     let (session_key, expires_at) = create_session(&verified_user.id, &ext.database)
         .await
         .or_internal_server_error("Error creating session")?;
+    record_login(verified_user.id, "password", &ext.database).await;
 
-    cookies.add(
-        Cookie::build((constants::SESSION_COOKIE_NAME, session_key))
-            .http_only(true)
-            .same_site(SameSite::Strict)
-            .secure(!cfg!(debug_assertions))
-            .path("/")
-            .expires(expires_at)
-            .build(),
-    );
-    Ok(UserInfo::from_user_model(verified_user))
+    add_session_cookie(&cookies, session_key, expires_at);
+    Ok(LoginResponse::LoggedIn(UserInfo::from_user_model(
+        verified_user,
+    )))
 }
 
-#[post("/api/logout", ext: Extension<server::AppState>, mut auth: Extension<server::AuthenticationState>
-)]
-pub async fn logout() -> Result<NoContent, ServerFnError> {
-    auth.logout(&ext.database).await?;
-    Ok(NoContent)
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct LogoutResponse {
+    /// End session url of the OIDC provider the browser should be redirected to
+    pub redirect_url: Option<String>,
 }
 
-#[delete("/api/users/{user_id}", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>
+#[post("/api/logout", ext: Extension<server::AppState>, mut auth: Extension<server::AuthenticationState>, cookies: Extension<tower_cookies::Cookies>
 )]
-pub async fn delete_user(user_id: i32) -> Result<NoContent, ServerFnError> {
-    use entity::user::Entity as User;
-    use sea_orm::EntityTrait;
-
-    (auth.is_authenticated()
-        && auth
-            .user
-            .as_ref()
-            .expect("Impossible panic. User is authenticated")
-            .id
-            == user_id)
-        .or_unauthorized("Invalid authentication")?;
-
-    let delete_result = User::delete_by_id(user_id)
-        .exec(&ext.database)
-        .await
-        .or_internal_server_error("Error deleting user")?;
+pub async fn logout() -> Result<LogoutResponse, ApiError> {
+    use crate::server::auth::oidc_logout;
+    use crate::server::auth::remove_session_cookie;
+    use crate::server::live_updates::Revocation;
+
+    // Streams authenticated with OIDC tokens cannot be told apart, so all streams of the user end and the
+    // clients that are still logged in reconnect
+    let revocation = match (auth.session_id(), &auth.user) {
+        (Some(session_id), _) => Some(Revocation::Session(session_id)),
+        (None, Some(user)) => Some(Revocation::User(user.id)),
+        (None, None) => None,
+    };
+    if auth.has_session() {
+        auth.logout(&ext.database)
+            .await
+            .inspect_err(|e| error!("Error logging out: {e}"))
+            .or_internal_server_error("Error logging out")?;
+    }
+    remove_session_cookie(&cookies);
+    if let Some(revocation) = revocation {
+        ext.live_updates.revoke(revocation);
+    }
 
-    (delete_result.rows_affected == 1).or_not_found("User not found")?;
-    Ok(NoContent)
+    let redirect_url =
+        oidc_logout::logout(&cookies, &ext.oidc_providers, &ext.settings.server_url).await;
+    Ok(LogoutResponse { redirect_url })
 }
 
 #[allow(clippy::unused_async)]
 #[get("/api/me", auth: Extension<server::AuthenticationState>)]
-pub async fn get_me() -> Result<UserInfo, ServerFnError> {
+pub async fn get_me() -> Result<UserInfo, ApiError> {
     let auth_user = auth.user.clone().or_unauthorized("Not authenticated")?;
     Ok(UserInfo::from_user_model(auth_user))
 }
@@ -156,16 +195,17 @@ pub async fn change_user_info(
     first_name: String,
     last_name: String,
     email: String,
-) -> dioxus::Result<UserInfo, ServerFnError> {
+) -> dioxus::Result<UserInfo, ApiError> {
+    use crate::server::auth::email_verification::send_verification_email;
     use entity::user::Entity as User;
     use regex::Regex;
-    use sea_orm::{EntityTrait, IntoActiveModel};
+    use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter};
 
     let email = email.trim().to_lowercase();
     let email_regex = Regex::new(EMAIL_REGEX).expect("EMAIL_REGEX must be valid");
-    email_regex
-        .is_match(&email)
-        .or_bad_request("email is not a valid email")?;
+    if !email_regex.is_match(&email) {
+        return Err(ApiError::validation("email", "email is not a valid email"));
+    }
 
     let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
 
@@ -173,12 +213,33 @@ pub async fn change_user_info(
 
     user_active.first_name = sea_orm::Set(first_name);
     user_active.last_name = sea_orm::Set(last_name);
-    if !user.is_oidc_user {
-        user_active.email = sea_orm::Set(email);
+
+    // A new email only replaces the current one once it has been verified
+    if !user.is_oidc_user && email != user.email {
+        let email_taken = User::find()
+            .filter(entity::user::Column::Email.eq(&email))
+            .one(&ext.database)
+            .await
+            .or_internal_server_error("Error loading user from database")?
+            .is_some();
+        if email_taken {
+            return Err(ApiError::conflict("Email already registered"));
+        }
+
+        send_verification_email(
+            user,
+            &email,
+            &ext.mailer,
+            &ext.settings.server_url,
+            &ext.database,
+        )
+        .await
+        .inspect_err(|e| error!("Failed to send verification email: {e}"))
+        .or_internal_server_error("Failed to send verification email")?;
     }
 
-    let res = User::update(user_active)
-        .exec(&ext.database)
+    let res = user_active
+        .update(&ext.database)
         .await
         .or_internal_server_error("cant update user")?;
 
@@ -186,10 +247,11 @@ pub async fn change_user_info(
 }
 
 #[put("/api/users/password",  ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState> )]
-pub async fn change_password(password: String) -> dioxus::Result<NoContent, ServerFnError> {
+pub async fn change_password(password: String) -> dioxus::Result<NoContent, ApiError> {
+    use crate::server::audit::AuditEntry;
     use crate::server::auth::hash_password;
-    use entity::user::Entity as User;
-    use sea_orm::EntityTrait;
+    use entity::audit_log::{AuditAction, AuditTarget};
+    use sea_orm::ActiveModelTrait;
     use sea_orm::IntoActiveModel;
 
     let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
@@ -201,10 +263,19 @@ pub async fn change_password(password: String) -> dioxus::Result<NoContent, Serv
     let mut user_active: entity::user::ActiveModel = user.clone().into_active_model();
     user_active.password = sea_orm::Set(Some(hashed_pass));
 
-    User::update(user_active)
-        .exec(&ext.database)
+    user_active
+        .update(&ext.database)
         .await
         .or_internal_server_error("couldnt change password")?;
 
+    AuditEntry::new(
+        Some(user.id),
+        AuditAction::PasswordChanged,
+        AuditTarget::User,
+        user.id,
+    )
+    .record(&ext.database)
+    .await;
+
     Ok(NoContent)
 }
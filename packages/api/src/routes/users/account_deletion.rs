@@ -0,0 +1,117 @@
+use crate::dioxus_fullstack::NoContent;
+use crate::error::ApiError;
+use crate::routes::users::UserInfo;
+#[cfg(feature = "server")]
+use crate::server;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use dioxus::server::axum::Extension;
+
+/// What happens to the data of a user when the account is deleted
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug, Default)]
+pub struct AccountDeletionPlan {
+    /// Events owned by the user
+    pub events: Vec<EventDeletion>,
+    /// To-Do lists the user is a member of, including the ones in the trash
+    pub todo_lists: Vec<TodoListDeletion>,
+    /// Groups the user is a member of, including the ones in the trash
+    pub groups: Vec<GroupDeletion>,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct EventDeletion {
+    pub event_id: i32,
+    pub title: String,
+    pub outcome: EventOutcome,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum EventOutcome {
+    /// The event keeps existing for everyone it is shared with and belongs to this user from now on
+    TransferredTo(UserInfo),
+    /// Nobody else takes part in the event
+    Deleted,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct TodoListDeletion {
+    pub todo_list_id: i32,
+    pub title: String,
+    pub outcome: TodoListOutcome,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum TodoListOutcome {
+    /// The other members keep the list. The tasks created by the user are handed over to `successor`,
+    /// who becomes admin if `promoted` is set because nobody else administrates the list
+    Kept { successor: UserInfo, promoted: bool },
+    /// The user is the only member
+    Deleted,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct GroupDeletion {
+    pub group_id: i32,
+    pub name: String,
+    pub outcome: GroupOutcome,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum GroupOutcome {
+    /// The user leaves the group
+    Left,
+    /// The user is the last member
+    Deleted,
+}
+
+/// Reports what happens to the events, To-Do lists and groups of the user when the account is deleted
+#[get("/api/users/me/deletion", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn preview_account_deletion() -> Result<AccountDeletionPlan, ApiError> {
+    use crate::server::account_deletion::plan_account_deletion;
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+
+    let plan = plan_account_deletion(user, &ext.database)
+        .await
+        .inspect_err(|e| error!("Error planning the deletion of user {}: {e}", user.id))
+        .or_internal_server_error("Error loading your data")?;
+    Ok(plan)
+}
+
+/// Deletes the account of the user as described by [`preview_account_deletion`] and ends the session.
+///
+/// Users with a password have to enter it, users without one have to authenticate again right before, either at
+/// their identity provider or with a passkey. All users have to confirm by entering their email address.
+#[post("/api/users/me/delete", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>, cookies: Extension<tower_cookies::Cookies>, session: Extension<tower_sessions::Session>)]
+pub async fn delete_account(
+    password: Option<String>,
+    confirmation_email: String,
+) -> Result<NoContent, ApiError> {
+    use crate::server::account_deletion;
+    use crate::server::auth::reauthentication::take_reauthentication;
+    use crate::server::auth::{remove_session_cookie, verify_password};
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+
+    (confirmation_email.trim().to_lowercase() == user.email.to_lowercase())
+        .or_bad_request("The email address does not match your account")?;
+    if let Some(hash) = user.password.as_deref() {
+        let password = password.or_bad_request("Password is required")?;
+        verify_password(&password, hash).or_forbidden("Incorrect password")?;
+    } else {
+        take_reauthentication(&session, user.id)
+            .await?
+            .or_forbidden("Please log in again before deleting your account")?;
+    }
+
+    account_deletion::delete_account(user, &ext)
+        .await
+        .inspect_err(|e| error!("Error deleting user {}: {e}", user.id))
+        .or_internal_server_error("Error deleting your account")?;
+
+    // The sessions were deleted together with the user
+    remove_session_cookie(&cookies);
+    Ok(NoContent)
+}
@@ -0,0 +1,185 @@
+use crate::error::ApiError;
+#[cfg(feature = "server")]
+use crate::server;
+use dioxus::fullstack::NoContent;
+use dioxus::prelude::*;
+
+#[cfg(feature = "server")]
+use dioxus::server::axum::Extension;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use time::OffsetDateTime;
+
+/// Longest lifetime a token may be created with
+pub const MAX_API_TOKEN_LIFETIME_DAYS: i32 = 365;
+
+/// Permission granted to a personal API token. Read scopes allow `GET` requests, write scopes all other methods
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum ApiScope {
+    ReadEvents,
+    WriteEvents,
+    ReadTodos,
+    WriteTodos,
+    ReadGroups,
+    WriteGroups,
+}
+
+impl ApiScope {
+    pub const ALL: [ApiScope; 6] = [
+        ApiScope::ReadEvents,
+        ApiScope::WriteEvents,
+        ApiScope::ReadTodos,
+        ApiScope::WriteTodos,
+        ApiScope::ReadGroups,
+        ApiScope::WriteGroups,
+    ];
+
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiScope::ReadEvents => "read-events",
+            ApiScope::WriteEvents => "write-events",
+            ApiScope::ReadTodos => "read-todos",
+            ApiScope::WriteTodos => "write-todos",
+            ApiScope::ReadGroups => "read-groups",
+            ApiScope::WriteGroups => "write-groups",
+        }
+    }
+
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == value.trim())
+    }
+}
+
+impl Display for ApiScope {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct ApiTokenInfo {
+    pub id: i32,
+    pub name: String,
+    pub token_hint: String,
+    pub scopes: Vec<ApiScope>,
+    pub created_at: OffsetDateTime,
+    pub expires_at: OffsetDateTime,
+    pub last_used_at: Option<OffsetDateTime>,
+}
+
+impl ApiTokenInfo {
+    pub fn from_model(model: entity::api_token::Model) -> Self {
+        ApiTokenInfo {
+            id: model.id,
+            name: model.name,
+            token_hint: model.token_hint,
+            scopes: model
+                .scopes
+                .split(',')
+                .filter_map(ApiScope::parse)
+                .collect(),
+            created_at: model.created_at,
+            expires_at: model.expires_at,
+            last_used_at: model.last_used_at,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct CreatedApiToken {
+    /// The plain text token. It is only returned once and cannot be recovered afterwards
+    pub token: String,
+    pub info: ApiTokenInfo,
+}
+
+#[get("/api/users/api-tokens", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn list_api_tokens() -> Result<Vec<ApiTokenInfo>, ApiError> {
+    use entity::api_token::Column as ApiTokenColumn;
+    use entity::prelude::ApiToken;
+    use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+
+    let tokens = ApiToken::find()
+        .filter(ApiTokenColumn::UserId.eq(user.id))
+        .order_by_asc(ApiTokenColumn::CreatedAt)
+        .all(&ext.database)
+        .await
+        .or_internal_server_error("Error loading API tokens")?;
+
+    Ok(tokens.into_iter().map(ApiTokenInfo::from_model).collect())
+}
+
+#[post("/api/users/api-tokens", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn create_api_token(
+    name: String,
+    scopes: Vec<ApiScope>,
+    lifetime_days: i32,
+) -> Result<CreatedApiToken, ApiError> {
+    use crate::error::FieldError;
+    use crate::server::auth::api_token;
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+
+    let name = name.trim().to_string();
+    let mut field_errors = Vec::new();
+    if name.is_empty() {
+        field_errors.push(FieldError {
+            field: "name".to_string(),
+            message: "Token name must not be empty".to_string(),
+        });
+    }
+    if scopes.is_empty() {
+        field_errors.push(FieldError {
+            field: "scopes".to_string(),
+            message: "Select at least one scope".to_string(),
+        });
+    }
+    if !(1..=MAX_API_TOKEN_LIFETIME_DAYS).contains(&lifetime_days) {
+        field_errors.push(FieldError {
+            field: "lifetime_days".to_string(),
+            message: format!("Tokens must expire within {MAX_API_TOKEN_LIFETIME_DAYS} days"),
+        });
+    }
+    if !field_errors.is_empty() {
+        return Err(ApiError::Validation {
+            message: "Invalid API token".to_string(),
+            fields: field_errors,
+        });
+    }
+
+    let (token, model) =
+        api_token::create_api_token(user.id, name, &scopes, lifetime_days, &ext.database)
+            .await
+            .inspect_err(|e| error!("Error creating API token: {e}"))
+            .or_internal_server_error("Failed to create API token")?;
+
+    Ok(CreatedApiToken {
+        token,
+        info: ApiTokenInfo::from_model(model),
+    })
+}
+
+#[delete("/api/users/api-tokens/{token_id}", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn revoke_api_token(token_id: i32) -> Result<NoContent, ApiError> {
+    use entity::api_token::Column as ApiTokenColumn;
+    use entity::prelude::ApiToken;
+    use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+
+    let delete_result = ApiToken::delete_many()
+        .filter(ApiTokenColumn::Id.eq(token_id))
+        .filter(ApiTokenColumn::UserId.eq(user.id))
+        .exec(&ext.database)
+        .await
+        .or_internal_server_error("Error deleting API token")?;
+
+    (delete_result.rows_affected == 1).or_not_found("API token not found")?;
+    Ok(NoContent)
+}
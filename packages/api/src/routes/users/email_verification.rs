@@ -0,0 +1,54 @@
+use crate::error::ApiError;
+#[cfg(feature = "server")]
+use crate::server;
+use dioxus::fullstack::NoContent;
+use dioxus::prelude::*;
+
+#[cfg(feature = "server")]
+use dioxus::server::axum::Extension;
+
+use super::UserInfo;
+
+/// Confirms an email address using the token sent by [`resend_verification_email`], signup or an email change
+#[post("/api/users/email-verification/confirm", ext: Extension<server::AppState>)]
+pub async fn verify_email(token: String) -> Result<UserInfo, ApiError> {
+    use crate::server::auth::email_verification::verify_email_token;
+
+    let user = verify_email_token(token.trim(), &ext.database).await?;
+
+    Ok(UserInfo::from_user_model(user))
+}
+
+/// Sends a new verification link to an unverified account.
+/// Responds the same way whether or not such an account exists, so it cannot be used to probe for registered emails.
+#[post("/api/users/email-verification/resend", ext: Extension<server::AppState>)]
+pub async fn resend_verification_email(email: String) -> Result<NoContent, ApiError> {
+    use crate::server::auth::email_verification::send_verification_email;
+    use entity::user::Entity as User;
+    use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+    let email = email.trim().to_lowercase();
+
+    let Some(user) = User::find()
+        .filter(entity::user::Column::Email.eq(&email))
+        .filter(entity::user::Column::EmailVerified.eq(false))
+        .one(&ext.database)
+        .await
+        .or_internal_server_error("Error loading user from database")?
+    else {
+        return Ok(NoContent);
+    };
+
+    send_verification_email(
+        &user,
+        &user.email,
+        &ext.mailer,
+        &ext.settings.server_url,
+        &ext.database,
+    )
+    .await
+    .inspect_err(|e| error!("Failed to send verification email: {e}"))
+    .or_internal_server_error("Failed to send verification email")?;
+
+    Ok(NoContent)
+}
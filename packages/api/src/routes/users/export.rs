@@ -0,0 +1,83 @@
+use crate::error::ApiError;
+#[cfg(feature = "server")]
+use crate::server;
+use dioxus::fullstack::FileStream;
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "server")]
+use dioxus::server::axum::Extension;
+
+/// Format of the data export
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// A single JSON document
+    #[default]
+    Json,
+    /// The JSON document together with the events as iCalendar file and the tasks as CSV file
+    Zip,
+}
+
+impl ExportFormat {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Zip => "zip",
+        }
+    }
+
+    /// Url of the export, which browsers download as file when linked to
+    #[must_use]
+    pub fn url(&self) -> String {
+        format!("/api/users/me/export?format={}", self.as_str())
+    }
+}
+
+/// Exports the profile, owned events and their shares, groups, created To-Do lists and tasks, invitations
+/// and sessions of the user
+#[get("/api/users/me/export?format", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn export_user_data(format: Option<ExportFormat>) -> Result<FileStream, ApiError> {
+    use crate::server::export::{collect_user_data, export_to_zip};
+    use dioxus::server::axum::body::Body;
+    use openidconnect::url::Url;
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+
+    let export = collect_user_data(user, &ext.database)
+        .await
+        .inspect_err(|e| error!("Error collecting the data of user {}: {e}", user.id))
+        .or_internal_server_error("Error collecting your data")?;
+
+    let date = export.exported_at.date();
+    let (extension, content_type, content) = match format.unwrap_or_default() {
+        ExportFormat::Json => (
+            "json",
+            "application/json",
+            serde_json::to_vec_pretty(&export)
+                .inspect_err(|e| error!("Error encoding the data export: {e}"))
+                .or_internal_server_error("Error exporting your data")?,
+        ),
+        ExportFormat::Zip => {
+            let server_host = Url::parse(&ext.settings.server_url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string))
+                .unwrap_or_else(|| "localhost".to_string());
+            (
+                "zip",
+                "application/zip",
+                export_to_zip(&export, &server_host)
+                    .inspect_err(|e| error!("Error packing the data export: {e}"))
+                    .or_internal_server_error("Error exporting your data")?,
+            )
+        }
+    };
+
+    Ok(FileStream::from_raw(
+        format!("roommates-export-{date}.{extension}"),
+        Some(content.len() as u64),
+        content_type.to_string(),
+        Body::from(content).into_data_stream(),
+    ))
+}
@@ -0,0 +1,64 @@
+use crate::error::ApiError;
+#[cfg(feature = "server")]
+use crate::server;
+use dioxus::fullstack::NoContent;
+use dioxus::prelude::*;
+
+#[cfg(feature = "server")]
+use dioxus::server::axum::Extension;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// An account of the OIDC provider linked to the user
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct OidcIdentityInfo {
+    pub id: i32,
+    pub issuer: String,
+    /// Email reported by the provider on the last login
+    pub email: Option<String>,
+    pub created_at: OffsetDateTime,
+    pub last_login_at: Option<OffsetDateTime>,
+}
+
+impl OidcIdentityInfo {
+    pub fn from_model(model: entity::oidc_identity::Model) -> Self {
+        OidcIdentityInfo {
+            id: model.id,
+            issuer: model.issuer,
+            email: model.email,
+            created_at: model.created_at,
+            last_login_at: model.last_login_at,
+        }
+    }
+}
+
+#[get("/api/users/oidc-identities", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn list_oidc_identities() -> Result<Vec<OidcIdentityInfo>, ApiError> {
+    use entity::oidc_identity::Column as IdentityColumn;
+    use entity::prelude::OidcIdentity;
+    use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+
+    let identities = OidcIdentity::find()
+        .filter(IdentityColumn::UserId.eq(user.id))
+        .order_by_asc(IdentityColumn::CreatedAt)
+        .all(&ext.database)
+        .await
+        .or_internal_server_error("Error loading linked accounts")?;
+
+    Ok(identities
+        .into_iter()
+        .map(OidcIdentityInfo::from_model)
+        .collect())
+}
+
+#[delete("/api/users/oidc-identities/{identity_id}", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn unlink_oidc_identity(identity_id: i32) -> Result<NoContent, ApiError> {
+    use crate::server::auth::oidc_identity;
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+
+    oidc_identity::unlink_identity(user, identity_id, &ext.database).await?;
+    Ok(NoContent)
+}
@@ -0,0 +1,274 @@
+use crate::error::ApiError;
+#[cfg(feature = "server")]
+use crate::server;
+use dioxus::fullstack::NoContent;
+use dioxus::prelude::*;
+
+#[cfg(feature = "server")]
+use dioxus::server::axum::Extension;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use super::UserInfo;
+
+const PASSKEY_REGISTRATION_SESSION_KEY: &str = "passkey_registration";
+const PASSKEY_AUTHENTICATION_SESSION_KEY: &str = "passkey_authentication";
+const PASSKEY_REAUTHENTICATION_SESSION_KEY: &str = "passkey_reauthentication";
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct PasskeyInfo {
+    pub id: i32,
+    pub name: String,
+    pub created_at: OffsetDateTime,
+    pub last_used_at: Option<OffsetDateTime>,
+}
+
+impl PasskeyInfo {
+    pub fn from_model(model: entity::passkey_credential::Model) -> Self {
+        PasskeyInfo {
+            id: model.id,
+            name: model.name,
+            created_at: model.created_at,
+            last_used_at: model.last_used_at,
+        }
+    }
+}
+
+/// Starts registering a new passkey for the current user.
+/// Returns the `PublicKeyCredentialCreationOptions` for `navigator.credentials.create` as JSON.
+#[post("/api/users/passkeys/registration", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>, session: Extension<tower_sessions::Session>)]
+pub async fn start_passkey_registration() -> Result<serde_json::Value, ApiError> {
+    use crate::server::auth::passkey::{find_passkeys, user_handle};
+    use entity::passkey_credential::Column as PasskeyColumn;
+    use sea_orm::ColumnTrait;
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+    // Access of OIDC users is managed by their provider, so they must not gain another way to log in
+    (!user.is_oidc_user).or_bad_request("OIDC Users cannot register passkeys")?;
+
+    let user_handle = user_handle(user.id, &ext.database)
+        .await
+        .or_internal_server_error("Error loading passkeys")?;
+    let existing_credentials = find_passkeys(PasskeyColumn::UserId.eq(user.id), &ext.database)
+        .await
+        .or_internal_server_error("Error loading passkeys")?
+        .into_iter()
+        .map(|(_, passkey)| passkey.cred_id().clone())
+        .collect();
+
+    let (creation_options, registration) = ext
+        .webauthn
+        .start_passkey_registration(
+            user_handle,
+            &user.email,
+            &format!("{} {}", user.first_name, user.last_name),
+            Some(existing_credentials),
+        )
+        .inspect_err(|e| error!("Error starting passkey registration: {e}"))
+        .or_internal_server_error("Failed to start passkey registration")?;
+
+    session
+        .insert(
+            PASSKEY_REGISTRATION_SESSION_KEY,
+            (user.id, user_handle, registration),
+        )
+        .await
+        .or_internal_server_error("Failed to create session")?;
+
+    Ok(serde_json::to_value(creation_options)
+        .or_internal_server_error("Failed to serialize passkey options")?)
+}
+
+/// Verifies and stores the credential created by the authenticator
+#[post("/api/users/passkeys", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>, session: Extension<tower_sessions::Session>)]
+pub async fn finish_passkey_registration(
+    name: String,
+    credential: serde_json::Value,
+) -> Result<PasskeyInfo, ApiError> {
+    use crate::server::auth::passkey::save_passkey;
+    use webauthn_rs::prelude::{PasskeyRegistration, RegisterPublicKeyCredential, Uuid};
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(ApiError::validation(
+            "name",
+            "Passkey name must not be empty",
+        ));
+    }
+
+    let (user_id, user_handle, registration): (i32, Uuid, PasskeyRegistration) = session
+        .remove(PASSKEY_REGISTRATION_SESSION_KEY)
+        .await
+        .or_internal_server_error("Failed to retrieve session")?
+        .or_bad_request("No passkey registration in progress")?;
+    (user_id == user.id).or_bad_request("Passkey registration belongs to another user")?;
+
+    let credential: RegisterPublicKeyCredential =
+        serde_json::from_value(credential).or_bad_request("Invalid passkey credential")?;
+    let passkey = ext
+        .webauthn
+        .finish_passkey_registration(&credential, &registration)
+        .inspect_err(|e| warn!("Passkey registration failed: {e}"))
+        .or_bad_request("Passkey could not be verified")?;
+
+    let model = save_passkey(user.id, user_handle, name, &passkey, &ext.database)
+        .await
+        .inspect_err(|e| error!("Error saving passkey: {e}"))
+        .or_internal_server_error("Failed to save passkey")?;
+
+    Ok(PasskeyInfo::from_model(model))
+}
+
+#[get("/api/users/passkeys", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn list_passkeys() -> Result<Vec<PasskeyInfo>, ApiError> {
+    use entity::passkey_credential::Column as PasskeyColumn;
+    use entity::prelude::PasskeyCredential;
+    use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+
+    let passkeys = PasskeyCredential::find()
+        .filter(PasskeyColumn::UserId.eq(user.id))
+        .order_by_asc(PasskeyColumn::CreatedAt)
+        .all(&ext.database)
+        .await
+        .or_internal_server_error("Error loading passkeys")?;
+
+    Ok(passkeys.into_iter().map(PasskeyInfo::from_model).collect())
+}
+
+#[delete("/api/users/passkeys/{passkey_id}", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn delete_passkey(passkey_id: i32) -> Result<NoContent, ApiError> {
+    use entity::passkey_credential::Column as PasskeyColumn;
+    use entity::prelude::PasskeyCredential;
+    use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+
+    let delete_result = PasskeyCredential::delete_many()
+        .filter(PasskeyColumn::Id.eq(passkey_id))
+        .filter(PasskeyColumn::UserId.eq(user.id))
+        .exec(&ext.database)
+        .await
+        .or_internal_server_error("Error deleting passkey")?;
+
+    (delete_result.rows_affected == 1).or_not_found("Passkey not found")?;
+    Ok(NoContent)
+}
+
+/// Starts a passwordless login. Returns the `PublicKeyCredentialRequestOptions` for `navigator.credentials.get`
+/// as JSON. No email is needed, the authenticator offers all passkeys it stores for this site.
+#[post("/api/users/login/passkey/start", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>, session: Extension<tower_sessions::Session>)]
+pub async fn start_passkey_login() -> Result<serde_json::Value, ApiError> {
+    auth.is_anonymous().or_bad_request("Already logged in")?;
+
+    let (request_options, authentication) = ext
+        .webauthn
+        .start_discoverable_authentication()
+        .inspect_err(|e| error!("Error starting passkey login: {e}"))
+        .or_internal_server_error("Failed to start passkey login")?;
+
+    session
+        .insert(PASSKEY_AUTHENTICATION_SESSION_KEY, authentication)
+        .await
+        .or_internal_server_error("Failed to create session")?;
+
+    Ok(serde_json::to_value(request_options)
+        .or_internal_server_error("Failed to serialize passkey options")?)
+}
+
+/// Verifies the assertion of the authenticator and sets the session cookie
+#[post("/api/users/login/passkey", ext: Extension<server::AppState>, cookies: Extension<tower_cookies::Cookies>, session: Extension<tower_sessions::Session>)]
+pub async fn finish_passkey_login(credential: serde_json::Value) -> Result<UserInfo, ApiError> {
+    use crate::server::audit::record_login;
+    use crate::server::auth::passkey::verify_passkey_assertion;
+    use crate::server::auth::{add_session_cookie, create_session};
+    use entity::prelude::User;
+    use sea_orm::EntityTrait;
+    use webauthn_rs::prelude::{DiscoverableAuthentication, PublicKeyCredential};
+
+    let authentication: DiscoverableAuthentication = session
+        .remove(PASSKEY_AUTHENTICATION_SESSION_KEY)
+        .await
+        .or_internal_server_error("Failed to retrieve session")?
+        .or_bad_request("No passkey login in progress")?;
+
+    let credential: PublicKeyCredential =
+        serde_json::from_value(credential).or_bad_request("Invalid passkey credential")?;
+    let user_id =
+        verify_passkey_assertion(&ext.webauthn, &credential, authentication, &ext.database).await?;
+
+    let user = User::find_by_id(user_id)
+        .one(&ext.database)
+        .await
+        .or_internal_server_error("Error loading user from database")?
+        .or_not_found("User not found")?;
+    (user.email_verified || !ext.settings.email_verification_required)
+        .or_forbidden("Please verify your email address before logging in")?;
+
+    let (session_key, expires_at) = create_session(&user.id, &ext.database)
+        .await
+        .or_internal_server_error("Error creating session")?;
+    record_login(user.id, "passkey", &ext.database).await;
+
+    add_session_cookie(&cookies, session_key, expires_at);
+    Ok(UserInfo::from_user_model(user))
+}
+
+/// Starts confirming the identity of the logged in user with one of their passkeys, e.g. before deleting the
+/// account. Returns the `PublicKeyCredentialRequestOptions` for `navigator.credentials.get` as JSON.
+#[post("/api/users/passkeys/reauthentication/start", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>, session: Extension<tower_sessions::Session>)]
+pub async fn start_passkey_reauthentication() -> Result<serde_json::Value, ApiError> {
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+
+    let (request_options, authentication) = ext
+        .webauthn
+        .start_discoverable_authentication()
+        .inspect_err(|e| error!("Error starting passkey authentication: {e}"))
+        .or_internal_server_error("Failed to start passkey authentication")?;
+
+    session
+        .insert(
+            PASSKEY_REAUTHENTICATION_SESSION_KEY,
+            (user.id, authentication),
+        )
+        .await
+        .or_internal_server_error("Failed to create session")?;
+
+    Ok(serde_json::to_value(request_options)
+        .or_internal_server_error("Failed to serialize passkey options")?)
+}
+
+/// Verifies the assertion of the authenticator and remembers the re-authentication in the session
+#[post("/api/users/passkeys/reauthentication", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>, session: Extension<tower_sessions::Session>)]
+pub async fn finish_passkey_reauthentication(
+    credential: serde_json::Value,
+) -> Result<NoContent, ApiError> {
+    use crate::server::auth::passkey::verify_passkey_assertion;
+    use crate::server::auth::reauthentication::record_reauthentication;
+    use webauthn_rs::prelude::{DiscoverableAuthentication, PublicKeyCredential};
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+
+    let (user_id, authentication): (i32, DiscoverableAuthentication) = session
+        .remove(PASSKEY_REAUTHENTICATION_SESSION_KEY)
+        .await
+        .or_internal_server_error("Failed to retrieve session")?
+        .or_bad_request("No passkey authentication in progress")?;
+    (user_id == user.id).or_bad_request("Passkey authentication belongs to another user")?;
+
+    let credential: PublicKeyCredential =
+        serde_json::from_value(credential).or_bad_request("Invalid passkey credential")?;
+    let passkey_user_id =
+        verify_passkey_assertion(&ext.webauthn, &credential, authentication, &ext.database).await?;
+    (passkey_user_id == user.id).or_forbidden("The passkey belongs to another account")?;
+
+    record_reauthentication(
+        &session,
+        user.id,
+        time::OffsetDateTime::now_utc().unix_timestamp(),
+    )
+    .await?;
+    Ok(NoContent)
+}
@@ -0,0 +1,45 @@
+use crate::error::ApiError;
+#[cfg(feature = "server")]
+use crate::server;
+use dioxus::fullstack::NoContent;
+use dioxus::prelude::*;
+
+#[cfg(feature = "server")]
+use dioxus::server::axum::Extension;
+
+/// Sends a password reset link to the supplied email.
+/// Responds immediately and the same way whether or not an account exists, so it cannot be used to probe for
+/// registered emails. Requests are throttled per address and client like logins.
+#[post("/api/users/password-reset/request", ext: Extension<server::AppState>)]
+pub async fn request_password_reset(email: String) -> Result<NoContent, ApiError> {
+    use crate::server::auth::send_password_reset_email;
+
+    let state = ext.0.clone();
+    // Sent in the background, as waiting for the mail server would reveal that an account exists
+    tokio::spawn(async move {
+        if let Err(e) = send_password_reset_email(
+            &email,
+            &state.mailer,
+            &state.settings.server_url,
+            &state.database,
+        )
+        .await
+        {
+            error!("Failed to send password reset email: {e}");
+        }
+    });
+
+    Ok(NoContent)
+}
+
+/// Sets a new password using a token from [`request_password_reset`]. Signs the user out everywhere.
+#[post("/api/users/password-reset/confirm", ext: Extension<server::AppState>)]
+pub async fn reset_password(token: String, password: String) -> Result<NoContent, ApiError> {
+    use crate::server::auth::reset_password_with_token;
+    use crate::server::live_updates::Revocation;
+
+    let user_id = reset_password_with_token(token.trim(), password, &ext.database).await?;
+    ext.live_updates.revoke(Revocation::User(user_id));
+
+    Ok(NoContent)
+}
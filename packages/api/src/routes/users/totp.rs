@@ -0,0 +1,179 @@
+use crate::error::ApiError;
+#[cfg(feature = "server")]
+use crate::server;
+use dioxus::fullstack::NoContent;
+use dioxus::prelude::*;
+
+#[cfg(feature = "server")]
+use dioxus::server::axum::Extension;
+use serde::{Deserialize, Serialize};
+
+use super::UserInfo;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct TotpStatus {
+    pub enabled: bool,
+    pub remaining_recovery_codes: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct TotpEnrolment {
+    /// `otpauth://` URI understood by authenticator apps
+    pub otpauth_url: String,
+    /// Base32 encoded secret for manual entry
+    pub secret: String,
+    /// Base64 encoded PNG of a QR code containing `otpauth_url`
+    pub qr_code: String,
+}
+
+#[get("/api/users/totp", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn totp_status() -> Result<TotpStatus, ApiError> {
+    use crate::server::auth::totp::find_confirmed_credential;
+    use entity::prelude::TotpRecoveryCode;
+    use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter};
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+
+    let enabled = find_confirmed_credential(user.id, &ext.database)
+        .await
+        .or_internal_server_error("Error loading TOTP credential")?
+        .is_some();
+    let remaining_recovery_codes = TotpRecoveryCode::find()
+        .filter(entity::totp_recovery_code::Column::UserId.eq(user.id))
+        .count(&ext.database)
+        .await
+        .or_internal_server_error("Error loading recovery codes")?;
+
+    Ok(TotpStatus {
+        enabled,
+        remaining_recovery_codes,
+    })
+}
+
+/// Starts the TOTP enrolment of the current user. Two-factor authentication is only enforced after
+/// the enrolment has been confirmed with [`confirm_totp_enrolment`]
+#[post("/api/users/totp/enrolment", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn start_totp_enrolment() -> Result<TotpEnrolment, ApiError> {
+    use crate::server::auth::totp::{find_confirmed_credential, start_enrolment};
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+    (!user.is_oidc_user)
+        .or_bad_request("OIDC Users manage two-factor authentication with their provider")?;
+
+    let enabled = find_confirmed_credential(user.id, &ext.database)
+        .await
+        .or_internal_server_error("Error loading TOTP credential")?
+        .is_some();
+    if enabled {
+        return Err(ApiError::conflict(
+            "Two-factor authentication is already enabled",
+        ));
+    }
+
+    let enrolment = start_enrolment(user, &ext.database)
+        .await
+        .inspect_err(|e| error!("Error starting TOTP enrolment: {e}"))
+        .or_internal_server_error("Failed to start two-factor enrolment")?;
+
+    Ok(TotpEnrolment {
+        otpauth_url: enrolment.otpauth_url,
+        secret: enrolment.secret,
+        qr_code: enrolment.qr_code,
+    })
+}
+
+/// Enables two-factor authentication, if `code` matches the secret from [`start_totp_enrolment`].
+/// Returns the recovery codes, which are only shown this once.
+#[post("/api/users/totp/enrolment/confirm", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn confirm_totp_enrolment(code: String) -> Result<Vec<String>, ApiError> {
+    use crate::server::auth::totp::confirm_enrolment;
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+
+    confirm_enrolment(user, &code, &ext.database).await
+}
+
+/// Replaces the recovery codes of the current user. Requires a current TOTP or recovery code.
+#[post("/api/users/totp/recovery-codes", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn regenerate_totp_recovery_codes(code: String) -> Result<Vec<String>, ApiError> {
+    use crate::server::auth::totp::regenerate_recovery_codes;
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+
+    regenerate_recovery_codes(user, &code, &ext.database).await
+}
+
+/// Disables two-factor authentication for the current user. Requires the password of the user.
+#[post("/api/users/totp/disable", ext: Extension<server::AppState>, auth: Extension<server::AuthenticationState>)]
+pub async fn disable_totp(password: String) -> Result<NoContent, ApiError> {
+    use crate::server::auth::{totp, verify_password};
+
+    let user = auth.user.as_ref().or_unauthorized("Not authenticated")?;
+    let password_hash = user
+        .password
+        .as_deref()
+        .or_bad_request("OIDC Users cannot disable two-factor authentication")?;
+    verify_password(&password, password_hash).or_forbidden("Incorrect password")?;
+
+    totp::disable(user.id, &ext.database)
+        .await
+        .inspect_err(|e| error!("Error disabling two-factor authentication: {e}"))
+        .or_internal_server_error("Failed to disable two-factor authentication")?;
+
+    Ok(NoContent)
+}
+
+/// Second step of a login for users with two-factor authentication.
+/// Accepts a TOTP code or one of the recovery codes and sets the session cookie.
+#[post("/api/users/login/totp", ext: Extension<server::AppState>, cookies: Extension<tower_cookies::Cookies>)]
+pub async fn complete_totp_login(challenge: String, code: String) -> Result<UserInfo, ApiError> {
+    use crate::server::audit::record_login;
+    use crate::server::auth::totp::{
+        find_confirmed_credential, find_login_challenge, record_failed_challenge,
+        verify_second_factor,
+    };
+    use crate::server::auth::{add_session_cookie, create_session};
+    use sea_orm::ModelTrait;
+    use std::time::Instant;
+
+    let (login_challenge, user) = find_login_challenge(&challenge, &ext.database).await?;
+
+    let limiter = &ext.login_rate_limiter;
+    if let Some(retry_after) = limiter.second_factor_retry_after(user.id, Instant::now()) {
+        let retry_after_seconds = retry_after.as_secs().max(1);
+        return Err(ApiError::rate_limited(
+            "Too many invalid codes",
+            retry_after_seconds,
+        ));
+    }
+
+    let credential = find_confirmed_credential(user.id, &ext.database)
+        .await
+        .or_internal_server_error("Error loading TOTP credential")?
+        .or_unauthorized("Two-factor authentication is not enabled")?;
+
+    let valid = verify_second_factor(&user, &credential, &code, &ext.database)
+        .await
+        .inspect_err(|e| error!("Error verifying second factor: {e}"))
+        .or_internal_server_error("Failed to verify code")?;
+    if !valid {
+        limiter.record_second_factor_failure(user.id, Instant::now());
+        record_failed_challenge(login_challenge, &ext.database)
+            .await
+            .or_internal_server_error("Error updating login challenge")?;
+        return Err(ApiError::unauthorized("Invalid code"));
+    }
+    limiter.record_second_factor_success(user.id);
+
+    login_challenge
+        .delete(&ext.database)
+        .await
+        .or_internal_server_error("Error deleting login challenge")?;
+    let (session_key, expires_at) = create_session(&user.id, &ext.database)
+        .await
+        .or_internal_server_error("Error creating session")?;
+    record_login(user.id, "password and TOTP", &ext.database).await;
+
+    add_session_cookie(&cookies, session_key, expires_at);
+    Ok(UserInfo::from_user_model(user))
+}
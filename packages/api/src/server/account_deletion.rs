@@ -0,0 +1,727 @@
+//! Deletion of user accounts.
+//!
+//! Deleting only the user row would cascade to the events of the user, removing them for everyone they are
+//! shared with, and could leave To-Do lists without an admin. [`plan_account_deletion`] decides what happens to
+//! everything shared with others, [`delete_account`] plans again within its transaction and carries the plan out.
+
+use crate::routes::live_updates::ChangeEvent;
+use crate::routes::users::UserInfo;
+use crate::routes::users::account_deletion::{
+    AccountDeletionPlan, EventDeletion, EventOutcome, GroupDeletion, GroupOutcome,
+    TodoListDeletion, TodoListOutcome,
+};
+use crate::server::AppState;
+use crate::server::audit::AuditEntry;
+use crate::server::live_updates::{Audience, Revocation};
+use entity::audit_log::{AuditAction, AuditTarget};
+use entity::invitation::{Column as InvitationColumn, InvitationStatus};
+use entity::is_in_group::Column as InGroupColumn;
+use entity::prelude::{
+    Event, FriendShare, Group, GroupShare, InGroup, Invitation, Todo, TodoList, TodoListInvitation,
+    User,
+};
+use entity::todo_list_invitation::{Column as InviteColumn, InvitationPermission};
+use sea_orm::prelude::Expr;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, QueryTrait,
+    TransactionTrait,
+};
+use std::collections::HashMap;
+
+/// Decides what happens to the events, To-Do lists and groups of the user when the account is deleted
+pub(crate) async fn plan_account_deletion<C: ConnectionTrait>(
+    user: &entity::user::Model,
+    db: &C,
+) -> Result<AccountDeletionPlan, DbErr> {
+    let mut plan = AccountDeletionPlan::default();
+
+    let events = user
+        .find_related(Event)
+        .order_by_asc(entity::event::Column::Id)
+        .all(db)
+        .await?;
+    for event in events {
+        let outcome = match event_successor(&event, user.id, db).await? {
+            Some(successor) => EventOutcome::TransferredTo(UserInfo::from_user_model(successor)),
+            None => EventOutcome::Deleted,
+        };
+        plan.events.push(EventDeletion {
+            event_id: event.id,
+            title: event.title,
+            outcome,
+        });
+    }
+
+    let memberships = TodoListInvitation::find()
+        .filter(InviteColumn::ReceivingUserId.eq(user.id))
+        .filter(InviteColumn::IsAccepted.eq(true))
+        .order_by_asc(InviteColumn::TodoListId)
+        .all(db)
+        .await?;
+    for membership in memberships {
+        let Some(todo_list) = TodoList::find_by_id(membership.todo_list_id)
+            .one(db)
+            .await?
+        else {
+            continue;
+        };
+        let outcome = match todo_list_successor(todo_list.id, user.id, db).await? {
+            Some((successor, permission)) => TodoListOutcome::Kept {
+                successor: UserInfo::from_user_model(successor),
+                promoted: membership.permission.can_admin() && !permission.can_admin(),
+            },
+            None => TodoListOutcome::Deleted,
+        };
+        plan.todo_lists.push(TodoListDeletion {
+            todo_list_id: todo_list.id,
+            title: todo_list.title,
+            outcome,
+        });
+    }
+
+    let groups = user
+        .find_related(Group)
+        .order_by_asc(entity::group::Column::Id)
+        .all(db)
+        .await?;
+    for group in groups {
+        let other_members = InGroup::find()
+            .filter(InGroupColumn::GroupId.eq(group.id))
+            .filter(InGroupColumn::UserId.ne(user.id))
+            .count(db)
+            .await?;
+        plan.groups.push(GroupDeletion {
+            group_id: group.id,
+            name: group.name,
+            outcome: if other_members == 0 {
+                GroupOutcome::Deleted
+            } else {
+                GroupOutcome::Left
+            },
+        });
+    }
+
+    Ok(plan)
+}
+
+/// The user an event is handed over to: whoever accepted an invitation first or, unless the event is private,
+/// the longest registered member of a group it is shared with
+async fn event_successor<C: ConnectionTrait>(
+    event: &entity::event::Model,
+    user_id: i32,
+    db: &C,
+) -> Result<Option<entity::user::Model>, DbErr> {
+    let invitee = User::find()
+        .inner_join(Invitation)
+        .filter(InvitationColumn::EventId.eq(event.id))
+        .filter(InvitationColumn::Status.eq(InvitationStatus::Accepted))
+        .filter(entity::user::Column::Id.ne(user_id))
+        .order_by_asc(InvitationColumn::Id)
+        .one(db)
+        .await?;
+    if invitee.is_some() || event.private {
+        return Ok(invitee);
+    }
+
+    let groups = GroupShare::find()
+        .inner_join(Group)
+        .select_only()
+        .column(entity::shared_group_event::Column::GroupId)
+        .filter(entity::shared_group_event::Column::EventId.eq(event.id))
+        .filter(entity::group::Column::DeletedAt.is_null())
+        .into_query();
+    let group_members = InGroup::find()
+        .select_only()
+        .column(InGroupColumn::UserId)
+        .filter(InGroupColumn::GroupId.in_subquery(groups))
+        .into_query();
+    User::find()
+        .filter(entity::user::Column::Id.in_subquery(group_members))
+        .filter(entity::user::Column::Id.ne(user_id))
+        .order_by_asc(entity::user::Column::Id)
+        .one(db)
+        .await
+}
+
+/// The member taking over the To-Do list: the one with the highest permission, the longest registered first
+async fn todo_list_successor<C: ConnectionTrait>(
+    todo_list_id: i32,
+    user_id: i32,
+    db: &C,
+) -> Result<Option<(entity::user::Model, InvitationPermission)>, DbErr> {
+    let successor = TodoListInvitation::find()
+        .filter(InviteColumn::TodoListId.eq(todo_list_id))
+        .filter(InviteColumn::ReceivingUserId.ne(user_id))
+        .filter(InviteColumn::IsAccepted.eq(true))
+        .all(db)
+        .await?
+        .into_iter()
+        .max_by_key(|member| {
+            (
+                member.permission.can_admin(),
+                member.permission.can_write(),
+                std::cmp::Reverse(member.receiving_user_id),
+            )
+        });
+    let Some(successor) = successor else {
+        return Ok(None);
+    };
+
+    let user = User::find_by_id(successor.receiving_user_id)
+        .one(db)
+        .await?;
+    Ok(user.map(|user| (user, successor.permission)))
+}
+
+/// Hands the events and To-Do lists over, deletes what nobody else uses and finally the user.
+/// The plan is made within the transaction, so successors cannot have left in the meantime
+///
+/// returns: The plan which has been carried out
+pub(crate) async fn execute_account_deletion(
+    user: &entity::user::Model,
+    db: &DatabaseConnection,
+) -> Result<AccountDeletionPlan, DbErr> {
+    let user = user.clone();
+    db.transaction::<_, AccountDeletionPlan, DbErr>(|txn| {
+        Box::pin(async move {
+            let user_id = user.id;
+            let plan = plan_account_deletion(&user, txn).await?;
+            for event in plan.events.clone() {
+                match event.outcome {
+                    EventOutcome::TransferredTo(successor) => {
+                        Event::update_many()
+                            .col_expr(entity::event::Column::OwnerId, Expr::value(successor.id))
+                            .filter(entity::event::Column::Id.eq(event.event_id))
+                            .exec(txn)
+                            .await?;
+                        // The new owner does not need to be invited anymore
+                        Invitation::delete_many()
+                            .filter(InvitationColumn::EventId.eq(event.event_id))
+                            .filter(InvitationColumn::RecievingUser.eq(successor.id))
+                            .exec(txn)
+                            .await?;
+                        FriendShare::delete_many()
+                            .filter(entity::shared_friend_event::Column::EventId.eq(event.event_id))
+                            .filter(entity::shared_friend_event::Column::UserId.eq(successor.id))
+                            .exec(txn)
+                            .await?;
+                    }
+                    EventOutcome::Deleted => {
+                        Invitation::delete_many()
+                            .filter(InvitationColumn::EventId.eq(event.event_id))
+                            .exec(txn)
+                            .await?;
+                        GroupShare::delete_many()
+                            .filter(entity::shared_group_event::Column::EventId.eq(event.event_id))
+                            .exec(txn)
+                            .await?;
+                        FriendShare::delete_many()
+                            .filter(entity::shared_friend_event::Column::EventId.eq(event.event_id))
+                            .exec(txn)
+                            .await?;
+                        Event::delete_by_id(event.event_id).exec(txn).await?;
+                    }
+                }
+            }
+
+            // Invitations to the events of others do not cascade
+            Invitation::delete_many()
+                .filter(InvitationColumn::RecievingUser.eq(user_id))
+                .exec(txn)
+                .await?;
+            FriendShare::delete_many()
+                .filter(entity::shared_friend_event::Column::UserId.eq(user_id))
+                .exec(txn)
+                .await?;
+
+            for todo_list in plan.todo_lists.clone() {
+                match todo_list.outcome {
+                    TodoListOutcome::Kept {
+                        successor,
+                        promoted,
+                    } => {
+                        if promoted {
+                            entity::todo_list_invitation::ActiveModel {
+                                todo_list_id: ActiveValue::Unchanged(todo_list.todo_list_id),
+                                receiving_user_id: ActiveValue::Unchanged(successor.id),
+                                permission: ActiveValue::Set(InvitationPermission::Admin),
+                                ..Default::default()
+                            }
+                            .update(txn)
+                            .await?;
+                        }
+                        Todo::update_many()
+                            .col_expr(entity::todo::Column::OwnerId, Expr::value(successor.id))
+                            .filter(entity::todo::Column::TodoListId.eq(todo_list.todo_list_id))
+                            .filter(entity::todo::Column::OwnerId.eq(user_id))
+                            .exec(txn)
+                            .await?;
+                        TodoList::update_many()
+                            .col_expr(
+                                entity::todo_list::Column::CreatedById,
+                                Expr::value(successor.id),
+                            )
+                            .filter(entity::todo_list::Column::Id.eq(todo_list.todo_list_id))
+                            .filter(entity::todo_list::Column::CreatedById.eq(user_id))
+                            .exec(txn)
+                            .await?;
+                    }
+                    TodoListOutcome::Deleted => {
+                        TodoList::delete_by_id(todo_list.todo_list_id)
+                            .exec(txn)
+                            .await?;
+                    }
+                }
+            }
+
+            for group in &plan.groups {
+                if group.outcome == GroupOutcome::Deleted {
+                    GroupShare::delete_many()
+                        .filter(entity::shared_group_event::Column::GroupId.eq(group.group_id))
+                        .exec(txn)
+                        .await?;
+                    Group::delete_by_id(group.group_id).exec(txn).await?;
+                }
+            }
+
+            User::delete_by_id(user_id).exec(txn).await?;
+            Ok(plan)
+        })
+    })
+    .await
+    .map_err(|e| match e {
+        sea_orm::TransactionError::Connection(e) | sea_orm::TransactionError::Transaction(e) => e,
+    })
+}
+
+/// Deletes the account of the user, records the handovers in the audit log and notifies everyone affected
+pub(crate) async fn delete_account(
+    user: &entity::user::Model,
+    state: &AppState,
+) -> Result<(), DbErr> {
+    let db = &state.database;
+
+    // Audiences have to be known before the events and To-Do lists are deleted. Whether they are deleted is only
+    // decided within the transaction, so the audiences of everything that might be deleted are resolved
+    let mut event_audiences = HashMap::new();
+    for event in user.find_related(Event).all(db).await? {
+        event_audiences.insert(event.id, Audience::Event(event.id).resolve(db).await);
+    }
+    let mut todo_list_audiences = HashMap::new();
+    for membership in TodoListInvitation::find()
+        .filter(InviteColumn::ReceivingUserId.eq(user.id))
+        .filter(InviteColumn::IsAccepted.eq(true))
+        .all(db)
+        .await?
+    {
+        let todo_list_id = membership.todo_list_id;
+        todo_list_audiences.insert(
+            todo_list_id,
+            Audience::TodoList(todo_list_id).resolve(db).await,
+        );
+    }
+    let joined_events: Vec<i32> = Invitation::find()
+        .select_only()
+        .column(InvitationColumn::EventId)
+        .filter(InvitationColumn::RecievingUser.eq(user.id))
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let plan = execute_account_deletion(user, db).await?;
+    // The sessions were deleted along with the user
+    state.live_updates.revoke(Revocation::User(user.id));
+
+    let mut deleted_audiences = Vec::new();
+    for event in &plan.events {
+        if event.outcome == EventOutcome::Deleted {
+            deleted_audiences.push((
+                event_audiences.remove(&event.event_id).unwrap_or_default(),
+                ChangeEvent::EventDeleted {
+                    event_id: event.event_id,
+                },
+            ));
+        }
+    }
+    for todo_list in &plan.todo_lists {
+        if todo_list.outcome == TodoListOutcome::Deleted {
+            deleted_audiences.push((
+                todo_list_audiences
+                    .remove(&todo_list.todo_list_id)
+                    .unwrap_or_default(),
+                ChangeEvent::TodoListDeleted {
+                    todo_list_id: todo_list.todo_list_id,
+                },
+            ));
+        }
+    }
+
+    AuditEntry::new(
+        Some(user.id),
+        AuditAction::AccountDeleted,
+        AuditTarget::User,
+        user.id,
+    )
+    .record(db)
+    .await;
+    for event in &plan.events {
+        let entry = match &event.outcome {
+            EventOutcome::TransferredTo(successor) => AuditEntry::new(
+                Some(user.id),
+                AuditAction::EventTransferred,
+                AuditTarget::Event,
+                event.event_id,
+            )
+            .affected_user(successor.id),
+            EventOutcome::Deleted => AuditEntry::new(
+                Some(user.id),
+                AuditAction::EventDeleted,
+                AuditTarget::Event,
+                event.event_id,
+            ),
+        };
+        entry.details(event.title.clone()).record(db).await;
+    }
+    for todo_list in &plan.todo_lists {
+        let entry = match &todo_list.outcome {
+            TodoListOutcome::Kept {
+                successor,
+                promoted: true,
+            } => AuditEntry::new(
+                Some(user.id),
+                AuditAction::TodoListPermissionChanged,
+                AuditTarget::TodoList,
+                todo_list.todo_list_id,
+            )
+            .affected_user(successor.id),
+            TodoListOutcome::Kept { .. } => AuditEntry::new(
+                Some(user.id),
+                AuditAction::TodoListMemberRemoved,
+                AuditTarget::TodoList,
+                todo_list.todo_list_id,
+            )
+            .affected_user(user.id),
+            TodoListOutcome::Deleted => AuditEntry::new(
+                Some(user.id),
+                AuditAction::TodoListDeleted,
+                AuditTarget::TodoList,
+                todo_list.todo_list_id,
+            ),
+        };
+        entry.record(db).await;
+    }
+    for group in &plan.groups {
+        let action = match group.outcome {
+            GroupOutcome::Left => AuditAction::GroupMemberRemoved,
+            GroupOutcome::Deleted => AuditAction::GroupDeleted,
+        };
+        AuditEntry::new(Some(user.id), action, AuditTarget::Group, group.group_id)
+            .affected_user(user.id)
+            .record(db)
+            .await;
+    }
+
+    for (audience, change) in deleted_audiences {
+        state
+            .live_updates
+            .publish(Audience::Users(audience), change, db)
+            .await;
+    }
+    for event in &plan.events {
+        if matches!(event.outcome, EventOutcome::TransferredTo(_)) {
+            state
+                .live_updates
+                .publish(
+                    Audience::Event(event.event_id),
+                    ChangeEvent::EventChanged {
+                        event_id: event.event_id,
+                    },
+                    db,
+                )
+                .await;
+        }
+    }
+    for event_id in joined_events {
+        state
+            .live_updates
+            .publish(
+                Audience::Event(event_id),
+                ChangeEvent::EventChanged { event_id },
+                db,
+            )
+            .await;
+    }
+    for todo_list in &plan.todo_lists {
+        if matches!(todo_list.outcome, TodoListOutcome::Kept { .. }) {
+            state
+                .live_updates
+                .publish(
+                    Audience::TodoList(todo_list.todo_list_id),
+                    ChangeEvent::TodoListChanged {
+                        todo_list_id: todo_list.todo_list_id,
+                    },
+                    db,
+                )
+                .await;
+        }
+    }
+    for group in &plan.groups {
+        if group.outcome == GroupOutcome::Left {
+            state
+                .live_updates
+                .publish(
+                    Audience::Group(group.group_id),
+                    ChangeEvent::GroupChanged {
+                        group_id: group.group_id,
+                    },
+                    db,
+                )
+                .await;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::test_support::{
+        create_test_event, create_test_group, create_test_todo_list,
+        create_test_todo_list_invitation, create_test_user, setup,
+    };
+    use sea_orm::Set;
+
+    // region: Factories
+    async fn invite_to_event(
+        database: &DatabaseConnection,
+        event_id: i32,
+        user_id: i32,
+        status: InvitationStatus,
+    ) {
+        entity::invitation::ActiveModel {
+            status: Set(status),
+            recieving_user: Set(user_id),
+            event_id: Set(event_id),
+            ..Default::default()
+        }
+        .insert(database)
+        .await
+        .unwrap();
+    }
+
+    async fn create_todo_list(
+        database: &DatabaseConnection,
+        id: i32,
+        created_by_id: i32,
+        members: &[(i32, InvitationPermission)],
+    ) -> entity::todo_list::Model {
+        let todo_list = create_test_todo_list(database, id, created_by_id).await;
+        for (member_id, permission) in members {
+            create_test_todo_list_invitation(
+                database,
+                todo_list.id,
+                *member_id,
+                created_by_id,
+                true,
+                *permission,
+            )
+            .await;
+        }
+        todo_list
+    }
+    // endregion
+
+    #[tokio::test]
+    async fn test_plan_event_successors() {
+        let database = setup().await;
+        let user = create_test_user(&database, 1).await;
+        create_test_user(&database, 2).await;
+        create_test_user(&database, 3).await;
+        create_test_user(&database, 4).await;
+
+        // Accepted invitations take precedence over pending ones and groups
+        let invited = create_test_event(&database, 1, false).await;
+        invite_to_event(&database, invited.id, 4, InvitationStatus::Sent).await;
+        invite_to_event(&database, invited.id, 2, InvitationStatus::Accepted).await;
+        let group = create_test_group(&database, &[1, 3]).await;
+        let group_event = create_test_event(&database, 1, false).await;
+        let private_group_event = create_test_event(&database, 1, true).await;
+        for event_id in [invited.id, group_event.id, private_group_event.id] {
+            entity::shared_group_event::ActiveModel {
+                event_id: Set(event_id),
+                group_id: Set(group.id),
+            }
+            .insert(&database)
+            .await
+            .unwrap();
+        }
+        let unshared = create_test_event(&database, 1, false).await;
+
+        let plan = plan_account_deletion(&user, &database).await.unwrap();
+        let outcomes: Vec<(i32, Option<i32>)> = plan
+            .events
+            .iter()
+            .map(|event| match &event.outcome {
+                EventOutcome::TransferredTo(successor) => (event.event_id, Some(successor.id)),
+                EventOutcome::Deleted => (event.event_id, None),
+            })
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (invited.id, Some(2)),
+                (group_event.id, Some(3)),
+                // Private events are not visible to the group
+                (private_group_event.id, None),
+                (unshared.id, None),
+            ]
+        );
+        assert_eq!(plan.groups[0].outcome, GroupOutcome::Left);
+    }
+
+    #[tokio::test]
+    async fn test_delete_account() {
+        let database = setup().await;
+        let user = create_test_user(&database, 1).await;
+        create_test_user(&database, 2).await;
+        create_test_user(&database, 3).await;
+
+        let event = create_test_event(&database, 1, false).await;
+        invite_to_event(&database, event.id, 2, InvitationStatus::Accepted).await;
+        let others_event = create_test_event(&database, 3, false).await;
+        invite_to_event(&database, others_event.id, 1, InvitationStatus::Accepted).await;
+
+        let shared_list = create_todo_list(
+            &database,
+            1,
+            1,
+            &[
+                (2, InvitationPermission::Read),
+                (3, InvitationPermission::Write),
+            ],
+        )
+        .await;
+        let task = entity::todo::ActiveModel {
+            title: Set("Milk".to_string()),
+            completed: Set(false),
+            todo_list_id: Set(shared_list.id),
+            owner_id: Set(1),
+            ..Default::default()
+        }
+        .insert(&database)
+        .await
+        .unwrap();
+        let own_list = create_test_todo_list(&database, 2, 1).await;
+
+        let shared_group = create_test_group(&database, &[1, 2]).await;
+        let own_group = create_test_group(&database, &[1]).await;
+
+        let plan = plan_account_deletion(&user, &database).await.unwrap();
+        assert_eq!(
+            plan.todo_lists
+                .iter()
+                .map(|todo_list| todo_list.outcome.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                TodoListOutcome::Kept {
+                    successor: UserInfo::from_user_model(
+                        User::find_by_id(3).one(&database).await.unwrap().unwrap()
+                    ),
+                    promoted: true,
+                },
+                TodoListOutcome::Deleted,
+            ]
+        );
+        assert_eq!(
+            plan.groups
+                .iter()
+                .map(|group| (group.group_id, group.outcome))
+                .collect::<Vec<_>>(),
+            vec![
+                (shared_group.id, GroupOutcome::Left),
+                (own_group.id, GroupOutcome::Deleted),
+            ]
+        );
+
+        // Accepting an invitation after the preview makes the event stay instead of being deleted
+        let unshared = create_test_event(&database, 1, false).await;
+        let preview = plan_account_deletion(&user, &database).await.unwrap();
+        assert_eq!(preview.events[1].outcome, EventOutcome::Deleted);
+        invite_to_event(&database, unshared.id, 2, InvitationStatus::Accepted).await;
+
+        let executed = execute_account_deletion(&user, &database).await.unwrap();
+        assert_eq!(executed.todo_lists, plan.todo_lists);
+        assert_eq!(executed.groups, plan.groups);
+        assert!(matches!(
+            &executed.events[1].outcome,
+            EventOutcome::TransferredTo(successor) if successor.id == 2
+        ));
+
+        assert!(User::find_by_id(1).one(&database).await.unwrap().is_none());
+
+        let event = Event::find_by_id(event.id)
+            .one(&database)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(event.owner_id, 2);
+        assert_eq!(
+            Invitation::find()
+                .filter(InvitationColumn::EventId.eq(event.id))
+                .count(&database)
+                .await
+                .unwrap(),
+            0
+        );
+        assert!(
+            Event::find_by_id(others_event.id)
+                .one(&database)
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        let successor = TodoListInvitation::find_by_id((shared_list.id, 3))
+            .one(&database)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(successor.permission, InvitationPermission::Admin);
+        let shared_list = TodoList::find_by_id(shared_list.id)
+            .one(&database)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(shared_list.created_by_id, Some(3));
+        let task = Todo::find_by_id(task.id)
+            .one(&database)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(task.owner_id, 3);
+        assert!(
+            TodoList::find_by_id(own_list.id)
+                .one(&database)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        assert!(
+            Group::find_by_id(shared_group.id)
+                .one(&database)
+                .await
+                .unwrap()
+                .is_some()
+        );
+        assert!(
+            Group::find_by_id(own_group.id)
+                .one(&database)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
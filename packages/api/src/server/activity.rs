@@ -0,0 +1,191 @@
+use crate::routes::activity::{ACTIVITY_PAGE_SIZE, ActivityEntry, ActivityPage};
+use crate::routes::users::UserInfo;
+use dioxus::prelude::*;
+use entity::activity::{ActivityKind, Column as ActivityColumn};
+use entity::prelude::{Activity, Group, User};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, ModelTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+use std::collections::HashMap;
+use time::OffsetDateTime;
+
+/// Feed an activity is shown in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Feed {
+    Group(i32),
+    TodoList(i32),
+}
+
+/// Appends an activity to the feed. Failures are logged instead of failing the already completed action
+pub(crate) async fn record_activity<C: ConnectionTrait>(
+    actor_id: i32,
+    feed: Feed,
+    kind: ActivityKind,
+    subject: impl Into<String>,
+    database: &C,
+) {
+    let (group_id, todo_list_id) = match feed {
+        Feed::Group(group_id) => (Some(group_id), None),
+        Feed::TodoList(todo_list_id) => (None, Some(todo_list_id)),
+    };
+    let activity = entity::activity::ActiveModel {
+        kind: Set(kind),
+        subject: Set(subject.into()),
+        created_at: Set(OffsetDateTime::now_utc()),
+        actor_id: Set(Some(actor_id)),
+        group_id: Set(group_id),
+        todo_list_id: Set(todo_list_id),
+        ..Default::default()
+    };
+    if let Err(e) = activity.insert(database).await {
+        error!("Error recording {kind:?} in the activity feed of {feed:?}: {e}");
+    }
+}
+
+/// Records an activity about a group member, using the member's name as subject
+pub(crate) async fn record_member_activity(
+    actor_id: i32,
+    group_id: i32,
+    kind: ActivityKind,
+    member_id: i32,
+    database: &DatabaseConnection,
+) {
+    let member_name = match User::find_by_id(member_id).one(database).await {
+        Ok(Some(member)) => format!("{} {}", member.first_name, member.last_name),
+        Ok(None) => return,
+        Err(e) => {
+            error!("Error loading member for the activity feed: {e}");
+            return;
+        }
+    };
+    record_activity(actor_id, Feed::Group(group_id), kind, member_name, database).await;
+}
+
+/// Records the removal of an event in the feeds of all groups it is shared with.
+/// Has to be called before the event is removed from the groups
+pub(crate) async fn record_event_removed(
+    actor_id: i32,
+    event: &entity::event::Model,
+    database: &DatabaseConnection,
+) {
+    let groups = match event.find_related(Group).all(database).await {
+        Ok(groups) => groups,
+        Err(e) => {
+            error!("Error loading groups of event for the activity feed: {e}");
+            return;
+        }
+    };
+    for group in groups {
+        record_activity(
+            actor_id,
+            Feed::Group(group.id),
+            ActivityKind::EventRemoved,
+            event.title.clone(),
+            database,
+        )
+        .await;
+    }
+}
+
+/// Loads a page of the feed, newest first. Pages start at 1
+pub(crate) async fn load_activity_page(
+    feed: Feed,
+    page: u64,
+    database: &DatabaseConnection,
+) -> Result<ActivityPage, ServerFnError> {
+    (page >= 1).or_bad_request("Page must be at least 1")?;
+
+    let query = match feed {
+        Feed::Group(group_id) => Activity::find().filter(ActivityColumn::GroupId.eq(group_id)),
+        Feed::TodoList(todo_list_id) => {
+            Activity::find().filter(ActivityColumn::TodoListId.eq(todo_list_id))
+        }
+    };
+    let paginator = query
+        .order_by_desc(ActivityColumn::Id)
+        .paginate(database, ACTIVITY_PAGE_SIZE);
+    let total_pages = paginator
+        .num_pages()
+        .await
+        .inspect_err(|e| error!("Error counting activities: {e}"))
+        .or_internal_server_error("Error loading activities")?;
+    let activities = paginator
+        .fetch_page(page - 1)
+        .await
+        .inspect_err(|e| error!("Error loading activities: {e}"))
+        .or_internal_server_error("Error loading activities")?;
+
+    let actor_ids: Vec<i32> = activities
+        .iter()
+        .filter_map(|activity| activity.actor_id)
+        .collect();
+    let actors: HashMap<i32, UserInfo> = User::find()
+        .filter(entity::user::Column::Id.is_in(actor_ids))
+        .all(database)
+        .await
+        .or_internal_server_error("Error loading users")?
+        .into_iter()
+        .map(|user| (user.id, UserInfo::from_user_model(user)))
+        .collect();
+
+    Ok(ActivityPage {
+        items: activities
+            .into_iter()
+            .map(|activity| ActivityEntry {
+                id: activity.id,
+                actor: activity.actor_id.and_then(|id| actors.get(&id).cloned()),
+                kind: activity.kind,
+                subject: activity.subject,
+                created_at: activity.created_at,
+            })
+            .collect(),
+        page,
+        total_pages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::test_support::{create_test_group, create_test_user, setup};
+
+    #[tokio::test]
+    async fn test_activity_feeds_are_paginated_newest_first() {
+        let database = setup().await;
+        let user = create_test_user(&database, 1).await;
+        let group = create_test_group(&database, &[user.id]).await;
+
+        for i in 0..ACTIVITY_PAGE_SIZE + 1 {
+            record_activity(
+                user.id,
+                Feed::Group(group.id),
+                ActivityKind::EventAdded,
+                format!("Event {i}"),
+                &database,
+            )
+            .await;
+        }
+
+        let first_page = load_activity_page(Feed::Group(group.id), 1, &database)
+            .await
+            .unwrap();
+        assert_eq!(first_page.total_pages, 2);
+        assert_eq!(first_page.items.len() as u64, ACTIVITY_PAGE_SIZE);
+        assert_eq!(
+            first_page.items[0].message(),
+            format!("firstname1 lastname1 added event 'Event {ACTIVITY_PAGE_SIZE}'")
+        );
+
+        let last_page = load_activity_page(Feed::Group(group.id), 2, &database)
+            .await
+            .unwrap();
+        assert_eq!(last_page.items.len(), 1);
+        assert_eq!(last_page.items[0].subject, "Event 0");
+
+        let other_feed = load_activity_page(Feed::TodoList(group.id), 1, &database)
+            .await
+            .unwrap();
+        assert!(other_feed.items.is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+//! Versioned REST API for third-party clients, nested under `/api/v1`.
+//!
+//! Unlike the server functions in [`crate::routes`], which are shaped after the needs of the frontend, the request
+//! and response bodies of this API only change in backwards compatible ways. Collections are paginated, errors
+//! always use the [`ApiV1Error`] body and the whole surface is described by the OpenAPI document served at
+//! `/api/v1/openapi.json`.
+
+mod error;
+mod events;
+mod groups;
+mod invitations;
+mod pagination;
+mod todo_lists;
+
+pub use error::ApiV1Error;
+pub use pagination::{Page, PageParams};
+
+use crate::server::AuthenticationState;
+use crate::server::versioning::VersionedUpdate;
+use dioxus::prelude::*;
+use dioxus::server::axum::extract::{FromRequest, Request};
+use dioxus::server::axum::http::StatusCode;
+use dioxus::server::axum::routing::get;
+use dioxus::server::axum::{Extension, Json, Router};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "RoomMates API",
+        version = "1.0.0",
+        description = "Manage events, groups and To-Do lists of a RoomMates instance"
+    ),
+    paths(
+        me,
+        events::list_events,
+        events::create_event,
+        events::retrieve_event,
+        events::update_event,
+        events::delete_event,
+        events::list_event_groups,
+        events::add_event_to_group,
+        events::remove_event_from_group,
+        events::list_event_members,
+        invitations::list_invitations,
+        invitations::send_invitation,
+        invitations::accept_invitation,
+        invitations::decline_invitation,
+        groups::list_groups,
+        groups::create_group,
+        groups::retrieve_group,
+        groups::rename_group,
+        groups::delete_group,
+        groups::list_group_members,
+        groups::add_group_member,
+        groups::remove_group_member,
+        groups::list_group_events,
+        todo_lists::list_todo_lists,
+        todo_lists::create_todo_list,
+        todo_lists::retrieve_todo_list,
+        todo_lists::update_todo_list,
+        todo_lists::delete_todo_list,
+        todo_lists::list_todo_list_members,
+        todo_lists::remove_todo_list_member,
+        todo_lists::list_todo_list_todos,
+        todo_lists::create_todo,
+        todo_lists::list_todos,
+        todo_lists::retrieve_todo,
+        todo_lists::update_todo,
+        todo_lists::delete_todo,
+    ),
+    modifiers(&SecurityAddon),
+    security(("api_token" = [])),
+    tags(
+        (name = "users", description = "The authenticated user"),
+        (name = "events", description = "Events and their invitations"),
+        (name = "groups", description = "Groups and their members"),
+        (name = "todos", description = "To-Do lists and their tasks"),
+    )
+)]
+struct ApiDoc;
+
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_token",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some(
+                        "Personal API token created on the profile page, e.g. `rmt_...`",
+                    ))
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// Creates the router of the versioned API. It has to be nested under `/api/v1`
+pub fn router() -> Router {
+    Router::new()
+        .route("/openapi.json", get(openapi_document))
+        .route("/me", get(me))
+        .merge(events::router())
+        .merge(invitations::router())
+        .merge(groups::router())
+        .merge(todo_lists::router())
+}
+
+async fn openapi_document() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UserV1 {
+    pub id: i32,
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+}
+
+impl From<entity::user::Model> for UserV1 {
+    fn from(user: entity::user::Model) -> Self {
+        UserV1 {
+            id: user.id,
+            email: user.email,
+            first_name: user.first_name,
+            last_name: user.last_name,
+        }
+    }
+}
+
+/// JSON request body. Bodies that cannot be parsed, e.g. because a required field is missing, are
+/// rejected with `400 Bad Request`
+struct JsonBody<T>(T);
+
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiV1Error;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(body) = Json::<T>::from_request(request, state)
+            .await
+            .map_err(|rejection| ApiV1Error::new(StatusCode::BAD_REQUEST, rejection.body_text()))?;
+        Ok(JsonBody(body))
+    }
+}
+
+/// Returns the authenticated user or rejects the request with `401 Unauthorized`
+fn current_user(auth: &AuthenticationState) -> Result<&entity::user::Model, ApiV1Error> {
+    Ok(auth.user.as_ref().or_unauthorized("Not authenticated")?)
+}
+
+/// Returns the saved row, or rejects the request with `409 Conflict` containing the current row as `V`
+fn saved_or_edit_conflict<M, V: From<M> + Serialize>(
+    update: VersionedUpdate<M>,
+    message: &str,
+) -> Result<M, ApiV1Error> {
+    match update {
+        VersionedUpdate::Saved(model) => Ok(model),
+        VersionedUpdate::Stale(current) => {
+            Err(ApiV1Error::edit_conflict(message, &V::from(current)))
+        }
+    }
+}
+
+/// Returns the authenticated user
+#[utoipa::path(
+    get,
+    path = "/api/v1/me",
+    tag = "users",
+    responses(
+        (status = 200, body = UserV1),
+        (status = 401, body = ApiV1Error),
+    )
+)]
+async fn me(Extension(auth): Extension<AuthenticationState>) -> Result<Json<UserV1>, ApiV1Error> {
+    Ok(Json(current_user(&auth)?.clone().into()))
+}
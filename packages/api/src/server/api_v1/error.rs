@@ -0,0 +1,96 @@
+use crate::error::ApiError;
+use dioxus::fullstack::{AsStatusCode, HttpError};
+use dioxus::prelude::*;
+use dioxus::server::axum::Json;
+use dioxus::server::axum::http::StatusCode;
+use dioxus::server::axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+use utoipa::ToSchema;
+
+/// Body of every failed request
+#[derive(Serialize, ToSchema, Debug)]
+#[schema(example = json!({"status": 404, "message": "Event not found"}))]
+pub struct ApiV1Error {
+    /// HTTP status code of the response
+    pub status: u16,
+    /// Human readable description of the error
+    pub message: String,
+    /// Current state of the object if the update was based on an outdated `version` (409)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub current: Option<serde_json::Value>,
+}
+
+impl ApiV1Error {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        ApiV1Error {
+            status: status.as_u16(),
+            message: message.into(),
+            current: None,
+        }
+    }
+
+    /// Creates a 409 error for an update based on an outdated version of `current`
+    pub fn edit_conflict(message: impl Into<String>, current: &impl Serialize) -> Self {
+        ApiV1Error {
+            current: serde_json::to_value(current).ok(),
+            ..ApiV1Error::new(StatusCode::CONFLICT, message)
+        }
+    }
+}
+
+impl Display for ApiV1Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.status, self.message)
+    }
+}
+
+impl IntoResponse for ApiV1Error {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        (status, Json(self)).into_response()
+    }
+}
+
+impl From<HttpError> for ApiV1Error {
+    fn from(error: HttpError) -> Self {
+        let message = error.message.unwrap_or_else(|| {
+            error
+                .status
+                .canonical_reason()
+                .unwrap_or("Unknown error")
+                .to_string()
+        });
+        ApiV1Error::new(error.status, message)
+    }
+}
+
+impl From<ServerFnError> for ApiV1Error {
+    fn from(error: ServerFnError) -> Self {
+        match error {
+            ServerFnError::ServerError { message, code, .. } => ApiV1Error {
+                status: code,
+                message,
+                current: None,
+            },
+            error => {
+                error!("Unexpected server function error: {error}");
+                ApiV1Error::new(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")
+            }
+        }
+    }
+}
+
+impl From<ApiError> for ApiV1Error {
+    fn from(error: ApiError) -> Self {
+        let status = error.as_status_code();
+        match error {
+            ApiError::EditConflict { message, current } => ApiV1Error {
+                current: Some(current),
+                ..ApiV1Error::new(status, message)
+            },
+            error => ApiV1Error::new(status, error.message()),
+        }
+    }
+}
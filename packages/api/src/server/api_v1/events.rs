@@ -0,0 +1,391 @@
+use super::groups::GroupV1;
+use super::pagination::{Page, PageParams, paginate};
+use super::{ApiV1Error, JsonBody, UserV1, current_user, saved_or_edit_conflict};
+use crate::server::events::{self, find_visible_event, visible_events_query};
+use crate::server::{AppState, AuthenticationState};
+use dioxus::prelude::*;
+use dioxus::server::axum::extract::{Path, Query};
+use dioxus::server::axum::http::StatusCode;
+use dioxus::server::axum::routing::{get, put};
+use dioxus::server::axum::{Extension, Json, Router};
+use entity::event::Column as EventColumn;
+use entity::event::{PartialEventModel, Weekday};
+use entity::prelude::{Group, User};
+use sea_orm::{ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+use time::{Date, Time};
+use utoipa::{IntoParams, ToSchema};
+
+pub(super) fn router() -> Router {
+    Router::new()
+        .route("/events", get(list_events).post(create_event))
+        .route(
+            "/events/{event_id}",
+            get(retrieve_event).put(update_event).delete(delete_event),
+        )
+        .route("/events/{event_id}/groups", get(list_event_groups))
+        .route(
+            "/events/{event_id}/groups/{group_id}",
+            put(add_event_to_group).delete(remove_event_from_group),
+        )
+        .route("/events/{event_id}/members", get(list_event_members))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EventV1 {
+    pub id: i32,
+    pub title: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    /// Private events are hidden from the groups they are shared with
+    pub private: bool,
+    /// Recurring events take place every week on `weekday`
+    pub recurring: bool,
+    pub date: Date,
+    #[schema(value_type = String, example = "18:30:00.0")]
+    pub start_time: Time,
+    #[schema(value_type = String, example = "20:00:00.0")]
+    pub end_time: Time,
+    #[schema(value_type = String, example = "Monday")]
+    pub weekday: Weekday,
+    pub owner_id: i32,
+    /// Incremented on every change, see `version` of [`EventUpdate`]
+    pub version: i32,
+}
+
+impl From<entity::event::Model> for EventV1 {
+    fn from(event: entity::event::Model) -> Self {
+        EventV1 {
+            id: event.id,
+            title: event.title,
+            description: event.description,
+            location: event.location,
+            private: event.private,
+            recurring: event.reoccurring,
+            date: event.date,
+            start_time: event.start_time,
+            end_time: event.end_time,
+            weekday: event.weekday,
+            owner_id: event.owner_id,
+            version: event.version,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct EventInput {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub location: Option<String>,
+    #[serde(default)]
+    pub private: bool,
+    #[serde(default)]
+    pub recurring: bool,
+    pub date: Date,
+    #[schema(value_type = String, example = "18:30:00.0")]
+    pub start_time: Time,
+    #[schema(value_type = String, example = "20:00:00.0")]
+    pub end_time: Time,
+    #[schema(value_type = String, example = "Monday")]
+    pub weekday: Weekday,
+}
+
+impl EventInput {
+    fn validate(&self) -> Result<(), ApiV1Error> {
+        (!self.title.trim().is_empty()).or_bad_request("Title must not be empty")?;
+        (self.start_time <= self.end_time)
+            .or_bad_request("Events must not end before they start")?;
+        Ok(())
+    }
+}
+
+impl From<EventInput> for PartialEventModel {
+    fn from(input: EventInput) -> Self {
+        PartialEventModel {
+            title: input.title,
+            reoccurring: input.recurring,
+            private: input.private,
+            description: input.description,
+            location: input.location,
+            date: input.date,
+            start_time: input.start_time,
+            end_time: input.end_time,
+            weekday: input.weekday,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct EventUpdate {
+    #[serde(flatten)]
+    pub event: EventInput,
+    /// Version of the event the update is based on. If the event was changed since, the update
+    /// is rejected with `409 Conflict`
+    pub version: i32,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateEventInput {
+    #[serde(flatten)]
+    pub event: EventInput,
+    /// Group to share the new event with. The user has to be a member of the group
+    #[serde(default)]
+    pub group_id: Option<i32>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventFilter {
+    /// Only return events on or after this date
+    pub min_date: Option<Date>,
+    /// Only return events on or before this date
+    pub max_date: Option<Date>,
+}
+
+/// Lists all events of the user, including events shared with the user directly or via a group
+#[utoipa::path(
+    get,
+    path = "/api/v1/events",
+    tag = "events",
+    params(EventFilter, PageParams),
+    responses(
+        (status = 200, body = Page<EventV1>),
+        (status = 400, body = ApiV1Error),
+        (status = 401, body = ApiV1Error),
+    )
+)]
+pub(super) async fn list_events(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Query(filter): Query<EventFilter>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page<EventV1>>, ApiV1Error> {
+    let user = current_user(&auth)?;
+
+    let query = visible_events_query(user, filter.min_date, filter.max_date)
+        .order_by_asc(EventColumn::Date)
+        .order_by_asc(EventColumn::StartTime)
+        .order_by_asc(EventColumn::Id);
+    let events = paginate(query, &page, &state.database).await?;
+
+    Ok(Json(events.map(EventV1::from)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/events",
+    tag = "events",
+    request_body = CreateEventInput,
+    responses(
+        (status = 201, body = EventV1),
+        (status = 400, body = ApiV1Error),
+        (status = 401, body = ApiV1Error),
+        (status = 403, body = ApiV1Error),
+    )
+)]
+pub(super) async fn create_event(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    JsonBody(input): JsonBody<CreateEventInput>,
+) -> Result<(StatusCode, Json<EventV1>), ApiV1Error> {
+    let user_id = current_user(&auth)?.id;
+    input.event.validate()?;
+
+    let event = events::create_event(&state, user_id, input.event.into(), input.group_id).await?;
+
+    Ok((StatusCode::CREATED, Json(event.into())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/events/{event_id}",
+    tag = "events",
+    params(("event_id" = i32, Path)),
+    responses(
+        (status = 200, body = EventV1),
+        (status = 401, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn retrieve_event(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(event_id): Path<i32>,
+) -> Result<Json<EventV1>, ApiV1Error> {
+    let user = current_user(&auth)?;
+    let event = find_visible_event(user, event_id, &state.database).await?;
+    Ok(Json(event.into()))
+}
+
+/// Replaces all fields of an event. Only the owner may edit an event
+#[utoipa::path(
+    put,
+    path = "/api/v1/events/{event_id}",
+    tag = "events",
+    params(("event_id" = i32, Path)),
+    request_body = EventUpdate,
+    responses(
+        (status = 200, body = EventV1),
+        (status = 400, body = ApiV1Error),
+        (status = 401, body = ApiV1Error),
+        (status = 403, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+        (status = 409, body = ApiV1Error, description = "The event was changed since `version`. `current` contains the event"),
+    )
+)]
+pub(super) async fn update_event(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(event_id): Path<i32>,
+    JsonBody(input): JsonBody<EventUpdate>,
+) -> Result<Json<EventV1>, ApiV1Error> {
+    let user = current_user(&auth)?;
+    input.event.validate()?;
+
+    let event =
+        events::update_event(&state, user, event_id, input.version, input.event.into()).await?;
+    let event = saved_or_edit_conflict::<_, EventV1>(
+        event,
+        "The event was changed by someone else in the meantime",
+    )?;
+
+    Ok(Json(event.into()))
+}
+
+/// Deletes an event together with its invitations and shares. Only the owner may delete an event
+#[utoipa::path(
+    delete,
+    path = "/api/v1/events/{event_id}",
+    tag = "events",
+    params(("event_id" = i32, Path)),
+    responses(
+        (status = 204),
+        (status = 401, body = ApiV1Error),
+        (status = 403, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn delete_event(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(event_id): Path<i32>,
+) -> Result<StatusCode, ApiV1Error> {
+    let user = current_user(&auth)?;
+    events::delete_event(&state, user, event_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/events/{event_id}/groups",
+    tag = "events",
+    params(("event_id" = i32, Path)),
+    responses(
+        (status = 200, body = Vec<GroupV1>),
+        (status = 401, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn list_event_groups(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(event_id): Path<i32>,
+) -> Result<Json<Vec<GroupV1>>, ApiV1Error> {
+    let user = current_user(&auth)?;
+    let event = find_visible_event(user, event_id, &state.database).await?;
+
+    let groups = event
+        .find_related(Group)
+        .filter(entity::group::Column::DeletedAt.is_null())
+        .all(&state.database)
+        .await
+        .or_internal_server_error("Error loading groups")?;
+
+    Ok(Json(groups.into_iter().map(GroupV1::from).collect()))
+}
+
+/// Shares an event with a group. Sharing an event that is already part of the group has no effect
+#[utoipa::path(
+    put,
+    path = "/api/v1/events/{event_id}/groups/{group_id}",
+    tag = "events",
+    params(("event_id" = i32, Path), ("group_id" = i32, Path)),
+    responses(
+        (status = 204),
+        (status = 401, body = ApiV1Error),
+        (status = 403, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn add_event_to_group(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path((event_id, group_id)): Path<(i32, i32)>,
+) -> Result<StatusCode, ApiV1Error> {
+    let user = current_user(&auth)?;
+    events::add_event_to_group(&state, user, event_id, group_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/events/{event_id}/groups/{group_id}",
+    tag = "events",
+    params(("event_id" = i32, Path), ("group_id" = i32, Path)),
+    responses(
+        (status = 204),
+        (status = 401, body = ApiV1Error),
+        (status = 403, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn remove_event_from_group(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path((event_id, group_id)): Path<(i32, i32)>,
+) -> Result<StatusCode, ApiV1Error> {
+    let user = current_user(&auth)?;
+    events::remove_event_from_group(&state, user.id, event_id, group_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Lists the owner and all users who accepted an invitation to the event
+#[utoipa::path(
+    get,
+    path = "/api/v1/events/{event_id}/members",
+    tag = "events",
+    params(("event_id" = i32, Path)),
+    responses(
+        (status = 200, body = Vec<UserV1>),
+        (status = 401, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn list_event_members(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(event_id): Path<i32>,
+) -> Result<Json<Vec<UserV1>>, ApiV1Error> {
+    use entity::links::EventUserMembers;
+
+    let user = current_user(&auth)?;
+    let event = find_visible_event(user, event_id, &state.database).await?;
+
+    let owner = User::find_by_id(event.owner_id)
+        .one(&state.database)
+        .await
+        .or_internal_server_error("Error loading members")?
+        .or_not_found("Owner not found")?;
+    let mut members = vec![owner];
+    members.extend(
+        event
+            .find_linked(EventUserMembers)
+            .all(&state.database)
+            .await
+            .or_internal_server_error("Error loading members")?,
+    );
+
+    Ok(Json(members.into_iter().map(UserV1::from).collect()))
+}
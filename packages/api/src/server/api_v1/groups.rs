@@ -0,0 +1,296 @@
+use super::events::EventV1;
+use super::pagination::{Page, PageParams, paginate};
+use super::{ApiV1Error, JsonBody, UserV1, current_user};
+use crate::server::groups::{self, find_member_group};
+use crate::server::{AppState, AuthenticationState};
+use dioxus::prelude::*;
+use dioxus::server::axum::extract::{Path, Query};
+use dioxus::server::axum::http::StatusCode;
+use dioxus::server::axum::routing::{delete, get};
+use dioxus::server::axum::{Extension, Json, Router};
+use entity::prelude::{Event, Group, User};
+use sea_orm::{ColumnTrait, Condition, ModelTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub(super) fn router() -> Router {
+    Router::new()
+        .route("/groups", get(list_groups).post(create_group))
+        .route(
+            "/groups/{group_id}",
+            get(retrieve_group).put(rename_group).delete(delete_group),
+        )
+        .route(
+            "/groups/{group_id}/members",
+            get(list_group_members).post(add_group_member),
+        )
+        .route(
+            "/groups/{group_id}/members/{user_id}",
+            delete(remove_group_member),
+        )
+        .route("/groups/{group_id}/events", get(list_group_events))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct GroupV1 {
+    pub id: i32,
+    pub name: String,
+}
+
+impl From<entity::group::Model> for GroupV1 {
+    fn from(group: entity::group::Model) -> Self {
+        GroupV1 {
+            id: group.id,
+            name: group.name,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct GroupInput {
+    pub name: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct MemberInput {
+    /// Email address of the user to add
+    pub email: String,
+}
+
+/// Lists all groups the user is a member of
+#[utoipa::path(
+    get,
+    path = "/api/v1/groups",
+    tag = "groups",
+    params(PageParams),
+    responses(
+        (status = 200, body = Page<GroupV1>),
+        (status = 400, body = ApiV1Error),
+        (status = 401, body = ApiV1Error),
+    )
+)]
+pub(super) async fn list_groups(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page<GroupV1>>, ApiV1Error> {
+    let user = current_user(&auth)?;
+
+    let query = user
+        .find_related(Group)
+        .filter(entity::group::Column::DeletedAt.is_null())
+        .order_by_asc(entity::group::Column::Name)
+        .order_by_asc(entity::group::Column::Id);
+    let groups = paginate(query, &page, &state.database).await?;
+
+    Ok(Json(groups.map(GroupV1::from)))
+}
+
+/// Creates a group with the user as its first member
+#[utoipa::path(
+    post,
+    path = "/api/v1/groups",
+    tag = "groups",
+    request_body = GroupInput,
+    responses(
+        (status = 201, body = GroupV1),
+        (status = 400, body = ApiV1Error),
+        (status = 401, body = ApiV1Error),
+    )
+)]
+pub(super) async fn create_group(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    JsonBody(input): JsonBody<GroupInput>,
+) -> Result<(StatusCode, Json<GroupV1>), ApiV1Error> {
+    let user_id = current_user(&auth)?.id;
+    (!input.name.trim().is_empty()).or_bad_request("Name must not be empty")?;
+
+    let group = groups::create_group(&state, user_id, input.name).await?;
+
+    Ok((StatusCode::CREATED, Json(group.into())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/groups/{group_id}",
+    tag = "groups",
+    params(("group_id" = i32, Path)),
+    responses(
+        (status = 200, body = GroupV1),
+        (status = 401, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn retrieve_group(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(group_id): Path<i32>,
+) -> Result<Json<GroupV1>, ApiV1Error> {
+    let user = current_user(&auth)?;
+    let group = find_member_group(user.id, group_id, &state.database).await?;
+    Ok(Json(group.into()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/groups/{group_id}",
+    tag = "groups",
+    params(("group_id" = i32, Path)),
+    request_body = GroupInput,
+    responses(
+        (status = 200, body = GroupV1),
+        (status = 400, body = ApiV1Error),
+        (status = 401, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn rename_group(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(group_id): Path<i32>,
+    JsonBody(input): JsonBody<GroupInput>,
+) -> Result<Json<GroupV1>, ApiV1Error> {
+    let user = current_user(&auth)?;
+    (!input.name.trim().is_empty()).or_bad_request("Name must not be empty")?;
+
+    let group = groups::rename_group(&state, user.id, group_id, input.name).await?;
+
+    Ok(Json(group.into()))
+}
+
+/// Moves a group to the trash, from where it can be restored until the retention period has passed.
+/// Events shared with the group are kept
+#[utoipa::path(
+    delete,
+    path = "/api/v1/groups/{group_id}",
+    tag = "groups",
+    params(("group_id" = i32, Path)),
+    responses(
+        (status = 204),
+        (status = 401, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn delete_group(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(group_id): Path<i32>,
+) -> Result<StatusCode, ApiV1Error> {
+    let user = current_user(&auth)?;
+    groups::delete_group(&state, user.id, group_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/groups/{group_id}/members",
+    tag = "groups",
+    params(("group_id" = i32, Path)),
+    responses(
+        (status = 200, body = Vec<UserV1>),
+        (status = 401, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn list_group_members(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(group_id): Path<i32>,
+) -> Result<Json<Vec<UserV1>>, ApiV1Error> {
+    let user = current_user(&auth)?;
+    let group = find_member_group(user.id, group_id, &state.database).await?;
+
+    let members = group
+        .find_related(User)
+        .all(&state.database)
+        .await
+        .or_internal_server_error("Error loading members")?;
+
+    Ok(Json(members.into_iter().map(UserV1::from).collect()))
+}
+
+/// Adds a user to the group by their email address
+#[utoipa::path(
+    post,
+    path = "/api/v1/groups/{group_id}/members",
+    tag = "groups",
+    params(("group_id" = i32, Path)),
+    request_body = MemberInput,
+    responses(
+        (status = 201, body = UserV1),
+        (status = 401, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+        (status = 409, body = ApiV1Error),
+    )
+)]
+pub(super) async fn add_group_member(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(group_id): Path<i32>,
+    JsonBody(input): JsonBody<MemberInput>,
+) -> Result<(StatusCode, Json<UserV1>), ApiV1Error> {
+    let user = current_user(&auth)?;
+    let new_member = groups::add_group_member(&state, user.id, group_id, &input.email).await?;
+    Ok((StatusCode::CREATED, Json(new_member.into())))
+}
+
+/// Removes a member from the group. The group is moved to the trash when its last member leaves
+#[utoipa::path(
+    delete,
+    path = "/api/v1/groups/{group_id}/members/{user_id}",
+    tag = "groups",
+    params(("group_id" = i32, Path), ("user_id" = i32, Path)),
+    responses(
+        (status = 204),
+        (status = 401, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn remove_group_member(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path((group_id, user_id)): Path<(i32, i32)>,
+) -> Result<StatusCode, ApiV1Error> {
+    let user = current_user(&auth)?;
+    groups::remove_group_member(&state, user.id, group_id, user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Lists the events shared with the group. Private events are only included for their owner
+#[utoipa::path(
+    get,
+    path = "/api/v1/groups/{group_id}/events",
+    tag = "groups",
+    params(("group_id" = i32, Path)),
+    responses(
+        (status = 200, body = Vec<EventV1>),
+        (status = 401, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn list_group_events(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(group_id): Path<i32>,
+) -> Result<Json<Vec<EventV1>>, ApiV1Error> {
+    use entity::event::Column as EventColumn;
+
+    let user = current_user(&auth)?;
+    let group = find_member_group(user.id, group_id, &state.database).await?;
+
+    let events = group
+        .find_related(Event)
+        .filter(
+            Condition::any()
+                .add(EventColumn::Private.eq(false))
+                .add(EventColumn::OwnerId.eq(user.id)),
+        )
+        .order_by_asc(EventColumn::Date)
+        .order_by_asc(EventColumn::StartTime)
+        .all(&state.database)
+        .await
+        .or_internal_server_error("Error loading events")?;
+
+    Ok(Json(events.into_iter().map(EventV1::from).collect()))
+}
@@ -0,0 +1,162 @@
+use super::events::EventV1;
+use super::pagination::{Page, PageParams, paginate};
+use super::{ApiV1Error, JsonBody, current_user};
+use crate::server::invitations;
+use crate::server::{AppState, AuthenticationState};
+use dioxus::server::axum::extract::{Path, Query};
+use dioxus::server::axum::http::StatusCode;
+use dioxus::server::axum::routing::{get, post};
+use dioxus::server::axum::{Extension, Json, Router};
+use entity::invitation::Column as InvitationColumn;
+use entity::invitation::InvitationStatus;
+use entity::prelude::{Event, Invitation};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub(super) fn router() -> Router {
+    Router::new()
+        .route(
+            "/event-invitations",
+            get(list_invitations).post(send_invitation),
+        )
+        .route(
+            "/event-invitations/{invitation_id}/accept",
+            post(accept_invitation),
+        )
+        .route(
+            "/event-invitations/{invitation_id}/decline",
+            post(decline_invitation),
+        )
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct InvitationV1 {
+    pub id: i32,
+    #[schema(value_type = String, example = "Sent")]
+    pub status: InvitationStatus,
+    pub event_id: i32,
+    pub receiving_user_id: i32,
+    /// The event the invitation is for. Only included when listing received invitations
+    pub event: Option<EventV1>,
+}
+
+impl InvitationV1 {
+    fn new(invitation: entity::invitation::Model, event: Option<entity::event::Model>) -> Self {
+        InvitationV1 {
+            id: invitation.id,
+            status: invitation.status,
+            event_id: invitation.event_id,
+            receiving_user_id: invitation.recieving_user,
+            event: event.map(EventV1::from),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct InvitationInput {
+    pub event_id: i32,
+    /// Email address of the invited user
+    pub email: String,
+}
+
+/// Lists the pending event invitations the user received
+#[utoipa::path(
+    get,
+    path = "/api/v1/event-invitations",
+    tag = "events",
+    params(PageParams),
+    responses(
+        (status = 200, body = Page<InvitationV1>),
+        (status = 400, body = ApiV1Error),
+        (status = 401, body = ApiV1Error),
+    )
+)]
+pub(super) async fn list_invitations(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page<InvitationV1>>, ApiV1Error> {
+    let user = current_user(&auth)?;
+
+    let query = Invitation::find()
+        .filter(InvitationColumn::RecievingUser.eq(user.id))
+        .filter(InvitationColumn::Status.eq(InvitationStatus::Sent))
+        .order_by_asc(InvitationColumn::Id)
+        .find_also_related(Event);
+    let invitations = paginate(query, &page, &state.database).await?;
+
+    Ok(Json(invitations.map(|(invitation, event)| {
+        InvitationV1::new(invitation, event)
+    })))
+}
+
+/// Invites a user to an event. Only the owner of the event may send invitations
+#[utoipa::path(
+    post,
+    path = "/api/v1/event-invitations",
+    tag = "events",
+    request_body = InvitationInput,
+    responses(
+        (status = 201, body = InvitationV1),
+        (status = 401, body = ApiV1Error),
+        (status = 403, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+        (status = 409, body = ApiV1Error),
+    )
+)]
+pub(super) async fn send_invitation(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    JsonBody(input): JsonBody<InvitationInput>,
+) -> Result<(StatusCode, Json<InvitationV1>), ApiV1Error> {
+    let user = current_user(&auth)?;
+    let invitation =
+        invitations::send_invitation(&state, user.id, input.event_id, &input.email).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(InvitationV1::new(invitation, None)),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/event-invitations/{invitation_id}/accept",
+    tag = "events",
+    params(("invitation_id" = i32, Path)),
+    responses(
+        (status = 204),
+        (status = 401, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn accept_invitation(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(invitation_id): Path<i32>,
+) -> Result<StatusCode, ApiV1Error> {
+    let user_id = current_user(&auth)?.id;
+    invitations::accept_invitation(&state, user_id, invitation_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/event-invitations/{invitation_id}/decline",
+    tag = "events",
+    params(("invitation_id" = i32, Path)),
+    responses(
+        (status = 204),
+        (status = 401, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn decline_invitation(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(invitation_id): Path<i32>,
+) -> Result<StatusCode, ApiV1Error> {
+    let user_id = current_user(&auth)?.id;
+    invitations::decline_invitation(&state, user_id, invitation_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
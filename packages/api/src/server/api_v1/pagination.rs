@@ -0,0 +1,83 @@
+use super::ApiV1Error;
+use dioxus::prelude::*;
+use sea_orm::{DatabaseConnection, PaginatorTrait, SelectorTrait};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+const DEFAULT_PAGE_SIZE: u64 = 20;
+const MAX_PAGE_SIZE: u64 = 100;
+
+#[derive(Deserialize, IntoParams, Debug)]
+#[into_params(parameter_in = Query)]
+pub struct PageParams {
+    /// Page to return, starting at 1
+    #[param(minimum = 1, default = 1)]
+    pub page: Option<u64>,
+    /// Number of items per page
+    #[param(minimum = 1, maximum = 100, default = 20)]
+    pub per_page: Option<u64>,
+}
+
+/// One page of a collection
+#[derive(Serialize, ToSchema)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page: u64,
+    pub per_page: u64,
+    pub total_items: u64,
+    pub total_pages: u64,
+}
+
+impl<T> Page<T> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            page: self.page,
+            per_page: self.per_page,
+            total_items: self.total_items,
+            total_pages: self.total_pages,
+        }
+    }
+}
+
+/// Loads the requested page of the query
+///
+/// # Errors
+///
+/// Returns `400 Bad Request` if the page parameters are out of range and `500 Internal Server Error` if the
+/// database query fails
+pub async fn paginate<'db, Q>(
+    query: Q,
+    params: &PageParams,
+    db: &'db DatabaseConnection,
+) -> Result<Page<<Q::Selector as SelectorTrait>::Item>, ApiV1Error>
+where
+    Q: PaginatorTrait<'db, DatabaseConnection>,
+{
+    let page = params.page.unwrap_or(1);
+    let per_page = params.per_page.unwrap_or(DEFAULT_PAGE_SIZE);
+    (page >= 1).or_bad_request("page must be at least 1")?;
+    (1..=MAX_PAGE_SIZE)
+        .contains(&per_page)
+        .or_bad_request(format!("per_page must be between 1 and {MAX_PAGE_SIZE}"))?;
+
+    let paginator = query.paginate(db, per_page);
+    let totals = paginator
+        .num_items_and_pages()
+        .await
+        .inspect_err(|e| error!("Error counting page items: {e}"))
+        .or_internal_server_error("Error loading items")?;
+    let items = paginator
+        .fetch_page(page - 1)
+        .await
+        .inspect_err(|e| error!("Error loading page: {e}"))
+        .or_internal_server_error("Error loading items")?;
+
+    Ok(Page {
+        items,
+        page,
+        per_page,
+        total_items: totals.number_of_items,
+        total_pages: totals.number_of_pages,
+    })
+}
@@ -0,0 +1,623 @@
+use super::pagination::{Page, PageParams, paginate};
+use super::{ApiV1Error, JsonBody, current_user, saved_or_edit_conflict};
+use crate::server::todo_lists::{
+    self, find_todo, find_todo_list_invitation, find_todo_list_permission, publish_member_removed,
+    remove_user_from_todo_list,
+};
+use crate::server::versioning::VersionedUpdate;
+use crate::server::{AppState, AuthenticationState};
+use dioxus::prelude::*;
+use dioxus::server::axum::extract::{Path, Query};
+use dioxus::server::axum::http::StatusCode;
+use dioxus::server::axum::routing::{delete, get};
+use dioxus::server::axum::{Extension, Json, Router};
+use entity::prelude::{Todo, TodoList, TodoListInvitation, User};
+use entity::todo::Column as TodoColumn;
+use entity::todo::{CreateToDo, UpdateToDo};
+use entity::todo_list::Column as TodoListColumn;
+use entity::todo_list::{CreateTodoList, TodoListWithPermission, UpdateTodoList};
+use entity::todo_list_invitation::Column as InvitationColumn;
+use entity::todo_list_invitation::InvitationPermission;
+use sea_orm::{
+    ColumnTrait, EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect, QueryTrait,
+    RelationTrait,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+pub(super) fn router() -> Router {
+    Router::new()
+        .route("/todolists", get(list_todo_lists).post(create_todo_list))
+        .route(
+            "/todolists/{todo_list_id}",
+            get(retrieve_todo_list)
+                .patch(update_todo_list)
+                .delete(delete_todo_list),
+        )
+        .route(
+            "/todolists/{todo_list_id}/members",
+            get(list_todo_list_members),
+        )
+        .route(
+            "/todolists/{todo_list_id}/members/{user_id}",
+            delete(remove_todo_list_member),
+        )
+        .route(
+            "/todolists/{todo_list_id}/todos",
+            get(list_todo_list_todos).post(create_todo),
+        )
+        .route("/todos", get(list_todos))
+        .route(
+            "/todos/{todo_id}",
+            get(retrieve_todo).patch(update_todo).delete(delete_todo),
+        )
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TodoListV1 {
+    pub id: i32,
+    pub title: String,
+    pub description: Option<String>,
+    /// Permission of the user in this list
+    #[schema(value_type = String, example = "Write")]
+    pub permission: InvitationPermission,
+    pub is_favorite: bool,
+    /// Incremented on every change, see `version` of [`TodoListPatch`]
+    pub version: i32,
+}
+
+impl From<TodoListWithPermission> for TodoListV1 {
+    fn from(todo_list: TodoListWithPermission) -> Self {
+        TodoListV1 {
+            id: todo_list.id,
+            title: todo_list.title,
+            description: todo_list.description,
+            permission: todo_list.invitation.permission,
+            is_favorite: todo_list.invitation.is_favorite,
+            version: todo_list.version,
+        }
+    }
+}
+
+impl TodoListV1 {
+    fn new(
+        todo_list: entity::todo_list::Model,
+        invitation: &entity::todo_list_invitation::Model,
+    ) -> Self {
+        TodoListV1 {
+            id: todo_list.id,
+            title: todo_list.title,
+            description: todo_list.description,
+            permission: invitation.permission,
+            is_favorite: invitation.is_favorite,
+            version: todo_list.version,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TodoListMemberV1 {
+    pub id: i32,
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    #[schema(value_type = String, example = "Read")]
+    pub permission: InvitationPermission,
+    /// False while the invitation to the list is still pending
+    pub is_accepted: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TodoV1 {
+    pub id: i32,
+    pub title: String,
+    pub details: Option<String>,
+    pub completed: bool,
+    pub todo_list_id: i32,
+    /// Incremented on every change, see `version` of [`TodoPatch`]
+    pub version: i32,
+}
+
+impl From<entity::todo::Model> for TodoV1 {
+    fn from(todo: entity::todo::Model) -> Self {
+        TodoV1 {
+            id: todo.id,
+            title: todo.title,
+            details: todo.details,
+            completed: todo.completed,
+            todo_list_id: todo.todo_list_id,
+            version: todo.version,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TodoListInput {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Fields to change. Omitted fields keep their value, an empty description removes it
+#[derive(Deserialize, ToSchema)]
+pub struct TodoListPatch {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Version of the list the changes are based on. If the list was changed since, the update
+    /// is rejected with `409 Conflict`
+    pub version: i32,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TodoInput {
+    pub title: String,
+    #[serde(default)]
+    pub details: Option<String>,
+}
+
+/// Fields to change. Omitted fields keep their value, empty details remove them
+#[derive(Deserialize, ToSchema)]
+pub struct TodoPatch {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub details: Option<String>,
+    #[serde(default)]
+    pub completed: Option<bool>,
+    /// Version of the task the changes are based on. If the task was changed since, the update
+    /// is rejected with `409 Conflict`
+    pub version: i32,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TodoFilter {
+    /// Only return completed or open tasks
+    pub completed: Option<bool>,
+    /// Only return tasks of favorite or other lists
+    pub favorite: Option<bool>,
+}
+
+fn non_empty(value: String) -> Option<String> {
+    (!value.trim().is_empty()).then_some(value)
+}
+
+/// Lists all To-Do lists the user joined
+#[utoipa::path(
+    get,
+    path = "/api/v1/todolists",
+    tag = "todos",
+    params(PageParams),
+    responses(
+        (status = 200, body = Page<TodoListV1>),
+        (status = 400, body = ApiV1Error),
+        (status = 401, body = ApiV1Error),
+    )
+)]
+pub(super) async fn list_todo_lists(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page<TodoListV1>>, ApiV1Error> {
+    let user = current_user(&auth)?;
+
+    let query = TodoList::find()
+        .inner_join(TodoListInvitation)
+        .filter(InvitationColumn::ReceivingUserId.eq(user.id))
+        .filter(InvitationColumn::IsAccepted.eq(true))
+        .filter(TodoListColumn::DeletedAt.is_null())
+        .order_by_asc(TodoListColumn::Title)
+        .order_by_asc(TodoListColumn::Id)
+        .into_partial_model::<TodoListWithPermission>();
+    let todo_lists = paginate(query, &page, &state.database).await?;
+
+    Ok(Json(todo_lists.map(TodoListV1::from)))
+}
+
+/// Creates a To-Do list with the user as its admin
+#[utoipa::path(
+    post,
+    path = "/api/v1/todolists",
+    tag = "todos",
+    request_body = TodoListInput,
+    responses(
+        (status = 201, body = TodoListV1),
+        (status = 400, body = ApiV1Error),
+        (status = 401, body = ApiV1Error),
+    )
+)]
+pub(super) async fn create_todo_list(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    JsonBody(input): JsonBody<TodoListInput>,
+) -> Result<(StatusCode, Json<TodoListV1>), ApiV1Error> {
+    let user_id = current_user(&auth)?.id;
+    (!input.title.trim().is_empty()).or_bad_request("Title must not be empty")?;
+
+    let data = CreateTodoList {
+        title: input.title,
+        description: input.description.and_then(non_empty),
+    };
+    let todo_list = todo_lists::create_todo_list(&state, user_id, data).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(TodoListV1 {
+            id: todo_list.id,
+            title: todo_list.title,
+            description: todo_list.description,
+            permission: InvitationPermission::Admin,
+            is_favorite: false,
+            version: todo_list.version,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/todolists/{todo_list_id}",
+    tag = "todos",
+    params(("todo_list_id" = i32, Path)),
+    responses(
+        (status = 200, body = TodoListV1),
+        (status = 401, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn retrieve_todo_list(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(todo_list_id): Path<i32>,
+) -> Result<Json<TodoListV1>, ApiV1Error> {
+    let user = current_user(&auth)?;
+
+    let todo_list = TodoList::find_by_id(todo_list_id)
+        .inner_join(TodoListInvitation)
+        .filter(InvitationColumn::ReceivingUserId.eq(user.id))
+        .filter(InvitationColumn::IsAccepted.eq(true))
+        .filter(TodoListColumn::DeletedAt.is_null())
+        .into_partial_model::<TodoListWithPermission>()
+        .one(&state.database)
+        .await
+        .inspect_err(|e| error!("Error loading To-Do List: {e}"))
+        .or_internal_server_error("Error loading To-Do List")?
+        .or_not_found("To-Do List not found")?;
+
+    Ok(Json(todo_list.into()))
+}
+
+/// Changes the title or description of a list. Requires write permission
+#[utoipa::path(
+    patch,
+    path = "/api/v1/todolists/{todo_list_id}",
+    tag = "todos",
+    params(("todo_list_id" = i32, Path)),
+    request_body = TodoListPatch,
+    responses(
+        (status = 200, body = TodoListV1),
+        (status = 400, body = ApiV1Error),
+        (status = 401, body = ApiV1Error),
+        (status = 403, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+        (status = 409, body = ApiV1Error, description = "The list was changed since `version`. `current` contains the list"),
+    )
+)]
+pub(super) async fn update_todo_list(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(todo_list_id): Path<i32>,
+    JsonBody(patch): JsonBody<TodoListPatch>,
+) -> Result<Json<TodoListV1>, ApiV1Error> {
+    let user = current_user(&auth)?;
+    if let Some(title) = &patch.title {
+        (!title.trim().is_empty()).or_bad_request("Title must not be empty")?;
+    }
+
+    let data = UpdateTodoList {
+        title: patch.title,
+        description: patch.description.map(non_empty),
+    };
+    let update =
+        todo_lists::update_todo_list(&state, user.id, todo_list_id, patch.version, data).await?;
+
+    let invitation = find_todo_list_invitation(todo_list_id, user.id, &state.database)
+        .await
+        .or_internal_server_error("Error loading To-Do List")?
+        .or_not_found("To-Do List not found")?;
+    match update {
+        VersionedUpdate::Saved(todo_list) => Ok(Json(TodoListV1::new(todo_list, &invitation))),
+        VersionedUpdate::Stale(current) => Err(ApiV1Error::edit_conflict(
+            "The To-Do List was changed by someone else in the meantime",
+            &TodoListV1::new(current, &invitation),
+        )),
+    }
+}
+
+/// Moves a list to the trash, from where it can be restored until the retention period has passed.
+/// Requires admin permission
+#[utoipa::path(
+    delete,
+    path = "/api/v1/todolists/{todo_list_id}",
+    tag = "todos",
+    params(("todo_list_id" = i32, Path)),
+    responses(
+        (status = 204),
+        (status = 401, body = ApiV1Error),
+        (status = 403, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn delete_todo_list(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(todo_list_id): Path<i32>,
+) -> Result<StatusCode, ApiV1Error> {
+    let user = current_user(&auth)?;
+    todo_lists::delete_todo_list(&state, user.id, todo_list_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Lists all users invited to the list, including pending invitations
+#[utoipa::path(
+    get,
+    path = "/api/v1/todolists/{todo_list_id}/members",
+    tag = "todos",
+    params(("todo_list_id" = i32, Path)),
+    responses(
+        (status = 200, body = Vec<TodoListMemberV1>),
+        (status = 401, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn list_todo_list_members(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(todo_list_id): Path<i32>,
+) -> Result<Json<Vec<TodoListMemberV1>>, ApiV1Error> {
+    let user = current_user(&auth)?;
+    find_todo_list_permission(todo_list_id, user.id, &state.database).await?;
+
+    let members = User::find()
+        .join(
+            JoinType::InnerJoin,
+            entity::todo_list_invitation::Relation::Receiver.def().rev(),
+        )
+        .filter(InvitationColumn::TodoListId.eq(todo_list_id))
+        .into_partial_model::<entity::user::UserWithTodoListInvitation>()
+        .all(&state.database)
+        .await
+        .inspect_err(|e| error!("Error loading To-Do List members: {e}"))
+        .or_internal_server_error("Error loading members")?;
+
+    Ok(Json(
+        members
+            .into_iter()
+            .map(|member| TodoListMemberV1 {
+                id: member.id,
+                email: member.email,
+                first_name: member.first_name,
+                last_name: member.last_name,
+                permission: member.invitation.permission,
+                is_accepted: member.invitation.is_accepted,
+            })
+            .collect(),
+    ))
+}
+
+/// Removes a user from the list. Requires admin permission and cannot be used to leave a list
+#[utoipa::path(
+    delete,
+    path = "/api/v1/todolists/{todo_list_id}/members/{user_id}",
+    tag = "todos",
+    params(("todo_list_id" = i32, Path), ("user_id" = i32, Path)),
+    responses(
+        (status = 204),
+        (status = 400, body = ApiV1Error),
+        (status = 401, body = ApiV1Error),
+        (status = 403, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn remove_todo_list_member(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path((todo_list_id, user_id)): Path<(i32, i32)>,
+) -> Result<StatusCode, ApiV1Error> {
+    let user = current_user(&auth)?;
+    remove_user_from_todo_list(todo_list_id, user_id, user.id, &state.database).await?;
+    publish_member_removed(todo_list_id, user_id, &state).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/todolists/{todo_list_id}/todos",
+    tag = "todos",
+    params(("todo_list_id" = i32, Path), PageParams),
+    responses(
+        (status = 200, body = Page<TodoV1>),
+        (status = 400, body = ApiV1Error),
+        (status = 401, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn list_todo_list_todos(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(todo_list_id): Path<i32>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page<TodoV1>>, ApiV1Error> {
+    let user = current_user(&auth)?;
+    find_todo_list_permission(todo_list_id, user.id, &state.database).await?;
+
+    let query = Todo::find()
+        .filter(TodoColumn::TodoListId.eq(todo_list_id))
+        .order_by_asc(TodoColumn::Completed)
+        .order_by_asc(TodoColumn::Title)
+        .order_by_asc(TodoColumn::Id);
+    let todos = paginate(query, &page, &state.database).await?;
+
+    Ok(Json(todos.map(TodoV1::from)))
+}
+
+/// Adds a task to the list. Requires write permission
+#[utoipa::path(
+    post,
+    path = "/api/v1/todolists/{todo_list_id}/todos",
+    tag = "todos",
+    params(("todo_list_id" = i32, Path)),
+    request_body = TodoInput,
+    responses(
+        (status = 201, body = TodoV1),
+        (status = 400, body = ApiV1Error),
+        (status = 401, body = ApiV1Error),
+        (status = 403, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn create_todo(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(todo_list_id): Path<i32>,
+    JsonBody(input): JsonBody<TodoInput>,
+) -> Result<(StatusCode, Json<TodoV1>), ApiV1Error> {
+    let user = current_user(&auth)?;
+    (!input.title.trim().is_empty()).or_bad_request("Title must not be empty")?;
+
+    let data = CreateToDo {
+        title: input.title,
+        details: input.details.and_then(non_empty),
+    };
+    let todo = todo_lists::create_todo(&state, user.id, todo_list_id, data).await?;
+
+    Ok((StatusCode::CREATED, Json(todo.into())))
+}
+
+/// Lists the tasks of all lists the user joined
+#[utoipa::path(
+    get,
+    path = "/api/v1/todos",
+    tag = "todos",
+    params(TodoFilter, PageParams),
+    responses(
+        (status = 200, body = Page<TodoV1>),
+        (status = 400, body = ApiV1Error),
+        (status = 401, body = ApiV1Error),
+    )
+)]
+pub(super) async fn list_todos(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Query(filter): Query<TodoFilter>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page<TodoV1>>, ApiV1Error> {
+    let user = current_user(&auth)?;
+
+    let query = Todo::find()
+        .join(JoinType::InnerJoin, entity::todo::Relation::TodoList.def())
+        .join(
+            JoinType::InnerJoin,
+            entity::todo_list::Relation::TodoListInvitation.def(),
+        )
+        .filter(InvitationColumn::ReceivingUserId.eq(user.id))
+        .filter(InvitationColumn::IsAccepted.eq(true))
+        .filter(TodoListColumn::DeletedAt.is_null())
+        .apply_if(filter.completed, |query, completed| {
+            query.filter(TodoColumn::Completed.eq(completed))
+        })
+        .apply_if(filter.favorite, |query, favorite| {
+            query.filter(InvitationColumn::IsFavorite.eq(favorite))
+        })
+        .order_by_asc(TodoColumn::Completed)
+        .order_by_asc(TodoColumn::Title)
+        .order_by_asc(TodoColumn::Id);
+    let todos = paginate(query, &page, &state.database).await?;
+
+    Ok(Json(todos.map(TodoV1::from)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/todos/{todo_id}",
+    tag = "todos",
+    params(("todo_id" = i32, Path)),
+    responses(
+        (status = 200, body = TodoV1),
+        (status = 401, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn retrieve_todo(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(todo_id): Path<i32>,
+) -> Result<Json<TodoV1>, ApiV1Error> {
+    let user = current_user(&auth)?;
+    let (todo, _) = find_todo(todo_id, user.id, &state.database).await?;
+    Ok(Json(todo.into()))
+}
+
+/// Changes a task, e.g. to complete it. Requires write permission
+#[utoipa::path(
+    patch,
+    path = "/api/v1/todos/{todo_id}",
+    tag = "todos",
+    params(("todo_id" = i32, Path)),
+    request_body = TodoPatch,
+    responses(
+        (status = 200, body = TodoV1),
+        (status = 400, body = ApiV1Error),
+        (status = 401, body = ApiV1Error),
+        (status = 403, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+        (status = 409, body = ApiV1Error, description = "The task was changed since `version`. `current` contains the task"),
+    )
+)]
+pub(super) async fn update_todo(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(todo_id): Path<i32>,
+    JsonBody(patch): JsonBody<TodoPatch>,
+) -> Result<Json<TodoV1>, ApiV1Error> {
+    let user = current_user(&auth)?;
+    if let Some(title) = &patch.title {
+        (!title.trim().is_empty()).or_bad_request("Title must not be empty")?;
+    }
+
+    let data = UpdateToDo {
+        title: patch.title,
+        details: patch.details.map(non_empty),
+        completed: patch.completed,
+    };
+    let todo = todo_lists::update_todo(&state, user.id, todo_id, patch.version, data).await?;
+    let todo = saved_or_edit_conflict::<_, TodoV1>(
+        todo,
+        "The task was changed by someone else in the meantime",
+    )?;
+
+    Ok(Json(todo.into()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/todos/{todo_id}",
+    tag = "todos",
+    params(("todo_id" = i32, Path)),
+    responses(
+        (status = 204),
+        (status = 401, body = ApiV1Error),
+        (status = 403, body = ApiV1Error),
+        (status = 404, body = ApiV1Error),
+    )
+)]
+pub(super) async fn delete_todo(
+    Extension(state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(todo_id): Path<i32>,
+) -> Result<StatusCode, ApiV1Error> {
+    let user = current_user(&auth)?;
+    todo_lists::delete_todo(&state, user.id, todo_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
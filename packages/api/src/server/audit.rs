@@ -0,0 +1,199 @@
+use crate::server::events::is_user_in_group;
+use crate::server::todo_lists::get_todo_list_permission;
+use dioxus::prelude::*;
+use entity::audit_log::{AuditAction, AuditTarget};
+use entity::prelude::{AuditLog, Event};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    Set,
+};
+use time::OffsetDateTime;
+
+/// Entry of the audit log, written with [`AuditEntry::record`]
+#[derive(Clone, Debug)]
+pub(crate) struct AuditEntry {
+    actor_id: Option<i32>,
+    action: AuditAction,
+    target_type: AuditTarget,
+    target_id: i32,
+    affected_user_id: Option<i32>,
+    details: Option<String>,
+}
+
+impl AuditEntry {
+    pub(crate) fn new(
+        actor_id: Option<i32>,
+        action: AuditAction,
+        target_type: AuditTarget,
+        target_id: i32,
+    ) -> Self {
+        AuditEntry {
+            actor_id,
+            action,
+            target_type,
+            target_id,
+            affected_user_id: None,
+            details: None,
+        }
+    }
+
+    pub(crate) fn affected_user(mut self, user_id: i32) -> Self {
+        self.affected_user_id = Some(user_id);
+        self
+    }
+
+    pub(crate) fn details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+
+    /// Appends the entry to the audit log. Failures are logged instead of failing the already completed action
+    pub(crate) async fn record<C: ConnectionTrait>(self, database: &C) {
+        let entry = entity::audit_log::ActiveModel {
+            actor_id: Set(self.actor_id),
+            action: Set(self.action),
+            target_type: Set(self.target_type),
+            target_id: Set(self.target_id),
+            affected_user_id: Set(self.affected_user_id),
+            details: Set(self.details),
+            created_at: Set(OffsetDateTime::now_utc()),
+            ..Default::default()
+        };
+        if let Err(e) = entry.insert(database).await {
+            error!(
+                "Error recording {:?} of {:?} {} in the audit log: {e}",
+                self.action, self.target_type, self.target_id
+            );
+        }
+    }
+}
+
+/// Records a successful login of the user with the given method, e.g. `passkey`
+pub(crate) async fn record_login(user_id: i32, method: &str, database: &DatabaseConnection) {
+    AuditEntry::new(
+        Some(user_id),
+        AuditAction::Login,
+        AuditTarget::User,
+        user_id,
+    )
+    .details(method)
+    .record(database)
+    .await;
+}
+
+/// Whether the user administers the target and may read its audit log:
+/// their own account, groups they are a member of, events they own and To-Do lists they are an admin of.
+/// The log of a deleted event stays readable for its last owner, who is the actor of the deletion
+pub(crate) async fn can_view_audit_log(
+    user_id: i32,
+    target_type: AuditTarget,
+    target_id: i32,
+    database: &DatabaseConnection,
+) -> Result<bool, ServerFnError> {
+    Ok(match target_type {
+        AuditTarget::User => target_id == user_id,
+        AuditTarget::Group => is_user_in_group(database, target_id, user_id).await?,
+        AuditTarget::Event => match Event::find_by_id(target_id)
+            .one(database)
+            .await
+            .or_internal_server_error("Error loading event")?
+        {
+            Some(event) => event.owner_id == user_id,
+            // Only the owner may delete an event, so the actor of the deletion was the owner
+            None => AuditLog::find()
+                .filter(entity::audit_log::Column::TargetType.eq(AuditTarget::Event))
+                .filter(entity::audit_log::Column::TargetId.eq(target_id))
+                .filter(entity::audit_log::Column::Action.eq(AuditAction::EventDeleted))
+                .filter(entity::audit_log::Column::ActorId.eq(user_id))
+                .one(database)
+                .await
+                .or_internal_server_error("Error loading audit log")?
+                .is_some(),
+        },
+        AuditTarget::TodoList => get_todo_list_permission(target_id, user_id, database)
+            .await?
+            .is_some_and(|permission| permission.can_admin()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server;
+    use crate::server::test_support::{create_test_event, create_test_user, setup};
+    use sea_orm::{IntoActiveModel, ModelTrait};
+
+    #[tokio::test]
+    async fn test_audit_log_is_append_only() {
+        let database = setup().await;
+
+        AuditEntry::new(
+            Some(1),
+            AuditAction::GroupMemberRemoved,
+            AuditTarget::Group,
+            2,
+        )
+        .affected_user(3)
+        .record(&database)
+        .await;
+        let entry = AuditLog::find().one(&database).await.unwrap().unwrap();
+        assert_eq!(entry.action, AuditAction::GroupMemberRemoved);
+        assert_eq!(entry.affected_user_id, Some(3));
+
+        let mut changed = entry.clone().into_active_model();
+        changed.actor_id = Set(None);
+        assert!(changed.update(&database).await.is_err());
+        assert!(entry.delete(&database).await.is_err());
+        assert_eq!(AuditLog::find().all(&database).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_users_can_only_view_their_own_account() {
+        let database = server::database::establish_test_connection().await.unwrap();
+        assert!(
+            can_view_audit_log(1, AuditTarget::User, 1, &database)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !can_view_audit_log(1, AuditTarget::User, 2, &database)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_owner_can_view_audit_log_of_deleted_event() {
+        let database = setup().await;
+        let owner = create_test_user(&database, 1).await;
+        let other = create_test_user(&database, 2).await;
+        let event = create_test_event(&database, owner.id, false).await;
+        assert!(
+            can_view_audit_log(owner.id, AuditTarget::Event, event.id, &database)
+                .await
+                .unwrap()
+        );
+
+        event.clone().delete(&database).await.unwrap();
+        AuditEntry::new(
+            Some(owner.id),
+            AuditAction::EventDeleted,
+            AuditTarget::Event,
+            event.id,
+        )
+        .record(&database)
+        .await;
+
+        assert!(
+            can_view_audit_log(owner.id, AuditTarget::Event, event.id, &database)
+                .await
+                .unwrap(),
+            "Expected the audit log to stay readable for the owner"
+        );
+        assert!(
+            !can_view_audit_log(other.id, AuditTarget::Event, event.id, &database)
+                .await
+                .unwrap()
+        );
+    }
+}
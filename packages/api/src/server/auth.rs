@@ -1,9 +1,19 @@
+pub mod api_token;
+pub mod email_verification;
 pub mod middleware;
 pub mod oidc;
+pub mod oidc_groups;
+pub mod oidc_identity;
+pub mod oidc_logout;
+pub mod passkey;
+pub mod rate_limit;
+pub mod reauthentication;
+pub mod totp;
 pub use middleware::AuthenticationState;
 
 use crate::routes::users::EMAIL_REGEX;
 use crate::routes::users::UserInfo;
+use crate::server::mail::Mailer;
 
 use argon2::password_hash::rand_core::RngCore;
 use argon2::{
@@ -103,6 +113,7 @@ pub async fn create_user(
         first_name: Set(first_name),
         last_name: Set(last_name),
         is_oidc_user: Set(false),
+        email_verified: Set(false),
         ..Default::default()
     };
     let user = user
@@ -121,7 +132,7 @@ pub async fn create_user(
 /// * `session_key`: Session key. Can be any string
 ///
 /// returns: String
-fn hash_session_key(session_key: &str) -> String {
+pub(crate) fn hash_session_key(session_key: &str) -> String {
     let hash = blake3::hash(session_key.as_bytes());
     hash.to_hex().to_string()
 }
@@ -138,6 +149,7 @@ pub fn create_session_key() -> String {
 }
 
 const SESSION_EXPIRATION_DURATION: i64 = 5;
+const PASSWORD_RESET_EXPIRATION_DURATION: i64 = 30;
 
 /// Creates a new session in the database and links it to the supplied user
 ///
@@ -171,6 +183,37 @@ pub async fn create_session(
     Ok((session_key, expires_at))
 }
 
+/// Sets the session cookie returned by [`create_session`]
+pub(crate) fn add_session_cookie(
+    cookies: &tower_cookies::Cookies,
+    session_key: String,
+    expires_at: OffsetDateTime,
+) {
+    use tower_cookies::Cookie;
+    use tower_cookies::cookie::SameSite;
+
+    cookies.add(
+        Cookie::build((super::constants::SESSION_COOKIE_NAME, session_key))
+            .http_only(true)
+            .same_site(SameSite::Strict)
+            .secure(!cfg!(debug_assertions))
+            .path("/")
+            .expires(expires_at)
+            .build(),
+    );
+}
+
+/// Removes the session cookie set by [`add_session_cookie`]
+pub(crate) fn remove_session_cookie(cookies: &tower_cookies::Cookies) {
+    use tower_cookies::Cookie;
+
+    cookies.remove(
+        Cookie::build((super::constants::SESSION_COOKIE_NAME, ""))
+            .path("/")
+            .build(),
+    );
+}
+
 /// Searches the database for a user based on the unhashed session key. Returns `Ok(None)` if the session is expired
 ///
 /// # Arguments
@@ -200,16 +243,170 @@ pub async fn find_user_by_session(
     Ok(None)
 }
 
+/// Creates a single use password reset token for the supplied user. Only the hash of the token is stored.
+///
+/// # Arguments
+///
+/// * `user_id`: Id of the user the token will belong to
+/// * `db`: Connection to the database
+///
+/// returns: Result<String, Error> - The plain text token. Padding is stripped, so it can be used in urls as is.
+///     Returns an error when saving into the database fails
+pub async fn create_password_reset_token(
+    user_id: i32,
+    db: &DatabaseConnection,
+) -> Result<String, anyhow::Error> {
+    let token = create_session_key().trim_end_matches('=').to_string();
+
+    let now = OffsetDateTime::now_local()?;
+    let reset_token = entity::password_reset_token::ActiveModel {
+        token: Set(hash_session_key(&token)),
+        created_at: Set(now),
+        expires_at: Set(now + Duration::minutes(PASSWORD_RESET_EXPIRATION_DURATION)),
+        user_id: Set(user_id),
+        ..Default::default()
+    };
+    reset_token.insert(db).await?;
+
+    Ok(token)
+}
+
+/// Sends a password reset link to the password account registered with `email`. Does nothing if there is none.
+///
+/// # Arguments
+///
+/// * `email`: Address the user entered. Compared case-insensitively
+/// * `mailer`: Mailer used to send the link
+/// * `server_url`: Public url of the server the link points to
+/// * `db`: Connection to the database
+///
+/// # Errors
+///
+/// Returns an error when loading the user, saving the token or sending the email fails
+pub async fn send_password_reset_email(
+    email: &str,
+    mailer: &Mailer,
+    server_url: &str,
+    db: &DatabaseConnection,
+) -> Result<(), anyhow::Error> {
+    let email = email.trim().to_lowercase();
+    let Some(user) = User::find()
+        .filter(entity::user::Column::Email.eq(&email))
+        .filter(entity::user::Column::IsOidcUser.eq(false))
+        .one(db)
+        .await?
+    else {
+        return Ok(());
+    };
+
+    let token = create_password_reset_token(user.id, db).await?;
+    let body = format!(
+        "Hi {},\n\n\
+        someone requested to reset the password of your RoomMates account.\n\
+        Open the following link to choose a new password. It is valid for 30 minutes:\n\n\
+        {server_url}/reset-password?token={token}\n\n\
+        If you did not request this, you can ignore this email.",
+        user.first_name
+    );
+    mailer
+        .send(&user.email, "Reset your RoomMates password", body)
+        .await
+}
+
+/// Sets a new password for the owner of a valid password reset token.
+/// Consumes all reset tokens of the user and revokes all of their sessions.
+///
+/// # Arguments
+///
+/// * `token`: Plain text token the user received
+/// * `password`: The new password
+/// * `db`: Connection to the database
+///
+/// returns: Id of the user whose password was reset
+///
+/// # Errors
+///
+/// * Bad Request: The token is unknown or expired
+/// * Internal Server Error: A database operation failed
+pub async fn reset_password_with_token(
+    token: &str,
+    password: String,
+    db: &DatabaseConnection,
+) -> Result<i32, ServerFnError> {
+    use crate::server::audit::AuditEntry;
+    use entity::audit_log::{AuditAction, AuditTarget};
+    use entity::password_reset_token::Column as ResetTokenColumn;
+    use sea_orm::{IntoActiveModel, TransactionTrait};
+
+    (!password.is_empty()).or_bad_request("Password must not be empty")?;
+
+    let now = OffsetDateTime::now_local().or_internal_server_error("Failed to get current time")?;
+    let reset_token = PasswordResetToken::find()
+        .filter(ResetTokenColumn::Token.eq(hash_session_key(token)))
+        .filter(ResetTokenColumn::ExpiresAt.gt(now))
+        .one(db)
+        .await
+        .or_internal_server_error("Error loading password reset token")?
+        .or_bad_request("Password reset link is invalid or expired")?;
+
+    let user = reset_token
+        .find_related(User)
+        .one(db)
+        .await
+        .or_internal_server_error("Error loading user from database")?
+        .or_not_found("User not found")?;
+    (!user.is_oidc_user).or_bad_request("OIDC Users cannot reset their password")?;
+
+    let hashed_password = hash_password(password)?;
+    let user_id = user.id;
+
+    db.transaction::<_, (), sea_orm::DbErr>(|txn| {
+        Box::pin(async move {
+            let mut user = user.into_active_model();
+            user.password = Set(Some(hashed_password));
+            user.update(txn).await?;
+
+            PasswordResetToken::delete_many()
+                .filter(ResetTokenColumn::UserId.eq(user_id))
+                .exec(txn)
+                .await?;
+            Session::delete_many()
+                .filter(entity::session::Column::UserId.eq(user_id))
+                .exec(txn)
+                .await?;
+            Ok(())
+        })
+    })
+    .await
+    .inspect_err(|e| error!("Error resetting password: {e}"))
+    .or_internal_server_error("Failed to reset password")?;
+
+    AuditEntry::new(
+        Some(user_id),
+        AuditAction::PasswordReset,
+        AuditTarget::User,
+        user_id,
+    )
+    .record(db)
+    .await;
+
+    Ok(user_id)
+}
+
 pub async fn find_user_by_email(
     email: &str,
+    verification_required: bool,
     db: &DatabaseConnection,
 ) -> dioxus::Result<UserInfo, ServerFnError> {
     use crate::routes::users::UserInfo;
     use entity::user::Entity as User;
-    use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+    use sea_orm::{EntityTrait, QueryFilter};
 
     let user_option = User::find()
-        .filter(entity::user::Column::Email.eq(email))
+        .filter(email_verification::addressable_by_email(
+            email,
+            verification_required,
+        ))
         .one(db)
         .await
         .or_internal_server_error("Error loading user from database")?;
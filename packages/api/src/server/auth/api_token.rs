@@ -0,0 +1,209 @@
+use crate::routes::users::api_tokens::ApiScope;
+use crate::server::auth::{create_session_key, hash_session_key};
+use dioxus::server::axum::http::Method;
+use entity::api_token::Column as ApiTokenColumn;
+use entity::prelude::*;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, ModelTrait,
+    QueryFilter, Set,
+};
+use time::{Duration, OffsetDateTime};
+
+/// Prefix of all personal API tokens. Tells them apart from OIDC access tokens in the `Authorization` header
+pub const API_TOKEN_PREFIX: &str = "rmt_";
+
+/// Creates a new API token for the supplied user. Only the hash of the token is stored.
+///
+/// returns: Result<(String, Model), Error> - The plain text token and the stored model
+///
+/// # Errors
+///
+/// Returns an error when saving into the database fails
+pub async fn create_api_token(
+    user_id: i32,
+    name: String,
+    scopes: &[ApiScope],
+    lifetime_days: i32,
+    db: &DatabaseConnection,
+) -> Result<(String, entity::api_token::Model), anyhow::Error> {
+    let token = format!(
+        "{API_TOKEN_PREFIX}{}",
+        create_session_key().trim_end_matches('=')
+    );
+    let token_hint = token[token.len() - 4..].to_string();
+
+    let mut scopes: Vec<&str> = scopes.iter().map(ApiScope::as_str).collect();
+    scopes.sort_unstable();
+    scopes.dedup();
+
+    let now = OffsetDateTime::now_local()?;
+    let api_token = entity::api_token::ActiveModel {
+        name: Set(name),
+        token: Set(hash_session_key(&token)),
+        token_hint: Set(token_hint),
+        scopes: Set(scopes.join(",")),
+        created_at: Set(now),
+        expires_at: Set(now + Duration::days(i64::from(lifetime_days))),
+        last_used_at: Set(None),
+        user_id: Set(user_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok((token, api_token))
+}
+
+/// Searches the database for the owner of an unexpired API token and marks the token as used
+///
+/// # Arguments
+///
+/// * `token`: plain API token
+/// * `db`: Connection to the database
+///
+/// returns: Result<Option<(entity::user::Model, Vec<ApiScope>)>, Error> - The owner and the scopes granted by the token
+///
+/// # Errors
+///
+/// Returns an error if a database operation fails
+pub async fn find_user_by_api_token(
+    token: &str,
+    db: &DatabaseConnection,
+) -> Result<Option<(entity::user::Model, Vec<ApiScope>)>, anyhow::Error> {
+    let now = OffsetDateTime::now_local()?;
+    let Some(api_token) = ApiToken::find()
+        .filter(ApiTokenColumn::Token.eq(hash_session_key(token)))
+        .filter(ApiTokenColumn::ExpiresAt.gt(now))
+        .one(db)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let Some(user) = api_token.find_related(User).one(db).await? else {
+        return Ok(None);
+    };
+    let scopes = api_token
+        .scopes
+        .split(',')
+        .filter_map(ApiScope::parse)
+        .collect();
+
+    let mut api_token = api_token.into_active_model();
+    api_token.last_used_at = Set(Some(now));
+    api_token.update(db).await?;
+
+    Ok(Some((user, scopes)))
+}
+
+/// Checks whether a token with the supplied scopes may send the request.
+/// Tokens are limited to the event, todo and group routes plus `/api/me`, both with and without the `/api/v1`
+/// prefix. Everything else, e.g. account management, stays exclusive to interactive logins.
+#[must_use]
+pub fn is_request_allowed(scopes: &[ApiScope], method: &Method, path: &str) -> bool {
+    let is_read = *method == Method::GET || *method == Method::HEAD;
+    let path = path
+        .strip_prefix("/api/v1/")
+        .map_or_else(|| path.to_string(), |rest| format!("/api/{rest}"));
+    let matches_prefix = |prefix: &str| {
+        path == prefix
+            || path
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('/'))
+    };
+
+    let required_scope = if matches_prefix("/api/me") || path == "/api/openapi.json" {
+        return is_read;
+    } else if matches_prefix("/api/events") || matches_prefix("/api/event-invitations") {
+        if is_read {
+            ApiScope::ReadEvents
+        } else {
+            ApiScope::WriteEvents
+        }
+    } else if matches_prefix("/api/todos") || matches_prefix("/api/todolists") {
+        if is_read {
+            ApiScope::ReadTodos
+        } else {
+            ApiScope::WriteTodos
+        }
+    } else if matches_prefix("/api/groups") {
+        if is_read {
+            ApiScope::ReadGroups
+        } else {
+            ApiScope::WriteGroups
+        }
+    } else {
+        return false;
+    };
+
+    scopes.contains(&required_scope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_are_enforced_per_route() {
+        let scopes = [ApiScope::ReadEvents, ApiScope::WriteTodos];
+
+        assert!(is_request_allowed(&scopes, &Method::GET, "/api/events"));
+        assert!(is_request_allowed(&scopes, &Method::GET, "/api/events/1"));
+        assert!(!is_request_allowed(&scopes, &Method::POST, "/api/events"));
+        assert!(is_request_allowed(&scopes, &Method::PATCH, "/api/todos/3"));
+        assert!(is_request_allowed(
+            &scopes,
+            &Method::POST,
+            "/api/todolists/1/todos"
+        ));
+        assert!(!is_request_allowed(&scopes, &Method::GET, "/api/groups"));
+        assert!(is_request_allowed(&scopes, &Method::GET, "/api/me"));
+    }
+
+    #[test]
+    fn scopes_apply_to_versioned_api() {
+        let scopes = [ApiScope::ReadEvents, ApiScope::WriteTodos];
+
+        assert!(is_request_allowed(&scopes, &Method::GET, "/api/v1/events"));
+        assert!(is_request_allowed(
+            &scopes,
+            &Method::GET,
+            "/api/v1/event-invitations"
+        ));
+        assert!(!is_request_allowed(
+            &scopes,
+            &Method::POST,
+            "/api/v1/event-invitations/1/accept"
+        ));
+        assert!(is_request_allowed(
+            &scopes,
+            &Method::PATCH,
+            "/api/v1/todos/3"
+        ));
+        assert!(!is_request_allowed(&scopes, &Method::GET, "/api/v1/groups"));
+        assert!(is_request_allowed(
+            &scopes,
+            &Method::GET,
+            "/api/v1/openapi.json"
+        ));
+        assert!(!is_request_allowed(&scopes, &Method::GET, "/api/v1/users"));
+    }
+
+    #[test]
+    fn account_management_is_never_allowed() {
+        let scopes = ApiScope::ALL;
+
+        assert!(!is_request_allowed(
+            &scopes,
+            &Method::GET,
+            "/api/users/api-tokens"
+        ));
+        assert!(!is_request_allowed(
+            &scopes,
+            &Method::PUT,
+            "/api/users/password"
+        ));
+        assert!(!is_request_allowed(&scopes, &Method::POST, "/api/logout"));
+        assert!(!is_request_allowed(&scopes, &Method::GET, "/api/eventsfoo"));
+    }
+}
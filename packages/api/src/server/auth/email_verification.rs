@@ -0,0 +1,138 @@
+use crate::server::auth::{create_session_key, hash_session_key};
+use crate::server::mail::Mailer;
+use dioxus::prelude::*;
+use entity::email_verification_token::Column as VerificationTokenColumn;
+use entity::prelude::*;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, IntoActiveModel,
+    ModelTrait, QueryFilter, Set, TransactionTrait,
+};
+use time::{Duration, OffsetDateTime};
+
+const EMAIL_VERIFICATION_EXPIRATION_DURATION: i64 = 24;
+
+/// Condition matching the user that can be addressed by `email`, e.g. when sending invitations.
+/// Unverified addresses are ignored when verification is required, so nobody can receive invitations meant for
+/// someone else by signing up with their address.
+#[must_use]
+pub fn addressable_by_email(email: &str, verification_required: bool) -> Condition {
+    let condition = Condition::all().add(entity::user::Column::Email.eq(email));
+    if verification_required {
+        condition.add(entity::user::Column::EmailVerified.eq(true))
+    } else {
+        condition
+    }
+}
+
+/// Creates a verification token for `email` and sends a link containing it to that address.
+/// Only the hash of the token is stored.
+///
+/// # Arguments
+///
+/// * `user`: The user the email should belong to
+/// * `email`: Address to verify. May differ from the users current email when changing it
+/// * `mailer`: Mailer used to send the link
+/// * `server_url`: Public url of the server the link points to
+/// * `db`: Connection to the database
+///
+/// # Errors
+///
+/// Returns an error when saving the token or sending the email fails
+pub async fn send_verification_email(
+    user: &entity::user::Model,
+    email: &str,
+    mailer: &Mailer,
+    server_url: &str,
+    db: &DatabaseConnection,
+) -> Result<(), anyhow::Error> {
+    let token = create_session_key().trim_end_matches('=').to_string();
+
+    let now = OffsetDateTime::now_local()?;
+    let verification_token = entity::email_verification_token::ActiveModel {
+        token: Set(hash_session_key(&token)),
+        email: Set(email.to_string()),
+        created_at: Set(now),
+        expires_at: Set(now + Duration::hours(EMAIL_VERIFICATION_EXPIRATION_DURATION)),
+        user_id: Set(user.id),
+        ..Default::default()
+    };
+    verification_token.insert(db).await?;
+
+    let body = format!(
+        "Hi {},\n\n\
+        please confirm that {email} belongs to your RoomMates account by opening the following link. \
+        It is valid for 24 hours:\n\n\
+        {server_url}/verify-email?token={token}\n\n\
+        If you did not expect this email, you can ignore it.",
+        user.first_name,
+    );
+    mailer
+        .send(email, "Verify your RoomMates email address", body)
+        .await
+}
+
+/// Marks the address stored with the token as the verified email of its user and consumes all of the users
+/// verification tokens.
+///
+/// # Errors
+///
+/// * Bad Request: The token is unknown or expired
+/// * Conflict: The address was taken by another account in the meantime
+/// * Internal Server Error: A database operation failed
+pub async fn verify_email_token(
+    token: &str,
+    db: &DatabaseConnection,
+) -> Result<entity::user::Model, ServerFnError> {
+    let now = OffsetDateTime::now_local().or_internal_server_error("Failed to get current time")?;
+    let verification_token = EmailVerificationToken::find()
+        .filter(VerificationTokenColumn::Token.eq(hash_session_key(token)))
+        .filter(VerificationTokenColumn::ExpiresAt.gt(now))
+        .one(db)
+        .await
+        .or_internal_server_error("Error loading verification token")?
+        .or_bad_request("Verification link is invalid or expired")?;
+
+    let user = verification_token
+        .find_related(User)
+        .one(db)
+        .await
+        .or_internal_server_error("Error loading user from database")?
+        .or_not_found("User not found")?;
+
+    let email_taken = User::find()
+        .filter(entity::user::Column::Email.eq(&verification_token.email))
+        .filter(entity::user::Column::Id.ne(user.id))
+        .one(db)
+        .await
+        .or_internal_server_error("Error loading user from database")?
+        .is_some();
+    if email_taken {
+        return Err(ServerFnError::ServerError {
+            message: "Email already registered".to_string(),
+            code: 409,
+            details: None,
+        });
+    }
+
+    let user_id = user.id;
+    let user = db
+        .transaction::<_, entity::user::Model, sea_orm::DbErr>(|txn| {
+            Box::pin(async move {
+                let mut user = user.into_active_model();
+                user.email = Set(verification_token.email);
+                user.email_verified = Set(true);
+                let user = user.update(txn).await?;
+
+                EmailVerificationToken::delete_many()
+                    .filter(VerificationTokenColumn::UserId.eq(user_id))
+                    .exec(txn)
+                    .await?;
+                Ok(user)
+            })
+        })
+        .await
+        .inspect_err(|e| error!("Error verifying email: {e}"))
+        .or_internal_server_error("Failed to verify email")?;
+
+    Ok(user)
+}
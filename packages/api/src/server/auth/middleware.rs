@@ -1,6 +1,10 @@
+use crate::server::auth::api_token::{
+    API_TOKEN_PREFIX, find_user_by_api_token, is_request_allowed,
+};
 use crate::server::auth::find_user_by_session;
 use crate::server::auth::oidc::{
     add_oidc_cookies, get_user_from_authorization_token, refresh_authorization_token,
+    sync_groups_after_refresh,
 };
 use crate::server::{AppState, constants};
 use anyhow::anyhow;
@@ -30,6 +34,18 @@ impl AuthenticationState {
         self.user.is_none()
     }
 
+    /// Whether the user is authenticated using a local session
+    #[must_use]
+    pub fn has_session(&self) -> bool {
+        self.session_id.is_some()
+    }
+
+    /// Id of the local session the user is authenticated with
+    #[must_use]
+    pub fn session_id(&self) -> Option<i32> {
+        self.session_id
+    }
+
     /// Logs the authenticated user out
     ///
     /// # Errors
@@ -55,9 +71,10 @@ fn extract_bearer(value: &str) -> Option<&str> {
 
 /// Middleware handling the auth state of a request.
 /// Auth gets checked in the following order:\
-/// Header (Authorization Token) -> Cookie (Session Token) -> Cookie (Authorization Token) -> Cookie (Refresh Token)
+/// Header (API Token or Authorization Token) -> Cookie (Session Token) -> Cookie (Authorization Token) -> Cookie (Refresh Token)
 ///
 /// If the authorization using session and authorization token fails, the session gets refreshed using the OIDC provider.
+/// Requests using an API token are rejected, if the token is invalid or lacks the scope for the requested route.
 ///
 /// # Arguments
 ///
@@ -68,6 +85,8 @@ fn extract_bearer(value: &str) -> Option<&str> {
 ///
 /// # Errors
 /// * [`StatusCode::INTERNAL_SERVER_ERROR`]: If extracting either cookies or the app state fails
+/// * [`StatusCode::UNAUTHORIZED`]: If the API token is unknown or expired
+/// * [`StatusCode::FORBIDDEN`]: If the API token lacks the scope for the requested route
 pub async fn authentication_middleware(
     mut request: Request,
     next: Next,
@@ -88,9 +107,26 @@ pub async fn authentication_middleware(
         .get::<Cookies>()
         .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    if let Some(token) = request.headers().get("Authorization")
-        && let Ok(token) = token.to_str()
-        && let Some(token) = extract_bearer(token)
+    let bearer_token = request
+        .headers()
+        .get("Authorization")
+        .and_then(|token| token.to_str().ok())
+        .and_then(extract_bearer);
+
+    if let Some(token) = bearer_token
+        && token.starts_with(API_TOKEN_PREFIX)
+    {
+        let (user, scopes) = find_user_by_api_token(token, database)
+            .await
+            .inspect_err(|e| error!("Error validating API token: {e}"))
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .ok_or(StatusCode::UNAUTHORIZED)?;
+        if !is_request_allowed(&scopes, request.method(), request.uri().path()) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        authentication_state.user = Some(user);
+    } else if let Some(token) = bearer_token
+        && !app_state.oidc_providers.is_empty()
         && let Ok(user) = get_user_from_authorization_token(token, app_state).await
     {
         authentication_state.user = user;
@@ -106,14 +142,22 @@ pub async fn authentication_middleware(
         authentication_state.user = user;
     } else if let Some(cookie) = cookies.get(constants::OIDC_REFRESH_COOKIE_NAME)
         && let Some(refresh_token) = extract_bearer(cookie.value())
+        && let Some(provider) = app_state.oidc_providers.from_cookies(cookies)
     {
-        match refresh_authorization_token(refresh_token, app_state).await {
+        match refresh_authorization_token(refresh_token, provider).await {
             Ok(token_response) => {
-                if let Err(err) = add_oidc_cookies(cookies, &token_response) {
+                if let Err(err) = add_oidc_cookies(cookies, &token_response, provider) {
                     error!("Error adding OAuth Cookies: {err}");
                 }
                 let access_token = token_response.access_token().secret();
                 if let Ok(user) = get_user_from_authorization_token(access_token, app_state).await {
+                    if let Some(user) = &user
+                        && let Err(err) =
+                            sync_groups_after_refresh(user.id, &token_response, provider, database)
+                                .await
+                    {
+                        error!("Error syncing OIDC groups: {err}");
+                    }
                     authentication_state.user = user;
                 }
             }
@@ -1,25 +1,32 @@
 use crate::server;
-use crate::server::utils::get_env_or;
+use crate::server::auth::oidc_groups::{GroupSync, sync_groups};
+use crate::server::auth::oidc_identity::{
+    ClaimMapping, OidcProfile, ProvisioningRules, decode_id_token_claims, find_user_by_identity,
+};
+use crate::server::settings::{OidcProviderSettings, Settings};
+use anyhow::{Context, anyhow};
 use dioxus::prelude::*;
-use entity::prelude::User;
 use jsonwebtoken::jwk::JwkSet;
 use jsonwebtoken::{DecodingKey, Validation};
 use openidconnect::core::{
-    CoreAuthDisplay, CoreAuthPrompt, CoreAuthenticationFlow, CoreClient, CoreErrorResponseType,
-    CoreGenderClaim, CoreJsonWebKey, CoreJweContentEncryptionAlgorithm, CoreProviderMetadata,
-    CoreRevocableToken, CoreRevocationErrorResponse, CoreTokenIntrospectionResponse,
-    CoreTokenResponse,
+    CoreAuthDisplay, CoreAuthPrompt, CoreAuthenticationFlow, CoreClaimName, CoreClaimType,
+    CoreClient, CoreClientAuthMethod, CoreErrorResponseType, CoreGenderClaim, CoreGrantType,
+    CoreJsonWebKey, CoreJweContentEncryptionAlgorithm, CoreJweKeyManagementAlgorithm,
+    CoreResponseMode, CoreResponseType, CoreRevocableToken, CoreRevocationErrorResponse,
+    CoreSubjectIdentifierType, CoreTokenIntrospectionResponse, CoreTokenResponse,
 };
 use openidconnect::url::Url;
 use openidconnect::{
-    AuthorizationCode, Client, ClientId, ClientSecret, CsrfToken, EmptyAdditionalClaims,
-    EndpointMaybeSet, EndpointNotSet, EndpointSet, IssuerUrl, JsonWebKeySetUrl, Nonce,
-    OAuth2TokenResponse, PkceCodeChallenge, PkceCodeVerifier, RedirectUrl, RefreshToken, Scope,
-    StandardErrorResponse, reqwest,
+    AdditionalProviderMetadata, AuthorizationCode, Client, ClientId, ClientSecret, CsrfToken,
+    EmptyAdditionalClaims, EndSessionUrl, EndpointMaybeSet, EndpointNotSet, EndpointSet, IssuerUrl,
+    JsonWebKeySetUrl, Nonce, OAuth2TokenResponse, PkceCodeChallenge, PkceCodeVerifier,
+    ProviderMetadata, RedirectUrl, RefreshToken, RevocationUrl, Scope, StandardErrorResponse,
+    TokenResponse, reqwest,
 };
+use sea_orm::DatabaseConnection;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use server::{AppState, constants};
-use std::env;
 use std::sync::Arc;
 use std::time::Duration;
 use time::ext::NumericalDuration;
@@ -47,36 +54,175 @@ pub type OidcClient = Client<
     EndpointMaybeSet,
 >;
 
+/// Logout endpoints of the provider, which are not part of the core discovery metadata
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LogoutEndpoints {
+    /// Endpoint of the RP-initiated logout the browser is redirected to
+    pub end_session_endpoint: Option<EndSessionUrl>,
+    /// Endpoint revoking refresh tokens ([RFC 7009](https://www.rfc-editor.org/rfc/rfc7009))
+    pub revocation_endpoint: Option<RevocationUrl>,
+}
+
+impl AdditionalProviderMetadata for LogoutEndpoints {}
+
+pub type OidcProviderMetadata = ProviderMetadata<
+    LogoutEndpoints,
+    CoreAuthDisplay,
+    CoreClientAuthMethod,
+    CoreClaimName,
+    CoreClaimType,
+    CoreGrantType,
+    CoreJweContentEncryptionAlgorithm,
+    CoreJweKeyManagementAlgorithm,
+    CoreJsonWebKey,
+    CoreResponseMode,
+    CoreResponseType,
+    CoreSubjectIdentifierType,
+>;
+
 #[derive(Clone)]
 pub struct JwksState {
     pub jwks: Arc<RwLock<JwkSet>>,
     pub jwks_uri: JsonWebKeySetUrl,
 }
 
+/// Id of the provider configured by the unprefixed `OIDC_*` variables
+pub const DEFAULT_PROVIDER_ID: &str = "default";
+
+/// Environment variables of a single provider. The default provider reads the `OIDC_*` variables,
+/// named providers read `OIDC_<ID>_*`, e.g. `OIDC_COMPANY_CLIENT_ID` for the provider `company`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProviderEnv {
+    pub id: String,
+}
+
+impl ProviderEnv {
+    #[must_use]
+    pub fn new(id: impl Into<String>) -> Self {
+        Self { id: id.into() }
+    }
+
+    /// Name of the variable of this provider, e.g. `OIDC_COMPANY_CLIENT_ID` for `OIDC_CLIENT_ID`
+    #[must_use]
+    pub fn var_name(&self, name: &str) -> String {
+        if self.id == DEFAULT_PROVIDER_ID {
+            return name.to_string();
+        }
+        let suffix = name.strip_prefix("OIDC_").unwrap_or(name);
+        format!("OIDC_{}_{suffix}", self.id.to_uppercase().replace('-', "_"))
+    }
+
+    /// Path the provider redirects to after the login. The default provider keeps the path used before
+    /// multiple providers were supported
+    #[must_use]
+    pub fn redirect_path(&self) -> String {
+        if self.id == DEFAULT_PROVIDER_ID {
+            "/api/oidc/redirect".to_string()
+        } else {
+            format!("/api/oidc/{}/redirect", self.id)
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct OidcConfig {
+    /// Identifies the provider in urls, cookies and environment variables
+    pub id: String,
+    /// Name displayed on the login button
+    pub name: String,
     pub client: OidcClient,
-    pub metadata: CoreProviderMetadata,
+    /// Needed to authenticate requests the [`OidcClient`] does not support, e.g. token revocation
+    pub(crate) client_secret: ClientSecret,
+    pub metadata: OidcProviderMetadata,
     pub jwks_state: JwksState,
+    pub jwks_refresh_interval: time::Duration,
+    pub scopes: Vec<String>,
+    pub audiences: Vec<String>,
+    pub claim_mapping: ClaimMapping,
+    pub provisioning: ProvisioningRules,
+    pub group_sync: GroupSync,
 }
 
 impl OidcConfig {
     #[must_use]
     pub fn new(
+        settings: &OidcProviderSettings,
         client: OidcClient,
-        metadata: CoreProviderMetadata,
+        client_secret: ClientSecret,
+        metadata: OidcProviderMetadata,
         jwks: JwkSet,
         jwks_uri: JsonWebKeySetUrl,
     ) -> Self {
+        let default_name = if settings.id == DEFAULT_PROVIDER_ID {
+            "SSO".to_string()
+        } else {
+            settings.id.clone()
+        };
         Self {
+            id: settings.id.clone(),
+            name: settings.name.clone().unwrap_or(default_name),
+            jwks_refresh_interval: time::Duration::seconds(
+                settings
+                    .jwks_refresh_interval
+                    .try_into()
+                    .unwrap_or(i64::MAX),
+            ),
+            scopes: settings.scopes.clone(),
+            audiences: settings
+                .audiences
+                .clone()
+                .unwrap_or_else(|| vec![client.client_id().as_str().to_string()]),
             client,
+            client_secret,
             metadata,
             jwks_state: JwksState {
                 jwks: Arc::new(RwLock::new(jwks)),
                 jwks_uri,
             },
+            claim_mapping: settings.claims.clone(),
+            provisioning: settings.provisioning.clone(),
+            group_sync: settings.group_sync.clone(),
+        }
+    }
+}
+
+/// All configured providers. Cloning is cheap, so it can live in the [`AppState`]
+#[derive(Clone, Default)]
+pub struct OidcProviders {
+    providers: Arc<Vec<OidcConfig>>,
+}
+
+impl OidcProviders {
+    #[must_use]
+    pub fn new(providers: Vec<OidcConfig>) -> Self {
+        Self {
+            providers: Arc::new(providers),
         }
     }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    #[must_use]
+    pub fn get(&self, id: &str) -> Option<&OidcConfig> {
+        self.providers.iter().find(|provider| provider.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &OidcConfig> {
+        self.providers.iter()
+    }
+
+    /// Returns the provider that issued the tokens stored in the cookies. Falls back to the first provider for
+    /// cookies set before multiple providers were supported
+    #[must_use]
+    pub fn from_cookies(&self, cookies: &Cookies) -> Option<&OidcConfig> {
+        cookies
+            .get(constants::OIDC_PROVIDER_COOKIE_NAME)
+            .and_then(|cookie| self.get(cookie.value()))
+            .or_else(|| self.providers.first())
+    }
 }
 
 impl From<OidcConfig> for OidcClient {
@@ -123,35 +269,93 @@ pub(crate) async fn jwks_refresh_loop(state: JwksState, refresh_duration: time::
     }
 }
 
-pub(crate) async fn create_oidc_config() -> Result<OidcConfig, anyhow::Error> {
+/// Discovers the provider and creates its client. The settings have to be validated before
+pub(crate) async fn create_oidc_config(
+    settings: &OidcProviderSettings,
+    server_url: &str,
+) -> Result<OidcConfig, anyhow::Error> {
     let http_client = build_http_client()?;
-
-    let provider_metadata = CoreProviderMetadata::discover_async(
-        IssuerUrl::new(env::var(constants::OIDC_ISSUER_URL_ENV_VAR)?)?,
+    let required = |value: &Option<String>, name: &str| {
+        value
+            .clone()
+            .with_context(|| format!("{name} of OIDC provider {} is not set", settings.id))
+    };
+
+    let provider_metadata = OidcProviderMetadata::discover_async(
+        IssuerUrl::new(required(&settings.issuer_url, "Issuer url")?)?,
         &http_client,
     )
     .await?;
     let jwks_uri = provider_metadata.jwks_uri().clone();
 
-    let redirect_url = format!(
-        "{}/api/oidc/redirect",
-        env::var(constants::SERVER_URL_ENV_VAR)?
-    );
+    let redirect_url = format!("{server_url}{}", settings.env().redirect_path());
+    let client_secret = ClientSecret::new(required(&settings.client_secret, "Client secret")?);
     let client = CoreClient::from_provider_metadata(
         provider_metadata.clone(),
-        ClientId::new(env::var(constants::OIDC_CLIENT_ID_ENV_VAR)?),
-        Some(ClientSecret::new(env::var(
-            constants::OIDC_CLIENT_SECRET_ENV_VAR,
-        )?)),
+        ClientId::new(required(&settings.client_id, "Client id")?),
+        Some(client_secret.clone()),
     )
     .set_redirect_uri(RedirectUrl::new(redirect_url)?);
 
     let jwks = fetch_jwks(&jwks_uri).await?;
 
-    Ok(OidcConfig::new(client, provider_metadata, jwks, jwks_uri))
+    Ok(OidcConfig::new(
+        settings,
+        client,
+        client_secret,
+        provider_metadata,
+        jwks,
+        jwks_uri,
+    ))
+}
+
+/// Parses the ids listed in `OIDC_PROVIDERS`. Ids may only contain lowercase letters, digits and dashes
+///
+/// # Errors
+///
+/// Returns an error for invalid or duplicate ids
+pub fn parse_provider_ids(value: &str) -> Result<Vec<String>, anyhow::Error> {
+    let mut ids: Vec<String> = Vec::new();
+    for id in value.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+        let id = id.to_lowercase();
+        if !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(anyhow!("Invalid OIDC provider id: {id}"));
+        }
+        if ids.contains(&id) {
+            return Err(anyhow!("Duplicate OIDC provider id: {id}"));
+        }
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+/// Discovers all configured providers if OIDC is enabled
+///
+/// # Errors
+///
+/// Returns an error if the discovery of a provider fails
+pub(crate) async fn create_oidc_providers(
+    settings: &Settings,
+) -> Result<OidcProviders, anyhow::Error> {
+    if !settings.oidc.enabled {
+        return Ok(OidcProviders::default());
+    }
+
+    let mut providers = Vec::with_capacity(settings.oidc.providers.len());
+    for provider_settings in &settings.oidc.providers {
+        let provider = create_oidc_config(provider_settings, &settings.server_url)
+            .await
+            .with_context(|| {
+                format!("Failed to configure OIDC provider {}", provider_settings.id)
+            })?;
+        info!("Configured OIDC provider {}", provider.id);
+        providers.push(provider);
+    }
+    Ok(OidcProviders::new(providers))
 }
 
 pub struct OidcMetadata {
+    pub provider_id: String,
     pub url: Url,
     pub csrf_token: CsrfToken,
     pub pkce_code_verifier: PkceCodeVerifier,
@@ -163,6 +367,15 @@ pub struct OidcSession {
     pub(crate) pkce_code_verifier: PkceCodeVerifier,
     pub(crate) csrf_token: CsrfToken,
     pub(crate) nonce: Nonce,
+    /// Provider the login was started with
+    #[serde(default)]
+    pub(crate) provider_id: String,
+    /// Set if the identity should be linked to the logged in user instead of logging in
+    #[serde(default)]
+    pub(crate) link_user_id: Option<i32>,
+    /// Set if the logged in user authenticates again instead of logging in
+    #[serde(default)]
+    pub(crate) reauthenticate_user_id: Option<i32>,
 }
 
 impl From<OidcMetadata> for OidcSession {
@@ -171,30 +384,37 @@ impl From<OidcMetadata> for OidcSession {
             pkce_code_verifier: value.pkce_code_verifier,
             nonce: value.nonce,
             csrf_token: value.csrf_token,
+            provider_id: value.provider_id,
+            link_user_id: None,
+            reauthenticate_user_id: None,
         }
     }
 }
 
-pub(crate) fn create_oidc_challenge(client: &OidcClient) -> OidcMetadata {
+/// Creates the authorization request of a login. With `force_login` the provider has to authenticate the user
+/// again even if they are still logged in there, and reports when in the `auth_time` claim
+pub(crate) fn create_oidc_challenge(provider: &OidcConfig, force_login: bool) -> OidcMetadata {
     let (pkce_challenge, pkce_code_verifier) = PkceCodeChallenge::new_random_sha256();
-    let mut authorization_request = client.authorize_url(
+    let mut authorization_request = provider.client.authorize_url(
         CoreAuthenticationFlow::AuthorizationCode,
         CsrfToken::new_random,
         Nonce::new_random,
     );
 
-    let env_scopes =
-        env::var(constants::OIDC_SCOPES_ENV_VAR).unwrap_or("openid email profile".to_string());
-    let scopes = env_scopes.split_whitespace();
-
-    for scope in scopes {
-        authorization_request = authorization_request.add_scope(Scope::new(scope.to_string()));
+    for scope in &provider.scopes {
+        authorization_request = authorization_request.add_scope(Scope::new(scope.clone()));
     }
     authorization_request = authorization_request.set_pkce_challenge(pkce_challenge);
+    if force_login {
+        authorization_request = authorization_request
+            .add_prompt(CoreAuthPrompt::Login)
+            .set_max_age(Duration::ZERO);
+    }
 
     let (auth_url, csrf_token, nonce) = authorization_request.url();
 
     OidcMetadata {
+        provider_id: provider.id.clone(),
         url: auth_url,
         pkce_code_verifier,
         nonce,
@@ -238,13 +458,26 @@ pub struct Claims {
     #[serde(default)]
     pub aud: Option<serde_json::Value>,
     pub exp: usize,
-    pub email: String,
+    #[serde(default)]
+    pub iat: Option<i64>,
+    #[serde(default)]
+    pub email: Option<String>,
 }
 
 pub(crate) async fn validate_authorization_token(
     oidc_config: &OidcConfig,
     token: &str,
 ) -> Result<Claims, anyhow::Error> {
+    validate_provider_token(oidc_config, token, &oidc_config.audiences, &["exp"]).await
+}
+
+/// Validates the signature, issuer and audience of a JWT issued by the provider and decodes its claims
+pub(crate) async fn validate_provider_token<T: DeserializeOwned>(
+    oidc_config: &OidcConfig,
+    token: &str,
+    audiences: &[String],
+    required_claims: &[&str],
+) -> Result<T, anyhow::Error> {
     let header = jsonwebtoken::decode_header(token)?;
     let kid = header.kid.ok_or(anyhow::anyhow!("Missing kid"))?;
     let jwk_lock = oidc_config.jwks_state.jwks.read().await;
@@ -252,12 +485,8 @@ pub(crate) async fn validate_authorization_token(
 
     let mut validation = Validation::new(header.alg);
     validation.set_issuer(&[oidc_config.metadata.issuer()]);
-    let audiences = get_env_or(
-        constants::OIDC_AUDIENCE_ENV_VAR,
-        vec![oidc_config.client.client_id().as_str().to_string()],
-        |val| val.trim().split(',').map(ToString::to_string).collect(),
-    );
-    validation.set_audience(&audiences);
+    validation.set_audience(audiences);
+    validation.set_required_spec_claims(required_claims);
 
     let token_data = jsonwebtoken::decode(token, &DecodingKey::from_jwk(jwk)?, &validation)?;
 
@@ -268,26 +497,32 @@ pub(crate) async fn get_user_from_authorization_token(
     token: &str,
     app_state: &AppState,
 ) -> Result<Option<entity::user::Model>, anyhow::Error> {
-    let oidc_config = app_state.oidc_config.as_ref().expect("OIDC is disabled!");
-
-    let claims = validate_authorization_token(oidc_config, token)
-        .await
-        .inspect_err(|e| error!("Error validating OIDC claims: {}", e))?;
-    let user = User::find_by_email(claims.email)
-        .one(&app_state.database)
-        .await?;
-    Ok(user)
+    // Each provider only knows its own signing keys, so the token is valid for at most one of them
+    let mut validation_error = anyhow!("No OIDC provider configured");
+    for provider in app_state.oidc_providers.iter() {
+        match validate_authorization_token(provider, token).await {
+            Ok(claims) => {
+                let user = find_user_by_identity(
+                    &claims.iss,
+                    &claims.sub,
+                    claims.iat,
+                    &app_state.database,
+                )
+                .await?;
+                return Ok(user);
+            }
+            Err(e) => validation_error = e,
+        }
+    }
+    error!("Error validating OIDC claims: {validation_error}");
+    Err(validation_error)
 }
 
 pub(crate) async fn refresh_authorization_token(
     refresh_token: &str,
-    app_state: &AppState,
+    provider: &OidcConfig,
 ) -> Result<CoreTokenResponse, anyhow::Error> {
-    let oidc_client = &app_state
-        .oidc_config
-        .as_ref()
-        .expect("OIDC is disabled!")
-        .client;
+    let oidc_client = &provider.client;
     let http_client = build_http_client()?;
 
     let refresh_token = RefreshToken::new(refresh_token.to_owned());
@@ -296,9 +531,37 @@ pub(crate) async fn refresh_authorization_token(
     Ok(new_token)
 }
 
+/// Syncs the groups of the user with the ID token returned when refreshing the tokens.
+/// Nothing is changed if the provider does not return an ID token or the token lacks the groups claim
+pub(crate) async fn sync_groups_after_refresh(
+    user_id: i32,
+    token_response: &CoreTokenResponse,
+    oidc_config: &OidcConfig,
+    db: &DatabaseConnection,
+) -> Result<(), anyhow::Error> {
+    if !oidc_config.group_sync.is_enabled() {
+        return Ok(());
+    }
+    let Some(id_token) = token_response.id_token() else {
+        return Ok(());
+    };
+    // Refreshed ID tokens carry no nonce, only the signature is verified
+    id_token.claims(
+        &oidc_config.client.id_token_verifier(),
+        |_: Option<&Nonce>| Ok(()),
+    )?;
+    let claims = decode_id_token_claims(&id_token.to_string())?;
+    let profile = OidcProfile::from_claims(&claims, &oidc_config.claim_mapping)?;
+    if let Some(groups) = profile.groups {
+        sync_groups(user_id, &groups, &oidc_config.group_sync, db).await?;
+    }
+    Ok(())
+}
+
 pub(crate) fn add_oidc_cookies(
     cookies: &Cookies,
     token_response: &CoreTokenResponse,
+    provider: &OidcConfig,
 ) -> Result<(), anyhow::Error> {
     let expires_at = token_response
         .expires_in()
@@ -330,5 +593,55 @@ pub(crate) fn add_oidc_cookies(
             .build(),
         );
     }
+    if let Some(id_token) = token_response.id_token() {
+        // Sent as hint to the end session endpoint of the provider on logout
+        cookies.add(
+            Cookie::build((constants::OIDC_ID_TOKEN_COOKIE_NAME, id_token.to_string()))
+                .secure(!cfg!(debug_assertions))
+                .http_only(true)
+                .path("/")
+                .same_site(SameSite::Strict)
+                .expires(time::OffsetDateTime::now_local()? + 30.days())
+                .build(),
+        );
+    }
+    cookies.add(
+        Cookie::build((constants::OIDC_PROVIDER_COOKIE_NAME, provider.id.clone()))
+            .secure(!cfg!(debug_assertions))
+            .http_only(true)
+            .path("/")
+            .same_site(SameSite::Strict)
+            .expires(time::OffsetDateTime::now_local()? + 30.days())
+            .build(),
+    );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provider_env_names() {
+        let default = ProviderEnv::new(DEFAULT_PROVIDER_ID);
+        assert_eq!(default.var_name("OIDC_CLIENT_ID"), "OIDC_CLIENT_ID");
+        assert_eq!(default.redirect_path(), "/api/oidc/redirect");
+
+        let named = ProviderEnv::new("social-login");
+        assert_eq!(
+            named.var_name("OIDC_CLIENT_ID"),
+            "OIDC_SOCIAL_LOGIN_CLIENT_ID"
+        );
+        assert_eq!(named.redirect_path(), "/api/oidc/social-login/redirect");
+    }
+
+    #[test]
+    fn provider_ids_are_parsed() {
+        assert_eq!(
+            parse_provider_ids(" Company, social-login ,").unwrap(),
+            vec!["company".to_string(), "social-login".to_string()]
+        );
+        assert!(parse_provider_ids("company,company").is_err());
+        assert!(parse_provider_ids("my company").is_err());
+    }
+}
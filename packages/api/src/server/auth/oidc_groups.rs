@@ -0,0 +1,471 @@
+use crate::server::audit::AuditEntry;
+use dioxus::prelude::*;
+use entity::audit_log::{AuditAction, AuditTarget};
+use entity::is_in_group::Column as InGroupColumn;
+use entity::prelude::{Group, InGroup, TodoList, TodoListInvitation};
+use entity::todo_list_invitation::{Column as InvitationColumn, InvitationPermission};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel,
+    QueryFilter, Set,
+};
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+
+/// Maps values of the groups claim to RoomMates groups and to the admin role of To-Do lists.
+///
+/// Only mapped groups are managed: users are added to a mapped group if their claim contains one of its values and
+/// removed if it does not. Memberships of unmapped groups are never changed.
+///
+/// RoomMates has no global admin role, so the admin role is granted per To-Do list: users whose claim contains a value
+/// of `admin_mapping` administer the mapped list. Admins without such a value are demoted to write access, except the
+/// creator of the list.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GroupSync {
+    /// Claim value to group id
+    pub mapping: HashMap<String, i32>,
+    /// Claim value to id of the To-Do list its users administer
+    pub admin_mapping: HashMap<String, i32>,
+    /// Only logs the changes instead of applying them
+    pub dry_run: bool,
+}
+
+impl GroupSync {
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        !self.mapping.is_empty() || !self.admin_mapping.is_empty()
+    }
+
+    fn managed_groups(&self) -> BTreeSet<i32> {
+        self.mapping.values().copied().collect()
+    }
+
+    fn desired_groups(&self, claim_values: &[String]) -> BTreeSet<i32> {
+        desired_ids(&self.mapping, claim_values)
+    }
+
+    fn managed_todo_lists(&self) -> BTreeSet<i32> {
+        self.admin_mapping.values().copied().collect()
+    }
+
+    fn desired_admin_todo_lists(&self, claim_values: &[String]) -> BTreeSet<i32> {
+        desired_ids(&self.admin_mapping, claim_values)
+    }
+}
+
+fn desired_ids(mapping: &HashMap<String, i32>, claim_values: &[String]) -> BTreeSet<i32> {
+    claim_values
+        .iter()
+        .filter_map(|value| mapping.get(value))
+        .copied()
+        .collect()
+}
+
+/// Parses a mapping like `flat-a=1,flat-b=2`. Invalid entries are skipped
+pub(crate) fn parse_group_mapping(value: &str) -> HashMap<String, i32> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let Some((claim_value, group_id)) = entry.rsplit_once('=') else {
+                if !entry.trim().is_empty() {
+                    warn!("Ignoring OIDC group mapping without group id: {entry}");
+                }
+                return None;
+            };
+            let Ok(group_id) = group_id.trim().parse() else {
+                warn!("Ignoring OIDC group mapping with invalid group id: {entry}");
+                return None;
+            };
+            Some((claim_value.trim().to_string(), group_id))
+        })
+        .collect()
+}
+
+/// Membership changes of a single sync
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GroupChanges {
+    pub added: Vec<i32>,
+    pub removed: Vec<i32>,
+    /// To-Do lists the user became admin of
+    pub admin_granted: Vec<i32>,
+    /// To-Do lists the user was demoted to write access in
+    pub admin_revoked: Vec<i32>,
+}
+
+impl GroupChanges {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.admin_granted.is_empty()
+            && self.admin_revoked.is_empty()
+    }
+}
+
+/// Updates the memberships of the managed groups and the admin role of the mapped To-Do lists to match the groups
+/// claim of the user.
+/// In dry-run mode the changes are only logged.
+///
+/// # Arguments
+///
+/// * `user_id`: The user to sync
+/// * `claim_values`: Values of the groups claim
+/// * `sync`: The configured mapping
+/// * `db`: Database connection
+///
+/// returns: The changes made, or that would have been made in dry-run mode
+///
+/// # Errors
+///
+/// Returns an error if a database operation fails
+pub async fn sync_groups(
+    user_id: i32,
+    claim_values: &[String],
+    sync: &GroupSync,
+    db: &DatabaseConnection,
+) -> Result<GroupChanges, DbErr> {
+    if !sync.is_enabled() {
+        return Ok(GroupChanges::default());
+    }
+    let (added, removed) = membership_changes(user_id, claim_values, sync, db).await?;
+    let (admin_grants, admin_demotions) = admin_changes(user_id, claim_values, sync, db).await?;
+    let changes = GroupChanges {
+        added,
+        removed,
+        admin_granted: admin_grants
+            .iter()
+            .map(|(todo_list_id, _)| *todo_list_id)
+            .collect(),
+        admin_revoked: admin_demotions
+            .iter()
+            .map(|invitation| invitation.todo_list_id)
+            .collect(),
+    };
+    if changes.is_empty() {
+        return Ok(changes);
+    }
+
+    if sync.dry_run {
+        info!(
+            "OIDC group sync (dry run) for user {user_id}: would add to {:?}, would remove from {:?}, \
+             would grant admin of To-Do lists {:?}, would revoke admin of To-Do lists {:?}",
+            changes.added, changes.removed, changes.admin_granted, changes.admin_revoked
+        );
+        return Ok(changes);
+    }
+
+    if !changes.added.is_empty() {
+        InGroup::insert_many(changes.added.iter().map(|group_id| {
+            entity::is_in_group::ActiveModel {
+                user_id: Set(user_id),
+                group_id: Set(*group_id),
+            }
+        }))
+        .exec(db)
+        .await?;
+    }
+    if !changes.removed.is_empty() {
+        InGroup::delete_many()
+            .filter(InGroupColumn::UserId.eq(user_id))
+            .filter(InGroupColumn::GroupId.is_in(changes.removed.clone()))
+            .exec(db)
+            .await?;
+    }
+    for (todo_list_id, invitation) in admin_grants {
+        if let Some(invitation) = invitation {
+            let mut invitation = invitation.into_active_model();
+            invitation.permission = Set(InvitationPermission::Admin);
+            invitation.is_accepted = Set(true);
+            invitation.update(db).await?;
+        } else {
+            entity::todo_list_invitation::ActiveModel {
+                todo_list_id: Set(todo_list_id),
+                receiving_user_id: Set(user_id),
+                sender_user_id: Set(None),
+                permission: Set(InvitationPermission::Admin),
+                is_accepted: Set(true),
+                is_favorite: Set(false),
+            }
+            .insert(db)
+            .await?;
+        }
+    }
+    for invitation in admin_demotions {
+        let mut invitation = invitation.into_active_model();
+        invitation.permission = Set(InvitationPermission::Write);
+        invitation.update(db).await?;
+    }
+    info!(
+        "OIDC group sync for user {user_id}: added to {:?}, removed from {:?}, \
+         granted admin of To-Do lists {:?}, revoked admin of To-Do lists {:?}",
+        changes.added, changes.removed, changes.admin_granted, changes.admin_revoked
+    );
+
+    let group_entries = changes
+        .added
+        .iter()
+        .map(|group_id| (AuditAction::GroupMemberAdded, AuditTarget::Group, *group_id))
+        .chain(changes.removed.iter().map(|group_id| {
+            (
+                AuditAction::GroupMemberRemoved,
+                AuditTarget::Group,
+                *group_id,
+            )
+        }));
+    let todo_list_entries = changes
+        .admin_granted
+        .iter()
+        .chain(&changes.admin_revoked)
+        .map(|todo_list_id| {
+            (
+                AuditAction::TodoListPermissionChanged,
+                AuditTarget::TodoList,
+                *todo_list_id,
+            )
+        });
+    for (action, target, target_id) in group_entries.chain(todo_list_entries) {
+        AuditEntry::new(None, action, target, target_id)
+            .affected_user(user_id)
+            .details("OIDC group sync")
+            .record(db)
+            .await;
+    }
+    Ok(changes)
+}
+
+/// Groups the user has to be added to and removed from
+async fn membership_changes(
+    user_id: i32,
+    claim_values: &[String],
+    sync: &GroupSync,
+    db: &DatabaseConnection,
+) -> Result<(Vec<i32>, Vec<i32>), DbErr> {
+    let managed = sync.managed_groups();
+    if managed.is_empty() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+    let existing_groups: BTreeSet<i32> = Group::find()
+        .filter(entity::group::Column::Id.is_in(managed.clone()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|group| group.id)
+        .collect();
+    for missing in managed.difference(&existing_groups) {
+        warn!("OIDC group mapping references unknown group {missing}");
+    }
+
+    let desired: BTreeSet<i32> = sync
+        .desired_groups(claim_values)
+        .intersection(&existing_groups)
+        .copied()
+        .collect();
+    let current: BTreeSet<i32> = InGroup::find()
+        .filter(InGroupColumn::UserId.eq(user_id))
+        .filter(InGroupColumn::GroupId.is_in(existing_groups.clone()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|membership| membership.group_id)
+        .collect();
+
+    Ok((
+        desired.difference(&current).copied().collect(),
+        current.difference(&desired).copied().collect(),
+    ))
+}
+
+type AdminGrant = (i32, Option<entity::todo_list_invitation::Model>);
+
+/// To-Do lists the user has to become admin of, together with their current invitation, and the invitations that
+/// have to be demoted to write access
+async fn admin_changes(
+    user_id: i32,
+    claim_values: &[String],
+    sync: &GroupSync,
+    db: &DatabaseConnection,
+) -> Result<(Vec<AdminGrant>, Vec<entity::todo_list_invitation::Model>), DbErr> {
+    let managed = sync.managed_todo_lists();
+    if managed.is_empty() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+    let todo_lists: HashMap<i32, entity::todo_list::Model> = TodoList::find()
+        .filter(entity::todo_list::Column::Id.is_in(managed.clone()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|todo_list| (todo_list.id, todo_list))
+        .collect();
+    for missing in managed.iter().filter(|id| !todo_lists.contains_key(id)) {
+        warn!("OIDC admin mapping references unknown To-Do list {missing}");
+    }
+
+    let desired = sync.desired_admin_todo_lists(claim_values);
+    let mut invitations: HashMap<i32, entity::todo_list_invitation::Model> =
+        TodoListInvitation::find()
+            .filter(InvitationColumn::ReceivingUserId.eq(user_id))
+            .filter(InvitationColumn::TodoListId.is_in(todo_lists.keys().copied()))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|invitation| (invitation.todo_list_id, invitation))
+            .collect();
+
+    let mut granted = Vec::new();
+    let mut revoked = Vec::new();
+    for todo_list in todo_lists.values() {
+        let invitation = invitations.remove(&todo_list.id);
+        let is_accepted_admin = invitation
+            .as_ref()
+            .is_some_and(|invitation| invitation.is_accepted && invitation.permission.can_admin());
+        if desired.contains(&todo_list.id) {
+            if !is_accepted_admin {
+                granted.push((todo_list.id, invitation));
+            }
+        } else if let Some(invitation) = invitation
+            && invitation.permission.can_admin()
+            && todo_list.created_by_id != Some(user_id)
+        {
+            revoked.push(invitation);
+        }
+    }
+    granted.sort_unstable_by_key(|(todo_list_id, _)| *todo_list_id);
+    revoked.sort_unstable_by_key(|invitation| invitation.todo_list_id);
+    Ok((granted, revoked))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::test_support::{
+        create_test_group, create_test_todo_list, create_test_todo_list_invitation,
+        create_test_user, setup,
+    };
+
+    async fn member_groups(database: &DatabaseConnection, user_id: i32) -> Vec<i32> {
+        InGroup::find()
+            .filter(InGroupColumn::UserId.eq(user_id))
+            .all(database)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|membership| membership.group_id)
+            .collect()
+    }
+
+    #[test]
+    fn group_mapping_is_parsed() {
+        let mapping = parse_group_mapping("flat-a=1, /flats/b = 2,invalid,broken=x");
+        assert_eq!(mapping.len(), 2);
+        assert_eq!(mapping.get("flat-a"), Some(&1));
+        assert_eq!(mapping.get("/flats/b"), Some(&2));
+    }
+
+    #[tokio::test]
+    async fn test_sync_groups() {
+        let database = setup().await;
+        create_test_user(&database, 1).await;
+        let flat_a = create_test_group(&database, &[]).await.id;
+        let flat_b = create_test_group(&database, &[]).await.id;
+        // Not mapped and must not be touched
+        let unmapped = create_test_group(&database, &[1]).await.id;
+
+        let mut sync = GroupSync {
+            mapping: HashMap::from([
+                ("flat-a".to_string(), flat_a),
+                ("flat-b".to_string(), flat_b),
+                ("gone".to_string(), 42),
+            ]),
+            dry_run: true,
+            ..GroupSync::default()
+        };
+        let claim = vec!["flat-a".to_string(), "other".to_string()];
+
+        let changes = sync_groups(1, &claim, &sync, &database).await.unwrap();
+        assert_eq!(changes.added, vec![flat_a]);
+        assert!(changes.removed.is_empty());
+        assert_eq!(
+            member_groups(&database, 1).await,
+            vec![unmapped],
+            "Expected dry run to not change memberships"
+        );
+
+        sync.dry_run = false;
+        sync_groups(1, &claim, &sync, &database).await.unwrap();
+        let mut groups = member_groups(&database, 1).await;
+        groups.sort_unstable();
+        assert_eq!(groups, vec![flat_a, unmapped]);
+
+        let changes = sync_groups(1, &["flat-b".to_string()], &sync, &database)
+            .await
+            .unwrap();
+        assert_eq!(changes.added, vec![flat_b]);
+        assert_eq!(changes.removed, vec![flat_a]);
+        let mut groups = member_groups(&database, 1).await;
+        groups.sort_unstable();
+        assert_eq!(groups, vec![flat_b, unmapped]);
+    }
+
+    async fn todo_list_permission(
+        database: &DatabaseConnection,
+        todo_list_id: i32,
+        user_id: i32,
+    ) -> Option<InvitationPermission> {
+        TodoListInvitation::find_by_id((todo_list_id, user_id))
+            .one(database)
+            .await
+            .unwrap()
+            .filter(|invitation| invitation.is_accepted)
+            .map(|invitation| invitation.permission)
+    }
+
+    #[tokio::test]
+    async fn test_sync_todo_list_admins() {
+        let database = setup().await;
+        for id in 1..=3 {
+            create_test_user(&database, id).await;
+        }
+        create_test_todo_list(&database, 1, 1).await;
+        create_test_todo_list_invitation(&database, 1, 3, 1, true, InvitationPermission::Admin)
+            .await;
+
+        let sync = GroupSync {
+            admin_mapping: HashMap::from([("flat-a-admins".to_string(), 1)]),
+            ..GroupSync::default()
+        };
+        let admins = vec!["flat-a-admins".to_string()];
+
+        let changes = sync_groups(2, &admins, &sync, &database).await.unwrap();
+        assert_eq!(changes.admin_granted, vec![1]);
+        assert_eq!(
+            todo_list_permission(&database, 1, 2).await,
+            Some(InvitationPermission::Admin)
+        );
+        assert!(
+            sync_groups(2, &admins, &sync, &database)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let changes = sync_groups(2, &[], &sync, &database).await.unwrap();
+        assert_eq!(changes.admin_revoked, vec![1]);
+        assert_eq!(
+            todo_list_permission(&database, 1, 2).await,
+            Some(InvitationPermission::Write),
+            "Expected demoted admins to keep write access"
+        );
+
+        sync_groups(3, &[], &sync, &database).await.unwrap();
+        assert_eq!(
+            todo_list_permission(&database, 1, 3).await,
+            Some(InvitationPermission::Write)
+        );
+        let changes = sync_groups(1, &[], &sync, &database).await.unwrap();
+        assert!(changes.is_empty(), "Expected the creator to stay admin");
+        assert_eq!(
+            todo_list_permission(&database, 1, 1).await,
+            Some(InvitationPermission::Admin)
+        );
+    }
+}
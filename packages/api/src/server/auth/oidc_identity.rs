@@ -0,0 +1,599 @@
+use crate::error::ApiError;
+use anyhow::{Context, anyhow};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use dioxus::prelude::*;
+use entity::oidc_identity::Column as IdentityColumn;
+use entity::prelude::{OidcIdentity, User};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel,
+    PaginatorTrait, QueryFilter, Set, TransactionError, TransactionTrait,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use time::OffsetDateTime;
+
+/// Names of the ID token claims the profile of a user is read from.
+/// Nested claims can be addressed with dots, e.g. `profile.first_name`
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClaimMapping {
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    /// Claim listing the groups of the user. Groups are not synced if unset
+    pub groups: Option<String>,
+}
+
+impl Default for ClaimMapping {
+    fn default() -> Self {
+        ClaimMapping {
+            email: "email".to_string(),
+            first_name: "given_name".to_string(),
+            last_name: "family_name".to_string(),
+            groups: None,
+        }
+    }
+}
+
+/// Rules deciding whether an unknown identity gets an account on its first login
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProvisioningRules {
+    /// Unknown identities are rejected if disabled. Existing users can still link their identity
+    pub enabled: bool,
+    /// Lowercase email domains allowed to sign up. All domains are allowed if empty
+    pub allowed_email_domains: Vec<String>,
+    /// Requires the provider to report the email as verified
+    pub require_verified_email: bool,
+}
+
+impl Default for ProvisioningRules {
+    fn default() -> Self {
+        ProvisioningRules {
+            enabled: true,
+            allowed_email_domains: Vec::new(),
+            require_verified_email: false,
+        }
+    }
+}
+
+/// Normalizes allowed email domains, e.g. `@Example.com` to `example.com`
+pub(crate) fn parse_email_domains<S: AsRef<str>>(domains: impl Iterator<Item = S>) -> Vec<String> {
+    domains
+        .map(|domain| {
+            domain
+                .as_ref()
+                .trim()
+                .trim_start_matches('@')
+                .to_lowercase()
+        })
+        .filter(|domain| !domain.is_empty())
+        .collect()
+}
+
+impl ProvisioningRules {
+    /// Checks if an account may be created for the profile
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason why the profile is rejected
+    pub fn check(&self, profile: &OidcProfile) -> Result<(), &'static str> {
+        if !self.enabled {
+            return Err("Signing up with this provider is disabled");
+        }
+        let email = profile
+            .email
+            .as_deref()
+            .ok_or("The provider did not supply an email address")?;
+        if self.require_verified_email && !profile.email_verified {
+            return Err("The provider did not verify your email address");
+        }
+        if !self.allowed_email_domains.is_empty() {
+            let domain = email
+                .rsplit_once('@')
+                .map(|(_, domain)| domain.to_lowercase())
+                .unwrap_or_default();
+            if !self.allowed_email_domains.contains(&domain) {
+                return Err("Your email domain is not allowed to sign up");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The user as described by the ID token of the provider
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OidcProfile {
+    pub issuer: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    /// Values of the mapped groups claim. `None` if no groups claim is configured or the token does not contain it
+    pub groups: Option<Vec<String>>,
+}
+
+impl OidcProfile {
+    /// Reads the profile from the claims of an ID token
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `iss` or `sub` claim is missing
+    pub fn from_claims(
+        claims: &Map<String, Value>,
+        mapping: &ClaimMapping,
+    ) -> Result<Self, anyhow::Error> {
+        Ok(OidcProfile {
+            issuer: claim_string(claims, "iss").context("Missing iss claim")?,
+            subject: claim_string(claims, "sub").context("Missing sub claim")?,
+            email: claim_string(claims, &mapping.email).map(|email| email.trim().to_lowercase()),
+            email_verified: claim(claims, "email_verified")
+                .is_some_and(|value| value.as_bool().unwrap_or(value.as_str() == Some("true"))),
+            first_name: claim_string(claims, &mapping.first_name),
+            last_name: claim_string(claims, &mapping.last_name),
+            groups: mapping
+                .groups
+                .as_deref()
+                .and_then(|path| claim(claims, path))
+                .map(|value| match value {
+                    Value::Array(values) => values
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(ToString::to_string)
+                        .collect(),
+                    Value::String(value) => vec![value.clone()],
+                    _ => Vec::new(),
+                }),
+        })
+    }
+}
+
+/// Decodes the payload of an ID token. The signature has to be verified beforehand
+///
+/// # Errors
+///
+/// Returns an error if the token is not a JWT with a JSON object as payload
+pub fn decode_id_token_claims(id_token: &str) -> Result<Map<String, Value>, anyhow::Error> {
+    let payload = id_token
+        .split('.')
+        .nth(1)
+        .ok_or(anyhow!("ID token is not a JWT"))?;
+    let payload = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('='))?;
+    Ok(serde_json::from_slice(&payload)?)
+}
+
+fn claim<'a>(claims: &'a Map<String, Value>, path: &str) -> Option<&'a Value> {
+    // Claims with dots in their name, e.g. URLs, take precedence over nested claims
+    if let Some(value) = claims.get(path) {
+        return Some(value);
+    }
+    let mut parts = path.split('.');
+    let mut value = claims.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_object()?.get(part)?;
+    }
+    Some(value)
+}
+
+fn claim_string(claims: &Map<String, Value>, path: &str) -> Option<String> {
+    claim(claims, path)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(ToString::to_string)
+}
+
+/// Returns the user the identity of the provider is linked to. Tokens issued before a back-channel logout of the
+/// identity are rejected
+///
+/// # Arguments
+///
+/// * `issuer`, `subject`: Identify the identity
+/// * `issued_at`: `iat` claim of the token used to authenticate
+/// * `db`: Database connection
+///
+/// # Errors
+///
+/// Returns an error if a database operation fails
+pub async fn find_user_by_identity(
+    issuer: &str,
+    subject: &str,
+    issued_at: Option<i64>,
+    db: &DatabaseConnection,
+) -> Result<Option<entity::user::Model>, DbErr> {
+    let identity = OidcIdentity::find_by_identity((issuer.to_string(), subject.to_string()))
+        .find_also_related(User)
+        .one(db)
+        .await?;
+    Ok(identity.and_then(|(identity, user)| {
+        let logged_out = identity.logged_out_at.is_some_and(|logged_out_at| {
+            issued_at.is_none_or(|issued_at| issued_at <= logged_out_at.unix_timestamp())
+        });
+        if logged_out { None } else { user }
+    }))
+}
+
+/// Records a login of a known identity. Accounts created by the provider are kept in sync with the profile,
+/// linked password accounts keep their own name and email
+async fn update_identity(
+    identity: entity::oidc_identity::Model,
+    user: entity::user::Model,
+    profile: &OidcProfile,
+    db: &DatabaseConnection,
+) -> Result<entity::user::Model, ApiError> {
+    let mut identity = identity.into_active_model();
+    identity.email = Set(profile.email.clone());
+    identity.last_login_at = Set(Some(OffsetDateTime::now_utc()));
+    identity
+        .update(db)
+        .await
+        .or_internal_server_error("Error updating OIDC identity")?;
+
+    if !user.is_oidc_user {
+        return Ok(user);
+    }
+
+    let mut changed_user = user.clone().into_active_model();
+    if let Some(first_name) = &profile.first_name {
+        changed_user.first_name = Set(first_name.clone());
+    }
+    if let Some(last_name) = &profile.last_name {
+        changed_user.last_name = Set(last_name.clone());
+    }
+    if let Some(email) = &profile.email
+        && *email != user.email
+    {
+        let email_taken = User::find_by_email(email.clone())
+            .count(db)
+            .await
+            .or_internal_server_error("Failed to retrieve user")?
+            > 0;
+        if email_taken {
+            warn!(
+                "Not updating email of user {} to {email}, as it belongs to another account",
+                user.id
+            );
+        } else {
+            changed_user.email = Set(email.clone());
+        }
+    }
+    if !changed_user.is_changed() {
+        return Ok(user);
+    }
+    Ok(changed_user
+        .update(db)
+        .await
+        .or_internal_server_error("Error updating user")?)
+}
+
+fn new_identity(user_id: i32, profile: &OidcProfile) -> entity::oidc_identity::ActiveModel {
+    let now = OffsetDateTime::now_utc();
+    entity::oidc_identity::ActiveModel {
+        issuer: Set(profile.issuer.clone()),
+        subject: Set(profile.subject.clone()),
+        email: Set(profile.email.clone()),
+        created_at: Set(now),
+        last_login_at: Set(Some(now)),
+        logged_out_at: Set(None),
+        user_id: Set(user_id),
+        ..Default::default()
+    }
+}
+
+/// Resolves the user logging in with an identity of the provider.
+///
+/// Users are looked up by issuer and subject. An unknown identity gets a new account if the
+/// [`ProvisioningRules`] allow it. Accounts are never matched by email, except for accounts a provider created
+/// before identities were recorded. Those are adopted by the first identity with a verified email.
+///
+/// # Errors
+///
+/// * Forbidden: The identity is unknown and may not sign up
+/// * Conflict: A password account already uses the email of the identity
+/// * Internal Server Error: A database operation failed
+pub async fn login_with_identity(
+    profile: &OidcProfile,
+    rules: &ProvisioningRules,
+    db: &DatabaseConnection,
+) -> Result<entity::user::Model, ApiError> {
+    let identity =
+        OidcIdentity::find_by_identity((profile.issuer.clone(), profile.subject.clone()))
+            .find_also_related(User)
+            .one(db)
+            .await
+            .or_internal_server_error("Error loading OIDC identity")?;
+    if let Some((identity, Some(user))) = identity {
+        return update_identity(identity, user, profile, db).await;
+    }
+
+    let existing_user = match &profile.email {
+        Some(email) => User::find_by_email(email.clone())
+            .one(db)
+            .await
+            .or_internal_server_error("Failed to retrieve user")?,
+        None => None,
+    };
+
+    if let Some(user) = existing_user {
+        // Accounts created by a provider before identities were recorded are adopted once. Once an account has
+        // an identity, a matching email of any provider must not take it over
+        let has_identity = OidcIdentity::find()
+            .filter(IdentityColumn::UserId.eq(user.id))
+            .count(db)
+            .await
+            .or_internal_server_error("Error loading OIDC identity")?
+            > 0;
+        if user.is_oidc_user && !has_identity && profile.email_verified {
+            info!(
+                "Linking existing OIDC user {} to {}",
+                user.id, profile.subject
+            );
+            new_identity(user.id, profile)
+                .insert(db)
+                .await
+                .or_internal_server_error("Error creating OIDC identity")?;
+            return Ok(user);
+        }
+        return Err(ApiError::conflict(
+            "An account with this email already exists. Log in to it and link your account from your profile",
+        ));
+    }
+
+    rules.check(profile).map_err(ApiError::forbidden)?;
+    let email = profile.email.clone().unwrap_or_default();
+    let first_name = profile.first_name.clone().unwrap_or_default();
+    let last_name = profile.last_name.clone().unwrap_or_default();
+    let identity = new_identity(0, profile);
+
+    db.transaction::<_, entity::user::Model, DbErr>(|txn| {
+        Box::pin(async move {
+            let user = entity::user::ActiveModel {
+                email: Set(email),
+                first_name: Set(first_name),
+                last_name: Set(last_name),
+                password: Set(None),
+                is_oidc_user: Set(true),
+                email_verified: Set(true),
+                ..Default::default()
+            }
+            .insert(txn)
+            .await?;
+            let mut identity = identity;
+            identity.user_id = Set(user.id);
+            identity.insert(txn).await?;
+            Ok(user)
+        })
+    })
+    .await
+    .map_err(|error| match error {
+        TransactionError::Connection(error) | TransactionError::Transaction(error) => error,
+    })
+    .inspect_err(|e| error!("Error provisioning OIDC user: {e}"))
+    .or_internal_server_error("Failed to create user")
+    .map_err(ApiError::from)
+}
+
+/// Links an identity of the provider to an existing user
+///
+/// # Errors
+///
+/// * Conflict: The identity belongs to another user
+/// * Internal Server Error: A database operation failed
+pub async fn link_identity(
+    user: &entity::user::Model,
+    profile: &OidcProfile,
+    db: &DatabaseConnection,
+) -> Result<entity::oidc_identity::Model, ApiError> {
+    let identity =
+        OidcIdentity::find_by_identity((profile.issuer.clone(), profile.subject.clone()))
+            .one(db)
+            .await
+            .or_internal_server_error("Error loading OIDC identity")?;
+
+    match identity {
+        Some(identity) if identity.user_id == user.id => Ok(identity),
+        Some(_) => Err(ApiError::conflict(
+            "This account of the provider is already linked to another user",
+        )),
+        None => Ok(new_identity(user.id, profile)
+            .insert(db)
+            .await
+            .inspect_err(|e| error!("Error linking OIDC identity: {e}"))
+            .or_internal_server_error("Error linking OIDC identity")?),
+    }
+}
+
+/// Removes an identity from the user. Users have to keep a way to log in, so the last identity of an account
+/// without password cannot be removed
+///
+/// # Errors
+///
+/// * Not Found: The identity does not belong to the user
+/// * Bad Request: The identity is the only way to log in
+/// * Internal Server Error: A database operation failed
+pub async fn unlink_identity(
+    user: &entity::user::Model,
+    identity_id: i32,
+    db: &DatabaseConnection,
+) -> Result<(), ApiError> {
+    let identity = OidcIdentity::find_by_id(identity_id)
+        .filter(IdentityColumn::UserId.eq(user.id))
+        .one(db)
+        .await
+        .or_internal_server_error("Error loading OIDC identity")?
+        .or_not_found("Linked account not found")?;
+
+    let identity_count = OidcIdentity::find()
+        .filter(IdentityColumn::UserId.eq(user.id))
+        .count(db)
+        .await
+        .or_internal_server_error("Error loading OIDC identity")?;
+    (user.password.is_some() || identity_count > 1)
+        .or_bad_request("This is the only way to log in to your account")?;
+
+    OidcIdentity::delete_by_id(identity.id)
+        .exec(db)
+        .await
+        .or_internal_server_error("Error unlinking OIDC identity")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::test_support::{create_test_user, setup};
+    use serde_json::json;
+
+    fn claims(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    fn profile(email: Option<&str>, email_verified: bool) -> OidcProfile {
+        OidcProfile {
+            issuer: "https://idp.example.com".to_string(),
+            subject: "1234".to_string(),
+            email: email.map(ToString::to_string),
+            email_verified,
+            first_name: None,
+            last_name: None,
+            groups: None,
+        }
+    }
+
+    #[test]
+    fn profile_uses_claim_mapping() {
+        let claims = claims(json!({
+            "iss": "https://idp.example.com",
+            "sub": "1234",
+            "email": "ignored@example.com",
+            "mail": " Jane@Example.com ",
+            "email_verified": true,
+            "profile": { "first": "Jane" },
+            "family_name": "Doe",
+            "realm_access": { "roles": ["flat-a", "flat-b"] },
+        }));
+        let mapping = ClaimMapping {
+            email: "mail".to_string(),
+            first_name: "profile.first".to_string(),
+            groups: Some("realm_access.roles".to_string()),
+            ..ClaimMapping::default()
+        };
+
+        let profile = OidcProfile::from_claims(&claims, &mapping).unwrap();
+        assert_eq!(profile.email.as_deref(), Some("jane@example.com"));
+        assert_eq!(profile.first_name.as_deref(), Some("Jane"));
+        assert_eq!(profile.last_name.as_deref(), Some("Doe"));
+        assert!(profile.email_verified);
+        assert_eq!(
+            profile.groups,
+            Some(vec!["flat-a".to_string(), "flat-b".to_string()])
+        );
+
+        let missing_subject = self::claims(json!({ "iss": "https://idp.example.com" }));
+        assert!(OidcProfile::from_claims(&missing_subject, &mapping).is_err());
+    }
+
+    #[test]
+    fn id_token_payload_is_decoded() {
+        let payload = URL_SAFE_NO_PAD.encode(r#"{"iss":"https://idp.example.com","sub":"1234"}"#);
+        let claims = decode_id_token_claims(&format!("header.{payload}.signature")).unwrap();
+        assert_eq!(claims.get("sub"), Some(&json!("1234")));
+        assert!(decode_id_token_claims("not-a-jwt").is_err());
+    }
+
+    #[test]
+    fn provisioning_rules() {
+        let rules = ProvisioningRules {
+            enabled: true,
+            allowed_email_domains: vec!["example.com".to_string()],
+            require_verified_email: true,
+        };
+        assert!(
+            rules
+                .check(&profile(Some("jane@example.com"), true))
+                .is_ok()
+        );
+        assert!(
+            rules
+                .check(&profile(Some("jane@example.com"), false))
+                .is_err()
+        );
+        assert!(rules.check(&profile(Some("jane@other.com"), true)).is_err());
+        assert!(rules.check(&profile(None, true)).is_err());
+
+        let disabled = ProvisioningRules {
+            enabled: false,
+            ..ProvisioningRules::default()
+        };
+        assert!(
+            disabled
+                .check(&profile(Some("jane@example.com"), true))
+                .is_err()
+        );
+        assert!(
+            ProvisioningRules::default()
+                .check(&profile(Some("jane@other.com"), false))
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_legacy_accounts_are_adopted_once() {
+        let database = setup().await;
+        let mut user = create_test_user(&database, 1).await.into_active_model();
+        user.password = Set(None);
+        user.is_oidc_user = Set(true);
+        let user = user.update(&database).await.unwrap();
+        let rules = ProvisioningRules::default();
+
+        let first_issuer = OidcProfile {
+            issuer: "https://idp.example.com".to_string(),
+            ..profile(Some(user.email.as_str()), true)
+        };
+        let adopted = login_with_identity(&first_issuer, &rules, &database)
+            .await
+            .unwrap();
+        assert_eq!(adopted.id, user.id);
+
+        let second_issuer = OidcProfile {
+            issuer: "https://other-idp.example.com".to_string(),
+            subject: "5678".to_string(),
+            ..profile(Some(user.email.as_str()), true)
+        };
+        let result = login_with_identity(&second_issuer, &rules, &database).await;
+        assert!(
+            matches!(result, Err(ApiError::Conflict { .. })),
+            "Expected another provider not to take over an account with an identity"
+        );
+
+        let identities = OidcIdentity::find()
+            .filter(IdentityColumn::UserId.eq(user.id))
+            .all(&database)
+            .await
+            .unwrap();
+        assert_eq!(identities.len(), 1);
+        assert_eq!(identities[0].issuer, "https://idp.example.com");
+    }
+
+    #[tokio::test]
+    async fn test_legacy_accounts_require_verified_email() {
+        let database = setup().await;
+        let mut user = create_test_user(&database, 1).await.into_active_model();
+        user.password = Set(None);
+        user.is_oidc_user = Set(true);
+        let user = user.update(&database).await.unwrap();
+
+        let result = login_with_identity(
+            &profile(Some(user.email.as_str()), false),
+            &ProvisioningRules::default(),
+            &database,
+        )
+        .await;
+        assert!(
+            matches!(result, Err(ApiError::Conflict { .. })),
+            "Expected an unverified email not to adopt an account"
+        );
+    }
+}
@@ -0,0 +1,450 @@
+use crate::server::AppState;
+use crate::server::auth::oidc::{
+    OidcConfig, OidcProviders, build_http_client, validate_provider_token,
+};
+use crate::server::constants;
+use crate::server::live_updates::Revocation;
+use anyhow::anyhow;
+use dioxus::prelude::*;
+use dioxus::server::axum::Extension;
+use dioxus::server::axum::extract::{Form, Path};
+use dioxus::server::axum::http::header;
+use dioxus::server::axum::response::{IntoResponse, Response};
+use entity::prelude::{OidcIdentity, Session};
+use openidconnect::core::CoreIdToken;
+use openidconnect::{LogoutRequest, PostLogoutRedirectUrl};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel,
+    QueryFilter, Set,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use time::OffsetDateTime;
+use tower_cookies::{Cookie, Cookies};
+
+const BACKCHANNEL_LOGOUT_EVENT: &str = "http://schemas.openid.net/event/backchannel-logout";
+/// Logout tokens issued longer ago than this are rejected. Their `jti` is remembered for as long
+const MAX_LOGOUT_TOKEN_AGE: i64 = 5 * 60;
+/// Tolerated clock difference to the provider for tokens issued in the future
+const MAX_CLOCK_SKEW: i64 = 60;
+
+/// Logs the user out of the provider the OIDC cookies belong to.
+/// The refresh token is revoked if the provider supports it and all OIDC cookies are removed.
+///
+/// returns: The end session url of the provider the browser should be redirected to. `None` if the user did not log
+/// in using OIDC or the provider does not support RP-initiated logout
+pub(crate) async fn logout(
+    cookies: &Cookies,
+    providers: &OidcProviders,
+    server_url: &str,
+) -> Option<String> {
+    let has_tokens = [
+        constants::OIDC_AUTHORIZATION_COOKIE_NAME,
+        constants::OIDC_REFRESH_COOKIE_NAME,
+    ]
+    .into_iter()
+    .any(|name| cookies.get(name).is_some());
+    if !has_tokens {
+        return None;
+    }
+
+    let provider = providers.from_cookies(cookies);
+    let refresh_token = cookies
+        .get(constants::OIDC_REFRESH_COOKIE_NAME)
+        .and_then(|cookie| {
+            cookie
+                .value()
+                .strip_prefix("Bearer ")
+                .map(ToString::to_string)
+        });
+    let id_token = cookies
+        .get(constants::OIDC_ID_TOKEN_COOKIE_NAME)
+        .map(|cookie| cookie.value().to_string());
+    remove_oidc_cookies(cookies);
+
+    let provider = provider?;
+    if let Some(refresh_token) = refresh_token
+        && let Err(e) = revoke_refresh_token(provider, &refresh_token).await
+    {
+        warn!("Error revoking refresh token at {}: {e}", provider.id);
+    }
+    end_session_url(provider, id_token.as_deref(), server_url)
+}
+
+pub(crate) fn remove_oidc_cookies(cookies: &Cookies) {
+    for name in [
+        constants::OIDC_AUTHORIZATION_COOKIE_NAME,
+        constants::OIDC_REFRESH_COOKIE_NAME,
+        constants::OIDC_ID_TOKEN_COOKIE_NAME,
+        constants::OIDC_PROVIDER_COOKIE_NAME,
+    ] {
+        cookies.remove(Cookie::build((name, "")).path("/").build());
+    }
+}
+
+async fn revoke_refresh_token(
+    provider: &OidcConfig,
+    refresh_token: &str,
+) -> Result<(), anyhow::Error> {
+    let Some(revocation_endpoint) = &provider.metadata.additional_metadata().revocation_endpoint
+    else {
+        return Ok(());
+    };
+    build_http_client()?
+        .post(revocation_endpoint.url().as_str())
+        .basic_auth(
+            provider.client.client_id().as_str(),
+            Some(provider.client_secret.secret()),
+        )
+        .form(&[
+            ("token", refresh_token),
+            ("token_type_hint", "refresh_token"),
+        ])
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+fn end_session_url(
+    provider: &OidcConfig,
+    id_token: Option<&str>,
+    server_url: &str,
+) -> Option<String> {
+    let end_session_endpoint = provider
+        .metadata
+        .additional_metadata()
+        .end_session_endpoint
+        .clone()?;
+    let mut request = LogoutRequest::from(end_session_endpoint)
+        .set_client_id(provider.client.client_id().clone());
+    if let Some(id_token) = id_token.and_then(|token| CoreIdToken::from_str(token).ok()) {
+        request = request.set_id_token_hint(&id_token);
+    }
+    match PostLogoutRedirectUrl::new(format!("{server_url}/")) {
+        Ok(redirect_url) => request = request.set_post_logout_redirect_uri(redirect_url),
+        Err(e) => warn!("Invalid post logout redirect url: {e}"),
+    }
+    Some(request.http_get_url().to_string())
+}
+
+/// Claims of a back-channel logout token as defined by
+/// [OpenID Connect Back-Channel Logout](https://openid.net/specs/openid-connect-backchannel-1_0.html#LogoutToken)
+#[derive(Deserialize, Debug)]
+pub struct LogoutTokenClaims {
+    pub iss: String,
+    #[serde(default)]
+    pub sub: Option<String>,
+    #[serde(default)]
+    pub sid: Option<String>,
+    pub iat: i64,
+    #[serde(default)]
+    pub jti: Option<String>,
+    #[serde(default)]
+    pub events: Map<String, Value>,
+    #[serde(default)]
+    pub nonce: Option<Value>,
+}
+
+impl LogoutTokenClaims {
+    /// Checks the requirements of the specification not covered by the signature validation
+    ///
+    /// returns: The subject to log out
+    ///
+    /// # Errors
+    ///
+    /// Returns the reason why the token is rejected
+    pub fn subject(&self, now: i64) -> Result<&str, &'static str> {
+        if self.iat < now - MAX_LOGOUT_TOKEN_AGE {
+            return Err("Logout token is too old");
+        }
+        if self.iat > now + MAX_CLOCK_SKEW {
+            return Err("Logout token is issued in the future");
+        }
+        if self.jti.is_none() {
+            return Err("Missing token identifier");
+        }
+        if self.nonce.is_some() {
+            return Err("Logout tokens must not contain a nonce");
+        }
+        if !self.events.contains_key(BACKCHANNEL_LOGOUT_EVENT) {
+            return Err("Missing back-channel logout event");
+        }
+        // Sessions of the provider are not tracked, so logout tokens only containing a `sid` cannot be processed
+        self.sub
+            .as_deref()
+            .ok_or("Logout tokens without subject are not supported")
+    }
+}
+
+/// Remembers the `jti` of processed logout tokens, so a captured token cannot be replayed while it is fresh
+#[derive(Clone, Debug, Default)]
+pub struct LogoutTokenCache {
+    // (issuer, jti) -> `iat` of the token
+    seen: Arc<Mutex<HashMap<(String, String), i64>>>,
+}
+
+impl LogoutTokenCache {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the token identifier. Identifiers of tokens which are too old to be accepted anyway are forgotten
+    ///
+    /// returns: `false` if the token has been seen before
+    pub fn insert(&self, issuer: &str, jti: &str, iat: i64, now: i64) -> bool {
+        let mut seen = self.seen.lock().expect("Logout token cache poisoned");
+        seen.retain(|_, issued_at| *issued_at >= now - MAX_LOGOUT_TOKEN_AGE);
+        seen.insert((issuer.to_string(), jti.to_string()), iat)
+            .is_none()
+    }
+}
+
+/// Ends all RoomMates sessions of an identity. Local sessions are deleted and OIDC tokens issued before are rejected
+///
+/// returns: Id of the logged out user or `None` if the identity is unknown
+///
+/// # Errors
+///
+/// Returns an error if a database operation fails
+pub async fn end_identity_sessions(
+    issuer: &str,
+    subject: &str,
+    db: &DatabaseConnection,
+) -> Result<Option<i32>, DbErr> {
+    let Some(identity) = OidcIdentity::find_by_identity((issuer.to_string(), subject.to_string()))
+        .one(db)
+        .await?
+    else {
+        return Ok(None);
+    };
+    let user_id = identity.user_id;
+
+    let mut identity = identity.into_active_model();
+    identity.logged_out_at = Set(Some(OffsetDateTime::now_utc()));
+    identity.update(db).await?;
+    Session::delete_many()
+        .filter(entity::session::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    Ok(Some(user_id))
+}
+
+async fn process_logout_token(
+    provider_id: &str,
+    logout_token: &str,
+    app_state: &AppState,
+) -> Result<(), anyhow::Error> {
+    let provider = app_state
+        .oidc_providers
+        .get(provider_id)
+        .ok_or(anyhow!("Unknown OIDC provider {provider_id}"))?;
+    let audiences = [provider.client.client_id().as_str().to_string()];
+    let claims: LogoutTokenClaims =
+        validate_provider_token(provider, logout_token, &audiences, &["iss", "aud"]).await?;
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let subject = claims.subject(now).map_err(|e| anyhow!(e))?;
+    let jti = claims.jti.as_deref().expect("Checked by subject");
+    if !app_state
+        .logout_tokens
+        .insert(&claims.iss, jti, claims.iat, now)
+    {
+        return Err(anyhow!("Logout token {jti} has already been used"));
+    }
+
+    if let Some(user_id) = end_identity_sessions(&claims.iss, subject, &app_state.database).await? {
+        app_state.live_updates.revoke(Revocation::User(user_id));
+        info!("Back-channel logout of {subject} by {provider_id}");
+    } else {
+        debug!("Ignoring back-channel logout of unknown subject {subject}");
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct BackchannelLogoutForm {
+    logout_token: String,
+}
+
+/// Receives back-channel logout requests of the provider with the id in the path.
+/// Responds with 400 if the logout token is invalid
+pub async fn backchannel_logout(
+    Path(provider_id): Path<String>,
+    Extension(app_state): Extension<AppState>,
+    Form(form): Form<BackchannelLogoutForm>,
+) -> Response {
+    let status = match process_logout_token(&provider_id, &form.logout_token, &app_state).await {
+        Ok(()) => StatusCode::OK,
+        Err(e) => {
+            warn!("Rejected back-channel logout: {e}");
+            StatusCode::BAD_REQUEST
+        }
+    };
+    (status, [(header::CACHE_CONTROL, "no-store")]).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::auth::oidc_identity::find_user_by_identity;
+    use crate::server::test_support::{create_test_user, setup};
+    use serde_json::json;
+
+    fn claims(value: Value) -> LogoutTokenClaims {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn logout_token_requirements() {
+        let now = 1_000_000;
+        let valid = claims(json!({
+            "iss": "https://idp.example.com",
+            "sub": "1234",
+            "iat": now,
+            "jti": "a",
+            "events": { BACKCHANNEL_LOGOUT_EVENT: {} },
+        }));
+        assert_eq!(valid.subject(now), Ok("1234"));
+
+        let with_nonce = claims(json!({
+            "iss": "https://idp.example.com",
+            "sub": "1234",
+            "iat": now,
+            "jti": "a",
+            "events": { BACKCHANNEL_LOGOUT_EVENT: {} },
+            "nonce": "abc",
+        }));
+        assert!(with_nonce.subject(now).is_err());
+
+        let without_event = claims(json!({
+            "iss": "https://idp.example.com",
+            "sub": "1234",
+            "iat": now,
+            "jti": "a",
+        }));
+        assert!(without_event.subject(now).is_err());
+
+        let session_only = claims(json!({
+            "iss": "https://idp.example.com",
+            "sid": "session",
+            "iat": now,
+            "jti": "a",
+            "events": { BACKCHANNEL_LOGOUT_EVENT: {} },
+        }));
+        assert!(session_only.subject(now).is_err());
+
+        let without_jti = claims(json!({
+            "iss": "https://idp.example.com",
+            "sub": "1234",
+            "iat": now,
+            "events": { BACKCHANNEL_LOGOUT_EVENT: {} },
+        }));
+        assert!(without_jti.subject(now).is_err());
+    }
+
+    #[test]
+    fn logout_token_freshness() {
+        let now = 1_000_000;
+        let token = |iat: i64| {
+            claims(json!({
+                "iss": "https://idp.example.com",
+                "sub": "1234",
+                "iat": iat,
+                "jti": "a",
+                "events": { BACKCHANNEL_LOGOUT_EVENT: {} },
+            }))
+        };
+        assert!(token(now - MAX_LOGOUT_TOKEN_AGE).subject(now).is_ok());
+        assert!(token(now - MAX_LOGOUT_TOKEN_AGE - 1).subject(now).is_err());
+        assert!(token(now + MAX_CLOCK_SKEW).subject(now).is_ok());
+        assert!(token(now + MAX_CLOCK_SKEW + 1).subject(now).is_err());
+    }
+
+    #[test]
+    fn logout_tokens_cannot_be_replayed() {
+        let cache = LogoutTokenCache::new();
+        let now = 1_000_000;
+        assert!(cache.insert("https://idp.example.com", "a", now, now));
+        assert!(!cache.insert("https://idp.example.com", "a", now, now + 10));
+        assert!(cache.insert("https://other.example.com", "a", now, now + 10));
+        assert!(cache.insert("https://idp.example.com", "b", now, now + 10));
+
+        // Expired identifiers are forgotten, the token itself is rejected by its `iat` by then
+        let later = now + MAX_LOGOUT_TOKEN_AGE + 1;
+        assert!(cache.insert("https://idp.example.com", "c", later, later));
+        assert_eq!(cache.seen.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_end_identity_sessions() {
+        let database = setup().await;
+        let user = create_test_user(&database, 1).await;
+        let now = OffsetDateTime::now_utc();
+        entity::oidc_identity::ActiveModel {
+            issuer: Set("https://idp.example.com".to_string()),
+            subject: Set("1234".to_string()),
+            email: Set(None),
+            created_at: Set(now),
+            last_login_at: Set(None),
+            logged_out_at: Set(None),
+            user_id: Set(user.id),
+            ..Default::default()
+        }
+        .insert(&database)
+        .await
+        .unwrap();
+        let issued_before = now.unix_timestamp() - 10;
+
+        assert!(
+            find_user_by_identity(
+                "https://idp.example.com",
+                "1234",
+                Some(issued_before),
+                &database
+            )
+            .await
+            .unwrap()
+            .is_some()
+        );
+        assert_eq!(
+            end_identity_sessions("https://idp.example.com", "unknown", &database)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            end_identity_sessions("https://idp.example.com", "1234", &database)
+                .await
+                .unwrap(),
+            Some(user.id)
+        );
+        assert!(
+            find_user_by_identity(
+                "https://idp.example.com",
+                "1234",
+                Some(issued_before),
+                &database
+            )
+            .await
+            .unwrap()
+            .is_none(),
+            "Expected tokens issued before the logout to be rejected"
+        );
+        assert!(
+            find_user_by_identity(
+                "https://idp.example.com",
+                "1234",
+                Some(now.unix_timestamp() + 10),
+                &database
+            )
+            .await
+            .unwrap()
+            .is_some(),
+            "Expected tokens issued after the logout to be accepted"
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use crate::error::ApiError;
+use base64::Engine;
+use dioxus::prelude::*;
+use entity::passkey_credential::Column as PasskeyColumn;
+use entity::prelude::*;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    Set,
+};
+use time::OffsetDateTime;
+use webauthn_rs::prelude::{
+    AuthenticationResult, DiscoverableAuthentication, DiscoverableKey, Passkey,
+    PublicKeyCredential, Url, Uuid, Webauthn, WebauthnBuilder,
+};
+
+const RELYING_PARTY_NAME: &str = "RoomMates";
+
+/// Creates the WebAuthn relying party. The relying party id is the host of the server url,
+/// so passkeys only work when the app is accessed through that url.
+///
+/// # Errors
+///
+/// Returns an error if `server_url` is not a valid url with a host
+pub fn create_webauthn(server_url: &str) -> Result<Webauthn, anyhow::Error> {
+    let origin = Url::parse(server_url)?;
+    let rp_id = origin
+        .host_str()
+        .ok_or(anyhow::anyhow!("SERVER_URL must contain a host"))?
+        .to_string();
+    Ok(WebauthnBuilder::new(&rp_id, &origin)?
+        .rp_name(RELYING_PARTY_NAME)
+        .build()?)
+}
+
+#[must_use]
+pub fn encode_credential_id(credential_id: &[u8]) -> String {
+    base64::prelude::BASE64_URL_SAFE_NO_PAD.encode(credential_id)
+}
+
+/// Returns the WebAuthn user handle of the user. All passkeys of a user share the same handle,
+/// a new random one is created for the first passkey.
+///
+/// # Errors
+///
+/// Returns an error if the database operation fails
+pub async fn user_handle(user_id: i32, db: &DatabaseConnection) -> Result<Uuid, anyhow::Error> {
+    let existing = PasskeyCredential::find()
+        .filter(PasskeyColumn::UserId.eq(user_id))
+        .one(db)
+        .await?;
+    match existing {
+        Some(credential) => Ok(Uuid::parse_str(&credential.user_handle)?),
+        None => Ok(Uuid::new_v4()),
+    }
+}
+
+/// Loads the passkeys of all users matching `condition` together with their database models
+///
+/// # Errors
+///
+/// Returns an error if the database operation fails or a stored passkey cannot be deserialized
+pub async fn find_passkeys(
+    condition: impl sea_orm::sea_query::IntoCondition,
+    db: &DatabaseConnection,
+) -> Result<Vec<(entity::passkey_credential::Model, Passkey)>, anyhow::Error> {
+    PasskeyCredential::find()
+        .filter(condition)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|model| {
+            let passkey = serde_json::from_str(&model.passkey)?;
+            Ok((model, passkey))
+        })
+        .collect()
+}
+
+/// Stores a newly registered passkey
+///
+/// # Errors
+///
+/// Returns an error if the database operation fails
+pub async fn save_passkey(
+    user_id: i32,
+    user_handle: Uuid,
+    name: String,
+    passkey: &Passkey,
+    db: &DatabaseConnection,
+) -> Result<entity::passkey_credential::Model, anyhow::Error> {
+    let credential = entity::passkey_credential::ActiveModel {
+        name: Set(name),
+        credential_id: Set(encode_credential_id(passkey.cred_id().as_ref())),
+        user_handle: Set(user_handle.to_string()),
+        passkey: Set(serde_json::to_string(passkey)?),
+        created_at: Set(OffsetDateTime::now_local()?),
+        last_used_at: Set(None),
+        user_id: Set(user_id),
+        ..Default::default()
+    };
+    Ok(credential.insert(db).await?)
+}
+
+/// Stores the signature counter and backup state reported during a login and marks the passkey as used
+///
+/// # Errors
+///
+/// Returns an error if the database operation fails
+pub async fn record_passkey_use(
+    model: entity::passkey_credential::Model,
+    mut passkey: Passkey,
+    result: &AuthenticationResult,
+    db: &DatabaseConnection,
+) -> Result<(), anyhow::Error> {
+    let passkey_changed = passkey.update_credential(result).unwrap_or(false);
+    let mut model = model.into_active_model();
+    if passkey_changed {
+        model.passkey = Set(serde_json::to_string(&passkey)?);
+    }
+    model.last_used_at = Set(Some(OffsetDateTime::now_local()?));
+    model.update(db).await?;
+    Ok(())
+}
+
+/// Verifies the assertion of a discoverable passkey against the stored passkeys of its user handle and records
+/// the use, so the signature counter of the passkey stays current
+///
+/// returns: Result<i32, ApiError> - Id of the user the passkey belongs to
+///
+/// # Errors
+///
+/// * Unauthorized: The passkey is unknown or the assertion is invalid
+/// * Internal Server Error: A database operation failed
+pub async fn verify_passkey_assertion(
+    webauthn: &Webauthn,
+    credential: &PublicKeyCredential,
+    authentication: DiscoverableAuthentication,
+    db: &DatabaseConnection,
+) -> Result<i32, ApiError> {
+    let (user_handle, _) = webauthn
+        .identify_discoverable_authentication(credential)
+        .or_unauthorized("Unknown passkey")?;
+
+    let passkeys = find_passkeys(PasskeyColumn::UserHandle.eq(user_handle.to_string()), db)
+        .await
+        .or_internal_server_error("Error loading passkeys")?;
+    let keys: Vec<DiscoverableKey> = passkeys
+        .iter()
+        .map(|(_, passkey)| DiscoverableKey::from(passkey))
+        .collect();
+
+    let result = webauthn
+        .finish_discoverable_authentication(credential, authentication, &keys)
+        .inspect_err(|e| warn!("Passkey login failed: {e}"))
+        .or_unauthorized("Passkey could not be verified")?;
+
+    let used_credential_id = encode_credential_id(result.cred_id().as_ref());
+    let (model, passkey) = passkeys
+        .into_iter()
+        .find(|(model, _)| model.credential_id == used_credential_id)
+        .or_unauthorized("Unknown passkey")?;
+    let user_id = model.user_id;
+    record_passkey_use(model, passkey, &result, db)
+        .await
+        .or_internal_server_error("Error updating passkey")?;
+    Ok(user_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::test_support::{create_test_user, setup};
+    use sea_orm::ModelTrait;
+    use webauthn_authenticator_rs::WebauthnAuthenticator;
+    use webauthn_authenticator_rs::softpasskey::SoftPasskey;
+
+    const SERVER_URL: &str = "http://localhost:8080";
+
+    fn origin() -> Url {
+        Url::parse(SERVER_URL).unwrap()
+    }
+
+    fn software_authenticator() -> WebauthnAuthenticator<SoftPasskey> {
+        // Reports user verification, which passkeys require
+        WebauthnAuthenticator::new(SoftPasskey::new(true))
+    }
+
+    fn signature_counter(model: &entity::passkey_credential::Model) -> u64 {
+        let passkey: serde_json::Value = serde_json::from_str(&model.passkey).unwrap();
+        passkey["cred"]["counter"].as_u64().unwrap()
+    }
+
+    /// Registers a passkey of the authenticator like `finish_passkey_registration`
+    async fn register(
+        webauthn: &Webauthn,
+        authenticator: &mut WebauthnAuthenticator<SoftPasskey>,
+        user: &entity::user::Model,
+        db: &DatabaseConnection,
+    ) -> (Uuid, Passkey, entity::passkey_credential::Model) {
+        let handle = user_handle(user.id, db).await.unwrap();
+        let (options, registration) = webauthn
+            .start_passkey_registration(handle, &user.email, &user.first_name, None)
+            .unwrap();
+        let credential = authenticator.do_registration(origin(), options).unwrap();
+        let passkey = webauthn
+            .finish_passkey_registration(&credential, &registration)
+            .unwrap();
+        let model = save_passkey(user.id, handle, "Laptop".to_string(), &passkey, db)
+            .await
+            .unwrap();
+        (handle, passkey, model)
+    }
+
+    /// Answers a discoverable login challenge. The software authenticator only signs challenges naming its
+    /// credential and omits the user handle, so both are added like a discoverable authenticator would.
+    /// Neither is covered by the signature.
+    fn sign_login_challenge(
+        webauthn: &Webauthn,
+        authenticator: &mut WebauthnAuthenticator<SoftPasskey>,
+        passkey: &Passkey,
+        handle: Uuid,
+    ) -> (PublicKeyCredential, DiscoverableAuthentication) {
+        let (mut options, authentication) = webauthn.start_discoverable_authentication().unwrap();
+        let (named, _) = webauthn
+            .start_passkey_authentication(std::slice::from_ref(passkey))
+            .unwrap();
+        options.public_key.allow_credentials = named.public_key.allow_credentials;
+        let mut credential = authenticator.do_authentication(origin(), options).unwrap();
+        credential.response.user_handle = Some(handle.as_bytes().to_vec().into());
+        (credential, authentication)
+    }
+
+    #[tokio::test]
+    async fn test_register_and_login_with_passkey() {
+        let database = setup().await;
+        let user = create_test_user(&database, 1).await;
+        let webauthn = create_webauthn(SERVER_URL).unwrap();
+        let mut authenticator = software_authenticator();
+
+        let (handle, passkey, registered) =
+            register(&webauthn, &mut authenticator, &user, &database).await;
+        assert_eq!(registered.last_used_at, None);
+        assert_eq!(
+            user_handle(user.id, &database).await.unwrap(),
+            handle,
+            "Expected further passkeys of the user to share the handle"
+        );
+
+        let (credential, authentication) =
+            sign_login_challenge(&webauthn, &mut authenticator, &passkey, handle);
+        let user_id = verify_passkey_assertion(&webauthn, &credential, authentication, &database)
+            .await
+            .unwrap();
+        assert_eq!(user_id, user.id);
+
+        let used = PasskeyCredential::find_by_id(registered.id)
+            .one(&database)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(used.last_used_at.is_some());
+        assert!(
+            signature_counter(&used) > signature_counter(&registered),
+            "Expected the signature counter of the login to be stored"
+        );
+
+        // The stored counter has to be accepted by the next login
+        let (credential, authentication) =
+            sign_login_challenge(&webauthn, &mut authenticator, &passkey, handle);
+        verify_passkey_assertion(&webauthn, &credential, authentication, &database)
+            .await
+            .unwrap();
+        let used_again = PasskeyCredential::find_by_id(registered.id)
+            .one(&database)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(signature_counter(&used_again) > signature_counter(&used));
+
+        // Assertions are bound to their challenge and cannot be replayed
+        let (_, authentication) =
+            sign_login_challenge(&webauthn, &mut authenticator, &passkey, handle);
+        assert!(matches!(
+            verify_passkey_assertion(&webauthn, &credential, authentication, &database).await,
+            Err(ApiError::Unauthorized { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_passkey_is_rejected() {
+        let database = setup().await;
+        let user = create_test_user(&database, 1).await;
+        let webauthn = create_webauthn(SERVER_URL).unwrap();
+        let mut authenticator = software_authenticator();
+
+        let (handle, passkey, registered) =
+            register(&webauthn, &mut authenticator, &user, &database).await;
+        registered.delete(&database).await.unwrap();
+
+        let (credential, authentication) =
+            sign_login_challenge(&webauthn, &mut authenticator, &passkey, handle);
+        assert!(matches!(
+            verify_passkey_assertion(&webauthn, &credential, authentication, &database).await,
+            Err(ApiError::Unauthorized { .. })
+        ));
+
+        // A passkey registered by another user must not log into this account
+        let mut new_authenticator = software_authenticator();
+        let (handle, _, _) = register(&webauthn, &mut new_authenticator, &user, &database).await;
+        let other = create_test_user(&database, 2).await;
+        let mut other_authenticator = software_authenticator();
+        let (_, other_passkey, _) =
+            register(&webauthn, &mut other_authenticator, &other, &database).await;
+        let (credential, authentication) =
+            sign_login_challenge(&webauthn, &mut other_authenticator, &other_passkey, handle);
+        assert!(matches!(
+            verify_passkey_assertion(&webauthn, &credential, authentication, &database).await,
+            Err(ApiError::Unauthorized { .. })
+        ));
+    }
+}
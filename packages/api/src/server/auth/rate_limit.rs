@@ -0,0 +1,470 @@
+use crate::error::ApiError;
+use crate::server::AppState;
+use crate::server::settings::LoginRateLimitSettings;
+use crate::server::utils::client_ip;
+use dioxus::fullstack::axum::middleware::Next;
+use dioxus::fullstack::extract::Request;
+use dioxus::fullstack::response::Response;
+use dioxus::prelude::*;
+use dioxus::server::axum::body::{Body, to_bytes};
+use dioxus::server::axum::http::{Method, header};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const LOGIN_PATH: &str = "/api/users/login";
+const PASSWORD_RESET_PATH: &str = "/api/users/password-reset/request";
+const MAX_LOGIN_BODY_SIZE: usize = 16 * 1024;
+
+/// Tuning parameters for the login throttling
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    /// Failed attempts allowed before the backoff kicks in
+    pub free_attempts: u32,
+    /// Delay after the first failure exceeding `free_attempts`. Doubles with every further failure
+    pub base_delay: Duration,
+    /// Failed attempts for a single account after which the account gets locked
+    pub account_lockout_threshold: u32,
+    /// Failed attempts from a single IP address after which the address gets locked
+    pub ip_lockout_threshold: u32,
+    /// How long a lockout lasts. Also caps the backoff delay
+    pub lockout_duration: Duration,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            free_attempts: 3,
+            base_delay: Duration::from_secs(1),
+            account_lockout_threshold: 10,
+            ip_lockout_threshold: 30,
+            lockout_duration: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    #[must_use]
+    pub fn from_settings(settings: &LoginRateLimitSettings) -> Self {
+        Self {
+            free_attempts: settings.free_attempts,
+            base_delay: Duration::from_secs(settings.backoff_base),
+            account_lockout_threshold: settings.account_lockout_threshold,
+            ip_lockout_threshold: settings.ip_lockout_threshold,
+            lockout_duration: Duration::from_secs(settings.lockout_duration),
+        }
+    }
+
+    /// Returns how long a client has to wait after `failures` consecutive failed attempts
+    fn delay_for(&self, failures: u32, lockout_threshold: u32) -> Duration {
+        if failures >= lockout_threshold {
+            return self.lockout_duration;
+        }
+        if failures < self.free_attempts {
+            return Duration::ZERO;
+        }
+        let exponent = (failures - self.free_attempts).min(31);
+        self.base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.lockout_duration)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum RateLimitKey {
+    Ip(IpAddr),
+    Account(String),
+    // Tracked separately from `Account`, so a correct password does not reset failed second factor attempts
+    SecondFactor(i32),
+    // Every password reset request counts, as each of them sends an email to the address
+    PasswordReset(String),
+    // Separate from `Ip`, so a successful login from the same address does not reset it
+    PasswordResetIp(IpAddr),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct FailedAttempts {
+    count: u32,
+    last_failure: Instant,
+}
+
+/// In-memory throttle for password logins, tracking failed attempts per IP address and per account
+#[derive(Clone, Debug)]
+pub struct LoginRateLimiter {
+    config: RateLimitConfig,
+    attempts: Arc<Mutex<HashMap<RateLimitKey, FailedAttempts>>>,
+}
+
+impl LoginRateLimiter {
+    #[must_use]
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            attempts: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn keys(ip: Option<IpAddr>, email: Option<&str>) -> Vec<RateLimitKey> {
+        let mut keys = Vec::with_capacity(2);
+        if let Some(ip) = ip {
+            keys.push(RateLimitKey::Ip(ip));
+        }
+        if let Some(email) = email {
+            keys.push(RateLimitKey::Account(email.trim().to_lowercase()));
+        }
+        keys
+    }
+
+    fn lockout_threshold(&self, key: &RateLimitKey) -> u32 {
+        match key {
+            RateLimitKey::Ip(_) | RateLimitKey::PasswordResetIp(_) => {
+                self.config.ip_lockout_threshold
+            }
+            RateLimitKey::Account(_)
+            | RateLimitKey::SecondFactor(_)
+            | RateLimitKey::PasswordReset(_) => self.config.account_lockout_threshold,
+        }
+    }
+
+    /// Returns how long the client has to wait before it may try again or `None` if the attempt is allowed
+    ///
+    /// # Arguments
+    ///
+    /// * `ip`: Address of the client, if known
+    /// * `email`: Email of the account the client tries to log into, if known
+    /// * `now`: Point in time of the attempt
+    ///
+    /// returns: Option<Duration>
+    #[must_use]
+    pub fn retry_after(
+        &self,
+        ip: Option<IpAddr>,
+        email: Option<&str>,
+        now: Instant,
+    ) -> Option<Duration> {
+        self.retry_after_keys(&Self::keys(ip, email), now)
+    }
+
+    /// Like [`LoginRateLimiter::retry_after`], but for second factor codes of the supplied user
+    #[must_use]
+    pub fn second_factor_retry_after(&self, user_id: i32, now: Instant) -> Option<Duration> {
+        self.retry_after_keys(&[RateLimitKey::SecondFactor(user_id)], now)
+    }
+
+    /// Like [`LoginRateLimiter::retry_after`], but for password reset requests
+    #[must_use]
+    pub fn password_reset_retry_after(
+        &self,
+        ip: Option<IpAddr>,
+        email: Option<&str>,
+        now: Instant,
+    ) -> Option<Duration> {
+        self.retry_after_keys(&Self::password_reset_keys(ip, email), now)
+    }
+
+    fn password_reset_keys(ip: Option<IpAddr>, email: Option<&str>) -> Vec<RateLimitKey> {
+        let mut keys = Vec::with_capacity(2);
+        if let Some(ip) = ip {
+            keys.push(RateLimitKey::PasswordResetIp(ip));
+        }
+        if let Some(email) = email {
+            keys.push(RateLimitKey::PasswordReset(email.trim().to_lowercase()));
+        }
+        keys
+    }
+
+    fn retry_after_keys(&self, keys: &[RateLimitKey], now: Instant) -> Option<Duration> {
+        let attempts = self.attempts.lock().expect("rate limiter lock poisoned");
+        keys.iter()
+            .filter_map(|key| {
+                let entry = attempts.get(key)?;
+                let delay = self
+                    .config
+                    .delay_for(entry.count, self.lockout_threshold(key));
+                let elapsed = now.saturating_duration_since(entry.last_failure);
+                (elapsed < delay).then(|| delay - elapsed)
+            })
+            .max()
+    }
+
+    /// Records a failed login attempt and drops entries that no longer influence any decision
+    pub fn record_failure(&self, ip: Option<IpAddr>, email: Option<&str>, now: Instant) {
+        self.record_failure_keys(Self::keys(ip, email), now);
+    }
+
+    /// Records a wrong second factor code of the supplied user
+    pub fn record_second_factor_failure(&self, user_id: i32, now: Instant) {
+        self.record_failure_keys(vec![RateLimitKey::SecondFactor(user_id)], now);
+    }
+
+    /// Records a password reset request. Never reset by a successful login
+    pub fn record_password_reset(&self, ip: Option<IpAddr>, email: Option<&str>, now: Instant) {
+        self.record_failure_keys(Self::password_reset_keys(ip, email), now);
+    }
+
+    fn record_failure_keys(&self, keys: Vec<RateLimitKey>, now: Instant) {
+        let mut attempts = self.attempts.lock().expect("rate limiter lock poisoned");
+        let lockout_duration = self.config.lockout_duration;
+        attempts.retain(|_, entry| {
+            now.saturating_duration_since(entry.last_failure) < lockout_duration
+        });
+        for key in keys {
+            attempts
+                .entry(key)
+                .and_modify(|entry| {
+                    entry.count = entry.count.saturating_add(1);
+                    entry.last_failure = now;
+                })
+                .or_insert(FailedAttempts {
+                    count: 1,
+                    last_failure: now,
+                });
+        }
+    }
+
+    /// Resets the counters after a successful login
+    pub fn record_success(&self, ip: Option<IpAddr>, email: Option<&str>) {
+        let mut attempts = self.attempts.lock().expect("rate limiter lock poisoned");
+        for key in Self::keys(ip, email) {
+            attempts.remove(&key);
+        }
+    }
+
+    /// Resets the second factor counter of the supplied user after a valid code
+    pub fn record_second_factor_success(&self, user_id: i32) {
+        let mut attempts = self.attempts.lock().expect("rate limiter lock poisoned");
+        attempts.remove(&RateLimitKey::SecondFactor(user_id));
+    }
+}
+
+#[derive(Deserialize)]
+struct LoginBody {
+    email: String,
+}
+
+fn too_many_requests(retry_after: Duration, reason: &str) -> Response {
+    // Round up, so clients never retry before the backoff is over
+    let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    let error = ApiError::rate_limited(reason, seconds);
+    // Same shape as the error payload of server functions, so the client decodes the `ApiError`
+    let body = serde_json::json!({
+        "message": error.to_string(),
+        "code": 429,
+        "data": error,
+    });
+
+    Response::builder()
+        .status(StatusCode::TOO_MANY_REQUESTS)
+        .header(header::RETRY_AFTER, seconds.to_string())
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("static response must be valid")
+}
+
+/// Middleware throttling password logins and password reset requests per IP address and per account.
+/// Rejected attempts are answered with `429 Too Many Requests` and a `Retry-After` header.
+///
+/// # Errors
+/// * [`StatusCode::INTERNAL_SERVER_ERROR`]: If extracting the app state fails
+/// * [`StatusCode::PAYLOAD_TOO_LARGE`]: If the login request body cannot be buffered
+pub async fn login_rate_limit_middleware(
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let path = request.uri().path();
+    if request.method() != Method::POST || (path != LOGIN_PATH && path != PASSWORD_RESET_PATH) {
+        return Ok(next.run(request).await);
+    }
+    let is_login = path == LOGIN_PATH;
+
+    let state = request
+        .extensions()
+        .get::<AppState>()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+    let limiter = state.login_rate_limiter.clone();
+    let ip = client_ip(&request, &state.settings.trusted_proxies);
+
+    let (parts, body) = request.into_parts();
+    let bytes = to_bytes(body, MAX_LOGIN_BODY_SIZE)
+        .await
+        .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+    let email = serde_json::from_slice::<LoginBody>(&bytes)
+        .ok()
+        .map(|body| body.email);
+
+    if !is_login {
+        let now = Instant::now();
+        if let Some(retry_after) = limiter.password_reset_retry_after(ip, email.as_deref(), now) {
+            warn!(
+                "Rejected password reset request for {} from {ip:?}",
+                email.as_deref().unwrap_or("<unknown>")
+            );
+            return Ok(too_many_requests(
+                retry_after,
+                "Too many password reset requests",
+            ));
+        }
+        limiter.record_password_reset(ip, email.as_deref(), now);
+        return Ok(next
+            .run(Request::from_parts(parts, Body::from(bytes)))
+            .await);
+    }
+
+    if let Some(retry_after) = limiter.retry_after(ip, email.as_deref(), Instant::now()) {
+        warn!(
+            "Rejected login attempt for {} from {ip:?}",
+            email.as_deref().unwrap_or("<unknown>")
+        );
+        return Ok(too_many_requests(
+            retry_after,
+            "Too many failed login attempts",
+        ));
+    }
+
+    let response = next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await;
+
+    if response.status().is_success() {
+        limiter.record_success(ip, email.as_deref());
+    } else if response.status() == StatusCode::UNAUTHORIZED {
+        limiter.record_failure(ip, email.as_deref(), Instant::now());
+    }
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter() -> LoginRateLimiter {
+        LoginRateLimiter::new(RateLimitConfig {
+            free_attempts: 2,
+            base_delay: Duration::from_secs(1),
+            account_lockout_threshold: 5,
+            ip_lockout_threshold: 8,
+            lockout_duration: Duration::from_secs(60),
+        })
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_until_lockout() {
+        let config = limiter().config;
+        assert_eq!(config.delay_for(1, 5), Duration::ZERO);
+        assert_eq!(config.delay_for(2, 5), Duration::from_secs(1));
+        assert_eq!(config.delay_for(3, 5), Duration::from_secs(2));
+        assert_eq!(config.delay_for(4, 5), Duration::from_secs(4));
+        assert_eq!(config.delay_for(5, 5), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn account_gets_locked_and_released() {
+        let limiter = limiter();
+        let start = Instant::now();
+        for _ in 0..5 {
+            limiter.record_failure(None, Some("Test@Test.de"), start);
+        }
+
+        assert_eq!(
+            limiter.retry_after(None, Some("test@test.de"), start),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            limiter.retry_after(None, Some("other@test.de"), start),
+            None,
+            "Expected other accounts to be unaffected"
+        );
+        assert_eq!(
+            limiter.retry_after(None, Some("test@test.de"), start + Duration::from_secs(61)),
+            None,
+            "Expected lockout to expire"
+        );
+    }
+
+    #[test]
+    fn success_resets_counters() {
+        let limiter = limiter();
+        let ip: IpAddr = [127, 0, 0, 1].into();
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.record_failure(Some(ip), Some("test@test.de"), start);
+        }
+        assert!(limiter.retry_after(Some(ip), None, start).is_some());
+
+        limiter.record_success(Some(ip), Some("test@test.de"));
+        assert_eq!(
+            limiter.retry_after(Some(ip), Some("test@test.de"), start),
+            None
+        );
+    }
+
+    #[test]
+    fn password_resets_are_throttled_per_address() {
+        let address: IpAddr = [127, 0, 0, 1].into();
+        for ip in [None, Some(address)] {
+            let limiter = limiter();
+            let start = Instant::now();
+            for _ in 0..2 {
+                assert_eq!(
+                    limiter.password_reset_retry_after(ip, Some("test@test.de"), start),
+                    None
+                );
+                limiter.record_password_reset(ip, Some("test@test.de"), start);
+            }
+            limiter.record_success(ip, Some("test@test.de"));
+
+            assert_eq!(
+                limiter.password_reset_retry_after(ip, Some("Test@Test.de"), start),
+                Some(Duration::from_secs(1)),
+                "Expected a successful login to leave the reset counter untouched"
+            );
+            assert_eq!(
+                limiter.retry_after(ip, Some("test@test.de"), start),
+                None,
+                "Expected reset requests to not throttle logins"
+            );
+            assert_eq!(
+                limiter.password_reset_retry_after(None, Some("other@test.de"), start),
+                None
+            );
+        }
+    }
+
+    #[test]
+    fn login_success_keeps_password_reset_address_counter() {
+        let limiter = limiter();
+        let ip: IpAddr = [127, 0, 0, 1].into();
+        let start = Instant::now();
+        for _ in 0..2 {
+            limiter.record_password_reset(Some(ip), None, start);
+        }
+        limiter.record_success(Some(ip), None);
+
+        assert_eq!(
+            limiter.password_reset_retry_after(Some(ip), Some("other@test.de"), start),
+            Some(Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn second_factor_survives_password_success() {
+        let limiter = limiter();
+        let start = Instant::now();
+        for _ in 0..5 {
+            limiter.record_second_factor_failure(1, start);
+        }
+        limiter.record_success(None, Some("test@test.de"));
+
+        assert_eq!(
+            limiter.second_factor_retry_after(1, start),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(limiter.second_factor_retry_after(2, start), None);
+
+        limiter.record_second_factor_success(1);
+        assert_eq!(limiter.second_factor_retry_after(1, start), None);
+    }
+}
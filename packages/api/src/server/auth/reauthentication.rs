@@ -0,0 +1,98 @@
+//! Proof that the user authenticated again right before an irreversible action, e.g. deleting the account.
+//! Users with a password enter it instead, accounts without one log in at their provider again or use a passkey.
+
+use crate::error::ApiError;
+use dioxus::prelude::*;
+use time::{Duration, OffsetDateTime};
+use tower_sessions::{Expiry, Session};
+
+const REAUTHENTICATION_SESSION_KEY: &str = "reauthentication";
+/// Authentications older than this many seconds do not count as recent
+const MAX_REAUTHENTICATION_AGE: i64 = 5 * 60;
+/// Tolerated clock difference to the identity provider
+const MAX_CLOCK_SKEW: i64 = 60;
+/// Expiry of the tower session. Keeps a recorded re-authentication as long as it counts as recent
+pub(crate) const SESSION_EXPIRY: Expiry =
+    Expiry::OnInactivity(Duration::seconds(MAX_REAUTHENTICATION_AGE));
+
+/// Whether an authentication at `authenticated_at` happened recently enough at `now`
+pub(crate) fn is_recent_authentication(authenticated_at: i64, now: i64) -> bool {
+    authenticated_at <= now + MAX_CLOCK_SKEW && now - authenticated_at <= MAX_REAUTHENTICATION_AGE
+}
+
+/// Remembers in the session that the user authenticated again at `authenticated_at`
+pub(crate) async fn record_reauthentication(
+    session: &Session,
+    user_id: i32,
+    authenticated_at: i64,
+) -> Result<(), ApiError> {
+    session
+        .insert(REAUTHENTICATION_SESSION_KEY, (user_id, authenticated_at))
+        .await
+        .or_internal_server_error("Failed to update session")?;
+    Ok(())
+}
+
+/// Consumes the re-authentication remembered in the session
+///
+/// returns: `true` if the user authenticated again recently
+pub(crate) async fn take_reauthentication(
+    session: &Session,
+    user_id: i32,
+) -> Result<bool, ApiError> {
+    let reauthentication: Option<(i32, i64)> =
+        session
+            .remove(REAUTHENTICATION_SESSION_KEY)
+            .await
+            .or_internal_server_error("Failed to retrieve session")?;
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    Ok(
+        reauthentication.is_some_and(|(reauthenticated_user_id, authenticated_at)| {
+            reauthenticated_user_id == user_id && is_recent_authentication(authenticated_at, now)
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::session_store::DatabaseSessionStore;
+    use crate::server::test_support::setup;
+    use std::sync::Arc;
+    use tower_sessions::SessionStore;
+
+    #[test]
+    fn test_is_recent_authentication() {
+        let now = 1_000_000;
+        assert!(is_recent_authentication(now, now));
+        assert!(is_recent_authentication(
+            now - MAX_REAUTHENTICATION_AGE,
+            now
+        ));
+        assert!(!is_recent_authentication(
+            now - MAX_REAUTHENTICATION_AGE - 1,
+            now
+        ));
+        assert!(is_recent_authentication(now + MAX_CLOCK_SKEW, now));
+        assert!(!is_recent_authentication(now + MAX_CLOCK_SKEW + 1, now));
+    }
+
+    #[tokio::test]
+    async fn test_reauthentication_outlives_session_inactivity() {
+        let store = Arc::new(DatabaseSessionStore::new(setup().await));
+        let session = Session::new(None, store.clone(), Some(SESSION_EXPIRY));
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        record_reauthentication(&session, 1, now).await.unwrap();
+        session.save().await.unwrap();
+
+        let id = session.id().unwrap();
+        let record = store.load(&id).await.unwrap().unwrap();
+        assert!(
+            record.expiry_date.unix_timestamp() >= now + MAX_REAUTHENTICATION_AGE,
+            "Expected the session to be kept until the re-authentication expires"
+        );
+
+        let session = Session::new(Some(id), store, Some(SESSION_EXPIRY));
+        assert!(take_reauthentication(&session, 1).await.unwrap());
+    }
+}
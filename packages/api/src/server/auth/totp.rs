@@ -0,0 +1,444 @@
+use crate::error::ApiError;
+use crate::server::auth::{create_session_key, hash_session_key};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use dioxus::prelude::*;
+use entity::login_challenge::Column as ChallengeColumn;
+use entity::prelude::*;
+use entity::totp_credential::Column as CredentialColumn;
+use entity::totp_recovery_code::Column as RecoveryCodeColumn;
+use sea_orm::prelude::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait,
+    IntoActiveModel, ModelTrait, QueryFilter, Set, TransactionTrait,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+use time::{Duration, OffsetDateTime};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+const TOTP_ISSUER: &str = "RoomMates";
+const RECOVERY_CODE_COUNT: usize = 10;
+const LOGIN_CHALLENGE_EXPIRATION_DURATION: i64 = 5;
+const MAX_LOGIN_CHALLENGE_ATTEMPTS: i32 = 5;
+
+/// Everything an authenticator app needs to add a new TOTP credential
+pub struct TotpEnrolment {
+    pub otpauth_url: String,
+    pub secret: String,
+    /// Base64 encoded PNG of a QR code containing `otpauth_url`
+    pub qr_code: String,
+}
+
+fn build_totp(secret: &str, account_name: &str) -> Result<TOTP, anyhow::Error> {
+    let secret = Secret::Encoded(secret.to_string()).to_bytes()?;
+    Ok(TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        secret,
+        Some(TOTP_ISSUER.to_string()),
+        account_name.replace(':', ""),
+    )?)
+}
+
+/// Compares two codes in constant time
+fn codes_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+/// Returns the time step of `code` if it is valid at `time` and newer than `last_used_step`,
+/// so every code can be used only once
+fn matching_step(totp: &TOTP, code: &str, time: u64, last_used_step: Option<i64>) -> Option<i64> {
+    let current = time / totp.step;
+    let skew = u64::from(totp.skew);
+    (current.saturating_sub(skew)..=current + skew)
+        .filter_map(|step| i64::try_from(step).ok().map(|signed| (step, signed)))
+        .filter(|(_, signed)| last_used_step.is_none_or(|last| *signed > last))
+        .find(|(step, _)| codes_match(&totp.generate(step * totp.step), code))
+        .map(|(_, signed)| signed)
+}
+
+fn unix_time() -> Result<u64, anyhow::Error> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+/// Marks `step` as used. Fails if a concurrent request used the same or a later step first
+async fn consume_step(
+    credential_id: i32,
+    step: i64,
+    db: &impl ConnectionTrait,
+) -> Result<bool, sea_orm::DbErr> {
+    let result = TotpCredential::update_many()
+        .col_expr(CredentialColumn::LastUsedStep, Expr::value(step))
+        .filter(CredentialColumn::Id.eq(credential_id))
+        .filter(
+            Condition::any()
+                .add(CredentialColumn::LastUsedStep.is_null())
+                .add(CredentialColumn::LastUsedStep.lt(step)),
+        )
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected == 1)
+}
+
+/// Recovery codes are compared case insensitive and ignore separators, so they can be typed in any form
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; 8];
+    OsRng.fill_bytes(&mut bytes);
+    let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!(
+        "{}-{}-{}-{}",
+        &hex[0..4],
+        &hex[4..8],
+        &hex[8..12],
+        &hex[12..16]
+    )
+}
+
+/// Returns the TOTP credential of the supplied user, if they finished the enrolment
+///
+/// # Errors
+///
+/// Returns an error if the database operation fails
+pub async fn find_confirmed_credential(
+    user_id: i32,
+    db: &impl ConnectionTrait,
+) -> Result<Option<entity::totp_credential::Model>, sea_orm::DbErr> {
+    TotpCredential::find()
+        .filter(CredentialColumn::UserId.eq(user_id))
+        .filter(CredentialColumn::Confirmed.eq(true))
+        .one(db)
+        .await
+}
+
+/// Creates a new unconfirmed TOTP secret for the user, replacing any unfinished enrolment
+///
+/// # Errors
+///
+/// Returns an error when generating the QR code or a database operation fails
+pub async fn start_enrolment(
+    user: &entity::user::Model,
+    db: &DatabaseConnection,
+) -> Result<TotpEnrolment, anyhow::Error> {
+    let mut secret = [0u8; 20];
+    OsRng.fill_bytes(&mut secret);
+    let secret = Secret::Raw(secret.to_vec()).to_encoded().to_string();
+    let totp = build_totp(&secret, &user.email)?;
+    let qr_code = totp.get_qr_base64().map_err(|e| anyhow::anyhow!(e))?;
+
+    TotpCredential::delete_many()
+        .filter(CredentialColumn::UserId.eq(user.id))
+        .filter(CredentialColumn::Confirmed.eq(false))
+        .exec(db)
+        .await?;
+    entity::totp_credential::ActiveModel {
+        secret: Set(secret.clone()),
+        confirmed: Set(false),
+        created_at: Set(OffsetDateTime::now_local()?),
+        user_id: Set(user.id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(TotpEnrolment {
+        otpauth_url: totp.get_url(),
+        secret,
+        qr_code,
+    })
+}
+
+/// Replaces all recovery codes of the user with new ones. Only the hashes are stored.
+///
+/// returns: Result<Vec<String>, DbErr> - The plain text codes, which can only be shown to the user once
+async fn replace_recovery_codes(
+    user_id: i32,
+    db: &impl ConnectionTrait,
+) -> Result<Vec<String>, sea_orm::DbErr> {
+    TotpRecoveryCode::delete_many()
+        .filter(RecoveryCodeColumn::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| generate_recovery_code())
+        .collect();
+    TotpRecoveryCode::insert_many(codes.iter().map(|code| {
+        entity::totp_recovery_code::ActiveModel {
+            code: Set(hash_session_key(&normalize_recovery_code(code))),
+            user_id: Set(user_id),
+            ..Default::default()
+        }
+    }))
+    .exec(db)
+    .await?;
+
+    Ok(codes)
+}
+
+/// Enables two-factor authentication once the user proved their authenticator generates valid codes
+///
+/// returns: Result<Vec<String>, ApiError> - Newly generated recovery codes
+///
+/// # Errors
+///
+/// * Bad Request: There is no pending enrolment
+/// * Validation: The code is wrong
+/// * Internal Server Error: A database operation failed
+pub async fn confirm_enrolment(
+    user: &entity::user::Model,
+    code: &str,
+    db: &DatabaseConnection,
+) -> Result<Vec<String>, ApiError> {
+    let credential = TotpCredential::find()
+        .filter(CredentialColumn::UserId.eq(user.id))
+        .filter(CredentialColumn::Confirmed.eq(false))
+        .one(db)
+        .await
+        .or_internal_server_error("Error loading TOTP credential")?
+        .or_bad_request("No two-factor enrolment in progress")?;
+
+    let totp = build_totp(&credential.secret, &user.email)
+        .or_internal_server_error("Stored TOTP secret is invalid")?;
+    let time = unix_time().or_internal_server_error("Failed to get current time")?;
+    let step = matching_step(&totp, code.trim(), time, credential.last_used_step)
+        .ok_or_else(|| ApiError::validation("code", "Invalid code"))?;
+
+    let user_id = user.id;
+    let codes = db
+        .transaction::<_, Vec<String>, sea_orm::DbErr>(|txn| {
+            Box::pin(async move {
+                let mut credential = credential.into_active_model();
+                credential.confirmed = Set(true);
+                credential.last_used_step = Set(Some(step));
+                credential.update(txn).await?;
+                replace_recovery_codes(user_id, txn).await
+            })
+        })
+        .await
+        .inspect_err(|e| error!("Error enabling two-factor authentication: {e}"))
+        .or_internal_server_error("Failed to enable two-factor authentication")?;
+
+    Ok(codes)
+}
+
+/// Checks a TOTP code or, failing that, a recovery code of the user. Used recovery codes are deleted and
+/// TOTP codes are rejected once a code of the same or a later time step was accepted.
+///
+/// # Errors
+///
+/// Returns an error if the stored secret is invalid or a database operation fails
+pub async fn verify_second_factor(
+    user: &entity::user::Model,
+    credential: &entity::totp_credential::Model,
+    code: &str,
+    db: &DatabaseConnection,
+) -> Result<bool, anyhow::Error> {
+    let code = code.trim();
+    let totp = build_totp(&credential.secret, &user.email)?;
+    if let Some(step) = matching_step(&totp, code, unix_time()?, credential.last_used_step) {
+        return Ok(consume_step(credential.id, step, db).await?);
+    }
+
+    let recovery_code = TotpRecoveryCode::find()
+        .filter(RecoveryCodeColumn::UserId.eq(user.id))
+        .filter(RecoveryCodeColumn::Code.eq(hash_session_key(&normalize_recovery_code(code))))
+        .one(db)
+        .await?;
+    let Some(recovery_code) = recovery_code else {
+        return Ok(false);
+    };
+    // Only the request that actually deletes the code may use it, so a code cannot be redeemed twice
+    let result = TotpRecoveryCode::delete_many()
+        .filter(RecoveryCodeColumn::Id.eq(recovery_code.id))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected == 1)
+}
+
+/// Generates a new set of recovery codes after checking a current code of the user
+///
+/// # Errors
+///
+/// * Bad Request: Two-factor authentication is disabled
+/// * Validation: The code is wrong
+/// * Internal Server Error: A database operation failed
+pub async fn regenerate_recovery_codes(
+    user: &entity::user::Model,
+    code: &str,
+    db: &DatabaseConnection,
+) -> Result<Vec<String>, ApiError> {
+    let credential = find_confirmed_credential(user.id, db)
+        .await
+        .or_internal_server_error("Error loading TOTP credential")?
+        .or_bad_request("Two-factor authentication is not enabled")?;
+    let valid = verify_second_factor(user, &credential, code, db)
+        .await
+        .or_internal_server_error("Failed to verify code")?;
+    if !valid {
+        return Err(ApiError::validation("code", "Invalid code"));
+    }
+
+    let codes = replace_recovery_codes(user.id, db)
+        .await
+        .or_internal_server_error("Failed to generate recovery codes")?;
+    Ok(codes)
+}
+
+/// Removes the TOTP credential and all recovery codes of the user
+///
+/// # Errors
+///
+/// Returns an error if the database operation fails
+pub async fn disable(user_id: i32, db: &DatabaseConnection) -> Result<(), anyhow::Error> {
+    db.transaction::<_, (), sea_orm::DbErr>(|txn| {
+        Box::pin(async move {
+            TotpCredential::delete_many()
+                .filter(CredentialColumn::UserId.eq(user_id))
+                .exec(txn)
+                .await?;
+            TotpRecoveryCode::delete_many()
+                .filter(RecoveryCodeColumn::UserId.eq(user_id))
+                .exec(txn)
+                .await?;
+            Ok(())
+        })
+    })
+    .await?;
+    Ok(())
+}
+
+/// Creates a short-lived challenge proving that the user already entered a correct password.
+/// Only the hash of the challenge is stored.
+///
+/// returns: Result<String, Error> - The plain text challenge
+///
+/// # Errors
+///
+/// Returns an error when saving into the database fails
+pub async fn create_login_challenge(
+    user_id: i32,
+    db: &DatabaseConnection,
+) -> Result<String, anyhow::Error> {
+    let challenge = create_session_key();
+
+    let now = OffsetDateTime::now_local()?;
+    entity::login_challenge::ActiveModel {
+        token: Set(hash_session_key(&challenge)),
+        failed_attempts: Set(0),
+        created_at: Set(now),
+        expires_at: Set(now + Duration::minutes(LOGIN_CHALLENGE_EXPIRATION_DURATION)),
+        user_id: Set(user_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(challenge)
+}
+
+/// Looks up a valid login challenge together with its user
+///
+/// # Errors
+///
+/// * Unauthorized: The challenge is unknown, expired or used up
+/// * Internal Server Error: A database operation failed
+pub async fn find_login_challenge(
+    challenge: &str,
+    db: &DatabaseConnection,
+) -> Result<(entity::login_challenge::Model, entity::user::Model), ServerFnError> {
+    let now = OffsetDateTime::now_local().or_internal_server_error("Failed to get current time")?;
+    let challenge = LoginChallenge::find()
+        .filter(ChallengeColumn::Token.eq(hash_session_key(challenge)))
+        .filter(ChallengeColumn::ExpiresAt.gt(now))
+        .one(db)
+        .await
+        .or_internal_server_error("Error loading login challenge")?
+        .or_unauthorized("Login expired. Please log in again")?;
+    let user = challenge
+        .find_related(User)
+        .one(db)
+        .await
+        .or_internal_server_error("Error loading user from database")?
+        .or_not_found("User not found")?;
+    Ok((challenge, user))
+}
+
+/// Counts a wrong code against the challenge and deletes it once it has been guessed too often
+///
+/// # Errors
+///
+/// Returns an error if the database operation fails
+pub async fn record_failed_challenge(
+    challenge: entity::login_challenge::Model,
+    db: &DatabaseConnection,
+) -> Result<(), sea_orm::DbErr> {
+    let failed_attempts = challenge.failed_attempts + 1;
+    if failed_attempts >= MAX_LOGIN_CHALLENGE_ATTEMPTS {
+        challenge.delete(db).await?;
+    } else {
+        let mut challenge = challenge.into_active_model();
+        challenge.failed_attempts = Set(failed_attempts);
+        challenge.update(db).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recovery_codes_are_normalized() {
+        let code = generate_recovery_code();
+        assert_eq!(code.len(), 19);
+        assert_eq!(
+            normalize_recovery_code(&code.to_uppercase().replace('-', " ")),
+            normalize_recovery_code(&code)
+        );
+    }
+
+    #[test]
+    fn codes_cannot_be_replayed() {
+        let totp = build_totp("JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP", "test@test.de")
+            .expect("secret must be valid");
+        let time = 1_700_000_000;
+        let code = totp.generate(time);
+        let step = matching_step(&totp, &code, time, None).expect("code must be valid");
+        assert_eq!(step, i64::try_from(time / 30).unwrap());
+
+        assert_eq!(matching_step(&totp, &code, time + 10, Some(step)), None);
+        assert_eq!(
+            matching_step(&totp, &totp.generate(time - 30), time, Some(step)),
+            None,
+            "Expected codes of earlier steps to be rejected"
+        );
+        let next_code = totp.generate(time + 30);
+        assert_eq!(
+            matching_step(&totp, &next_code, time + 30, Some(step)),
+            Some(step + 1)
+        );
+        assert!(codes_match(&code, &code));
+        assert!(!codes_match(&code, &next_code));
+    }
+
+    #[test]
+    fn generated_codes_verify() {
+        let totp = build_totp("JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP", "test@test.de")
+            .expect("secret must be valid");
+        let code = totp.generate(1_700_000_000);
+        assert!(totp.check(&code, 1_700_000_000));
+        assert!(!totp.check(&code, 1_700_000_000 + 300));
+    }
+}
@@ -1,4 +1,5 @@
 pub const OIDC_ENABLED_ENV_VAR: &str = "OIDC_ENABLED";
+pub const OIDC_PROVIDERS_ENV_VAR: &str = "OIDC_PROVIDERS";
 pub const OIDC_PROVIDER_NAME_ENV_VAR: &str = "OIDC_PROVIDER_NAME";
 pub const OIDC_ISSUER_URL_ENV_VAR: &str = "OIDC_ISSUER_URL";
 pub const OIDC_CLIENT_ID_ENV_VAR: &str = "OIDC_CLIENT_ID";
@@ -6,10 +7,41 @@ pub const OIDC_CLIENT_SECRET_ENV_VAR: &str = "OIDC_CLIENT_SECRET";
 pub const OIDC_SCOPES_ENV_VAR: &str = "OIDC_SCOPES";
 pub const OIDC_JWKS_REFRESH_INTERVAL_ENV_VAR: &str = "OIDC_JWKS_REFRESH_INTERVAL";
 pub const OIDC_AUDIENCE_ENV_VAR: &str = "OIDC_AUDIENCE";
+pub const OIDC_CLAIM_EMAIL_ENV_VAR: &str = "OIDC_CLAIM_EMAIL";
+pub const OIDC_CLAIM_FIRST_NAME_ENV_VAR: &str = "OIDC_CLAIM_FIRST_NAME";
+pub const OIDC_CLAIM_LAST_NAME_ENV_VAR: &str = "OIDC_CLAIM_LAST_NAME";
+pub const OIDC_CLAIM_GROUPS_ENV_VAR: &str = "OIDC_CLAIM_GROUPS";
+pub const OIDC_GROUP_MAPPING_ENV_VAR: &str = "OIDC_GROUP_MAPPING";
+pub const OIDC_TODO_LIST_ADMIN_MAPPING_ENV_VAR: &str = "OIDC_TODO_LIST_ADMIN_MAPPING";
+pub const OIDC_GROUPS_DRY_RUN_ENV_VAR: &str = "OIDC_GROUPS_DRY_RUN";
+pub const OIDC_PROVISIONING_ENV_VAR: &str = "OIDC_PROVISIONING";
+pub const OIDC_PROVISIONING_EMAIL_DOMAINS_ENV_VAR: &str = "OIDC_PROVISIONING_EMAIL_DOMAINS";
+pub const OIDC_PROVISIONING_REQUIRE_VERIFIED_EMAIL_ENV_VAR: &str =
+    "OIDC_PROVISIONING_REQUIRE_VERIFIED_EMAIL";
+pub const CONFIG_FILE_ENV_VAR: &str = "ROOMMATES_CONFIG";
 pub const SERVER_URL_ENV_VAR: &str = "SERVER_URL";
+pub const DATABASE_URL_ENV_VAR: &str = "DATABASE_URL";
+pub const ACCESS_LOG_ENV_VAR: &str = "ACCESS_LOG";
+pub const TRUSTED_PROXIES_ENV_VAR: &str = "TRUSTED_PROXIES";
+pub const LOG_FORMAT_ENV_VAR: &str = "LOG_FORMAT";
+pub const TRASH_RETENTION_DAYS_ENV_VAR: &str = "TRASH_RETENTION_DAYS";
+pub const OTLP_ENDPOINT_ENV_VAR: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+pub const OTEL_SERVICE_NAME_ENV_VAR: &str = "OTEL_SERVICE_NAME";
 pub const SIGNUP_ENABLED_ENV_VAR: &str = "SIGNUP_ENABLED";
+pub const EMAIL_VERIFICATION_REQUIRED_ENV_VAR: &str = "EMAIL_VERIFICATION_REQUIRED";
+pub const SMTP_URL_ENV_VAR: &str = "SMTP_URL";
+pub const SMTP_FROM_ENV_VAR: &str = "SMTP_FROM";
+pub const LOGIN_FREE_ATTEMPTS_ENV_VAR: &str = "LOGIN_FREE_ATTEMPTS";
+pub const LOGIN_BACKOFF_BASE_ENV_VAR: &str = "LOGIN_BACKOFF_BASE";
+pub const LOGIN_ACCOUNT_LOCKOUT_THRESHOLD_ENV_VAR: &str = "LOGIN_ACCOUNT_LOCKOUT_THRESHOLD";
+pub const LOGIN_IP_LOCKOUT_THRESHOLD_ENV_VAR: &str = "LOGIN_IP_LOCKOUT_THRESHOLD";
+pub const LOGIN_LOCKOUT_DURATION_ENV_VAR: &str = "LOGIN_LOCKOUT_DURATION";
 
 pub const OIDC_AUTHORIZATION_COOKIE_NAME: &str = "authorization";
 pub const OIDC_REFRESH_COOKIE_NAME: &str = "refresh_token";
+pub const OIDC_PROVIDER_COOKIE_NAME: &str = "oidc_provider";
+pub const OIDC_ID_TOKEN_COOKIE_NAME: &str = "id_token";
 
 pub const SESSION_COOKIE_NAME: &str = "session";
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
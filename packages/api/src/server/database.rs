@@ -1,13 +1,9 @@
 use anyhow::Context;
 use sea_orm::{ConnectOptions, Database, DatabaseConnection};
 
-pub async fn establish_connection() -> Result<DatabaseConnection, anyhow::Error> {
-    let _ = dotenvy::dotenv();
-    let database_url =
-        std::env::var("DATABASE_URL").context("Missing environment variable 'DATABASE_URL'")?;
-
+pub async fn establish_connection(database_url: &str) -> Result<DatabaseConnection, anyhow::Error> {
     #[allow(unused_mut)]
-    let mut options = ConnectOptions::new(database_url);
+    let mut options = ConnectOptions::new(database_url.to_string());
     #[cfg(not(debug_assertions))]
     options.sqlx_logging(false);
 
@@ -19,3 +15,12 @@ pub async fn establish_connection() -> Result<DatabaseConnection, anyhow::Error>
         .context("Database does not respond. Please check the database connection")?;
     Ok(db)
 }
+
+/// Connects to the database in `DATABASE_URL`, e.g. `sqlite::memory:` when running the tests
+#[cfg(test)]
+pub async fn establish_test_connection() -> Result<DatabaseConnection, anyhow::Error> {
+    let _ = dotenvy::dotenv();
+    let database_url = std::env::var(crate::server::constants::DATABASE_URL_ENV_VAR)
+        .context("Missing environment variable 'DATABASE_URL'")?;
+    establish_connection(&database_url).await
+}
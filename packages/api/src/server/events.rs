@@ -1,18 +1,401 @@
+use crate::error::ApiError;
+use crate::routes::live_updates::ChangeEvent;
+use crate::server::AppState;
+use crate::server::activity::{Feed, record_activity, record_event_removed};
+use crate::server::audit::AuditEntry;
+use crate::server::live_updates::Audience;
+use crate::server::versioning::{VersionedUpdate, update_versioned};
 use dioxus::prelude::*;
+use entity::activity::ActivityKind;
+use entity::audit_log::{AuditAction, AuditTarget};
+use entity::event::PartialEventModel;
 use entity::is_in_group::Entity as IsInGroup;
 use entity::shared_friend_event;
 use entity::shared_group_event::{self, Entity as SharedGroupEvent};
-use sea_orm::DatabaseConnection;
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    Set, TransactionError, TransactionTrait, Unchanged,
+};
 
+/// Builds the query for all events the user may see, i.e. owned events, events shared via accepted invitations
+/// and the public events of the user's groups. Recurring events match the date bounds by their weekday.
+///
+/// The selected `is_shared_with_user` and `is_group_event` columns allow loading the results as [`entity::event::FullEvent`].
+pub fn visible_events_query(
+    user: &entity::user::Model,
+    mindate: Option<time::Date>,
+    maxdate: Option<time::Date>,
+) -> sea_orm::Select<entity::event::Entity> {
+    use entity::event::Column as EventColumn;
+    use entity::group::Column as GroupColumn;
+    use entity::group::Entity as Group;
+    use entity::invitation::Column as InvitationColumn;
+    use entity::shared_group_event::Column as GroupEventColumn;
+    use sea_orm::{Condition, JoinType, ModelTrait, QuerySelect, QueryTrait, RelationTrait};
+
+    entity::prelude::Event::find()
+        .join_as(
+            JoinType::LeftJoin,
+            entity::invitation::Relation::Event.def().rev(),
+            "invitation",
+        )
+        .join_as(
+            JoinType::LeftJoin,
+            entity::shared_group_event::Relation::Event.def().rev(),
+            "shared_group_event",
+        )
+        .column_as(InvitationColumn::Id.is_not_null(), "is_shared_with_user")
+        .column_as(GroupEventColumn::GroupId.is_not_null(), "is_group_event")
+        .filter(
+            Condition::any()
+                .add(EventColumn::OwnerId.eq(user.id))
+                .add(
+                    Condition::all()
+                        .add(InvitationColumn::RecievingUser.eq(user.id))
+                        .add(
+                            InvitationColumn::Status
+                                .eq(entity::invitation::InvitationStatus::Accepted),
+                        ),
+                )
+                .add(
+                    Condition::all()
+                        .add(
+                            GroupEventColumn::GroupId.in_subquery(
+                                user.find_related(Group)
+                                    .filter(GroupColumn::DeletedAt.is_null())
+                                    .select_only()
+                                    .column(GroupColumn::Id)
+                                    .into_query(),
+                            ),
+                        )
+                        .add(EventColumn::Private.eq(false)),
+                ),
+        )
+        .apply_if(mindate, |query, v: time::Date| {
+            let weekday_num = v.weekday().number_from_monday();
+            query.filter(
+                Condition::any()
+                    .add(
+                        Condition::all()
+                            .add(EventColumn::Date.gte(v))
+                            .add(EventColumn::Reoccurring.eq(false)),
+                    )
+                    .add(
+                        Condition::all()
+                            .add(EventColumn::Reoccurring.eq(true))
+                            .add(EventColumn::Weekday.gte(weekday_num)),
+                    ),
+            )
+        })
+        .apply_if(maxdate, |query, v| {
+            let weekday_num = v.weekday().number_from_monday();
+            query.filter(
+                Condition::any()
+                    .add(
+                        Condition::all()
+                            .add(EventColumn::Date.lte(v))
+                            .add(EventColumn::Reoccurring.eq(false)),
+                    )
+                    .add(
+                        Condition::all()
+                            .add(EventColumn::Reoccurring.eq(true))
+                            .add(EventColumn::Weekday.lte(weekday_num)),
+                    ),
+            )
+        })
+        .distinct()
+}
+
+/// Loads an event the user is allowed to see. Other events are reported as not found
+pub(crate) async fn find_visible_event(
+    user: &entity::user::Model,
+    event_id: i32,
+    db: &DatabaseConnection,
+) -> Result<entity::event::Model, ApiError> {
+    Ok(visible_events_query(user, None, None)
+        .filter(entity::event::Column::Id.eq(event_id))
+        .one(db)
+        .await
+        .inspect_err(|e| error!("Error loading event: {e}"))
+        .or_internal_server_error("Error loading event")?
+        .or_not_found("Event not found")?)
+}
+
+/// Creates an event owned by the user, optionally sharing it with one of the user's groups
+pub(crate) async fn create_event(
+    state: &AppState,
+    user_id: i32,
+    data: PartialEventModel,
+    group_id: Option<i32>,
+) -> Result<entity::event::Model, ApiError> {
+    if let Some(group_id) = group_id {
+        is_user_in_group(&state.database, group_id, user_id)
+            .await?
+            .or_forbidden("No permission to add events to this group")?;
+    }
+
+    let event = state
+        .database
+        .transaction::<_, entity::event::Model, ApiError>(|txn| {
+            Box::pin(async move {
+                let mut event = data.into_active_model();
+                event.owner_id = Set(user_id);
+                let event = event
+                    .insert(txn)
+                    .await
+                    .inspect_err(|e| error!("Error creating event: {e}"))
+                    .or_internal_server_error("Error creating event")?;
+
+                if let Some(group_id) = group_id {
+                    shared_group_event::ActiveModel {
+                        group_id: Set(group_id),
+                        event_id: Set(event.id),
+                    }
+                    .insert(txn)
+                    .await
+                    .inspect_err(|e| error!("Error sharing event with group: {e}"))
+                    .or_internal_server_error("Error sharing event with group")?;
+                }
+                Ok(event)
+            })
+        })
+        .await
+        .map_err(|error| {
+            error!("Error creating event: {error}");
+            match error {
+                TransactionError::Connection(_) => ApiError::internal("Error creating event"),
+                TransactionError::Transaction(error) => error,
+            }
+        })?;
+
+    if let Some(group_id) = group_id {
+        record_activity(
+            user_id,
+            Feed::Group(group_id),
+            ActivityKind::EventAdded,
+            event.title.clone(),
+            &state.database,
+        )
+        .await;
+        state
+            .live_updates
+            .publish(
+                Audience::Group(group_id),
+                ChangeEvent::GroupChanged { group_id },
+                &state.database,
+            )
+            .await;
+    }
+    state
+        .live_updates
+        .publish(
+            Audience::Event(event.id),
+            ChangeEvent::EventChanged { event_id: event.id },
+            &state.database,
+        )
+        .await;
+
+    Ok(event)
+}
+
+/// Replaces all fields of an event if it still has `version`. Only the owner may edit an event
+pub(crate) async fn update_event(
+    state: &AppState,
+    user: &entity::user::Model,
+    event_id: i32,
+    version: i32,
+    data: PartialEventModel,
+) -> Result<VersionedUpdate<entity::event::Model>, ApiError> {
+    let event = find_visible_event(user, event_id, &state.database).await?;
+    (event.owner_id == user.id).or_forbidden("Only the owner may edit this event")?;
+
+    // Group members lose access when the event becomes private, so they are notified as well
+    let previous_audience = Audience::Event(event_id).resolve(&state.database).await;
+
+    let mut changes = data.into_active_model();
+    changes.id = Unchanged(event_id);
+    let update = update_versioned(changes, event_id, version, &state.database)
+        .await
+        .inspect_err(|e| error!("Error updating event: {e}"))
+        .or_internal_server_error("Error updating event")?;
+
+    if let VersionedUpdate::Saved(_) = update {
+        state
+            .live_updates
+            .publish_event_changed(event_id, previous_audience, &state.database)
+            .await;
+    }
+
+    Ok(update)
+}
+
+/// Deletes an event together with its invitations and shares. Only the owner may delete an event
+pub(crate) async fn delete_event(
+    state: &AppState,
+    user: &entity::user::Model,
+    event_id: i32,
+) -> Result<(), ApiError> {
+    let event = find_visible_event(user, event_id, &state.database).await?;
+    (event.owner_id == user.id).or_forbidden("Only the owner may delete this event")?;
+
+    // The audience has to be known before the shares are removed
+    let audience = Audience::Event(event_id).resolve(&state.database).await;
+    record_event_removed(user.id, &event, &state.database).await;
+    remove_event_invites(event_id, &state.database).await?;
+    remove_shared_event_groups(event_id, &state.database).await?;
+    remove_shared_event_members(event_id, &state.database).await?;
+    entity::event::Entity::delete_by_id(event_id)
+        .exec(&state.database)
+        .await
+        .inspect_err(|e| error!("Error deleting event: {e}"))
+        .or_internal_server_error("Error deleting event")?;
+
+    AuditEntry::new(
+        Some(user.id),
+        AuditAction::EventDeleted,
+        AuditTarget::Event,
+        event_id,
+    )
+    .details(event.title)
+    .record(&state.database)
+    .await;
+    state
+        .live_updates
+        .publish(
+            Audience::Users(audience),
+            ChangeEvent::EventDeleted { event_id },
+            &state.database,
+        )
+        .await;
+
+    Ok(())
+}
+
+/// Shares an event the user can see with one of the user's groups.
+/// Returns `false` if the event already was part of the group
+pub(crate) async fn add_event_to_group(
+    state: &AppState,
+    user: &entity::user::Model,
+    event_id: i32,
+    group_id: i32,
+) -> Result<bool, ApiError> {
+    is_user_in_group(&state.database, group_id, user.id)
+        .await?
+        .or_forbidden("No permission to add events to this group")?;
+    let event = find_visible_event(user, event_id, &state.database).await?;
+
+    if is_event_in_group(&state.database, group_id, event.id).await? {
+        return Ok(false);
+    }
+
+    shared_group_event::ActiveModel {
+        group_id: Set(group_id),
+        event_id: Set(event.id),
+    }
+    .insert(&state.database)
+    .await
+    .inspect_err(|e| error!("Error sharing event with group: {e}"))
+    .or_internal_server_error("Error sharing event with group")?;
+
+    AuditEntry::new(
+        Some(user.id),
+        AuditAction::EventSharedWithGroup,
+        AuditTarget::Event,
+        event.id,
+    )
+    .details(format!("Group {group_id}"))
+    .record(&state.database)
+    .await;
+    record_activity(
+        user.id,
+        Feed::Group(group_id),
+        ActivityKind::EventAdded,
+        event.title,
+        &state.database,
+    )
+    .await;
+    publish_group_events_changed(state, event_id, group_id).await;
+
+    Ok(true)
+}
+
+/// Removes an event from one of the user's groups
+pub(crate) async fn remove_event_from_group(
+    state: &AppState,
+    user_id: i32,
+    event_id: i32,
+    group_id: i32,
+) -> Result<(), ApiError> {
+    is_user_in_group(&state.database, group_id, user_id)
+        .await?
+        .or_forbidden("No permission to remove events from this group")?;
+    let event = entity::event::Entity::find_by_id(event_id)
+        .one(&state.database)
+        .await
+        .or_internal_server_error("Error loading event")?
+        .or_not_found("Event not found")?;
+
+    let result = SharedGroupEvent::delete_many()
+        .filter(shared_group_event::Column::EventId.eq(event_id))
+        .filter(shared_group_event::Column::GroupId.eq(group_id))
+        .exec(&state.database)
+        .await
+        .or_internal_server_error("Error removing event from group")?;
+    (result.rows_affected > 0).or_not_found("Event is not part of this group")?;
+
+    AuditEntry::new(
+        Some(user_id),
+        AuditAction::EventRemovedFromGroup,
+        AuditTarget::Event,
+        event_id,
+    )
+    .details(format!("Group {group_id}"))
+    .record(&state.database)
+    .await;
+    record_activity(
+        user_id,
+        Feed::Group(group_id),
+        ActivityKind::EventRemoved,
+        event.title,
+        &state.database,
+    )
+    .await;
+    publish_group_events_changed(state, event_id, group_id).await;
+
+    Ok(())
+}
+
+/// Pushes a change of the events shared with a group to the group and the event's audience
+async fn publish_group_events_changed(state: &AppState, event_id: i32, group_id: i32) {
+    state
+        .live_updates
+        .publish(
+            Audience::Group(group_id),
+            ChangeEvent::GroupChanged { group_id },
+            &state.database,
+        )
+        .await;
+    state
+        .live_updates
+        .publish(
+            Audience::Event(event_id),
+            ChangeEvent::EventChanged { event_id },
+            &state.database,
+        )
+        .await;
+}
+
+/// Whether the user is a member of the group. Groups in the trash have no members as far as permissions are concerned
 pub async fn is_user_in_group(
     db: &DatabaseConnection,
     group_id: i32,
     user_id: i32,
 ) -> Result<bool, ServerFnError> {
     Ok(IsInGroup::find()
+        .inner_join(entity::group::Entity)
         .filter(entity::is_in_group::Column::GroupId.eq(group_id))
         .filter(entity::is_in_group::Column::UserId.eq(user_id))
+        .filter(entity::group::Column::DeletedAt.is_null())
         .one(db)
         .await
         .or_internal_server_error("Error loading from database")?
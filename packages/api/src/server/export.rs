@@ -0,0 +1,670 @@
+//! Export of all data tied to a user, so users can take a copy of their data with them.
+//!
+//! The export is a single JSON document. The ZIP archive additionally contains the owned events as iCalendar file
+//! and the tasks as CSV file, which calendar and spreadsheet applications can import.
+
+use entity::prelude::{
+    ApiToken, AuditLog, Event, Group, GroupShare, Invitation, OidcIdentity, PasskeyCredential,
+    Session, Todo, TodoList, TodoListInvitation, TotpCredential, TotpRecoveryCode,
+};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, ModelTrait, PaginatorTrait,
+    QueryFilter, QueryOrder,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+use time::OffsetDateTime;
+
+#[derive(Serialize)]
+pub(crate) struct UserDataExport {
+    pub exported_at: OffsetDateTime,
+    pub profile: ProfileExport,
+    pub events: Vec<EventExport>,
+    /// Invitations to events of other users
+    pub event_invitations: Vec<entity::invitation::Model>,
+    pub groups: Vec<entity::group::Model>,
+    /// To-Do lists created by the user, including the ones in the trash
+    pub todo_lists: Vec<TodoListExport>,
+    /// Tasks created by the user in lists of other users
+    pub todos: Vec<entity::todo::Model>,
+    /// Invitations to To-Do lists sent or received by the user
+    pub todo_list_invitations: Vec<entity::todo_list_invitation::Model>,
+    pub sessions: Vec<SessionExport>,
+    /// Accounts of identity providers linked to the user
+    pub oidc_identities: Vec<entity::oidc_identity::Model>,
+    pub passkeys: Vec<PasskeyExport>,
+    pub api_tokens: Vec<ApiTokenExport>,
+    /// Two-factor authentication with an authenticator app. Empty if the user never enrolled
+    pub totp: Option<TotpExport>,
+    /// Audit log entries of actions performed by or affecting the user
+    pub audit_log: Vec<entity::audit_log::Model>,
+}
+
+/// The user without credentials
+#[derive(Serialize)]
+pub(crate) struct ProfileExport {
+    pub id: i32,
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub is_oidc_user: bool,
+    pub email_verified: bool,
+    pub created_at: Option<OffsetDateTime>,
+    pub updated_at: Option<OffsetDateTime>,
+}
+
+/// An event owned by the user together with whom it is shared with
+#[derive(Serialize)]
+pub(crate) struct EventExport {
+    #[serde(flatten)]
+    pub event: entity::event::Model,
+    pub shared_with_groups: Vec<i32>,
+    pub invitations: Vec<entity::invitation::Model>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct TodoListExport {
+    #[serde(flatten)]
+    pub todo_list: entity::todo_list::Model,
+    pub todos: Vec<entity::todo::Model>,
+}
+
+/// A session without its token
+#[derive(Serialize)]
+pub(crate) struct SessionExport {
+    pub id: i32,
+    pub created_at: OffsetDateTime,
+    pub expires_at: OffsetDateTime,
+}
+
+/// A passkey without its public key
+#[derive(Serialize)]
+pub(crate) struct PasskeyExport {
+    pub id: i32,
+    pub name: String,
+    pub created_at: OffsetDateTime,
+    pub last_used_at: Option<OffsetDateTime>,
+}
+
+/// An API token without its hash
+#[derive(Serialize)]
+pub(crate) struct ApiTokenExport {
+    pub id: i32,
+    pub name: String,
+    pub token_hint: String,
+    pub scopes: String,
+    pub created_at: OffsetDateTime,
+    pub expires_at: OffsetDateTime,
+    pub last_used_at: Option<OffsetDateTime>,
+}
+
+/// The TOTP enrollment without the shared secret and recovery codes
+#[derive(Serialize)]
+pub(crate) struct TotpExport {
+    pub confirmed: bool,
+    pub created_at: OffsetDateTime,
+    pub remaining_recovery_codes: u64,
+}
+
+/// Collects everything tied to the user
+pub(crate) async fn collect_user_data(
+    user: &entity::user::Model,
+    db: &DatabaseConnection,
+) -> Result<UserDataExport, DbErr> {
+    let events = user
+        .find_related(Event)
+        .order_by_asc(entity::event::Column::Id)
+        .all(db)
+        .await?;
+    let event_ids: Vec<i32> = events.iter().map(|event| event.id).collect();
+    let mut shared_with_groups: HashMap<i32, Vec<i32>> = HashMap::new();
+    for share in GroupShare::find()
+        .filter(entity::shared_group_event::Column::EventId.is_in(event_ids.clone()))
+        .all(db)
+        .await?
+    {
+        shared_with_groups
+            .entry(share.event_id)
+            .or_default()
+            .push(share.group_id);
+    }
+    let mut sent_invitations: HashMap<i32, Vec<entity::invitation::Model>> = HashMap::new();
+    for invitation in Invitation::find()
+        .filter(entity::invitation::Column::EventId.is_in(event_ids))
+        .all(db)
+        .await?
+    {
+        sent_invitations
+            .entry(invitation.event_id)
+            .or_default()
+            .push(invitation);
+    }
+    let events = events
+        .into_iter()
+        .map(|event| EventExport {
+            shared_with_groups: shared_with_groups.remove(&event.id).unwrap_or_default(),
+            invitations: sent_invitations.remove(&event.id).unwrap_or_default(),
+            event,
+        })
+        .collect();
+
+    let event_invitations = Invitation::find()
+        .filter(entity::invitation::Column::RecievingUser.eq(user.id))
+        .all(db)
+        .await?;
+
+    let groups = user
+        .find_related(Group)
+        .order_by_asc(entity::group::Column::Id)
+        .all(db)
+        .await?;
+
+    let todo_lists = TodoList::find()
+        .filter(entity::todo_list::Column::CreatedById.eq(user.id))
+        .order_by_asc(entity::todo_list::Column::Id)
+        .all(db)
+        .await?;
+    let todo_list_ids: Vec<i32> = todo_lists.iter().map(|todo_list| todo_list.id).collect();
+    let (list_todos, todos): (Vec<_>, Vec<_>) = Todo::find()
+        .filter(
+            Condition::any()
+                .add(entity::todo::Column::TodoListId.is_in(todo_list_ids.clone()))
+                .add(entity::todo::Column::OwnerId.eq(user.id)),
+        )
+        .order_by_asc(entity::todo::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .partition(|todo| todo_list_ids.contains(&todo.todo_list_id));
+    let mut todos_by_list: HashMap<i32, Vec<entity::todo::Model>> = HashMap::new();
+    for todo in list_todos {
+        todos_by_list
+            .entry(todo.todo_list_id)
+            .or_default()
+            .push(todo);
+    }
+    let todo_lists = todo_lists
+        .into_iter()
+        .map(|todo_list| TodoListExport {
+            todos: todos_by_list.remove(&todo_list.id).unwrap_or_default(),
+            todo_list,
+        })
+        .collect();
+
+    let todo_list_invitations = TodoListInvitation::find()
+        .filter(
+            Condition::any()
+                .add(entity::todo_list_invitation::Column::ReceivingUserId.eq(user.id))
+                .add(entity::todo_list_invitation::Column::SenderUserId.eq(user.id)),
+        )
+        .all(db)
+        .await?;
+
+    let sessions = Session::find()
+        .filter(entity::session::Column::UserId.eq(user.id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|session| SessionExport {
+            id: session.id,
+            created_at: session.created_at,
+            expires_at: session.expires_at,
+        })
+        .collect();
+
+    let oidc_identities = OidcIdentity::find()
+        .filter(entity::oidc_identity::Column::UserId.eq(user.id))
+        .order_by_asc(entity::oidc_identity::Column::Id)
+        .all(db)
+        .await?;
+
+    let passkeys = PasskeyCredential::find()
+        .filter(entity::passkey_credential::Column::UserId.eq(user.id))
+        .order_by_asc(entity::passkey_credential::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|passkey| PasskeyExport {
+            id: passkey.id,
+            name: passkey.name,
+            created_at: passkey.created_at,
+            last_used_at: passkey.last_used_at,
+        })
+        .collect();
+
+    let api_tokens = ApiToken::find()
+        .filter(entity::api_token::Column::UserId.eq(user.id))
+        .order_by_asc(entity::api_token::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|token| ApiTokenExport {
+            id: token.id,
+            name: token.name,
+            token_hint: token.token_hint,
+            scopes: token.scopes,
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            last_used_at: token.last_used_at,
+        })
+        .collect();
+
+    let totp = match TotpCredential::find()
+        .filter(entity::totp_credential::Column::UserId.eq(user.id))
+        .one(db)
+        .await?
+    {
+        Some(credential) => Some(TotpExport {
+            confirmed: credential.confirmed,
+            created_at: credential.created_at,
+            remaining_recovery_codes: TotpRecoveryCode::find()
+                .filter(entity::totp_recovery_code::Column::UserId.eq(user.id))
+                .count(db)
+                .await?,
+        }),
+        None => None,
+    };
+
+    let audit_log = AuditLog::find()
+        .filter(
+            Condition::any()
+                .add(entity::audit_log::Column::ActorId.eq(user.id))
+                .add(entity::audit_log::Column::AffectedUserId.eq(user.id)),
+        )
+        .order_by_asc(entity::audit_log::Column::Id)
+        .all(db)
+        .await?;
+
+    Ok(UserDataExport {
+        exported_at: OffsetDateTime::now_utc(),
+        profile: ProfileExport {
+            id: user.id,
+            email: user.email.clone(),
+            first_name: user.first_name.clone(),
+            last_name: user.last_name.clone(),
+            is_oidc_user: user.is_oidc_user,
+            email_verified: user.email_verified,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        },
+        events,
+        event_invitations,
+        groups,
+        todo_lists,
+        todos,
+        todo_list_invitations,
+        sessions,
+        oidc_identities,
+        passkeys,
+        api_tokens,
+        totp,
+        audit_log,
+    })
+}
+
+/// Escapes a text value of an iCalendar property
+fn escape_ics_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Appends a content line, folded after 75 octets as required by RFC 5545
+fn push_ics_line(ics: &mut String, line: &str) {
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            ics.push_str("\r\n ");
+            length = 1;
+        }
+        ics.push(c);
+        length += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn format_ics_date_time(date: time::Date, time: time::Time) -> String {
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}",
+        date.year(),
+        u8::from(date.month()),
+        date.day(),
+        time.hour(),
+        time.minute(),
+        time.second()
+    )
+}
+
+fn ics_weekday(weekday: time::Weekday) -> &'static str {
+    match weekday {
+        time::Weekday::Monday => "MO",
+        time::Weekday::Tuesday => "TU",
+        time::Weekday::Wednesday => "WE",
+        time::Weekday::Thursday => "TH",
+        time::Weekday::Friday => "FR",
+        time::Weekday::Saturday => "SA",
+        time::Weekday::Sunday => "SU",
+    }
+}
+
+/// Renders the events as iCalendar file. Times are floating, i.e. in the local time of the calendar,
+/// like they are shown in the app. Reoccurring events repeat weekly on their weekday
+pub(crate) fn events_to_ics(events: &[EventExport], server_host: &str) -> String {
+    let now = OffsetDateTime::now_utc();
+    let stamp = format!("{}Z", format_ics_date_time(now.date(), now.time()));
+
+    let mut ics = String::new();
+    push_ics_line(&mut ics, "BEGIN:VCALENDAR");
+    push_ics_line(&mut ics, "VERSION:2.0");
+    push_ics_line(&mut ics, "PRODID:-//RoomMates//Data Export//EN");
+    for EventExport { event, .. } in events {
+        push_ics_line(&mut ics, "BEGIN:VEVENT");
+        push_ics_line(&mut ics, &format!("UID:event-{}@{server_host}", event.id));
+        push_ics_line(&mut ics, &format!("DTSTAMP:{stamp}"));
+        push_ics_line(
+            &mut ics,
+            &format!(
+                "DTSTART:{}",
+                format_ics_date_time(event.date, event.start_time)
+            ),
+        );
+        push_ics_line(
+            &mut ics,
+            &format!("DTEND:{}", format_ics_date_time(event.date, event.end_time)),
+        );
+        if event.reoccurring {
+            let weekday = ics_weekday(event.weekday.into());
+            push_ics_line(&mut ics, &format!("RRULE:FREQ=WEEKLY;BYDAY={weekday}"));
+        }
+        push_ics_line(
+            &mut ics,
+            &format!("SUMMARY:{}", escape_ics_text(&event.title)),
+        );
+        if let Some(description) = &event.description {
+            push_ics_line(
+                &mut ics,
+                &format!("DESCRIPTION:{}", escape_ics_text(description)),
+            );
+        }
+        if let Some(location) = &event.location {
+            push_ics_line(&mut ics, &format!("LOCATION:{}", escape_ics_text(location)));
+        }
+        if event.private {
+            push_ics_line(&mut ics, "CLASS:PRIVATE");
+        }
+        push_ics_line(&mut ics, "END:VEVENT");
+    }
+    push_ics_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+/// Renders the tasks of the exported lists and the other tasks of the user as CSV file
+pub(crate) fn todos_to_csv(export: &UserDataExport) -> Result<Vec<u8>, csv::Error> {
+    let format_time = |time: Option<OffsetDateTime>| {
+        time.and_then(|time| {
+            time.format(&time::format_description::well_known::Rfc3339)
+                .ok()
+        })
+        .unwrap_or_default()
+    };
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record([
+        "id",
+        "todo_list_id",
+        "todo_list",
+        "title",
+        "details",
+        "completed",
+        "created_at",
+        "completed_at",
+    ])?;
+    let list_todos = export.todo_lists.iter().flat_map(|todo_list| {
+        todo_list
+            .todos
+            .iter()
+            .map(|todo| (Some(todo_list.todo_list.title.as_str()), todo))
+    });
+    let other_todos = export.todos.iter().map(|todo| (None, todo));
+    for (todo_list, todo) in list_todos.chain(other_todos) {
+        writer.write_record([
+            todo.id.to_string(),
+            todo.todo_list_id.to_string(),
+            todo_list.unwrap_or_default().to_string(),
+            todo.title.clone(),
+            todo.details.clone().unwrap_or_default(),
+            todo.completed.to_string(),
+            format_time(todo.created_at),
+            format_time(todo.completed_at),
+        ])?;
+    }
+    writer
+        .into_inner()
+        .map_err(|error| csv::Error::from(error.into_error()))
+}
+
+/// Packs the JSON export, the events as iCalendar file and the tasks as CSV file into a ZIP archive
+pub(crate) fn export_to_zip(
+    export: &UserDataExport,
+    server_host: &str,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+
+    zip.start_file("roommates.json", options)?;
+    serde_json::to_writer_pretty(&mut zip, export)?;
+    zip.start_file("events.ics", options)?;
+    zip.write_all(events_to_ics(&export.events, server_host).as_bytes())?;
+    zip.start_file("todos.csv", options)?;
+    zip.write_all(&todos_to_csv(export)?)?;
+
+    Ok(zip.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::audit::AuditEntry;
+    use crate::server::test_support::{create_test_user, setup};
+    use entity::audit_log::{AuditAction, AuditTarget};
+    use sea_orm::{ActiveModelTrait, Set};
+    use time::macros::{date, time};
+
+    fn event(owner_id: i32, title: &str, reoccurring: bool) -> entity::event::ActiveModel {
+        entity::event::ActiveModel {
+            title: Set(title.to_string()),
+            reoccurring: Set(reoccurring),
+            private: Set(false),
+            description: Set(Some("Bring snacks, drinks; and games".to_string())),
+            location: Set(None),
+            date: Set(date!(2026 - 10 - 19)),
+            start_time: Set(time!(18:00)),
+            end_time: Set(time!(20:30)),
+            weekday: Set(entity::event::Weekday::Monday),
+            owner_id: Set(owner_id),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collect_user_data() {
+        let database = setup().await;
+        let user = create_test_user(&database, 1).await;
+        let other = create_test_user(&database, 2).await;
+
+        let owned = event(user.id, "Game night", false)
+            .insert(&database)
+            .await
+            .unwrap();
+        event(other.id, "Not mine", false)
+            .insert(&database)
+            .await
+            .unwrap();
+        entity::invitation::ActiveModel {
+            event_id: Set(owned.id),
+            recieving_user: Set(other.id),
+            status: Set(entity::invitation::InvitationStatus::Sent),
+            ..Default::default()
+        }
+        .insert(&database)
+        .await
+        .unwrap();
+
+        let own_list = entity::todo_list::ActiveModel {
+            title: Set("Groceries".to_string()),
+            created_by_id: Set(Some(user.id)),
+            ..Default::default()
+        }
+        .insert(&database)
+        .await
+        .unwrap();
+        let other_list = entity::todo_list::ActiveModel {
+            title: Set("Chores".to_string()),
+            created_by_id: Set(Some(other.id)),
+            ..Default::default()
+        }
+        .insert(&database)
+        .await
+        .unwrap();
+        for (todo_list_id, owner_id, title) in [
+            (own_list.id, other.id, "Milk"),
+            (other_list.id, user.id, "Dishes"),
+            (other_list.id, other.id, "Laundry"),
+        ] {
+            entity::todo::ActiveModel {
+                title: Set(title.to_string()),
+                completed: Set(false),
+                todo_list_id: Set(todo_list_id),
+                owner_id: Set(owner_id),
+                ..Default::default()
+            }
+            .insert(&database)
+            .await
+            .unwrap();
+        }
+
+        entity::api_token::ActiveModel {
+            name: Set("Script".to_string()),
+            token: Set("hash".to_string()),
+            token_hint: Set("abcd".to_string()),
+            scopes: Set("read-events".to_string()),
+            created_at: Set(OffsetDateTime::now_utc()),
+            expires_at: Set(OffsetDateTime::now_utc()),
+            last_used_at: Set(None),
+            user_id: Set(user.id),
+            ..Default::default()
+        }
+        .insert(&database)
+        .await
+        .unwrap();
+        entity::totp_credential::ActiveModel {
+            secret: Set("SECRET".to_string()),
+            confirmed: Set(true),
+            last_used_step: Set(None),
+            created_at: Set(OffsetDateTime::now_utc()),
+            user_id: Set(user.id),
+            ..Default::default()
+        }
+        .insert(&database)
+        .await
+        .unwrap();
+        entity::totp_recovery_code::ActiveModel {
+            code: Set("hash".to_string()),
+            user_id: Set(user.id),
+            ..Default::default()
+        }
+        .insert(&database)
+        .await
+        .unwrap();
+        AuditEntry::new(
+            Some(other.id),
+            AuditAction::EventInvitationSent,
+            AuditTarget::Event,
+            1,
+        )
+        .affected_user(user.id)
+        .record(&database)
+        .await;
+        AuditEntry::new(
+            Some(other.id),
+            AuditAction::Login,
+            AuditTarget::User,
+            other.id,
+        )
+        .record(&database)
+        .await;
+
+        let export = collect_user_data(&user, &database).await.unwrap();
+
+        assert_eq!(export.profile.email, user.email);
+        assert_eq!(export.events.len(), 1);
+        assert_eq!(export.events[0].event.title, "Game night");
+        assert_eq!(export.events[0].invitations.len(), 1);
+        assert_eq!(export.todo_lists.len(), 1);
+        assert_eq!(export.todo_lists[0].todos.len(), 1);
+        assert_eq!(export.todo_lists[0].todos[0].title, "Milk");
+        assert_eq!(export.todos.len(), 1);
+        assert_eq!(export.todos[0].title, "Dishes");
+
+        assert_eq!(export.api_tokens.len(), 1);
+        assert_eq!(export.api_tokens[0].token_hint, "abcd");
+        let totp = export.totp.as_ref().unwrap();
+        assert!(totp.confirmed);
+        assert_eq!(totp.remaining_recovery_codes, 1);
+        assert_eq!(export.audit_log.len(), 1);
+        assert_eq!(export.audit_log[0].action, AuditAction::EventInvitationSent);
+
+        let json = serde_json::to_value(&export).unwrap();
+        assert!(json["profile"].get("password").is_none());
+        assert!(json["api_tokens"][0].get("token").is_none());
+        assert!(json["totp"].get("secret").is_none());
+    }
+
+    #[test]
+    fn test_events_to_ics() {
+        let event = |id, reoccurring| EventExport {
+            event: entity::event::Model {
+                id,
+                title: "Game night".to_string(),
+                reoccurring,
+                private: false,
+                description: Some("Bring snacks, drinks; and games".to_string()),
+                location: None,
+                date: date!(2026 - 10 - 19),
+                start_time: time!(18:00),
+                end_time: time!(20:30),
+                weekday: entity::event::Weekday::Monday,
+                version: 1,
+                created_at: None,
+                updated_at: None,
+                owner_id: 1,
+            },
+            shared_with_groups: Vec::new(),
+            invitations: Vec::new(),
+        };
+
+        let ics = events_to_ics(&[event(1, false), event(2, true)], "localhost");
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.contains("UID:event-1@localhost\r\n"));
+        assert!(ics.contains("DTSTART:20261019T180000\r\nDTEND:20261019T203000\r\n"));
+        assert!(ics.contains("DESCRIPTION:Bring snacks\\, drinks\\; and games\r\n"));
+        assert_eq!(ics.matches("RRULE:FREQ=WEEKLY;BYDAY=MO").count(), 1);
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    }
+
+    #[test]
+    fn test_long_ics_lines_are_folded() {
+        let mut ics = String::new();
+        push_ics_line(&mut ics, &format!("SUMMARY:{}", "a".repeat(100)));
+
+        let lines: Vec<&str> = ics.trim_end().split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 75);
+        assert!(lines[1].starts_with(' '));
+    }
+}
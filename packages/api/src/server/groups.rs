@@ -0,0 +1,279 @@
+use crate::error::ApiError;
+use crate::routes::live_updates::ChangeEvent;
+use crate::server::AppState;
+use crate::server::activity::{Feed, record_activity, record_member_activity};
+use crate::server::audit::AuditEntry;
+use crate::server::auth::email_verification::addressable_by_email;
+use crate::server::events::is_user_in_group;
+use crate::server::live_updates::Audience;
+use crate::server::trash::trash_group;
+use dioxus::prelude::*;
+use entity::activity::ActivityKind;
+use entity::audit_log::{AuditAction, AuditTarget};
+use entity::prelude::{Group, InGroup, User};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait, PaginatorTrait,
+    QueryFilter, Set, TransactionError, TransactionTrait,
+};
+
+/// Loads a group the user is a member of. Other groups are reported as not found
+pub(crate) async fn find_member_group(
+    user_id: i32,
+    group_id: i32,
+    db: &DatabaseConnection,
+) -> Result<entity::group::Model, ApiError> {
+    is_user_in_group(db, group_id, user_id)
+        .await?
+        .or_not_found("Group not found")?;
+    Ok(Group::find_by_id(group_id)
+        .one(db)
+        .await
+        .or_internal_server_error("Error loading group")?
+        .or_not_found("Group not found")?)
+}
+
+/// Creates a group with the user as its first member
+pub(crate) async fn create_group(
+    state: &AppState,
+    user_id: i32,
+    name: String,
+) -> Result<entity::group::Model, ApiError> {
+    let group = state
+        .database
+        .transaction::<_, entity::group::Model, ApiError>(|txn| {
+            Box::pin(async move {
+                let group = entity::group::ActiveModel {
+                    name: Set(name),
+                    ..Default::default()
+                }
+                .insert(txn)
+                .await
+                .inspect_err(|e| error!("Error creating group: {e}"))
+                .or_internal_server_error("Error creating group")?;
+
+                entity::is_in_group::ActiveModel {
+                    user_id: Set(user_id),
+                    group_id: Set(group.id),
+                }
+                .insert(txn)
+                .await
+                .inspect_err(|e| error!("Error adding creator to group: {e}"))
+                .or_internal_server_error("Error adding creator to group")?;
+
+                Ok(group)
+            })
+        })
+        .await
+        .map_err(|error| {
+            error!("Error creating group: {error}");
+            match error {
+                TransactionError::Connection(_) => ApiError::internal("Error creating group"),
+                TransactionError::Transaction(error) => error,
+            }
+        })?;
+
+    state
+        .live_updates
+        .publish(
+            Audience::Users(vec![user_id]),
+            ChangeEvent::GroupChanged { group_id: group.id },
+            &state.database,
+        )
+        .await;
+
+    Ok(group)
+}
+
+pub(crate) async fn rename_group(
+    state: &AppState,
+    user_id: i32,
+    group_id: i32,
+    name: String,
+) -> Result<entity::group::Model, ApiError> {
+    let group = find_member_group(user_id, group_id, &state.database).await?;
+    let mut group: entity::group::ActiveModel = group.into();
+    group.name = Set(name);
+    let group = group
+        .update(&state.database)
+        .await
+        .inspect_err(|e| error!("Error renaming group: {e}"))
+        .or_internal_server_error("Error renaming group")?;
+
+    record_activity(
+        user_id,
+        Feed::Group(group_id),
+        ActivityKind::GroupRenamed,
+        group.name.clone(),
+        &state.database,
+    )
+    .await;
+    state
+        .live_updates
+        .publish(
+            Audience::Group(group_id),
+            ChangeEvent::GroupChanged { group_id },
+            &state.database,
+        )
+        .await;
+
+    Ok(group)
+}
+
+/// Moves the group to the trash. Events stay shared with it until it is deleted permanently
+pub(crate) async fn delete_group(
+    state: &AppState,
+    user_id: i32,
+    group_id: i32,
+) -> Result<(), ApiError> {
+    find_member_group(user_id, group_id, &state.database).await?;
+
+    let members = Audience::Group(group_id).resolve(&state.database).await;
+    trash_group(group_id, &state.database)
+        .await
+        .inspect_err(|e| error!("Error deleting group: {e}"))
+        .or_internal_server_error("Error deleting group")?
+        .or_not_found("Group not found")?;
+
+    AuditEntry::new(
+        Some(user_id),
+        AuditAction::GroupDeleted,
+        AuditTarget::Group,
+        group_id,
+    )
+    .record(&state.database)
+    .await;
+    state
+        .live_updates
+        .publish(
+            Audience::Users(members),
+            ChangeEvent::GroupDeleted { group_id },
+            &state.database,
+        )
+        .await;
+
+    Ok(())
+}
+
+/// Adds the user with the email address to the group and returns them
+pub(crate) async fn add_group_member(
+    state: &AppState,
+    user_id: i32,
+    group_id: i32,
+    email: &str,
+) -> Result<entity::user::Model, ApiError> {
+    find_member_group(user_id, group_id, &state.database).await?;
+
+    let new_member = User::find()
+        .filter(addressable_by_email(
+            email,
+            state.settings.email_verification_required,
+        ))
+        .one(&state.database)
+        .await
+        .or_internal_server_error("Error loading user")?
+        .or_not_found("User not found")?;
+    if is_user_in_group(&state.database, group_id, new_member.id).await? {
+        return Err(ApiError::conflict("User is already a member of this group"));
+    }
+
+    entity::is_in_group::ActiveModel {
+        user_id: Set(new_member.id),
+        group_id: Set(group_id),
+    }
+    .insert(&state.database)
+    .await
+    .inspect_err(|e| error!("Error adding group member: {e}"))
+    .or_internal_server_error("Error adding group member")?;
+
+    AuditEntry::new(
+        Some(user_id),
+        AuditAction::GroupMemberAdded,
+        AuditTarget::Group,
+        group_id,
+    )
+    .affected_user(new_member.id)
+    .record(&state.database)
+    .await;
+    record_activity(
+        user_id,
+        Feed::Group(group_id),
+        ActivityKind::MemberAdded,
+        format!("{} {}", new_member.first_name, new_member.last_name),
+        &state.database,
+    )
+    .await;
+    state
+        .live_updates
+        .publish(
+            Audience::Group(group_id),
+            ChangeEvent::GroupChanged { group_id },
+            &state.database,
+        )
+        .await;
+
+    Ok(new_member)
+}
+
+/// Removes a member from the group. The group is moved to the trash when its last member leaves
+pub(crate) async fn remove_group_member(
+    state: &AppState,
+    user_id: i32,
+    group_id: i32,
+    member_id: i32,
+) -> Result<(), ApiError> {
+    use entity::is_in_group::Column as InGroupColumn;
+
+    let group = find_member_group(user_id, group_id, &state.database).await?;
+
+    let member_count = group
+        .find_related(User)
+        .count(&state.database)
+        .await
+        .or_internal_server_error("Error loading members")?;
+    if member_count <= 1 && member_id == user_id {
+        return delete_group(state, user_id, group_id).await;
+    }
+
+    let result = InGroup::delete_many()
+        .filter(InGroupColumn::GroupId.eq(group_id))
+        .filter(InGroupColumn::UserId.eq(member_id))
+        .exec(&state.database)
+        .await
+        .inspect_err(|e| error!("Error removing group member: {e}"))
+        .or_internal_server_error("Error removing group member")?;
+    (result.rows_affected > 0).or_not_found("User is not a member of this group")?;
+
+    AuditEntry::new(
+        Some(user_id),
+        AuditAction::GroupMemberRemoved,
+        AuditTarget::Group,
+        group_id,
+    )
+    .affected_user(member_id)
+    .record(&state.database)
+    .await;
+    let kind = if member_id == user_id {
+        ActivityKind::MemberLeft
+    } else {
+        ActivityKind::MemberRemoved
+    };
+    record_member_activity(user_id, group_id, kind, member_id, &state.database).await;
+    state
+        .live_updates
+        .publish(
+            Audience::Group(group_id),
+            ChangeEvent::GroupChanged { group_id },
+            &state.database,
+        )
+        .await;
+    state
+        .live_updates
+        .publish(
+            Audience::Users(vec![member_id]),
+            ChangeEvent::GroupDeleted { group_id },
+            &state.database,
+        )
+        .await;
+
+    Ok(())
+}
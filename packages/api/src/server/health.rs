@@ -0,0 +1,126 @@
+use crate::server::AppState;
+use dioxus::prelude::*;
+use dioxus::server::axum::Extension;
+use dioxus::server::axum::Json;
+use dioxus::server::axum::http::header;
+use dioxus::server::axum::response::{IntoResponse, Response};
+use entity::prelude::Session;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, PaginatorTrait, QueryFilter,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use time::OffsetDateTime;
+
+/// Liveness check. Responds as long as the process handles requests
+pub async fn healthz() -> &'static str {
+    "ok"
+}
+
+#[derive(Serialize, Debug)]
+pub struct Readiness {
+    pub database: bool,
+    /// Whether the signing keys of each OIDC provider are loaded
+    pub oidc_providers: BTreeMap<String, bool>,
+}
+
+impl Readiness {
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.database && self.oidc_providers.values().all(|loaded| *loaded)
+    }
+}
+
+/// Readiness check. Responds with 503 if the database does not respond or the keys of an OIDC provider are missing
+pub async fn readyz(Extension(state): Extension<AppState>) -> Response {
+    let database = state
+        .database
+        .ping()
+        .await
+        .inspect_err(|e| warn!("Readiness check: database ping failed: {e}"))
+        .is_ok();
+    let mut oidc_providers = BTreeMap::new();
+    for provider in state.oidc_providers.iter() {
+        let loaded = !provider.jwks_state.jwks.read().await.keys.is_empty();
+        oidc_providers.insert(provider.id.clone(), loaded);
+    }
+
+    let readiness = Readiness {
+        database,
+        oidc_providers,
+    };
+    let status = if readiness.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness)).into_response()
+}
+
+/// Connections of the database pool as `(in use, idle)`
+fn pool_connections(database: &DatabaseConnection) -> Option<(i64, i64)> {
+    let (size, idle) = match database.get_database_backend() {
+        DbBackend::MySql => {
+            let pool = database.get_mysql_connection_pool();
+            (pool.size(), pool.num_idle())
+        }
+        DbBackend::Sqlite => {
+            let pool = database.get_sqlite_connection_pool();
+            (pool.size(), pool.num_idle())
+        }
+        _ => return None,
+    };
+    let idle = i64::try_from(idle).unwrap_or(i64::MAX);
+    Some((i64::from(size) - idle, idle))
+}
+
+async fn count_active_sessions(database: &DatabaseConnection) -> Result<u64, DbErr> {
+    Session::find()
+        .filter(entity::session::Column::ExpiresAt.gt(OffsetDateTime::now_utc()))
+        .count(database)
+        .await
+}
+
+/// Prometheus metrics of the requests, the database pool and the active sessions
+pub async fn metrics(Extension(state): Extension<AppState>) -> Response {
+    let metrics = &state.metrics;
+    if let Some((in_use, idle)) = pool_connections(&state.database) {
+        metrics
+            .database_connections
+            .with_label_values(&["in_use"])
+            .set(in_use);
+        metrics
+            .database_connections
+            .with_label_values(&["idle"])
+            .set(idle);
+    }
+    match count_active_sessions(&state.database).await {
+        Ok(count) => metrics
+            .active_sessions
+            .set(i64::try_from(count).unwrap_or(i64::MAX)),
+        Err(e) => warn!("Error counting active sessions: {e}"),
+    }
+
+    match metrics.render() {
+        Ok(body) => ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response(),
+        Err(e) => {
+            error!("Error encoding metrics: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::test_support::setup;
+
+    #[tokio::test]
+    async fn test_database_stats() {
+        let database = setup().await;
+
+        let (in_use, idle) = pool_connections(&database).unwrap();
+        assert!(in_use >= 0 && idle >= 0);
+        assert_eq!(count_active_sessions(&database).await.unwrap(), 0);
+    }
+}
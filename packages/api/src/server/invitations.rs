@@ -0,0 +1,173 @@
+use crate::error::ApiError;
+use crate::routes::live_updates::ChangeEvent;
+use crate::server::AppState;
+use crate::server::audit::AuditEntry;
+use crate::server::auth::find_user_by_email;
+use crate::server::events::can_invite_user_to_event;
+use crate::server::live_updates::Audience;
+use dioxus::prelude::*;
+use entity::audit_log::{AuditAction, AuditTarget};
+use entity::invitation::Column as InvitationColumn;
+use entity::invitation::InvitationStatus;
+use entity::prelude::{Event, FriendShare, Invitation};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    Set, TransactionError, TransactionTrait,
+};
+
+/// Loads a pending invitation addressed to the user
+async fn find_pending_invitation(
+    user_id: i32,
+    invitation_id: i32,
+    db: &DatabaseConnection,
+) -> Result<entity::invitation::Model, ApiError> {
+    Ok(Invitation::find_by_id(invitation_id)
+        .filter(InvitationColumn::RecievingUser.eq(user_id))
+        .filter(InvitationColumn::Status.eq(InvitationStatus::Sent))
+        .one(db)
+        .await
+        .or_internal_server_error("Error loading invitation")?
+        .or_not_found("Invitation not found")?)
+}
+
+/// Invites the user with the email address to an event. Only the owner of the event may send invitations
+pub(crate) async fn send_invitation(
+    state: &AppState,
+    user_id: i32,
+    event_id: i32,
+    email: &str,
+) -> Result<entity::invitation::Model, ApiError> {
+    let event = Event::find_by_id(event_id)
+        .one(&state.database)
+        .await
+        .or_internal_server_error("Error loading event")?
+        .or_not_found("Event not found")?;
+    (event.owner_id == user_id).or_forbidden("Only the owner may invite users to this event")?;
+
+    let receiver = find_user_by_email(
+        email,
+        state.settings.email_verification_required,
+        &state.database,
+    )
+    .await?;
+    if !can_invite_user_to_event(&state.database, event.id, receiver.id).await? {
+        return Err(ApiError::conflict(
+            "User has already been invited to this event",
+        ));
+    }
+
+    let invitation = entity::invitation::ActiveModel {
+        status: Set(InvitationStatus::Sent),
+        recieving_user: Set(receiver.id),
+        event_id: Set(event.id),
+        ..Default::default()
+    }
+    .insert(&state.database)
+    .await
+    .inspect_err(|e| error!("Error sending invitation: {e}"))
+    .or_internal_server_error("Error sending invitation")?;
+
+    AuditEntry::new(
+        Some(user_id),
+        AuditAction::EventInvitationSent,
+        AuditTarget::Event,
+        event.id,
+    )
+    .affected_user(receiver.id)
+    .record(&state.database)
+    .await;
+    state
+        .live_updates
+        .publish(
+            Audience::Users(vec![receiver.id]),
+            ChangeEvent::InvitationsChanged,
+            &state.database,
+        )
+        .await;
+
+    Ok(invitation)
+}
+
+/// Accepts a pending invitation of the user, which shares the event with them
+pub(crate) async fn accept_invitation(
+    state: &AppState,
+    user_id: i32,
+    invitation_id: i32,
+) -> Result<(), ApiError> {
+    let invitation = find_pending_invitation(user_id, invitation_id, &state.database).await?;
+    let event_id = invitation.event_id;
+
+    state
+        .database
+        .transaction::<_, (), ApiError>(|txn| {
+            Box::pin(async move {
+                FriendShare::insert(entity::shared_friend_event::ActiveModel {
+                    user_id: Set(invitation.recieving_user),
+                    event_id: Set(invitation.event_id),
+                })
+                .exec(txn)
+                .await
+                .or_internal_server_error("Error accepting invitation")?;
+
+                let mut invitation = invitation.into_active_model();
+                invitation.status = Set(InvitationStatus::Accepted);
+                invitation
+                    .update(txn)
+                    .await
+                    .or_internal_server_error("Error accepting invitation")?;
+                Ok(())
+            })
+        })
+        .await
+        .map_err(|error| {
+            error!("Error accepting invitation: {error}");
+            match error {
+                TransactionError::Connection(_) => ApiError::internal("Error accepting invitation"),
+                TransactionError::Transaction(error) => error,
+            }
+        })?;
+
+    publish_invitation_answered(state, user_id, event_id).await;
+
+    Ok(())
+}
+
+/// Declines a pending invitation of the user
+pub(crate) async fn decline_invitation(
+    state: &AppState,
+    user_id: i32,
+    invitation_id: i32,
+) -> Result<(), ApiError> {
+    let invitation = find_pending_invitation(user_id, invitation_id, &state.database).await?;
+    let event_id = invitation.event_id;
+
+    let mut invitation = invitation.into_active_model();
+    invitation.status = Set(InvitationStatus::Declined);
+    invitation
+        .update(&state.database)
+        .await
+        .or_internal_server_error("Error declining invitation")?;
+
+    publish_invitation_answered(state, user_id, event_id).await;
+
+    Ok(())
+}
+
+async fn publish_invitation_answered(state: &AppState, user_id: i32, event_id: i32) {
+    state
+        .live_updates
+        .publish(
+            Audience::Event(event_id),
+            ChangeEvent::EventChanged { event_id },
+            &state.database,
+        )
+        .await;
+    state
+        .live_updates
+        .publish(
+            Audience::Users(vec![user_id]),
+            ChangeEvent::InvitationsChanged,
+            &state.database,
+        )
+        .await;
+}
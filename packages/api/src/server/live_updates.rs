@@ -0,0 +1,345 @@
+use crate::routes::live_updates::ChangeEvent;
+use dioxus::fullstack::{ServerEvents, SseTx};
+use dioxus::prelude::*;
+use entity::prelude::{Event, FriendShare, GroupShare, InGroup, Invitation, TodoListInvitation};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QuerySelect};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Number of changes buffered for slow subscribers. Subscribers falling further behind are asked to reload everything
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Users a change is pushed to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Audience {
+    Users(Vec<i32>),
+    /// Members of the group
+    Group(i32),
+    /// Members of the To-Do list. Users with a pending invitation only learn about it through
+    /// [`ChangeEvent::InvitationsChanged`]
+    TodoList(i32),
+    /// Owner, invited users and, unless the event is private, the members of the groups it is shared with
+    Event(i32),
+}
+
+impl Audience {
+    /// Loads the ids of the users. Has to be called before deleting the object the audience is derived from
+    pub(crate) async fn resolve(self, database: &DatabaseConnection) -> Vec<i32> {
+        let result = match self {
+            Audience::Users(user_ids) => Ok(user_ids),
+            Audience::Group(group_id) => group_members(group_id, database).await,
+            Audience::TodoList(todo_list_id) => todo_list_members(todo_list_id, database).await,
+            Audience::Event(event_id) => event_audience(event_id, database).await,
+        };
+        result.unwrap_or_else(|e| {
+            error!("Error loading the recipients of a live update: {e}");
+            Vec::new()
+        })
+    }
+}
+
+async fn group_members(group_id: i32, database: &DatabaseConnection) -> Result<Vec<i32>, DbErr> {
+    InGroup::find()
+        .select_only()
+        .column(entity::is_in_group::Column::UserId)
+        .filter(entity::is_in_group::Column::GroupId.eq(group_id))
+        .into_tuple()
+        .all(database)
+        .await
+}
+
+async fn todo_list_members(
+    todo_list_id: i32,
+    database: &DatabaseConnection,
+) -> Result<Vec<i32>, DbErr> {
+    TodoListInvitation::find()
+        .select_only()
+        .column(entity::todo_list_invitation::Column::ReceivingUserId)
+        .filter(entity::todo_list_invitation::Column::TodoListId.eq(todo_list_id))
+        .filter(entity::todo_list_invitation::Column::IsAccepted.eq(true))
+        .into_tuple()
+        .all(database)
+        .await
+}
+
+async fn event_audience(event_id: i32, database: &DatabaseConnection) -> Result<Vec<i32>, DbErr> {
+    let Some(event) = Event::find_by_id(event_id).one(database).await? else {
+        return Ok(Vec::new());
+    };
+    let mut users = BTreeSet::from([event.owner_id]);
+
+    let invited: Vec<i32> = Invitation::find()
+        .select_only()
+        .column(entity::invitation::Column::RecievingUser)
+        .filter(entity::invitation::Column::EventId.eq(event_id))
+        .into_tuple()
+        .all(database)
+        .await?;
+    users.extend(invited);
+    let shared: Vec<i32> = FriendShare::find()
+        .select_only()
+        .column(entity::shared_friend_event::Column::UserId)
+        .filter(entity::shared_friend_event::Column::EventId.eq(event_id))
+        .into_tuple()
+        .all(database)
+        .await?;
+    users.extend(shared);
+
+    if !event.private {
+        let groups: Vec<i32> = GroupShare::find()
+            .select_only()
+            .column(entity::shared_group_event::Column::GroupId)
+            .filter(entity::shared_group_event::Column::EventId.eq(event_id))
+            .into_tuple()
+            .all(database)
+            .await?;
+        for group_id in groups {
+            users.extend(group_members(group_id, database).await?);
+        }
+    }
+
+    Ok(users.into_iter().collect())
+}
+
+/// Sessions whose live update streams have to end, because the sessions are no longer valid
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Revocation {
+    /// The session was logged out
+    Session(i32),
+    /// All sessions of the user ended, e.g. by a back-channel logout, a password reset or the deletion of the account
+    User(i32),
+}
+
+impl Revocation {
+    fn ends(self, user_id: i32, session_id: Option<i32>) -> bool {
+        match self {
+            Revocation::Session(revoked) => session_id == Some(revoked),
+            Revocation::User(revoked) => revoked == user_id,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Broadcast {
+    Change {
+        recipients: Arc<[i32]>,
+        change: ChangeEvent,
+    },
+    Revoked(Revocation),
+}
+
+/// Fans changes out to the live update streams of all connected users
+#[derive(Clone)]
+pub struct LiveUpdates {
+    sender: broadcast::Sender<Broadcast>,
+}
+
+impl Default for LiveUpdates {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LiveUpdates {
+    #[must_use]
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        LiveUpdates { sender }
+    }
+
+    /// Pushes the change to all connected users of the audience
+    pub(crate) async fn publish(
+        &self,
+        audience: Audience,
+        change: ChangeEvent,
+        database: &DatabaseConnection,
+    ) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+        let recipients = audience.resolve(database).await;
+        if recipients.is_empty() {
+            return;
+        }
+        // Sending only fails if nobody is connected anymore
+        let _ = self.sender.send(Broadcast::Change {
+            recipients: recipients.into(),
+            change,
+        });
+    }
+
+    /// Ends the streams of the revoked sessions. Clients reconnect if they are still authenticated
+    pub(crate) fn revoke(&self, revocation: Revocation) {
+        // Sending only fails if nobody is connected anymore
+        let _ = self.sender.send(Broadcast::Revoked(revocation));
+    }
+
+    /// Pushes the update of an event to its current audience and to the users who could see it before,
+    /// so users who lost access to the event reload it as well
+    pub(crate) async fn publish_event_changed(
+        &self,
+        event_id: i32,
+        previous_audience: Vec<i32>,
+        database: &DatabaseConnection,
+    ) {
+        let mut audience = Audience::Event(event_id).resolve(database).await;
+        audience.extend(previous_audience);
+        audience.sort_unstable();
+        audience.dedup();
+        self.publish(
+            Audience::Users(audience),
+            ChangeEvent::EventChanged { event_id },
+            database,
+        )
+        .await;
+    }
+
+    /// Creates the event stream of the user, which ends when the client disconnects or the session is revoked
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id`: The subscribing user
+    /// * `session_id`: Local session the user is authenticated with, if any
+    pub(crate) fn subscribe(
+        &self,
+        user_id: i32,
+        session_id: Option<i32>,
+    ) -> ServerEvents<ChangeEvent> {
+        let mut receiver = self.sender.subscribe();
+        ServerEvents::new(move |mut tx: SseTx<ChangeEvent>| async move {
+            loop {
+                let change = match receiver.recv().await {
+                    Ok(Broadcast::Change { recipients, change })
+                        if recipients.contains(&user_id) =>
+                    {
+                        change
+                    }
+                    Ok(Broadcast::Revoked(revocation)) if revocation.ends(user_id, session_id) => {
+                        break;
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(_)) => ChangeEvent::Resync,
+                    Err(RecvError::Closed) => break,
+                };
+                if tx.send(change).await.is_err() {
+                    break;
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server;
+    use crate::server::test_support::{
+        create_test_event, create_test_group, create_test_todo_list,
+        create_test_todo_list_invitation, create_test_user, setup,
+    };
+    use entity::todo_list_invitation::InvitationPermission;
+    use sea_orm::{ActiveModelTrait, Set};
+
+    #[tokio::test]
+    async fn test_changes_are_only_sent_to_the_audience() {
+        let database = server::database::establish_test_connection().await.unwrap();
+        let live_updates = LiveUpdates::new();
+        let mut receiver = live_updates.sender.subscribe();
+
+        live_updates
+            .publish(
+                Audience::Users(vec![1, 2]),
+                ChangeEvent::GroupChanged { group_id: 3 },
+                &database,
+            )
+            .await;
+
+        let Broadcast::Change { recipients, change } = receiver.recv().await.unwrap() else {
+            panic!("Expected a change");
+        };
+        assert_eq!(&*recipients, &[1, 2]);
+        assert_eq!(change, ChangeEvent::GroupChanged { group_id: 3 });
+    }
+
+    #[tokio::test]
+    async fn test_revocations_end_the_streams_of_the_session() {
+        let live_updates = LiveUpdates::new();
+        let mut receiver = live_updates.sender.subscribe();
+
+        live_updates.revoke(Revocation::Session(4));
+        let Broadcast::Revoked(revocation) = receiver.recv().await.unwrap() else {
+            panic!("Expected a revocation");
+        };
+        assert!(revocation.ends(1, Some(4)));
+        assert!(!revocation.ends(1, Some(5)));
+        assert!(
+            !revocation.ends(1, None),
+            "Expected streams authenticated without a session to be kept"
+        );
+
+        let revocation = Revocation::User(1);
+        assert!(revocation.ends(1, Some(4)));
+        assert!(revocation.ends(1, None));
+        assert!(!revocation.ends(2, Some(4)));
+    }
+
+    #[tokio::test]
+    async fn test_private_events_are_not_pushed_to_groups() {
+        let database = setup().await;
+        for id in 1..=3 {
+            create_test_user(&database, id).await;
+        }
+        let group = create_test_group(&database, &[2, 3]).await;
+        let event = create_test_event(&database, 1, false).await;
+        entity::shared_group_event::ActiveModel {
+            group_id: Set(group.id),
+            event_id: Set(event.id),
+        }
+        .insert(&database)
+        .await
+        .unwrap();
+
+        assert_eq!(
+            Audience::Event(event.id).resolve(&database).await,
+            vec![1, 2, 3]
+        );
+
+        let mut event = entity::event::ActiveModel::from(event);
+        event.private = Set(true);
+        let event = event.update(&database).await.unwrap();
+        assert_eq!(Audience::Event(event.id).resolve(&database).await, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_pending_invitations_do_not_receive_todo_list_changes() {
+        let database = setup().await;
+        for id in 1..=3 {
+            create_test_user(&database, id).await;
+        }
+        let todo_list = create_test_todo_list(&database, 1, 1).await;
+        create_test_todo_list_invitation(
+            &database,
+            todo_list.id,
+            2,
+            1,
+            false,
+            InvitationPermission::Write,
+        )
+        .await;
+        create_test_todo_list_invitation(
+            &database,
+            todo_list.id,
+            3,
+            1,
+            true,
+            InvitationPermission::Read,
+        )
+        .await;
+
+        let mut audience = Audience::TodoList(todo_list.id).resolve(&database).await;
+        audience.sort_unstable();
+        assert_eq!(audience, vec![1, 3]);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::server::settings::{LogFormat, Settings, TelemetrySettings};
+use dioxus::logger::tracing::Level;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{ExporterBuildError, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// Installs the log subscriber if JSON output or the export of traces is configured.
+/// Has to be called before `dioxus::serve`, which otherwise installs its default text subscriber.
+/// Invalid settings are ignored here and reported once the server is set up
+pub fn init() {
+    let settings = Settings::load().unwrap_or_default();
+    if settings.log_format == LogFormat::Text && settings.telemetry.otlp_endpoint.is_none() {
+        return;
+    }
+
+    let level = if cfg!(debug_assertions) {
+        Level::DEBUG
+    } else {
+        Level::INFO
+    };
+    let filter = EnvFilter::builder()
+        .with_default_directive(level.into())
+        .from_env_lossy()
+        .add_directive("hyper_util=warn".parse().expect("valid directive"));
+    let fmt_layer = match settings.log_format {
+        LogFormat::Text => tracing_subscriber::fmt::layer().boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .boxed(),
+    };
+    let telemetry_layer = match otlp_tracer(&settings.telemetry) {
+        Ok(tracer) => tracer.map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer)),
+        Err(e) => {
+            eprintln!("Failed to set up the export of traces: {e}");
+            None
+        }
+    };
+
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt_layer)
+        .with(telemetry_layer)
+        .try_init();
+    if let Err(e) = result {
+        eprintln!("Failed to install the logger: {e}");
+    }
+}
+
+/// Creates a tracer exporting spans in batches to the configured OTLP/HTTP collector
+fn otlp_tracer(settings: &TelemetrySettings) -> Result<Option<SdkTracer>, ExporterBuildError> {
+    let Some(endpoint) = &settings.otlp_endpoint else {
+        return Ok(None);
+    };
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(settings.service_name.clone())
+                .build(),
+        )
+        .build();
+    opentelemetry::global::set_tracer_provider(provider.clone());
+    Ok(Some(provider.tracer("roommates")))
+}
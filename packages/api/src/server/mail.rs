@@ -0,0 +1,83 @@
+use crate::server::settings::SmtpSettings;
+use anyhow::Context;
+use dioxus::prelude::*;
+use lettre::message::Mailbox;
+use lettre::message::header::ContentType;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+/// Sends emails to users. Debug builds fall back to writing emails to the log when no SMTP server is configured,
+/// which keeps development setups working without a mail server. Release builds only log the receiver and
+/// subject, as bodies contain password reset and verification tokens.
+#[derive(Clone)]
+pub enum Mailer {
+    Smtp {
+        transport: Box<AsyncSmtpTransport<Tokio1Executor>>,
+        from: Mailbox,
+    },
+    Log,
+}
+
+impl Mailer {
+    /// Creates the mailer from the SMTP settings
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an SMTP url is set but either the url or the sender are invalid
+    pub fn from_settings(settings: &SmtpSettings) -> Result<Self, anyhow::Error> {
+        let Some(smtp_url) = &settings.url else {
+            if cfg!(debug_assertions) {
+                warn!("No SMTP server is configured. Emails will be written to the log instead");
+            } else {
+                warn!("No SMTP server is configured. Emails will not be delivered");
+            }
+            return Ok(Self::Log);
+        };
+
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::from_url(smtp_url)
+            .context("Invalid SMTP_URL")?
+            .build();
+        let transport = Box::new(transport);
+        let from = settings
+            .from
+            .as_deref()
+            .context("Missing SMTP_FROM")?
+            .parse()
+            .context("SMTP_FROM is not a valid mailbox")?;
+
+        Ok(Self::Smtp { transport, from })
+    }
+
+    /// Sends a plain text email
+    ///
+    /// # Arguments
+    ///
+    /// * `to`: Email address of the receiver
+    /// * `subject`: Subject line of the email
+    /// * `body`: Plain text content of the email
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the receiver is not a valid address or the SMTP server rejects the email
+    pub async fn send(&self, to: &str, subject: &str, body: String) -> Result<(), anyhow::Error> {
+        match self {
+            Self::Smtp { transport, from } => {
+                let message = Message::builder()
+                    .from(from.clone())
+                    .to(to.parse()?)
+                    .subject(subject)
+                    .header(ContentType::TEXT_PLAIN)
+                    .body(body)?;
+                transport.send(message).await?;
+            }
+            Self::Log if cfg!(debug_assertions) => {
+                info!("Email to {to} with subject '{subject}':\n{body}");
+            }
+            Self::Log => {
+                warn!(
+                    "Email to {to} with subject '{subject}' was not sent, no SMTP server is configured"
+                );
+            }
+        }
+        Ok(())
+    }
+}
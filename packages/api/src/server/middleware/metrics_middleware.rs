@@ -0,0 +1,160 @@
+use crate::server::AppState;
+use axum::extract::MatchedPath;
+use dioxus::fullstack::axum::middleware::Next;
+use dioxus::fullstack::extract::Request;
+use dioxus::fullstack::response::Response;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use std::time::Instant;
+
+/// Label of requests not matching an api route, e.g. pages rendered by the frontend
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Prometheus metrics exposed by `/metrics`
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    request_duration: HistogramVec,
+    pub database_connections: IntGaugeVec,
+    pub active_sessions: IntGauge,
+}
+
+impl Metrics {
+    /// Creates and registers all metrics
+    ///
+    /// # Panics
+    ///
+    /// Panics if a metric is invalid, which only happens if the definitions below are changed incorrectly
+    #[must_use]
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some("roommates".to_string()), None)
+            .expect("valid registry prefix");
+        let labels = ["method", "route", "status"];
+        let requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Handled HTTP requests"),
+            &labels,
+        )
+        .expect("valid metric");
+        let request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time until the response of a request was created",
+            ),
+            &labels,
+        )
+        .expect("valid metric");
+        let database_connections = IntGaugeVec::new(
+            Opts::new(
+                "database_connections",
+                "Connections of the database pool by state",
+            ),
+            &["state"],
+        )
+        .expect("valid metric");
+        let active_sessions = IntGauge::new(
+            "active_sessions",
+            "Unexpired sessions of users logged in with a password or passkey",
+        )
+        .expect("valid metric");
+
+        registry
+            .register(Box::new(requests.clone()))
+            .expect("unique metric");
+        registry
+            .register(Box::new(request_duration.clone()))
+            .expect("unique metric");
+        registry
+            .register(Box::new(database_connections.clone()))
+            .expect("unique metric");
+        registry
+            .register(Box::new(active_sessions.clone()))
+            .expect("unique metric");
+
+        Metrics {
+            registry,
+            requests,
+            request_duration,
+            database_connections,
+            active_sessions,
+        }
+    }
+
+    fn observe(&self, method: &str, route: &str, status: u16, seconds: f64) {
+        let status = status.to_string();
+        let labels = [method, route, status.as_str()];
+        self.requests.with_label_values(&labels).inc();
+        self.request_duration
+            .with_label_values(&labels)
+            .observe(seconds);
+    }
+
+    /// Encodes all metrics in the Prometheus text format
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the metrics cannot be encoded
+    pub fn render(&self) -> Result<String, prometheus::Error> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        String::from_utf8(buffer).map_err(|e| prometheus::Error::Msg(e.to_string()))
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Middleware counting requests and their latency by method, route and status.
+/// Routes are labeled with their pattern, e.g. `/api/groups/{group_id}`, to keep the number of series bounded
+pub async fn metrics_middleware(request: Request, next: Next) -> Response {
+    let Some(metrics) = request
+        .extensions()
+        .get::<AppState>()
+        .map(|state| state.metrics.clone())
+    else {
+        return next.run(request).await;
+    };
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED_ROUTE.to_string(), |path| {
+            path.as_str().to_string()
+        });
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    metrics.observe(
+        &method,
+        &route,
+        response.status().as_u16(),
+        start.elapsed().as_secs_f64(),
+    );
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_rendered_by_route() {
+        let metrics = Metrics::new();
+        metrics.observe("GET", "/api/groups/{group_id}", 200, 0.01);
+        metrics.observe("GET", "/api/groups/{group_id}", 200, 0.02);
+        metrics.active_sessions.set(3);
+
+        let output = metrics.render().unwrap();
+        assert!(output.contains(
+            r#"roommates_http_requests_total{method="GET",route="/api/groups/{group_id}",status="200"} 2"#
+        ));
+        assert!(output.contains("roommates_http_request_duration_seconds_bucket"));
+        assert!(output.contains("roommates_active_sessions 3"));
+    }
+}
@@ -1,2 +1,4 @@
+mod metrics_middleware;
 mod tracing_middleware;
-pub use tracing_middleware::tracing_middleware;
+pub use metrics_middleware::{Metrics, metrics_middleware};
+pub use tracing_middleware::{RequestId, tracing_middleware};
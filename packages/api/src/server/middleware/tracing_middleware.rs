@@ -1,39 +1,151 @@
+use crate::server::AppState;
 use crate::server::auth::AuthenticationState;
+use crate::server::constants::REQUEST_ID_HEADER;
+use crate::server::utils::client_ip;
+use axum::extract::MatchedPath;
 use dioxus::fullstack::axum::middleware::Next;
 use dioxus::fullstack::extract::Request;
 use dioxus::fullstack::response::Response;
+use dioxus::logger::tracing::{Instrument, field, info_span};
 use dioxus::prelude::*;
-use std::env;
+use dioxus::server::axum::http::{HeaderMap, HeaderValue};
+use std::time::Instant;
+use uuid::Uuid;
 
-pub async fn tracing_middleware(request: Request, next: Next) -> Response {
+/// Longest request id accepted from a client
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Id correlating the logs of a request. Taken from the `X-Request-Id` header of the request if present,
+/// generated otherwise, and returned in the same header of the response
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let incoming = headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| {
+                !id.is_empty()
+                    && id.len() <= MAX_REQUEST_ID_LENGTH
+                    && id
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "-_.:".contains(c))
+            });
+        RequestId(incoming.map_or_else(|| Uuid::new_v4().to_string(), str::to_string))
+    }
+}
+
+/// Handles the request within a span carrying its id, so every log of the request can be correlated,
+/// and writes the access log if enabled
+pub async fn tracing_middleware(mut request: Request, next: Next) -> Response {
+    let settings = request
+        .extensions()
+        .get::<AppState>()
+        .map(|state| state.settings.clone());
+    let access_log = settings
+        .as_ref()
+        .is_some_and(|settings| settings.access_log);
+    let request_id = RequestId::from_headers(request.headers());
+    request.extensions_mut().insert(request_id.clone());
     let method = request.method().clone();
     let path = request.uri().path().to_string();
-    let username = request.extensions().get::<AuthenticationState>().map_or(
-        "Anonymous".to_string(),
-        |state| {
-            state
-                .user
-                .as_ref()
-                .map(|user| user.email.clone())
-                .unwrap_or("Anonymous".to_string())
-        },
+    let client_ip = client_ip(
+        &request,
+        settings
+            .as_ref()
+            .map_or(&[][..], |settings| settings.trusted_proxies.as_slice()),
     );
+    let user_id = request
+        .extensions()
+        .get::<AuthenticationState>()
+        .and_then(|state| state.user.as_ref().map(|user| user.id));
 
-    let response = next.run(request).await;
+    // Exported traces are named by the route, e.g. `POST /api/events`, to group the calls of a server function
+    let trace_name = request.extensions().get::<MatchedPath>().map_or_else(
+        || method.to_string(),
+        |route| format!("{method} {}", route.as_str()),
+    );
 
-    if !env::var("ACCESS_LOG").is_ok_and(|value| value.to_lowercase() == "true") {
-        return response;
+    let span = info_span!(
+        "request",
+        request_id = %request_id.0,
+        otel.name = trace_name,
+        otel.kind = "server",
+        otel.status_code = field::Empty,
+        http.response.status_code = field::Empty,
+    );
+    let start = Instant::now();
+    let mut response = next.run(request).instrument(span.clone()).await;
+    let latency_ms = u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX);
+
+    let status = response.status();
+    span.record("http.response.status_code", status.as_u16());
+    if status.is_server_error() {
+        span.record("otel.status_code", "ERROR");
+    }
+
+    if let Ok(value) = HeaderValue::from_str(&request_id.0) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
     }
 
-    let base_message = format!("{method} {path} {} - {username}", response.status());
+    if !access_log {
+        return response;
+    }
 
-    if response.status().is_server_error() {
-        error!("{}", base_message);
-    } else if response.status().is_client_error() {
-        warn!("{}", base_message);
+    let _entered = span.enter();
+    macro_rules! access_log {
+        ($level:ident) => {
+            $level!(
+                method = %method,
+                path,
+                status = status.as_u16(),
+                latency_ms,
+                client_ip = client_ip.map(field::display),
+                user_id,
+                "{method} {path} {status}"
+            )
+        };
+    }
+    if status.is_server_error() {
+        access_log!(error);
+    } else if status.is_client_error() {
+        access_log!(warn);
     } else {
-        info!("{}", base_message);
+        access_log!(info);
     }
 
     response
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_id(header: Option<&str>) -> RequestId {
+        let mut headers = HeaderMap::new();
+        if let Some(header) = header {
+            headers.insert(REQUEST_ID_HEADER, HeaderValue::from_str(header).unwrap());
+        }
+        RequestId::from_headers(&headers)
+    }
+
+    #[test]
+    fn incoming_request_ids_are_validated() {
+        assert_eq!(
+            request_id(Some("proxy-1234.abc")),
+            RequestId("proxy-1234.abc".to_string())
+        );
+
+        let generated = request_id(None);
+        assert!(Uuid::parse_str(&generated.0).is_ok());
+        assert_ne!(request_id(None), generated);
+
+        for invalid in ["", "with space", "quote\"", &"a".repeat(129)] {
+            assert!(
+                Uuid::parse_str(&request_id(Some(invalid)).0).is_ok(),
+                "Expected '{invalid}' to be replaced"
+            );
+        }
+    }
+}
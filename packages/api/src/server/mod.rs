@@ -1,3 +1,7 @@
+pub(crate) mod account_deletion;
+pub(crate) mod activity;
+pub mod api_v1;
+pub(crate) mod audit;
 pub mod setup;
 pub use setup::{AppState, setup_api};
 pub mod auth;
@@ -5,6 +9,20 @@ pub use auth::AuthenticationState;
 pub(crate) mod constants;
 mod database;
 pub mod events;
+pub(crate) mod export;
+pub(crate) mod groups;
+pub mod health;
+pub(crate) mod invitations;
+pub mod live_updates;
+pub mod logging;
+pub mod mail;
 pub mod middleware;
+pub mod session_store;
+pub mod settings;
+pub use settings::Settings;
+#[cfg(test)]
+pub(crate) mod test_support;
 pub(crate) mod todo_lists;
+pub(crate) mod trash;
 pub mod utils;
+pub(crate) mod versioning;
@@ -0,0 +1,191 @@
+use async_trait::async_trait;
+use dioxus::prelude::*;
+use entity::prelude::SessionRecord;
+use entity::session_record::{ActiveModel, Column};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set};
+use time::OffsetDateTime;
+use tower_sessions::SessionStore;
+use tower_sessions::session::{Id, Record};
+use tower_sessions::session_store::{self, ExpiredDeletion};
+
+/// Session store keeping the data of tower-sessions in the database,
+/// so flows like the OIDC handshake survive restarts and work across replicas
+#[derive(Clone, Debug)]
+pub struct DatabaseSessionStore {
+    database: DatabaseConnection,
+}
+
+impl DatabaseSessionStore {
+    #[must_use]
+    pub fn new(database: DatabaseConnection) -> Self {
+        DatabaseSessionStore { database }
+    }
+}
+
+fn backend_error(error: DbErr) -> session_store::Error {
+    session_store::Error::Backend(error.to_string())
+}
+
+fn to_active_model(record: &Record) -> session_store::Result<ActiveModel> {
+    Ok(ActiveModel {
+        id: Set(record.id.to_string()),
+        data: Set(serde_json::to_string(&record.data)
+            .map_err(|e| session_store::Error::Encode(e.to_string()))?),
+        expires_at: Set(record.expiry_date),
+    })
+}
+
+#[async_trait]
+impl SessionStore for DatabaseSessionStore {
+    async fn create(&self, record: &mut Record) -> session_store::Result<()> {
+        while SessionRecord::find_by_id(record.id.to_string())
+            .one(&self.database)
+            .await
+            .map_err(backend_error)?
+            .is_some()
+        {
+            record.id = Id::default();
+        }
+        SessionRecord::insert(to_active_model(record)?)
+            .exec(&self.database)
+            .await
+            .map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn save(&self, record: &Record) -> session_store::Result<()> {
+        SessionRecord::insert(to_active_model(record)?)
+            .on_conflict(
+                OnConflict::column(Column::Id)
+                    .update_columns([Column::Data, Column::ExpiresAt])
+                    .to_owned(),
+            )
+            .exec(&self.database)
+            .await
+            .map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn load(&self, session_id: &Id) -> session_store::Result<Option<Record>> {
+        let Some(model) = SessionRecord::find_by_id(session_id.to_string())
+            .filter(Column::ExpiresAt.gt(OffsetDateTime::now_utc()))
+            .one(&self.database)
+            .await
+            .map_err(backend_error)?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(Record {
+            id: *session_id,
+            data: serde_json::from_str(&model.data)
+                .map_err(|e| session_store::Error::Decode(e.to_string()))?,
+            expiry_date: model.expires_at,
+        }))
+    }
+
+    async fn delete(&self, session_id: &Id) -> session_store::Result<()> {
+        SessionRecord::delete_by_id(session_id.to_string())
+            .exec(&self.database)
+            .await
+            .map_err(backend_error)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExpiredDeletion for DatabaseSessionStore {
+    async fn delete_expired(&self) -> session_store::Result<()> {
+        SessionRecord::delete_many()
+            .filter(Column::ExpiresAt.lte(OffsetDateTime::now_utc()))
+            .exec(&self.database)
+            .await
+            .map_err(backend_error)?;
+        Ok(())
+    }
+}
+
+pub(crate) async fn delete_expired_loop(store: DatabaseSessionStore, period: time::Duration) {
+    let mut interval = tokio::time::interval(period.try_into().expect("invalid duration"));
+    loop {
+        interval.tick().await;
+
+        if let Err(err) = store.delete_expired().await {
+            warn!("Deleting expired sessions failed: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::test_support;
+    use time::ext::NumericalDuration;
+
+    async fn setup() -> DatabaseSessionStore {
+        DatabaseSessionStore::new(test_support::setup().await)
+    }
+
+    fn record(expiry_date: OffsetDateTime) -> Record {
+        let mut record = Record {
+            id: Id::default(),
+            data: Default::default(),
+            expiry_date,
+        };
+        record.data.insert(
+            "oidc_metadata".to_string(),
+            serde_json::json!({"state": "abc"}),
+        );
+        record
+    }
+
+    #[tokio::test]
+    async fn test_session_store() {
+        let store = setup().await;
+        let mut session = record(OffsetDateTime::now_utc() + 1.minutes());
+        store.create(&mut session).await.unwrap();
+        assert_eq!(
+            store.load(&session.id).await.unwrap(),
+            Some(session.clone())
+        );
+
+        let mut collision = record(OffsetDateTime::now_utc() + 1.minutes());
+        collision.id = session.id;
+        store.create(&mut collision).await.unwrap();
+        assert_ne!(collision.id, session.id, "Expected a new id on collision");
+
+        session.data.clear();
+        session.expiry_date = OffsetDateTime::now_utc() + 2.minutes();
+        store.save(&session).await.unwrap();
+        let loaded = store.load(&session.id).await.unwrap().unwrap();
+        assert!(loaded.data.is_empty());
+
+        store.delete(&session.id).await.unwrap();
+        assert_eq!(store.load(&session.id).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_expired_sessions() {
+        let store = setup().await;
+        let mut expired = record(OffsetDateTime::now_utc() - 1.minutes());
+        store.create(&mut expired).await.unwrap();
+        let mut active = record(OffsetDateTime::now_utc() + 1.minutes());
+        store.create(&mut active).await.unwrap();
+
+        assert_eq!(
+            store.load(&expired.id).await.unwrap(),
+            None,
+            "Expected expired sessions to not be loaded"
+        );
+        store.delete_expired().await.unwrap();
+        assert_eq!(
+            SessionRecord::find()
+                .all(&store.database)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(store.load(&active.id).await.unwrap().is_some());
+    }
+}
@@ -0,0 +1,772 @@
+use crate::server::auth::oidc::{DEFAULT_PROVIDER_ID, ProviderEnv, parse_provider_ids};
+use crate::server::auth::oidc_groups::{GroupSync, parse_group_mapping};
+use crate::server::auth::oidc_identity::{ClaimMapping, ProvisioningRules, parse_email_domains};
+use crate::server::auth::rate_limit::RateLimitConfig;
+use crate::server::constants;
+use crate::server::utils::{convert_env_to_bool, parse_env_string};
+use anyhow::anyhow;
+use dioxus::prelude::*;
+use openidconnect::url::Url;
+use serde::Deserialize;
+use std::env;
+use std::fmt::Display;
+use std::net::IpAddr;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Read if `ROOMMATES_CONFIG` does not point to another file. Missing files are ignored
+const DEFAULT_CONFIG_FILE: &str = "roommates.toml";
+
+/// Settings of the server. Loaded once at startup from the configuration file, with environment variables
+/// overriding its values.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Public url of the server used to build links and redirects, without trailing slash
+    pub server_url: String,
+    pub database_url: Option<String>,
+    pub signup_enabled: bool,
+    /// Users have to verify their email before they can log in or receive invitations
+    pub email_verification_required: bool,
+    /// Logs every request
+    pub access_log: bool,
+    /// Reverse proxies whose `X-Forwarded-For` and `X-Real-IP` headers are trusted to carry the client address
+    pub trusted_proxies: Vec<IpAddr>,
+    pub log_format: LogFormat,
+    /// Days deleted To-Do lists and groups stay in the trash before they are deleted permanently
+    pub trash_retention_days: u32,
+    pub smtp: SmtpSettings,
+    pub login_rate_limit: LoginRateLimitSettings,
+    pub oidc: OidcSettings,
+    pub telemetry: TelemetrySettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            server_url: "http://localhost:8080".to_string(),
+            database_url: None,
+            signup_enabled: true,
+            email_verification_required: false,
+            access_log: false,
+            trusted_proxies: Vec::new(),
+            log_format: LogFormat::default(),
+            trash_retention_days: 30,
+            smtp: SmtpSettings::default(),
+            login_rate_limit: LoginRateLimitSettings::default(),
+            oidc: OidcSettings::default(),
+            telemetry: TelemetrySettings::default(),
+        }
+    }
+}
+
+/// Output format of the log
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable lines
+    #[default]
+    Text,
+    /// One JSON object per line, for log aggregators
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err("expected text or json".to_string()),
+        }
+    }
+}
+
+/// SMTP server used to send emails. Emails are written to the log if no url is set
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmtpSettings {
+    pub url: Option<String>,
+    /// Sender mailbox, e.g. `RoomMates <noreply@example.com>`
+    pub from: Option<String>,
+}
+
+/// See [`RateLimitConfig`]. Durations are in seconds
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoginRateLimitSettings {
+    pub free_attempts: u32,
+    pub backoff_base: u64,
+    pub account_lockout_threshold: u32,
+    pub ip_lockout_threshold: u32,
+    pub lockout_duration: u64,
+}
+
+impl Default for LoginRateLimitSettings {
+    fn default() -> Self {
+        let defaults = RateLimitConfig::default();
+        LoginRateLimitSettings {
+            free_attempts: defaults.free_attempts,
+            backoff_base: defaults.base_delay.as_secs(),
+            account_lockout_threshold: defaults.account_lockout_threshold,
+            ip_lockout_threshold: defaults.ip_lockout_threshold,
+            lockout_duration: defaults.lockout_duration.as_secs(),
+        }
+    }
+}
+
+/// Export of OpenTelemetry traces. Disabled unless an endpoint is set
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetrySettings {
+    /// Base url of an OTLP/HTTP collector, e.g. `http://localhost:4318`
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        TelemetrySettings {
+            otlp_endpoint: None,
+            service_name: "roommates".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OidcSettings {
+    pub enabled: bool,
+    /// Providers in the order they are shown on the login page.
+    /// A single provider with the id `default` is configured if OIDC is enabled without providers
+    pub providers: Vec<OidcProviderSettings>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OidcProviderSettings {
+    /// Identifies the provider in urls, cookies and environment variables
+    pub id: String,
+    /// Name displayed on the login button. Defaults to `SSO` for the default provider and the id otherwise
+    pub name: Option<String>,
+    pub issuer_url: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub scopes: Vec<String>,
+    /// Accepted audiences of access tokens. Defaults to the client id
+    pub audiences: Option<Vec<String>>,
+    /// Seconds between reloads of the keys used to validate tokens
+    pub jwks_refresh_interval: u64,
+    pub claims: ClaimMapping,
+    pub provisioning: ProvisioningRules,
+    pub group_sync: GroupSync,
+}
+
+impl Default for OidcProviderSettings {
+    fn default() -> Self {
+        OidcProviderSettings {
+            id: DEFAULT_PROVIDER_ID.to_string(),
+            name: None,
+            issuer_url: None,
+            client_id: None,
+            client_secret: None,
+            scopes: vec![
+                "openid".to_string(),
+                "email".to_string(),
+                "profile".to_string(),
+            ],
+            audiences: None,
+            jwks_refresh_interval: 600,
+            claims: ClaimMapping::default(),
+            provisioning: ProvisioningRules::default(),
+            group_sync: GroupSync::default(),
+        }
+    }
+}
+
+impl OidcProviderSettings {
+    fn new(id: String) -> Self {
+        OidcProviderSettings {
+            id,
+            ..Default::default()
+        }
+    }
+
+    /// Environment variables of the provider, e.g. `OIDC_COMPANY_CLIENT_ID`
+    #[must_use]
+    pub fn env(&self) -> ProviderEnv {
+        ProviderEnv::new(self.id.clone())
+    }
+}
+
+/// Parses a comma separated list of IP addresses
+fn parse_trusted_proxies(value: &str) -> Result<Vec<IpAddr>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|proxy| !proxy.is_empty())
+        .map(|proxy| {
+            proxy
+                .parse()
+                .map_err(|_| format!("invalid IP address '{proxy}'"))
+        })
+        .collect()
+}
+
+/// Applies environment variables on top of the configuration file and collects invalid values
+struct EnvOverrides<F> {
+    lookup: F,
+    errors: Vec<String>,
+}
+
+impl<F: Fn(&str) -> Option<String>> EnvOverrides<F> {
+    /// Empty values are ignored
+    fn string(&mut self, name: &str, target: &mut String) {
+        if let Some(value) = (self.lookup)(name).as_deref().and_then(parse_env_string) {
+            *target = value;
+        }
+    }
+
+    /// Empty values unset the target
+    fn optional_string(&mut self, name: &str, target: &mut Option<String>) {
+        if let Some(value) = (self.lookup)(name) {
+            *target = parse_env_string(&value);
+        }
+    }
+
+    fn bool(&mut self, name: &str, target: &mut bool) {
+        self.convert(name, target, convert_env_to_bool);
+    }
+
+    fn convert<T>(&mut self, name: &str, target: &mut T, conversion: impl FnOnce(&str) -> T) {
+        if let Some(value) = (self.lookup)(name) {
+            *target = conversion(&value);
+        }
+    }
+
+    fn parse<T: FromStr>(&mut self, name: &str, target: &mut T)
+    where
+        T::Err: Display,
+    {
+        if let Some(value) = (self.lookup)(name) {
+            match value.trim().parse() {
+                Ok(parsed) => *target = parsed,
+                Err(e) => self
+                    .errors
+                    .push(format!("{name}: invalid value '{value}' ({e})")),
+            }
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the settings from the file in `ROOMMATES_CONFIG` (or `roommates.toml` if it exists) and the
+    /// environment, including a `.env` file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error listing every invalid or missing value
+    pub fn load() -> Result<Self, anyhow::Error> {
+        let _ = dotenvy::dotenv();
+        let path = env::var(constants::CONFIG_FILE_ENV_VAR).ok().or_else(|| {
+            Path::new(DEFAULT_CONFIG_FILE)
+                .exists()
+                .then(|| DEFAULT_CONFIG_FILE.to_string())
+        });
+        let content = match &path {
+            Some(path) => {
+                info!("Loading configuration file {path}");
+                Some(
+                    std::fs::read_to_string(path)
+                        .map_err(|e| anyhow!("Failed to read configuration file {path}: {e}"))?,
+                )
+            }
+            None => None,
+        };
+        Self::from_sources(content.as_deref(), |name| env::var(name).ok())
+    }
+
+    /// Parses the configuration file content, applies the variables returned by `lookup` and validates the result
+    ///
+    /// # Errors
+    ///
+    /// Returns an error listing every invalid or missing value
+    pub fn from_sources(
+        content: Option<&str>,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, anyhow::Error> {
+        let mut settings: Settings = match content {
+            Some(content) => {
+                toml::from_str(content).map_err(|e| anyhow!("Invalid configuration: {e}"))?
+            }
+            None => Settings::default(),
+        };
+        let mut errors = settings.apply_env(lookup);
+        settings.server_url = settings.server_url.trim_end_matches('/').to_string();
+        errors.extend(settings.validate());
+
+        if errors.is_empty() {
+            Ok(settings)
+        } else {
+            Err(anyhow!(
+                "Invalid configuration:\n  - {}\n",
+                errors.join("\n  - ")
+            ))
+        }
+    }
+
+    fn apply_env(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Vec<String> {
+        let mut env = EnvOverrides {
+            lookup,
+            errors: Vec::new(),
+        };
+
+        env.string(constants::SERVER_URL_ENV_VAR, &mut self.server_url);
+        env.optional_string(constants::DATABASE_URL_ENV_VAR, &mut self.database_url);
+        env.bool(constants::SIGNUP_ENABLED_ENV_VAR, &mut self.signup_enabled);
+        env.bool(
+            constants::EMAIL_VERIFICATION_REQUIRED_ENV_VAR,
+            &mut self.email_verification_required,
+        );
+        env.bool(constants::ACCESS_LOG_ENV_VAR, &mut self.access_log);
+        if let Some(value) = (env.lookup)(constants::TRUSTED_PROXIES_ENV_VAR) {
+            match parse_trusted_proxies(&value) {
+                Ok(proxies) => self.trusted_proxies = proxies,
+                Err(e) => env
+                    .errors
+                    .push(format!("{}: {e}", constants::TRUSTED_PROXIES_ENV_VAR)),
+            }
+        }
+        env.parse(constants::LOG_FORMAT_ENV_VAR, &mut self.log_format);
+        env.parse(
+            constants::TRASH_RETENTION_DAYS_ENV_VAR,
+            &mut self.trash_retention_days,
+        );
+        env.optional_string(constants::SMTP_URL_ENV_VAR, &mut self.smtp.url);
+        env.optional_string(constants::SMTP_FROM_ENV_VAR, &mut self.smtp.from);
+
+        let rate_limit = &mut self.login_rate_limit;
+        env.parse(
+            constants::LOGIN_FREE_ATTEMPTS_ENV_VAR,
+            &mut rate_limit.free_attempts,
+        );
+        env.parse(
+            constants::LOGIN_BACKOFF_BASE_ENV_VAR,
+            &mut rate_limit.backoff_base,
+        );
+        env.parse(
+            constants::LOGIN_ACCOUNT_LOCKOUT_THRESHOLD_ENV_VAR,
+            &mut rate_limit.account_lockout_threshold,
+        );
+        env.parse(
+            constants::LOGIN_IP_LOCKOUT_THRESHOLD_ENV_VAR,
+            &mut rate_limit.ip_lockout_threshold,
+        );
+        env.parse(
+            constants::LOGIN_LOCKOUT_DURATION_ENV_VAR,
+            &mut rate_limit.lockout_duration,
+        );
+
+        env.optional_string(
+            constants::OTLP_ENDPOINT_ENV_VAR,
+            &mut self.telemetry.otlp_endpoint,
+        );
+        env.string(
+            constants::OTEL_SERVICE_NAME_ENV_VAR,
+            &mut self.telemetry.service_name,
+        );
+
+        env.bool(constants::OIDC_ENABLED_ENV_VAR, &mut self.oidc.enabled);
+        if let Some(value) = (env.lookup)(constants::OIDC_PROVIDERS_ENV_VAR) {
+            match parse_provider_ids(&value) {
+                Ok(ids) => {
+                    let mut configured = std::mem::take(&mut self.oidc.providers);
+                    self.oidc.providers = ids
+                        .into_iter()
+                        .map(|id| {
+                            configured
+                                .iter()
+                                .position(|provider| provider.id == id)
+                                .map_or_else(
+                                    || OidcProviderSettings::new(id),
+                                    |index| configured.swap_remove(index),
+                                )
+                        })
+                        .collect();
+                }
+                Err(e) => env
+                    .errors
+                    .push(format!("{}: {e}", constants::OIDC_PROVIDERS_ENV_VAR)),
+            }
+        }
+        if self.oidc.enabled && self.oidc.providers.is_empty() {
+            self.oidc.providers.push(OidcProviderSettings::default());
+        }
+        for provider in &mut self.oidc.providers {
+            provider.apply_env(&mut env);
+        }
+
+        env.errors
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if Url::parse(&self.server_url).map_or(true, |url| url.host_str().is_none()) {
+            errors.push(format!(
+                "server_url ({}) must be an absolute url with a host, e.g. https://roommates.example.com",
+                constants::SERVER_URL_ENV_VAR
+            ));
+        }
+        if self.database_url.is_none() {
+            errors.push(format!(
+                "database_url ({}) is not set",
+                constants::DATABASE_URL_ENV_VAR
+            ));
+        }
+        if self.trash_retention_days == 0 {
+            errors.push(format!(
+                "trash_retention_days ({}) must be at least 1",
+                constants::TRASH_RETENTION_DAYS_ENV_VAR
+            ));
+        }
+        if self.smtp.url.is_some() && self.smtp.from.is_none() {
+            errors.push(format!(
+                "smtp.from ({}) is required when an SMTP server is configured",
+                constants::SMTP_FROM_ENV_VAR
+            ));
+        }
+        if self
+            .telemetry
+            .otlp_endpoint
+            .as_deref()
+            .is_some_and(|endpoint| Url::parse(endpoint).is_err())
+        {
+            errors.push(format!(
+                "telemetry.otlp_endpoint ({}) must be an absolute url, e.g. http://localhost:4318",
+                constants::OTLP_ENDPOINT_ENV_VAR
+            ));
+        }
+        for (name, env_var, value) in [
+            (
+                "account_lockout_threshold",
+                constants::LOGIN_ACCOUNT_LOCKOUT_THRESHOLD_ENV_VAR,
+                self.login_rate_limit.account_lockout_threshold,
+            ),
+            (
+                "ip_lockout_threshold",
+                constants::LOGIN_IP_LOCKOUT_THRESHOLD_ENV_VAR,
+                self.login_rate_limit.ip_lockout_threshold,
+            ),
+        ] {
+            if value == 0 {
+                errors.push(format!(
+                    "login_rate_limit.{name} ({env_var}) must be at least 1"
+                ));
+            }
+        }
+
+        if self.oidc.enabled {
+            let ids: Vec<&str> = self
+                .oidc
+                .providers
+                .iter()
+                .map(|provider| provider.id.as_str())
+                .collect();
+            if let Err(e) = parse_provider_ids(&ids.join(",")) {
+                errors.push(format!("oidc.providers: {e}"));
+            }
+            for provider in &self.oidc.providers {
+                if provider.id != provider.id.to_lowercase() {
+                    errors.push(format!(
+                        "oidc provider {}: ids must be lowercase",
+                        provider.id
+                    ));
+                }
+                provider.validate(&mut errors);
+            }
+        }
+        errors
+    }
+}
+
+impl OidcProviderSettings {
+    fn apply_env<F: Fn(&str) -> Option<String>>(&mut self, env: &mut EnvOverrides<F>) {
+        let names = self.env();
+        let var = |name: &str| names.var_name(name);
+
+        env.optional_string(&var(constants::OIDC_PROVIDER_NAME_ENV_VAR), &mut self.name);
+        env.optional_string(
+            &var(constants::OIDC_ISSUER_URL_ENV_VAR),
+            &mut self.issuer_url,
+        );
+        env.optional_string(&var(constants::OIDC_CLIENT_ID_ENV_VAR), &mut self.client_id);
+        env.optional_string(
+            &var(constants::OIDC_CLIENT_SECRET_ENV_VAR),
+            &mut self.client_secret,
+        );
+        env.convert(
+            &var(constants::OIDC_SCOPES_ENV_VAR),
+            &mut self.scopes,
+            |value| value.split_whitespace().map(ToString::to_string).collect(),
+        );
+        env.convert(
+            &var(constants::OIDC_AUDIENCE_ENV_VAR),
+            &mut self.audiences,
+            |value| {
+                let audiences: Vec<String> = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|audience| !audience.is_empty())
+                    .map(ToString::to_string)
+                    .collect();
+                (!audiences.is_empty()).then_some(audiences)
+            },
+        );
+        env.parse(
+            &var(constants::OIDC_JWKS_REFRESH_INTERVAL_ENV_VAR),
+            &mut self.jwks_refresh_interval,
+        );
+
+        env.string(
+            &var(constants::OIDC_CLAIM_EMAIL_ENV_VAR),
+            &mut self.claims.email,
+        );
+        env.string(
+            &var(constants::OIDC_CLAIM_FIRST_NAME_ENV_VAR),
+            &mut self.claims.first_name,
+        );
+        env.string(
+            &var(constants::OIDC_CLAIM_LAST_NAME_ENV_VAR),
+            &mut self.claims.last_name,
+        );
+        env.optional_string(
+            &var(constants::OIDC_CLAIM_GROUPS_ENV_VAR),
+            &mut self.claims.groups,
+        );
+
+        env.bool(
+            &var(constants::OIDC_PROVISIONING_ENV_VAR),
+            &mut self.provisioning.enabled,
+        );
+        env.convert(
+            &var(constants::OIDC_PROVISIONING_EMAIL_DOMAINS_ENV_VAR),
+            &mut self.provisioning.allowed_email_domains,
+            |value| parse_email_domains(value.split(',')),
+        );
+        env.bool(
+            &var(constants::OIDC_PROVISIONING_REQUIRE_VERIFIED_EMAIL_ENV_VAR),
+            &mut self.provisioning.require_verified_email,
+        );
+
+        env.convert(
+            &var(constants::OIDC_GROUP_MAPPING_ENV_VAR),
+            &mut self.group_sync.mapping,
+            parse_group_mapping,
+        );
+        env.convert(
+            &var(constants::OIDC_TODO_LIST_ADMIN_MAPPING_ENV_VAR),
+            &mut self.group_sync.admin_mapping,
+            parse_group_mapping,
+        );
+        env.bool(
+            &var(constants::OIDC_GROUPS_DRY_RUN_ENV_VAR),
+            &mut self.group_sync.dry_run,
+        );
+
+        // Domains from the configuration file are normalized the same way as the variable
+        self.provisioning.allowed_email_domains =
+            parse_email_domains(self.provisioning.allowed_email_domains.iter());
+    }
+
+    fn validate(&self, errors: &mut Vec<String>) {
+        let env = self.env();
+        for (field, env_var, value) in [
+            (
+                "issuer_url",
+                constants::OIDC_ISSUER_URL_ENV_VAR,
+                &self.issuer_url,
+            ),
+            (
+                "client_id",
+                constants::OIDC_CLIENT_ID_ENV_VAR,
+                &self.client_id,
+            ),
+            (
+                "client_secret",
+                constants::OIDC_CLIENT_SECRET_ENV_VAR,
+                &self.client_secret,
+            ),
+        ] {
+            if value.is_none() {
+                errors.push(format!(
+                    "oidc provider {}: {field} ({}) is not set",
+                    self.id,
+                    env.var_name(env_var)
+                ));
+            }
+        }
+        if let Some(issuer_url) = &self.issuer_url
+            && Url::parse(issuer_url).is_err()
+        {
+            errors.push(format!(
+                "oidc provider {}: issuer_url ({}) is not a valid url",
+                self.id,
+                env.var_name(constants::OIDC_ISSUER_URL_ENV_VAR)
+            ));
+        }
+        if self.jwks_refresh_interval == 0 {
+            errors.push(format!(
+                "oidc provider {}: jwks_refresh_interval ({}) must be at least 1 second",
+                self.id,
+                env.var_name(constants::OIDC_JWKS_REFRESH_INTERVAL_ENV_VAR)
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn load(content: Option<&str>, vars: &[(&str, &str)]) -> Result<Settings, anyhow::Error> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+            .collect();
+        Settings::from_sources(content, |name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn file_values_are_overridden_by_env() {
+        let settings = load(
+            Some(
+                r#"
+                server_url = "https://roommates.example.com/"
+                database_url = "sqlite::memory:"
+                access_log = true
+                log_format = "json"
+
+                [login_rate_limit]
+                free_attempts = 5
+
+                [oidc]
+                enabled = true
+
+                [[oidc.providers]]
+                id = "company"
+                issuer_url = "https://idp.example.com"
+                client_id = "roommates"
+                client_secret = "secret"
+
+                [oidc.providers.provisioning]
+                allowed_email_domains = ["@Example.com"]
+
+                [oidc.providers.group_sync]
+                mapping = { flat-a = 1 }
+                "#,
+            ),
+            &[
+                ("ACCESS_LOG", "false"),
+                ("TRUSTED_PROXIES", "10.0.0.1, ::1"),
+                ("TRASH_RETENTION_DAYS", "7"),
+                ("LOGIN_FREE_ATTEMPTS", "7"),
+                ("OIDC_COMPANY_CLIENT_ID", "other"),
+                ("OIDC_COMPANY_TODO_LIST_ADMIN_MAPPING", "flat-a-admins=3"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(settings.server_url, "https://roommates.example.com");
+        assert!(!settings.access_log);
+        assert_eq!(
+            settings.trusted_proxies,
+            vec![
+                IpAddr::from([10, 0, 0, 1]),
+                IpAddr::from([0, 0, 0, 0, 0, 0, 0, 1])
+            ]
+        );
+        assert_eq!(settings.log_format, LogFormat::Json);
+        assert_eq!(settings.trash_retention_days, 7);
+        assert_eq!(settings.login_rate_limit.free_attempts, 7);
+        let provider = &settings.oidc.providers[0];
+        assert_eq!(provider.client_id.as_deref(), Some("other"));
+        assert_eq!(
+            provider.provisioning.allowed_email_domains,
+            vec!["example.com".to_string()]
+        );
+        assert_eq!(provider.group_sync.mapping.get("flat-a"), Some(&1));
+        assert_eq!(
+            provider.group_sync.admin_mapping.get("flat-a-admins"),
+            Some(&3)
+        );
+    }
+
+    #[test]
+    fn example_configuration_is_valid() {
+        let settings = load(
+            Some(include_str!("../../../../roommates.example.toml")),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(settings.oidc.providers[0].id, "company");
+    }
+
+    #[test]
+    fn env_only_configuration() {
+        let settings = load(
+            None,
+            &[
+                ("DATABASE_URL", "sqlite::memory:"),
+                ("OIDC_ENABLED", "true"),
+                ("OIDC_ISSUER_URL", "https://idp.example.com"),
+                ("OIDC_CLIENT_ID", "roommates"),
+                ("OIDC_CLIENT_SECRET", "secret"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(settings.server_url, "http://localhost:8080");
+        assert_eq!(settings.telemetry.otlp_endpoint, None);
+        assert_eq!(settings.oidc.providers.len(), 1);
+        assert_eq!(settings.oidc.providers[0].id, DEFAULT_PROVIDER_ID);
+    }
+
+    #[test]
+    fn invalid_settings_are_reported() {
+        let error = load(Some("unknown = 1"), &[("DATABASE_URL", "sqlite::memory:")])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("unknown"), "{error}");
+
+        let error = load(
+            None,
+            &[
+                ("SERVER_URL", "roommates"),
+                ("LOGIN_FREE_ATTEMPTS", "many"),
+                ("LOG_FORMAT", "xml"),
+                ("TRUSTED_PROXIES", "proxy.local"),
+                ("TRASH_RETENTION_DAYS", "0"),
+                ("OTEL_EXPORTER_OTLP_ENDPOINT", "localhost"),
+                ("OIDC_ENABLED", "true"),
+                ("OIDC_PROVIDERS", "company"),
+                ("OIDC_COMPANY_ISSUER_URL", "https://idp.example.com"),
+            ],
+        )
+        .unwrap_err()
+        .to_string();
+        for expected in [
+            "server_url (SERVER_URL)",
+            "database_url (DATABASE_URL)",
+            "LOGIN_FREE_ATTEMPTS: invalid value 'many'",
+            "LOG_FORMAT: invalid value 'xml'",
+            "TRUSTED_PROXIES: invalid IP address 'proxy.local'",
+            "trash_retention_days (TRASH_RETENTION_DAYS)",
+            "telemetry.otlp_endpoint (OTEL_EXPORTER_OTLP_ENDPOINT)",
+            "client_id (OIDC_COMPANY_CLIENT_ID)",
+            "client_secret (OIDC_COMPANY_CLIENT_SECRET)",
+        ] {
+            assert!(error.contains(expected), "Missing '{expected}' in {error}");
+        }
+    }
+}
@@ -1,20 +1,38 @@
-use super::{constants, database};
+use super::{api_v1, database};
 use crate::server::auth::middleware::authentication_middleware;
-use crate::server::auth::oidc::{OidcConfig, create_oidc_config, jwks_refresh_loop};
-use crate::server::middleware::tracing_middleware;
-use crate::server::utils::{convert_env_to_bool, get_env_or, parse_env_string};
+use crate::server::auth::oidc::{OidcProviders, create_oidc_providers, jwks_refresh_loop};
+use crate::server::auth::oidc_logout::{LogoutTokenCache, backchannel_logout};
+use crate::server::auth::passkey::create_webauthn;
+use crate::server::auth::rate_limit::{
+    LoginRateLimiter, RateLimitConfig, login_rate_limit_middleware,
+};
+use crate::server::auth::reauthentication::SESSION_EXPIRY;
+use crate::server::health::{healthz, metrics, readyz};
+use crate::server::live_updates::LiveUpdates;
+use crate::server::mail::Mailer;
+use crate::server::middleware::{Metrics, metrics_middleware, tracing_middleware};
+use crate::server::session_store::{DatabaseSessionStore, delete_expired_loop};
+use crate::server::settings::Settings;
+use crate::server::trash::{purge_trash_loop, retention};
 use dioxus::core::Element;
 use dioxus::prelude::*;
 use dioxus::server::axum;
 use dioxus::server::axum::Extension;
 use sea_orm::DatabaseConnection;
-use time::Duration;
+use std::sync::Arc;
 use time::ext::NumericalDuration;
 use tower_cookies::CookieManagerLayer;
-use tower_sessions::{Expiry, MemoryStore, SessionManagerLayer};
+use tower_sessions::SessionManagerLayer;
 
 pub async fn setup_api(app: fn() -> Element) -> Result<axum::Router, anyhow::Error> {
-    let database: DatabaseConnection = database::establish_connection().await?;
+    let settings = Settings::load()?;
+    let database: DatabaseConnection = database::establish_connection(
+        settings
+            .database_url
+            .as_deref()
+            .expect("Validated by Settings::load"),
+    )
+    .await?;
 
     // TODO: For the start of the project this is the simplest way to keep the DB in sync. At some point we should switch to migrations tho
     database
@@ -22,39 +40,55 @@ pub async fn setup_api(app: fn() -> Element) -> Result<axum::Router, anyhow::Err
         .sync(&database)
         .await?;
 
-    let oidc_config = if get_env_or(constants::OIDC_ENABLED_ENV_VAR, false, convert_env_to_bool) {
-        let config = create_oidc_config().await?;
-        let duration: i64 = get_env_or(
-            constants::OIDC_JWKS_REFRESH_INTERVAL_ENV_VAR,
-            None,
-            parse_env_string,
-        )
-        .map_or(600, |v| v.parse().unwrap_or(600));
+    let oidc_providers = create_oidc_providers(&settings).await?;
+    for provider in oidc_providers.iter() {
         tokio::spawn(jwks_refresh_loop(
-            config.jwks_state.clone(),
-            Duration::seconds(duration),
+            provider.jwks_state.clone(),
+            provider.jwks_refresh_interval,
         ));
-        Some(config)
-    } else {
-        None
-    };
+    }
 
     let app_state = AppState {
         database,
-        oidc_config,
+        oidc_providers,
+        login_rate_limiter: LoginRateLimiter::new(RateLimitConfig::from_settings(
+            &settings.login_rate_limit,
+        )),
+        logout_tokens: LogoutTokenCache::new(),
+        mailer: Mailer::from_settings(&settings.smtp)?,
+        webauthn: Arc::new(create_webauthn(&settings.server_url)?),
+        settings: Arc::new(settings),
+        metrics: Metrics::new(),
+        live_updates: LiveUpdates::new(),
     };
 
-    let session_store = MemoryStore::default();
+    let session_store = DatabaseSessionStore::new(app_state.database.clone());
+    tokio::spawn(delete_expired_loop(session_store.clone(), 10.minutes()));
+    tokio::spawn(purge_trash_loop(
+        app_state.database.clone(),
+        retention(app_state.settings.trash_retention_days),
+        1.hours(),
+    ));
     let session_layer = SessionManagerLayer::new(session_store)
         .with_secure(!cfg!(debug_assertions))
-        .with_expiry(Expiry::OnInactivity(60.seconds()));
+        .with_expiry(SESSION_EXPIRY);
 
     let router = axum::Router::new()
         .serve_dioxus_application(ServeConfig::default().enable_out_of_order_streaming(), app)
+        .nest("/api/v1", api_v1::router())
+        .route("/healthz", axum::routing::get(healthz))
+        .route("/readyz", axum::routing::get(readyz))
+        .route("/metrics", axum::routing::get(metrics))
+        .route(
+            "/api/oidc/{provider}/backchannel-logout",
+            axum::routing::post(backchannel_logout),
+        )
         .layer(axum::middleware::from_fn(tracing_middleware))
         .layer(axum::middleware::from_fn(authentication_middleware))
+        .layer(axum::middleware::from_fn(login_rate_limit_middleware))
         .layer(CookieManagerLayer::new())
         .layer(session_layer)
+        .layer(axum::middleware::from_fn(metrics_middleware))
         .layer(Extension(app_state));
 
     Ok(router)
@@ -63,5 +97,12 @@ pub async fn setup_api(app: fn() -> Element) -> Result<axum::Router, anyhow::Err
 #[derive(Clone)]
 pub struct AppState {
     pub database: DatabaseConnection,
-    pub oidc_config: Option<OidcConfig>,
+    pub oidc_providers: OidcProviders,
+    pub login_rate_limiter: LoginRateLimiter,
+    pub logout_tokens: LogoutTokenCache,
+    pub mailer: Mailer,
+    pub webauthn: Arc<webauthn_rs::Webauthn>,
+    pub settings: Arc<Settings>,
+    pub metrics: Metrics,
+    pub live_updates: LiveUpdates,
 }
@@ -0,0 +1,131 @@
+//! Database setup and factories shared by the tests of the server modules
+
+use crate::server;
+use entity::todo_list_invitation::InvitationPermission;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+
+/// Connects to the test database and creates the tables of all entities
+pub(crate) async fn setup() -> DatabaseConnection {
+    let database = server::database::establish_test_connection().await.unwrap();
+    database
+        .get_schema_registry("entity::*")
+        .sync(&database)
+        .await
+        .unwrap();
+    database
+}
+
+/// Creates a verified password user with the email `test{id}@test.de`
+pub(crate) async fn create_test_user(
+    database: &DatabaseConnection,
+    id: i32,
+) -> entity::user::Model {
+    entity::user::ActiveModel {
+        id: Set(id),
+        email: Set(format!("test{id}@test.de")),
+        first_name: Set(format!("firstname{id}")),
+        last_name: Set(format!("lastname{id}")),
+        password: Set(Some("test".to_string())),
+        is_oidc_user: Set(false),
+        email_verified: Set(true),
+        ..Default::default()
+    }
+    .insert(database)
+    .await
+    .unwrap()
+}
+
+/// Creates a To-Do list with the creator as its admin
+pub(crate) async fn create_test_todo_list(
+    database: &DatabaseConnection,
+    id: i32,
+    created_by_id: i32,
+) -> entity::todo_list::Model {
+    let todo_list = entity::todo_list::ActiveModel {
+        id: Set(id),
+        title: Set(format!("Test Todo List {id}")),
+        created_by_id: Set(Some(created_by_id)),
+        ..Default::default()
+    }
+    .insert(database)
+    .await
+    .unwrap();
+
+    create_test_todo_list_invitation(
+        database,
+        todo_list.id,
+        created_by_id,
+        created_by_id,
+        true,
+        InvitationPermission::Admin,
+    )
+    .await;
+
+    todo_list
+}
+
+pub(crate) async fn create_test_todo_list_invitation(
+    database: &DatabaseConnection,
+    todo_list_id: i32,
+    receiver: i32,
+    sender: i32,
+    accepted: bool,
+    permission: InvitationPermission,
+) -> entity::todo_list_invitation::Model {
+    entity::todo_list_invitation::ActiveModel {
+        todo_list_id: Set(todo_list_id),
+        receiving_user_id: Set(receiver),
+        permission: Set(permission),
+        is_accepted: Set(accepted),
+        is_favorite: Set(false),
+        sender_user_id: Set(Some(sender)),
+    }
+    .insert(database)
+    .await
+    .unwrap()
+}
+
+/// Creates a group with the supplied members
+pub(crate) async fn create_test_group(
+    database: &DatabaseConnection,
+    member_ids: &[i32],
+) -> entity::group::Model {
+    let group = entity::group::ActiveModel {
+        name: Set("Flat".to_string()),
+        ..Default::default()
+    }
+    .insert(database)
+    .await
+    .unwrap();
+    for member_id in member_ids {
+        entity::is_in_group::ActiveModel {
+            user_id: Set(*member_id),
+            group_id: Set(group.id),
+        }
+        .insert(database)
+        .await
+        .unwrap();
+    }
+    group
+}
+
+pub(crate) async fn create_test_event(
+    database: &DatabaseConnection,
+    owner_id: i32,
+    private: bool,
+) -> entity::event::Model {
+    entity::event::ActiveModel {
+        title: Set("Dinner".to_string()),
+        reoccurring: Set(false),
+        private: Set(private),
+        date: Set(time::macros::date!(2025 - 01 - 01)),
+        start_time: Set(time::macros::time!(18:00)),
+        end_time: Set(time::macros::time!(20:00)),
+        weekday: Set(entity::event::Weekday::Wednesday),
+        owner_id: Set(owner_id),
+        ..Default::default()
+    }
+    .insert(database)
+    .await
+    .unwrap()
+}
@@ -1,21 +1,37 @@
+use crate::error::ApiError;
+use crate::routes::live_updates::ChangeEvent;
+use crate::server::AppState;
+use crate::server::activity::{Feed, record_activity};
+use crate::server::audit::AuditEntry;
+use crate::server::live_updates::Audience;
+use crate::server::trash::trash_todo_list;
+use crate::server::versioning::{VersionedUpdate, update_versioned};
 use dioxus::fullstack::HttpError;
 use dioxus::prelude::{OrHttpError, ServerFnError, error};
-use entity::prelude::TodoListInvitation;
+use entity::activity::ActivityKind;
+use entity::audit_log::{AuditAction, AuditTarget};
+use entity::prelude::{Todo, TodoListInvitation};
+use entity::todo::{CreateToDo, UpdateToDo};
+use entity::todo_list::{CreateTodoList, UpdateTodoList};
 use entity::todo_list_invitation::Column as InviteColumn;
-use sea_orm::ModelTrait;
-use sea_orm::QueryFilter;
-use sea_orm::{ColumnTrait, DbErr};
-use sea_orm::{DatabaseConnection, EntityTrait};
+use entity::todo_list_invitation::InvitationPermission;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, IntoActiveModel,
+    ModelTrait, QueryFilter, Set, TransactionError, TransactionTrait, Unchanged,
+};
 
+/// Finds the accepted invitation of the user, ignoring To-Do lists in the trash
 async fn find_active_todo_list_invitation(
     todo_list_id: i32,
     user_id: i32,
     database: &DatabaseConnection,
 ) -> Result<Option<entity::todo_list_invitation::Model>, DbErr> {
     TodoListInvitation::find()
+        .inner_join(entity::todo_list::Entity)
         .filter(InviteColumn::ReceivingUserId.eq(user_id))
         .filter(InviteColumn::TodoListId.eq(todo_list_id))
         .filter(InviteColumn::IsAccepted.eq(true))
+        .filter(entity::todo_list::Column::DeletedAt.is_null())
         .one(database)
         .await
         .inspect_err(|e| error!("{e}"))
@@ -69,6 +85,335 @@ pub(crate) async fn remove_user_from_todo_list(
         .await
         .or_internal_server_error("Failed to remove user from todo list")?;
 
+    AuditEntry::new(
+        Some(request_user_id),
+        AuditAction::TodoListMemberRemoved,
+        AuditTarget::TodoList,
+        todo_list_id,
+    )
+    .affected_user(user_id)
+    .record(database)
+    .await;
+
+    Ok(())
+}
+
+/// Pushes the removal of a member to the remaining members and the removed user
+pub(crate) async fn publish_member_removed(todo_list_id: i32, user_id: i32, state: &AppState) {
+    state
+        .live_updates
+        .publish(
+            Audience::TodoList(todo_list_id),
+            ChangeEvent::TodoListChanged { todo_list_id },
+            &state.database,
+        )
+        .await;
+    state
+        .live_updates
+        .publish(
+            Audience::Users(vec![user_id]),
+            ChangeEvent::TodoListDeleted { todo_list_id },
+            &state.database,
+        )
+        .await;
+}
+
+/// Returns the permission of the user in the list. Lists the user has not joined are reported as not found
+pub(crate) async fn find_todo_list_permission(
+    todo_list_id: i32,
+    user_id: i32,
+    database: &DatabaseConnection,
+) -> Result<InvitationPermission, ApiError> {
+    Ok(get_todo_list_permission(todo_list_id, user_id, database)
+        .await?
+        .or_not_found("To-Do List not found")?)
+}
+
+/// Loads a task in one of the user's lists together with the user's permission in that list
+pub(crate) async fn find_todo(
+    todo_id: i32,
+    user_id: i32,
+    database: &DatabaseConnection,
+) -> Result<(entity::todo::Model, InvitationPermission), ApiError> {
+    let todo = Todo::find_by_id(todo_id)
+        .one(database)
+        .await
+        .or_internal_server_error("Error loading task")?
+        .or_not_found("Task not found")?;
+    let permission = get_todo_list_permission(todo.todo_list_id, user_id, database)
+        .await?
+        .or_not_found("Task not found")?;
+    Ok((todo, permission))
+}
+
+/// Creates a To-Do list with the user as its admin
+pub(crate) async fn create_todo_list(
+    state: &AppState,
+    user_id: i32,
+    data: CreateTodoList,
+) -> Result<entity::todo_list::Model, ApiError> {
+    let todo_list = state
+        .database
+        .transaction::<_, entity::todo_list::Model, ApiError>(|txn| {
+            Box::pin(async move {
+                let mut todo_list = data.into_active_model();
+                todo_list.created_by_id = Set(Some(user_id));
+                let todo_list = todo_list
+                    .insert(txn)
+                    .await
+                    .inspect_err(|e| error!("Error creating To-Do List: {e}"))
+                    .or_internal_server_error("Error creating To-Do List")?;
+
+                entity::todo_list_invitation::ActiveModel {
+                    todo_list_id: Set(todo_list.id),
+                    receiving_user_id: Set(user_id),
+                    permission: Set(InvitationPermission::Admin),
+                    is_accepted: Set(true),
+                    is_favorite: Set(false),
+                    ..Default::default()
+                }
+                .insert(txn)
+                .await
+                .inspect_err(|e| error!("Error creating To-Do List invitation: {e}"))
+                .or_internal_server_error("Error creating To-Do List")?;
+
+                Ok(todo_list)
+            })
+        })
+        .await
+        .map_err(|error| {
+            error!("Error creating To-Do List: {error}");
+            match error {
+                TransactionError::Connection(_) => ApiError::internal("Error creating To-Do List"),
+                TransactionError::Transaction(error) => error,
+            }
+        })?;
+
+    state
+        .live_updates
+        .publish(
+            Audience::Users(vec![user_id]),
+            ChangeEvent::TodoListChanged {
+                todo_list_id: todo_list.id,
+            },
+            &state.database,
+        )
+        .await;
+
+    Ok(todo_list)
+}
+
+/// Changes the title or description of a list if it still has `version`. Requires write permission
+pub(crate) async fn update_todo_list(
+    state: &AppState,
+    user_id: i32,
+    todo_list_id: i32,
+    version: i32,
+    data: UpdateTodoList,
+) -> Result<VersionedUpdate<entity::todo_list::Model>, ApiError> {
+    find_todo_list_permission(todo_list_id, user_id, &state.database)
+        .await?
+        .can_write()
+        .or_forbidden("You are not permitted to edit this To-Do List")?;
+
+    let mut todo_list = data.into_active_model();
+    todo_list.id = Unchanged(todo_list_id);
+    let update = update_versioned(todo_list, todo_list_id, version, &state.database)
+        .await
+        .inspect_err(|e| error!("Error updating To-Do List: {e}"))
+        .or_internal_server_error("Error updating To-Do List")?;
+
+    if let VersionedUpdate::Saved(_) = update {
+        state
+            .live_updates
+            .publish(
+                Audience::TodoList(todo_list_id),
+                ChangeEvent::TodoListChanged { todo_list_id },
+                &state.database,
+            )
+            .await;
+    }
+
+    Ok(update)
+}
+
+/// Moves the list to the trash, from where it can be restored until the retention period has passed.
+/// Requires admin permission
+pub(crate) async fn delete_todo_list(
+    state: &AppState,
+    user_id: i32,
+    todo_list_id: i32,
+) -> Result<(), ApiError> {
+    find_todo_list_permission(todo_list_id, user_id, &state.database)
+        .await?
+        .can_admin()
+        .or_forbidden("You are not permitted to delete this To-Do List")?;
+
+    let members = Audience::TodoList(todo_list_id)
+        .resolve(&state.database)
+        .await;
+    trash_todo_list(todo_list_id, &state.database)
+        .await
+        .inspect_err(|e| error!("Error deleting To-Do List: {e}"))
+        .or_internal_server_error("Error deleting To-Do List")?;
+
+    AuditEntry::new(
+        Some(user_id),
+        AuditAction::TodoListDeleted,
+        AuditTarget::TodoList,
+        todo_list_id,
+    )
+    .record(&state.database)
+    .await;
+    state
+        .live_updates
+        .publish(
+            Audience::Users(members),
+            ChangeEvent::TodoListDeleted { todo_list_id },
+            &state.database,
+        )
+        .await;
+
+    Ok(())
+}
+
+/// Adds an open task owned by the user to the list. Requires write permission
+pub(crate) async fn create_todo(
+    state: &AppState,
+    user_id: i32,
+    todo_list_id: i32,
+    data: CreateToDo,
+) -> Result<entity::todo::Model, ApiError> {
+    find_todo_list_permission(todo_list_id, user_id, &state.database)
+        .await?
+        .can_write()
+        .or_forbidden("You are not permitted to add tasks to this To-Do List")?;
+
+    let mut todo = data.into_active_model();
+    todo.completed = Set(false);
+    todo.owner_id = Set(user_id);
+    todo.todo_list_id = Set(todo_list_id);
+    let todo = todo
+        .insert(&state.database)
+        .await
+        .inspect_err(|e| error!("Error creating task: {e}"))
+        .or_internal_server_error("Error creating task")?;
+
+    record_activity(
+        user_id,
+        Feed::TodoList(todo_list_id),
+        ActivityKind::TodoCreated,
+        todo.title.clone(),
+        &state.database,
+    )
+    .await;
+    state
+        .live_updates
+        .publish(
+            Audience::TodoList(todo_list_id),
+            ChangeEvent::TodoSaved(todo.clone()),
+            &state.database,
+        )
+        .await;
+
+    Ok(todo)
+}
+
+/// Changes a task if it still has `version`. Requires write permission
+pub(crate) async fn update_todo(
+    state: &AppState,
+    user_id: i32,
+    todo_id: i32,
+    version: i32,
+    data: UpdateToDo,
+) -> Result<VersionedUpdate<entity::todo::Model>, ApiError> {
+    let (todo, permission) = find_todo(todo_id, user_id, &state.database).await?;
+    permission
+        .can_write()
+        .or_forbidden("You are not permitted to edit tasks in this To-Do List")?;
+
+    let was_completed = todo.completed;
+    let mut changes = todo.into_active_model();
+    if let Some(title) = data.title {
+        changes.title = Set(title);
+    }
+    if let Some(details) = data.details {
+        changes.details = Set(details);
+    }
+    if let Some(completed) = data.completed {
+        changes.completed = Set(completed);
+    }
+    let update = update_versioned(changes, todo_id, version, &state.database)
+        .await
+        .inspect_err(|e| error!("Error updating task: {e}"))
+        .or_internal_server_error("Error updating task")?;
+
+    if let VersionedUpdate::Saved(todo) = &update {
+        if todo.completed != was_completed {
+            let kind = if todo.completed {
+                ActivityKind::TodoCompleted
+            } else {
+                ActivityKind::TodoReopened
+            };
+            record_activity(
+                user_id,
+                Feed::TodoList(todo.todo_list_id),
+                kind,
+                todo.title.clone(),
+                &state.database,
+            )
+            .await;
+        }
+        state
+            .live_updates
+            .publish(
+                Audience::TodoList(todo.todo_list_id),
+                ChangeEvent::TodoSaved(todo.clone()),
+                &state.database,
+            )
+            .await;
+    }
+
+    Ok(update)
+}
+
+/// Deletes a task. Requires write permission
+pub(crate) async fn delete_todo(
+    state: &AppState,
+    user_id: i32,
+    todo_id: i32,
+) -> Result<(), ApiError> {
+    let (todo, permission) = find_todo(todo_id, user_id, &state.database).await?;
+    permission
+        .can_write()
+        .or_forbidden("You are not permitted to delete tasks in this To-Do List")?;
+
+    let (todo_list_id, title) = (todo.todo_list_id, todo.title.clone());
+    todo.delete(&state.database)
+        .await
+        .inspect_err(|e| error!("Error deleting task: {e}"))
+        .or_internal_server_error("Error deleting task")?;
+
+    record_activity(
+        user_id,
+        Feed::TodoList(todo_list_id),
+        ActivityKind::TodoDeleted,
+        title,
+        &state.database,
+    )
+    .await;
+    state
+        .live_updates
+        .publish(
+            Audience::TodoList(todo_list_id),
+            ChangeEvent::TodoDeleted {
+                todo_list_id,
+                todo_id,
+            },
+            &state.database,
+        )
+        .await;
+
     Ok(())
 }
 
@@ -76,11 +421,11 @@ pub(crate) async fn remove_user_from_todo_list(
 mod tests {
     use super::*;
     use crate::server;
-    use sea_orm::{ActiveModelTrait, ActiveValue};
+    use sea_orm::ActiveValue;
     use tokio;
 
     async fn setup() -> DatabaseConnection {
-        let database = server::database::establish_connection().await.unwrap();
+        let database = server::database::establish_test_connection().await.unwrap();
         database
             .get_schema_registry("entity::*")
             .sync(&database)
@@ -98,6 +443,8 @@ mod tests {
             last_name: sea_orm::Set(format!("firstname{id}")),
             password: sea_orm::Set(Some("test".to_string())),
             is_oidc_user: sea_orm::Set(false),
+            email_verified: sea_orm::Set(true),
+            ..Default::default()
         }
         .insert(database)
         .await
@@ -113,6 +460,10 @@ mod tests {
             id: sea_orm::Set(id),
             title: sea_orm::Set(format!("Test Todo List {id}")),
             description: ActiveValue::default(),
+            version: ActiveValue::default(),
+            created_at: ActiveValue::default(),
+            updated_at: ActiveValue::default(),
+            deleted_at: ActiveValue::default(),
             created_by_id: ActiveValue::Set(Some(created_by_id)),
         }
         .insert(database)
@@ -214,6 +565,25 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_no_permission_for_trashed_todo_list() {
+        let database = setup().await;
+        let user = create_test_user(&database, 1).await;
+        let todo_list = create_test_todo_list(&database, 1, user.id).await;
+
+        server::trash::trash_todo_list(todo_list.id, &database)
+            .await
+            .unwrap();
+
+        let permission = get_todo_list_permission(todo_list.id, user.id, &database)
+            .await
+            .unwrap();
+        assert_eq!(
+            permission, None,
+            "Expected lists in the trash to grant no permission"
+        );
+    }
+
     #[tokio::test]
     async fn test_remove_user_from_todo_list() {
         let database = setup().await;
@@ -272,4 +642,53 @@ mod tests {
             "Expected admin user to be able to remove owner from todo list"
         );
     }
+
+    #[tokio::test]
+    async fn test_todo_timestamps() {
+        let database = setup().await;
+        let user = create_test_user(&database, 1).await;
+        let todo_list = create_test_todo_list(&database, 1, user.id).await;
+        assert!(todo_list.created_at.is_some());
+        assert_eq!(todo_list.created_at, todo_list.updated_at);
+
+        let todo = entity::todo::ActiveModel {
+            title: sea_orm::Set("Buy milk".to_string()),
+            completed: sea_orm::Set(false),
+            todo_list_id: sea_orm::Set(todo_list.id),
+            owner_id: sea_orm::Set(user.id),
+            ..Default::default()
+        }
+        .insert(&database)
+        .await
+        .unwrap();
+        assert!(todo.created_at.is_some());
+        assert_eq!(
+            todo.completed_at, None,
+            "Expected open task to have no completion time"
+        );
+
+        let mut completed = todo.clone().into_active_model();
+        completed.completed = sea_orm::Set(true);
+        let completed = completed.update(&database).await.unwrap();
+        assert_eq!(completed.created_at, todo.created_at);
+        assert!(completed.updated_at >= todo.updated_at);
+        assert_eq!(completed.completed_at, completed.updated_at);
+
+        let mut renamed = completed.clone().into_active_model();
+        renamed.title = sea_orm::Set("Buy oat milk".to_string());
+        renamed.completed = sea_orm::Set(true);
+        let renamed = renamed.update(&database).await.unwrap();
+        assert_eq!(
+            renamed.completed_at, completed.completed_at,
+            "Expected completing a completed task again to keep the completion time"
+        );
+
+        let mut reopened = renamed.into_active_model();
+        reopened.completed = sea_orm::Set(false);
+        let reopened = reopened.update(&database).await.unwrap();
+        assert_eq!(
+            reopened.completed_at, None,
+            "Expected reopened task to have no completion time"
+        );
+    }
 }
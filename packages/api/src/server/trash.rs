@@ -0,0 +1,259 @@
+//! Soft deletion of To-Do lists and groups.
+//!
+//! Deleting moves them to the trash by setting `deleted_at`. Trashed rows are hidden from all queries,
+//! can be restored during the retention period and are deleted permanently by [`purge_trash_loop`] afterwards.
+
+use dioxus::prelude::warn;
+use entity::group::{Column as GroupColumn, Entity as Group};
+use entity::shared_group_event::{Column as GroupEventColumn, Entity as SharedGroupEvent};
+use entity::todo_list::{Column as TodoListColumn, Entity as TodoList};
+use sea_orm::prelude::{Expr, TimeDateTimeWithTimeZone};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use time::{Duration, OffsetDateTime};
+
+/// Time after which trashed rows are deleted permanently
+pub(crate) fn retention(days: u32) -> Duration {
+    Duration::days(i64::from(days))
+}
+
+/// Moves the To-Do list to the trash. Returns false if it does not exist or already is in the trash
+pub(crate) async fn trash_todo_list(
+    todo_list_id: i32,
+    db: &DatabaseConnection,
+) -> Result<bool, DbErr> {
+    let result = TodoList::update_many()
+        .col_expr(
+            TodoListColumn::DeletedAt,
+            Expr::value(OffsetDateTime::now_utc()),
+        )
+        .filter(TodoListColumn::Id.eq(todo_list_id))
+        .filter(TodoListColumn::DeletedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected == 1)
+}
+
+/// Moves the group to the trash. Returns false if it does not exist or already is in the trash
+pub(crate) async fn trash_group(group_id: i32, db: &DatabaseConnection) -> Result<bool, DbErr> {
+    let result = Group::update_many()
+        .col_expr(
+            GroupColumn::DeletedAt,
+            Expr::value(OffsetDateTime::now_utc()),
+        )
+        .filter(GroupColumn::Id.eq(group_id))
+        .filter(GroupColumn::DeletedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected == 1)
+}
+
+/// Takes the To-Do list out of the trash. Returns false if it is not in the trash or the retention period has passed
+pub(crate) async fn restore_todo_list(
+    todo_list_id: i32,
+    retention: Duration,
+    db: &DatabaseConnection,
+) -> Result<bool, DbErr> {
+    let result = TodoList::update_many()
+        .col_expr(
+            TodoListColumn::DeletedAt,
+            Expr::value(Option::<TimeDateTimeWithTimeZone>::None),
+        )
+        .filter(TodoListColumn::Id.eq(todo_list_id))
+        .filter(TodoListColumn::DeletedAt.gt(OffsetDateTime::now_utc() - retention))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected == 1)
+}
+
+/// Takes the group out of the trash. Returns false if it is not in the trash or the retention period has passed
+pub(crate) async fn restore_group(
+    group_id: i32,
+    retention: Duration,
+    db: &DatabaseConnection,
+) -> Result<bool, DbErr> {
+    let result = Group::update_many()
+        .col_expr(
+            GroupColumn::DeletedAt,
+            Expr::value(Option::<TimeDateTimeWithTimeZone>::None),
+        )
+        .filter(GroupColumn::Id.eq(group_id))
+        .filter(GroupColumn::DeletedAt.gt(OffsetDateTime::now_utc() - retention))
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected == 1)
+}
+
+/// Permanently deletes the To-Do lists and groups which have been in the trash for longer than the retention period.
+/// Returns the number of deleted rows
+pub(crate) async fn purge_trash(
+    db: &DatabaseConnection,
+    retention: Duration,
+) -> Result<u64, DbErr> {
+    let cutoff = OffsetDateTime::now_utc() - retention;
+
+    let todo_lists = TodoList::delete_many()
+        .filter(TodoListColumn::DeletedAt.lte(cutoff))
+        .exec(db)
+        .await?;
+
+    let group_ids: Vec<i32> = Group::find()
+        .filter(GroupColumn::DeletedAt.lte(cutoff))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|group| group.id)
+        .collect();
+    if group_ids.is_empty() {
+        return Ok(todo_lists.rows_affected);
+    }
+
+    SharedGroupEvent::delete_many()
+        .filter(GroupEventColumn::GroupId.is_in(group_ids.clone()))
+        .exec(db)
+        .await?;
+    let groups = Group::delete_many()
+        .filter(GroupColumn::Id.is_in(group_ids))
+        .exec(db)
+        .await?;
+
+    Ok(todo_lists.rows_affected + groups.rows_affected)
+}
+
+pub(crate) async fn purge_trash_loop(
+    db: DatabaseConnection,
+    retention: Duration,
+    period: Duration,
+) {
+    let mut interval = tokio::time::interval(period.try_into().expect("invalid duration"));
+    loop {
+        interval.tick().await;
+
+        if let Err(err) = purge_trash(&db, retention).await {
+            warn!("Purging the trash failed: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::test_support::setup;
+    use sea_orm::{ActiveModelTrait, Set};
+    use time::ext::NumericalDuration;
+
+    async fn create_todo_list(
+        database: &DatabaseConnection,
+        deleted_at: Option<OffsetDateTime>,
+    ) -> entity::todo_list::Model {
+        entity::todo_list::ActiveModel {
+            title: Set("Groceries".to_string()),
+            deleted_at: Set(deleted_at),
+            ..Default::default()
+        }
+        .insert(database)
+        .await
+        .unwrap()
+    }
+
+    async fn create_group(
+        database: &DatabaseConnection,
+        deleted_at: Option<OffsetDateTime>,
+    ) -> entity::group::Model {
+        entity::group::ActiveModel {
+            name: Set("Flat".to_string()),
+            deleted_at: Set(deleted_at),
+            ..Default::default()
+        }
+        .insert(database)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_trash_and_restore() {
+        let database = setup().await;
+        let todo_list = create_todo_list(&database, None).await;
+        let group = create_group(&database, None).await;
+
+        assert!(trash_todo_list(todo_list.id, &database).await.unwrap());
+        assert!(trash_group(group.id, &database).await.unwrap());
+        // Already in the trash
+        assert!(!trash_todo_list(todo_list.id, &database).await.unwrap());
+        assert!(!trash_group(group.id, &database).await.unwrap());
+
+        let trashed = TodoList::find_by_id(todo_list.id)
+            .one(&database)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(trashed.deleted_at.is_some());
+
+        assert!(
+            restore_todo_list(todo_list.id, 30.days(), &database)
+                .await
+                .unwrap()
+        );
+        assert!(restore_group(group.id, 30.days(), &database).await.unwrap());
+        // Not in the trash anymore
+        assert!(
+            !restore_todo_list(todo_list.id, 30.days(), &database)
+                .await
+                .unwrap()
+        );
+
+        let restored = Group::find_by_id(group.id)
+            .one(&database)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(restored.deleted_at, None);
+    }
+
+    #[tokio::test]
+    async fn test_restore_after_retention_period() {
+        let database = setup().await;
+        let deleted_at = OffsetDateTime::now_utc() - 31.days();
+        let todo_list = create_todo_list(&database, Some(deleted_at)).await;
+        let group = create_group(&database, Some(deleted_at)).await;
+
+        assert!(
+            !restore_todo_list(todo_list.id, 30.days(), &database)
+                .await
+                .unwrap()
+        );
+        assert!(!restore_group(group.id, 30.days(), &database).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_purge_trash() {
+        let database = setup().await;
+        let expired = OffsetDateTime::now_utc() - 31.days();
+        let recent = OffsetDateTime::now_utc() - 1.days();
+
+        let active_list = create_todo_list(&database, None).await;
+        let recent_list = create_todo_list(&database, Some(recent)).await;
+        create_todo_list(&database, Some(expired)).await;
+        let active_group = create_group(&database, None).await;
+        create_group(&database, Some(expired)).await;
+
+        assert_eq!(purge_trash(&database, 30.days()).await.unwrap(), 2);
+
+        let remaining_lists: Vec<i32> = TodoList::find()
+            .all(&database)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|todo_list| todo_list.id)
+            .collect();
+        assert_eq!(remaining_lists, vec![active_list.id, recent_list.id]);
+
+        let remaining_groups: Vec<i32> = Group::find()
+            .all(&database)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|group| group.id)
+            .collect();
+        assert_eq!(remaining_groups, vec![active_group.id]);
+    }
+}
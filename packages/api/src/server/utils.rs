@@ -1,4 +1,6 @@
-use std::env;
+use dioxus::server::axum::extract::ConnectInfo;
+use dioxus::server::axum::http::{HeaderMap, Request};
+use std::net::{IpAddr, SocketAddr};
 
 pub fn convert_env_to_bool(value: &str) -> bool {
     match value.to_lowercase().trim() {
@@ -16,6 +18,91 @@ pub fn parse_env_string(value: &str) -> Option<String> {
     }
 }
 
-pub fn get_env_or<T, F: FnOnce(&str) -> T>(env_key: &str, default: T, conversion: F) -> T {
-    env::var(env_key).map_or(default, |v| conversion(&v))
+/// Determines the address of the client sending the request.
+/// Proxy headers (`X-Forwarded-For`, `X-Real-IP`) are only honoured if the request comes from one of the
+/// `trusted_proxies`, otherwise clients could pick any address they like.
+pub fn client_ip<B>(request: &Request<B>, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip())?;
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+    forwarded_ip(request.headers(), trusted_proxies).or(Some(peer))
+}
+
+/// Walks `X-Forwarded-For` from the right, as every proxy appends the address it received the request from,
+/// and returns the first address that is not a trusted proxy
+fn forwarded_ip(headers: &HeaderMap, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let forwarded: Vec<IpAddr> = headers
+        .get_all("X-Forwarded-For")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|value| value.trim().parse().ok())
+        .collect();
+    if let Some(first) = forwarded.first() {
+        return forwarded
+            .iter()
+            .rev()
+            .find(|ip| !trusted_proxies.contains(ip))
+            .or(Some(first))
+            .copied();
+    }
+    headers
+        .get("X-Real-IP")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROXY: [u8; 4] = [10, 0, 0, 1];
+
+    fn build_request(peer: [u8; 4], headers: &[(&str, &str)]) -> Request<()> {
+        let mut builder = Request::builder().extension(ConnectInfo(SocketAddr::from((peer, 443))));
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(()).unwrap()
+    }
+
+    #[test]
+    fn forwarding_headers_of_untrusted_peers_are_ignored() {
+        let request = build_request(
+            [203, 0, 113, 7],
+            &[
+                ("X-Forwarded-For", "198.51.100.1"),
+                ("X-Real-IP", "198.51.100.2"),
+            ],
+        );
+        assert_eq!(
+            client_ip(&request, &[IpAddr::from(PROXY)]),
+            Some([203, 0, 113, 7].into())
+        );
+        assert_eq!(client_ip(&request, &[]), Some([203, 0, 113, 7].into()));
+    }
+
+    #[test]
+    fn trusted_proxies_are_skipped() {
+        let trusted: [IpAddr; 2] = [PROXY.into(), [10, 0, 0, 2].into()];
+        let request = build_request(
+            PROXY,
+            &[("X-Forwarded-For", "198.51.100.1, 192.0.2.5, 10.0.0.2")],
+        );
+        assert_eq!(
+            client_ip(&request, &trusted),
+            Some([192, 0, 2, 5].into()),
+            "Expected the address prepended by the client to be ignored"
+        );
+
+        let request = build_request(PROXY, &[("X-Real-IP", "192.0.2.5")]);
+        assert_eq!(client_ip(&request, &trusted), Some([192, 0, 2, 5].into()));
+
+        let request = build_request(PROXY, &[]);
+        assert_eq!(client_ip(&request, &trusted), Some(PROXY.into()));
+    }
 }
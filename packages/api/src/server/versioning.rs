@@ -0,0 +1,119 @@
+use crate::error::ApiError;
+use sea_orm::{
+    ActiveModelBehavior, ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
+    IntoActiveModel, PrimaryKeyTrait, QueryFilter, Value,
+};
+
+/// Entity with a `version` column which is incremented on every update
+pub(crate) trait Versioned: EntityTrait {
+    const VERSION: Self::Column;
+}
+
+impl Versioned for entity::todo::Entity {
+    const VERSION: Self::Column = entity::todo::Column::Version;
+}
+
+impl Versioned for entity::todo_list::Entity {
+    const VERSION: Self::Column = entity::todo_list::Column::Version;
+}
+
+impl Versioned for entity::event::Entity {
+    const VERSION: Self::Column = entity::event::Column::Version;
+}
+
+/// Outcome of [`update_versioned`]
+#[derive(Debug, PartialEq)]
+pub(crate) enum VersionedUpdate<M> {
+    /// The changes were saved. Contains the updated row
+    Saved(M),
+    /// The row was changed by someone else in the meantime and nothing was saved. Contains the current row
+    Stale(M),
+}
+
+impl<M: serde::Serialize> VersionedUpdate<M> {
+    /// Returns the saved row, or an [`ApiError::EditConflict`] containing the current row
+    pub(crate) fn or_edit_conflict(self, message: impl Into<String>) -> Result<M, ApiError> {
+        match self {
+            VersionedUpdate::Saved(model) => Ok(model),
+            VersionedUpdate::Stale(current) => Err(ApiError::edit_conflict(message, &current)),
+        }
+    }
+}
+
+/// Saves the changes of the row with `id` if it still has `version`, incrementing the version.
+///
+/// The version is checked by the `UPDATE` statement itself, so of two concurrent updates based on the same
+/// version only the first one is saved. Returns [`DbErr::RecordNotFound`] if the row does not exist anymore.
+pub(crate) async fn update_versioned<A>(
+    mut changes: A,
+    id: i32,
+    version: i32,
+    database: &DatabaseConnection,
+) -> Result<VersionedUpdate<<A::Entity as EntityTrait>::Model>, DbErr>
+where
+    A: ActiveModelTrait + ActiveModelBehavior + Send,
+    A::Entity: Versioned,
+    <A::Entity as EntityTrait>::Model: IntoActiveModel<A>,
+    i32: Into<<<A::Entity as EntityTrait>::PrimaryKey as PrimaryKeyTrait>::ValueType>,
+{
+    let version_column = <A::Entity as Versioned>::VERSION;
+    changes.set(version_column, Value::from(version + 1));
+    let changes = changes.before_save(database, false).await?;
+
+    match A::Entity::update(changes)
+        .validate()?
+        .filter(version_column.eq(version))
+        .exec(database)
+        .await
+    {
+        Ok(model) => Ok(VersionedUpdate::Saved(
+            A::after_save(model, database, false).await?,
+        )),
+        Err(DbErr::RecordNotUpdated) => A::Entity::find_by_id(id)
+            .one(database)
+            .await?
+            .map(VersionedUpdate::Stale)
+            .ok_or_else(|| DbErr::RecordNotFound(format!("Row {id} was deleted"))),
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::test_support::setup;
+    use sea_orm::Set;
+
+    #[tokio::test]
+    async fn test_stale_updates_are_rejected() {
+        let database = setup().await;
+        let todo_list = entity::todo_list::ActiveModel {
+            title: Set("Groceries".to_string()),
+            ..Default::default()
+        }
+        .insert(&database)
+        .await
+        .unwrap();
+        assert_eq!(todo_list.version, 1);
+
+        let mut first = todo_list.clone().into_active_model();
+        first.title = Set("Shopping".to_string());
+        let VersionedUpdate::Saved(saved) = update_versioned(first, todo_list.id, 1, &database)
+            .await
+            .unwrap()
+        else {
+            panic!("Update based on the current version was rejected");
+        };
+        assert_eq!(saved.title, "Shopping");
+        assert_eq!(saved.version, 2);
+
+        let mut second = todo_list.clone().into_active_model();
+        second.title = Set("Drugstore".to_string());
+        assert_eq!(
+            update_versioned(second, todo_list.id, 1, &database)
+                .await
+                .unwrap(),
+            VersionedUpdate::Stale(saved)
+        );
+    }
+}
@@ -48,6 +48,8 @@ mod test_db_mod {
             last_name: Set("rau".to_owned()),
             password: Set(Some("pass".to_owned())),
             is_oidc_user: Set(false),
+            email_verified: Set(true),
+            ..Default::default()
         };
 
         user::Entity::insert(user1).exec(db).await?;
@@ -59,6 +61,8 @@ mod test_db_mod {
             last_name: Set("hhh".to_owned()),
             password: Set(Some("word".to_owned())),
             is_oidc_user: Set(false),
+            email_verified: Set(true),
+            ..Default::default()
         };
 
         user::Entity::insert(user2).exec(db).await?;
@@ -66,6 +70,7 @@ mod test_db_mod {
         let group1: group::ActiveModel = group::ActiveModel {
             id: Set(8),
             name: Set("group1".to_owned()),
+            ..Default::default()
         };
 
         group::Entity::insert(group1).exec(db).await?;
@@ -99,6 +104,7 @@ mod test_db_mod {
             end_time: Set(time!(8:59:59.001)),
             weekday: Set(event::Weekday::Wednesday),
             owner_id: Set(1),
+            ..Default::default()
         };
 
         event::Entity::insert(ev2).exec(db).await?;
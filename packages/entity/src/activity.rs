@@ -0,0 +1,93 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Entry of the activity feed of a group or To-Do list, e.g. a completed task
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "activity")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub kind: ActivityKind,
+    // Title of the task or event, or name of the member the activity is about.
+    // Copied, so the entry stays readable after the subject has been deleted
+    pub subject: String,
+    pub created_at: TimeDateTimeWithTimeZone,
+
+    //relations
+    pub actor_id: Option<i32>,
+    #[sea_orm(
+        belongs_to,
+        from = "actor_id",
+        to = "id",
+        on_update = "Restrict",
+        on_delete = "SetNull"
+    )]
+    pub actor: HasOne<super::user::Entity>,
+
+    // Exactly one of group and To-Do list is set
+    pub group_id: Option<i32>,
+    #[sea_orm(
+        belongs_to,
+        from = "group_id",
+        to = "id",
+        on_update = "Restrict",
+        on_delete = "Cascade"
+    )]
+    pub group: HasOne<super::group::Entity>,
+
+    pub todo_list_id: Option<i32>,
+    #[sea_orm(
+        belongs_to,
+        from = "todo_list_id",
+        to = "id",
+        on_update = "Restrict",
+        on_delete = "Cascade"
+    )]
+    pub todo_list: HasOne<super::todo_list::Entity>,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[derive(EnumIter, DeriveActiveEnum, Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(20))")]
+pub enum ActivityKind {
+    #[sea_orm(string_value = "TodoCreated")]
+    TodoCreated,
+    #[sea_orm(string_value = "TodoCompleted")]
+    TodoCompleted,
+    #[sea_orm(string_value = "TodoReopened")]
+    TodoReopened,
+    #[sea_orm(string_value = "TodoDeleted")]
+    TodoDeleted,
+    #[sea_orm(string_value = "EventAdded")]
+    EventAdded,
+    #[sea_orm(string_value = "EventRemoved")]
+    EventRemoved,
+    #[sea_orm(string_value = "MemberAdded")]
+    MemberAdded,
+    #[sea_orm(string_value = "MemberRemoved")]
+    MemberRemoved,
+    #[sea_orm(string_value = "MemberLeft")]
+    MemberLeft,
+    #[sea_orm(string_value = "GroupRenamed")]
+    GroupRenamed,
+}
+
+impl ActivityKind {
+    /// Describes the activity of the actor, e.g. `Alex completed 'Buy milk'`
+    pub fn describe(&self, actor: &str, subject: &str) -> String {
+        match self {
+            Self::TodoCreated => format!("{actor} added '{subject}'"),
+            Self::TodoCompleted => format!("{actor} completed '{subject}'"),
+            Self::TodoReopened => format!("{actor} reopened '{subject}'"),
+            Self::TodoDeleted => format!("{actor} deleted '{subject}'"),
+            Self::EventAdded => format!("{actor} added event '{subject}'"),
+            Self::EventRemoved => format!("{actor} removed event '{subject}'"),
+            Self::MemberAdded => format!("{actor} added {subject}"),
+            Self::MemberRemoved => format!("{actor} removed {subject}"),
+            Self::MemberLeft => format!("{actor} left the group"),
+            Self::GroupRenamed => format!("{actor} renamed the group to '{subject}'"),
+        }
+    }
+}
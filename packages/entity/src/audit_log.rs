@@ -0,0 +1,133 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Append-only record of a security- or sharing-relevant action.
+/// Users and targets are referenced by id without foreign keys, so entries outlive what they refer to
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "audit_log")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    // User who performed the action. Empty for actions of the server, e.g. the OIDC group sync
+    pub actor_id: Option<i32>,
+    pub action: AuditAction,
+    pub target_type: AuditTarget,
+    pub target_id: i32,
+    // User affected by the action, e.g. the removed member
+    pub affected_user_id: Option<i32>,
+    // Additional information, e.g. the previous and new permission
+    pub details: Option<String>,
+    pub created_at: TimeDateTimeWithTimeZone,
+}
+
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        if insert {
+            Ok(self)
+        } else {
+            Err(DbErr::Custom(
+                "Audit log entries cannot be changed".to_string(),
+            ))
+        }
+    }
+
+    async fn before_delete<C>(self, _db: &C) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        Err(DbErr::Custom(
+            "Audit log entries cannot be deleted".to_string(),
+        ))
+    }
+}
+
+// Stored as strings, so new actions do not require a migration of the column type
+#[derive(EnumIter, DeriveActiveEnum, Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(40))")]
+pub enum AuditAction {
+    #[sea_orm(string_value = "Login")]
+    Login,
+    #[sea_orm(string_value = "PasswordChanged")]
+    PasswordChanged,
+    #[sea_orm(string_value = "PasswordReset")]
+    PasswordReset,
+    #[sea_orm(string_value = "AccountDeleted")]
+    AccountDeleted,
+    #[sea_orm(string_value = "GroupMemberAdded")]
+    GroupMemberAdded,
+    #[sea_orm(string_value = "GroupMemberRemoved")]
+    GroupMemberRemoved,
+    #[sea_orm(string_value = "GroupDeleted")]
+    GroupDeleted,
+    #[sea_orm(string_value = "GroupRestored")]
+    GroupRestored,
+    #[sea_orm(string_value = "EventDeleted")]
+    EventDeleted,
+    #[sea_orm(string_value = "EventSharedWithGroup")]
+    EventSharedWithGroup,
+    #[sea_orm(string_value = "EventRemovedFromGroup")]
+    EventRemovedFromGroup,
+    #[sea_orm(string_value = "EventInvitationSent")]
+    EventInvitationSent,
+    #[sea_orm(string_value = "EventLeft")]
+    EventLeft,
+    #[sea_orm(string_value = "EventTransferred")]
+    EventTransferred,
+    #[sea_orm(string_value = "TodoListMemberInvited")]
+    TodoListMemberInvited,
+    #[sea_orm(string_value = "TodoListPermissionChanged")]
+    TodoListPermissionChanged,
+    #[sea_orm(string_value = "TodoListMemberRemoved")]
+    TodoListMemberRemoved,
+    #[sea_orm(string_value = "TodoListDeleted")]
+    TodoListDeleted,
+    #[sea_orm(string_value = "TodoListRestored")]
+    TodoListRestored,
+}
+
+impl Display for AuditAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            Self::Login => "Logged in",
+            Self::PasswordChanged => "Changed the password",
+            Self::PasswordReset => "Reset the password",
+            Self::AccountDeleted => "Deleted the account",
+            Self::GroupMemberAdded => "Added a member",
+            Self::GroupMemberRemoved => "Removed a member",
+            Self::GroupDeleted => "Deleted the group",
+            Self::GroupRestored => "Restored the group",
+            Self::EventDeleted => "Deleted the event",
+            Self::EventSharedWithGroup => "Shared the event with a group",
+            Self::EventRemovedFromGroup => "Removed the event from a group",
+            Self::EventInvitationSent => "Invited a user to the event",
+            Self::EventLeft => "Left the event",
+            Self::EventTransferred => "Transferred the event to a new owner",
+            Self::TodoListMemberInvited => "Invited a member",
+            Self::TodoListPermissionChanged => "Changed the permission of a member",
+            Self::TodoListMemberRemoved => "Removed a member",
+            Self::TodoListDeleted => "Deleted the To-Do list",
+            Self::TodoListRestored => "Restored the To-Do list",
+        };
+        write!(f, "{description}")
+    }
+}
+
+/// Kind of object an audit log entry belongs to
+#[derive(EnumIter, DeriveActiveEnum, Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(20))")]
+pub enum AuditTarget {
+    #[sea_orm(string_value = "User")]
+    User,
+    #[sea_orm(string_value = "Group")]
+    Group,
+    #[sea_orm(string_value = "Event")]
+    Event,
+    #[sea_orm(string_value = "TodoList")]
+    TodoList,
+}
@@ -24,6 +24,13 @@ pub struct Model {
     pub start_time: TimeTime,
     pub end_time: TimeTime,
     pub weekday: Weekday,
+    // Incremented on every update. Updates have to name the version they are based on,
+    // so concurrent edits are detected instead of overwriting each other
+    #[sea_orm(default_value = 1)]
+    pub version: i32,
+    // Set by `ActiveModelBehavior::before_save`. Empty for rows created before timestamps were recorded
+    pub created_at: Option<TimeDateTimeWithTimeZone>,
+    pub updated_at: Option<TimeDateTimeWithTimeZone>,
 
     // Relation
     //belongs to this user
@@ -47,7 +54,16 @@ pub struct Model {
     pub invitations: HasMany<super::invitation::Entity>,
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        crate::timestamps::touch(&mut self.created_at, &mut self.updated_at, insert);
+        Ok(self)
+    }
+}
 
 #[derive(
     EnumIter,
@@ -134,6 +150,9 @@ pub struct FullEvent {
     pub start_time: TimeTime,
     pub end_time: TimeTime,
     pub weekday: Weekday,
+    pub version: i32,
+    pub created_at: Option<TimeDateTimeWithTimeZone>,
+    pub updated_at: Option<TimeDateTimeWithTimeZone>,
     pub owner_id: i32,
     #[sea_orm(alias = "is_group_event")]
     pub is_group_event: bool,
@@ -154,6 +173,9 @@ impl From<FullEvent> for Model {
             start_time: value.start_time,
             end_time: value.end_time,
             weekday: value.weekday,
+            version: value.version,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
             owner_id: value.owner_id,
         }
     }
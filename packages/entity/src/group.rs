@@ -8,6 +8,12 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub name: String,
+    // Set by `ActiveModelBehavior::before_save`. Empty for rows created before timestamps were recorded
+    pub created_at: Option<TimeDateTimeWithTimeZone>,
+    pub updated_at: Option<TimeDateTimeWithTimeZone>,
+    // Set while the group is in the trash. Trashed rows are hidden everywhere and deleted
+    // permanently once the retention period of the trash has passed
+    pub deleted_at: Option<TimeDateTimeWithTimeZone>,
 
     //relations
 
@@ -18,6 +24,19 @@ pub struct Model {
     //events shared with the group
     #[sea_orm(has_many, via = "shared_group_event")]
     pub shared_events: HasMany<super::event::Entity>,
+
+    //activity feed of the group
+    #[sea_orm(has_many)]
+    pub activities: HasMany<super::activity::Entity>,
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        crate::timestamps::touch(&mut self.created_at, &mut self.updated_at, insert);
+        Ok(self)
+    }
+}
@@ -9,6 +9,9 @@ pub struct Model {
     pub id: i32,
 
     pub status: InvitationStatus,
+    // Set by `ActiveModelBehavior::before_save`. Empty for rows created before timestamps were recorded
+    pub created_at: Option<TimeDateTimeWithTimeZone>,
+    pub updated_at: Option<TimeDateTimeWithTimeZone>,
 
     pub recieving_user: i32,
     #[sea_orm(belongs_to, from = "recieving_user", to = "id")]
@@ -19,7 +22,16 @@ pub struct Model {
     pub event: HasOne<super::event::Entity>,
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        crate::timestamps::touch(&mut self.created_at, &mut self.updated_at, insert);
+        Ok(self)
+    }
+}
 
 #[derive(EnumIter, DeriveActiveEnum, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "InvitationStatus")]
@@ -1,11 +1,23 @@
+pub mod activity;
+pub mod api_token;
+pub mod audit_log;
+pub mod email_verification_token;
 pub mod event;
 pub mod group;
 pub mod invitation;
 pub mod is_in_group;
 pub mod links;
+pub mod login_challenge;
+pub mod oidc_identity;
+pub mod passkey_credential;
+pub mod password_reset_token;
 pub mod session;
+pub mod session_record;
 pub mod shared_friend_event;
 pub mod shared_group_event;
+mod timestamps;
+pub mod totp_credential;
+pub mod totp_recovery_code;
 pub mod user;
 
 pub mod prelude;
@@ -0,0 +1,30 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "login_challenge")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    // blake3 hash of the challenge handed out after the password was verified
+    #[sea_orm(unique)]
+    #[serde(skip)]
+    pub token: String,
+    pub failed_attempts: i32,
+    pub created_at: TimeDateTimeWithTimeZone,
+    pub expires_at: TimeDateTimeWithTimeZone,
+
+    // Relation
+    pub user_id: i32,
+    #[sea_orm(
+        belongs_to,
+        from = "user_id",
+        to = "id",
+        on_update = "Restrict",
+        on_delete = "Cascade"
+    )]
+    pub user: HasOne<super::user::Entity>,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "oidc_identity")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    // `iss` claim of the identity provider
+    #[sea_orm(unique_key = "identity")]
+    pub issuer: String,
+    // `sub` claim. Only unique together with the issuer
+    #[sea_orm(unique_key = "identity")]
+    pub subject: String,
+    // Email reported by the provider at the last login. Only informational, never used for lookups
+    pub email: Option<String>,
+    pub created_at: TimeDateTimeWithTimeZone,
+    pub last_login_at: Option<TimeDateTimeWithTimeZone>,
+    // Set by a back-channel logout. Tokens issued before are rejected
+    pub logged_out_at: Option<TimeDateTimeWithTimeZone>,
+
+    // Relation
+    pub user_id: i32,
+    #[sea_orm(
+        belongs_to,
+        from = "user_id",
+        to = "id",
+        on_update = "Restrict",
+        on_delete = "Cascade"
+    )]
+    pub user: HasOne<super::user::Entity>,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
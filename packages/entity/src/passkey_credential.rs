@@ -0,0 +1,36 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "passkey_credential")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    // Name chosen by the user to tell their passkeys apart
+    pub name: String,
+    // Base64 encoded credential id assigned by the authenticator
+    #[sea_orm(unique)]
+    pub credential_id: String,
+    // WebAuthn user handle. Shared by all passkeys of a user, so discoverable logins can find them
+    pub user_handle: String,
+    // Serialized `webauthn_rs::prelude::Passkey` including the public key and signature counter
+    #[sea_orm(column_type = "Text")]
+    #[serde(skip)]
+    pub passkey: String,
+    pub created_at: TimeDateTimeWithTimeZone,
+    pub last_used_at: Option<TimeDateTimeWithTimeZone>,
+
+    // Relation
+    pub user_id: i32,
+    #[sea_orm(
+        belongs_to,
+        from = "user_id",
+        to = "id",
+        on_update = "Restrict",
+        on_delete = "Cascade"
+    )]
+    pub user: HasOne<super::user::Entity>,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,11 +1,22 @@
+pub use super::activity::Entity as Activity;
+pub use super::api_token::Entity as ApiToken;
+pub use super::audit_log::Entity as AuditLog;
+pub use super::email_verification_token::Entity as EmailVerificationToken;
 pub use super::event::Entity as Event;
 pub use super::group::Entity as Group;
 pub use super::invitation::Entity as Invitation;
 pub use super::is_in_group::Entity as InGroup;
+pub use super::login_challenge::Entity as LoginChallenge;
+pub use super::oidc_identity::Entity as OidcIdentity;
+pub use super::passkey_credential::Entity as PasskeyCredential;
+pub use super::password_reset_token::Entity as PasswordResetToken;
 pub use super::session::Entity as Session;
+pub use super::session_record::Entity as SessionRecord;
 pub use super::shared_friend_event::Entity as FriendShare;
 pub use super::shared_group_event::Entity as GroupShare;
 pub use super::todo::Entity as Todo;
 pub use super::todo_list::Entity as TodoList;
 pub use super::todo_list_invitation::Entity as TodoListInvitation;
+pub use super::totp_credential::Entity as TotpCredential;
+pub use super::totp_recovery_code::Entity as TotpRecoveryCode;
 pub use super::user::Entity as User;
@@ -0,0 +1,16 @@
+use sea_orm::ActiveValue::{self, Set};
+use sea_orm::prelude::TimeDateTimeWithTimeZone;
+use time::OffsetDateTime;
+
+/// Timestamp column which is empty for rows created before timestamps were recorded
+pub(crate) type Timestamp = ActiveValue<Option<TimeDateTimeWithTimeZone>>;
+
+/// Sets `created_at` when a row is inserted without one and `updated_at` whenever it is saved.
+/// Called by the `before_save` hooks of the entities with timestamps
+pub(crate) fn touch(created_at: &mut Timestamp, updated_at: &mut Timestamp, insert: bool) {
+    let now = OffsetDateTime::now_utc();
+    if insert && created_at.is_not_set() {
+        *created_at = Set(Some(now));
+    }
+    *updated_at = Set(Some(now));
+}
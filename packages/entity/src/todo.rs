@@ -1,3 +1,4 @@
+use sea_orm::ActiveValue::{self, Set};
 use sea_orm::DeriveIntoActiveModel;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -12,6 +13,15 @@ pub struct Model {
     pub completed: bool,
     #[sea_orm(nullable)]
     pub details: Option<String>,
+    // Incremented on every update. Updates have to name the version they are based on,
+    // so concurrent edits are detected instead of overwriting each other
+    #[sea_orm(default_value = 1)]
+    pub version: i32,
+    // Set by `ActiveModelBehavior::before_save`. Empty for rows created before timestamps were recorded
+    pub created_at: Option<TimeDateTimeWithTimeZone>,
+    pub updated_at: Option<TimeDateTimeWithTimeZone>,
+    // When the task was completed last. Empty while it is open
+    pub completed_at: Option<TimeDateTimeWithTimeZone>,
 
     //Relations to others
     pub todo_list_id: i32,
@@ -35,7 +45,26 @@ pub struct Model {
     pub user: HasOne<super::user::Entity>,
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        crate::timestamps::touch(&mut self.created_at, &mut self.updated_at, insert);
+        match self.completed {
+            // Completing an already completed task keeps the original time
+            ActiveValue::Set(true)
+                if !matches!(self.completed_at, ActiveValue::Unchanged(Some(_))) =>
+            {
+                self.completed_at = self.updated_at.clone();
+            }
+            ActiveValue::Set(false) => self.completed_at = Set(None),
+            _ => {}
+        }
+        Ok(self)
+    }
+}
 
 #[derive(Serialize, Deserialize, Default, DeriveIntoActiveModel)]
 pub struct CreateToDo {
@@ -60,6 +89,7 @@ pub struct TodoWithPermission {
     pub title: String,
     pub completed: bool,
     pub details: Option<String>,
+    pub version: i32,
     pub todo_list_id: i32,
     #[sea_orm(nested)]
     pub invitation: super::todo_list_invitation::TodoListInvitationPartialModel,
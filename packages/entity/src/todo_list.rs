@@ -11,6 +11,16 @@ pub struct Model {
     pub title: String,
     #[sea_orm(nullable)]
     pub description: Option<String>,
+    // Incremented on every update. Updates have to name the version they are based on,
+    // so concurrent edits are detected instead of overwriting each other
+    #[sea_orm(default_value = 1)]
+    pub version: i32,
+    // Set by `ActiveModelBehavior::before_save`. Empty for rows created before timestamps were recorded
+    pub created_at: Option<TimeDateTimeWithTimeZone>,
+    pub updated_at: Option<TimeDateTimeWithTimeZone>,
+    // Set while the To-Do list is in the trash. Trashed rows are hidden everywhere and deleted
+    // permanently once the retention period of the trash has passed
+    pub deleted_at: Option<TimeDateTimeWithTimeZone>,
 
     //relations
     pub created_by_id: Option<i32>,
@@ -28,9 +38,21 @@ pub struct Model {
 
     #[sea_orm(has_many)]
     pub invitations: HasMany<super::todo_list_invitation::Entity>,
+
+    #[sea_orm(has_many)]
+    pub activities: HasMany<super::activity::Entity>,
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        crate::timestamps::touch(&mut self.created_at, &mut self.updated_at, insert);
+        Ok(self)
+    }
+}
 
 #[derive(Serialize, Deserialize, Default, DeriveIntoActiveModel)]
 pub struct CreateTodoList {
@@ -52,6 +74,7 @@ pub struct TodoListWithPermission {
     pub id: i32,
     pub title: String,
     pub description: Option<String>,
+    pub version: i32,
     #[sea_orm(nested)]
     pub invitation: super::todo_list_invitation::TodoListInvitationPartialModel,
 }
@@ -0,0 +1,32 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[sea_orm::model]
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "totp_credential")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    // Base32 encoded shared secret. Has to be stored in plain text to validate codes
+    #[serde(skip)]
+    pub secret: String,
+    // Set once the user proved their authenticator works. Unconfirmed credentials are not enforced on login
+    pub confirmed: bool,
+    // Time step of the last accepted code. Codes of this or earlier steps are rejected, so they cannot be replayed
+    pub last_used_step: Option<i64>,
+    pub created_at: TimeDateTimeWithTimeZone,
+
+    // Relation
+    #[sea_orm(unique)]
+    pub user_id: i32,
+    #[sea_orm(
+        belongs_to,
+        from = "user_id",
+        to = "id",
+        on_update = "Restrict",
+        on_delete = "Cascade"
+    )]
+    pub user: HasOne<super::user::Entity>,
+}
+
+impl ActiveModelBehavior for ActiveModel {}
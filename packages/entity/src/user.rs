@@ -15,6 +15,12 @@ pub struct Model {
     pub password: Option<String>,
 
     pub is_oidc_user: bool,
+    // Accounts created before email verification existed count as verified
+    #[sea_orm(default_value = true)]
+    pub email_verified: bool,
+    // Set by `ActiveModelBehavior::before_save`. Empty for rows created before timestamps were recorded
+    pub created_at: Option<TimeDateTimeWithTimeZone>,
+    pub updated_at: Option<TimeDateTimeWithTimeZone>,
 
     //events that belong to this user
     #[sea_orm(has_many)]
@@ -33,6 +39,34 @@ pub struct Model {
     #[sea_orm(has_many)]
     pub sessions: HasMany<super::session::Entity>,
 
+    // Pending password reset requests of this user
+    #[sea_orm(has_many)]
+    pub password_reset_tokens: HasMany<super::password_reset_token::Entity>,
+
+    // Pending email verifications of this user
+    #[sea_orm(has_many)]
+    pub email_verification_tokens: HasMany<super::email_verification_token::Entity>,
+
+    // Two-factor authentication of this user
+    #[sea_orm(has_one)]
+    pub totp_credential: HasOne<super::totp_credential::Entity>,
+    #[sea_orm(has_many)]
+    pub totp_recovery_codes: HasMany<super::totp_recovery_code::Entity>,
+    #[sea_orm(has_many)]
+    pub login_challenges: HasMany<super::login_challenge::Entity>,
+
+    // Passkeys registered by this user
+    #[sea_orm(has_many)]
+    pub passkey_credentials: HasMany<super::passkey_credential::Entity>,
+
+    // Identities of OIDC providers linked to this user
+    #[sea_orm(has_many)]
+    pub oidc_identities: HasMany<super::oidc_identity::Entity>,
+
+    // Personal API tokens of this user
+    #[sea_orm(has_many)]
+    pub api_tokens: HasMany<super::api_token::Entity>,
+
     // TodoLists owned by this user
     #[sea_orm(has_many)]
     pub created_todo_lists: HasMany<super::todo_list::Entity>,
@@ -57,7 +91,16 @@ pub struct Model {
     pub received_todo_list_invitations: HasMany<super::todo_list_invitation::Entity>,
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        crate::timestamps::touch(&mut self.created_at, &mut self.updated_at, insert);
+        Ok(self)
+    }
+}
 
 #[derive(Serialize, Deserialize, Default, Clone, PartialEq, DerivePartialModel)]
 #[sea_orm(entity = "Entity")]
@@ -93,6 +93,29 @@ impl FormState {
             .any(|checker| !checker().is_empty())
     }
 
+    /// Adds an error to the registered field with the supplied name, e.g. an error returned by the server.
+    /// The error is removed once the field is validated again
+    ///
+    /// Returns `false` if no field with the name is registered
+    pub fn add_field_error(&mut self, name: &str, error: String) -> bool {
+        let field = self
+            .fields
+            .read()
+            .iter()
+            .find(|field| field.borrow().name() == name)
+            .cloned();
+        let Some(field) = field else {
+            return false;
+        };
+        {
+            let mut field = field.borrow_mut();
+            field.mark_touched();
+            field.add_error(error);
+        }
+        self.check_errors();
+        true
+    }
+
     /// Checks all registered fields for errors and updates `has_errors` accordingly
     pub fn check_errors(&mut self) {
         self.has_errors.set(self.has_errors());
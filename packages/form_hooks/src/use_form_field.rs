@@ -340,6 +340,7 @@ pub trait AnyField {
     /// [`serde_json::Error`] if the value could not be parsed
     fn value_json(&self) -> Result<Value, serde_json::Error>;
     fn is_touched(&self) -> bool;
+    fn mark_touched(&mut self);
     fn add_error(&mut self, error: String);
     fn reset(&mut self);
     fn mark_clean(&mut self);
@@ -359,6 +360,9 @@ where
     fn is_touched(&self) -> bool {
         *self.touched.read()
     }
+    fn mark_touched(&mut self) {
+        self.touched.set(true);
+    }
     fn add_error(&mut self, error: String) {
         self.errors.push(error);
     }
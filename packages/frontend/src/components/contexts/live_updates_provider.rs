@@ -0,0 +1,113 @@
+use super::use_auth;
+use api::routes::live_updates::{ChangeEvent, subscribe_to_changes};
+use dioxus::prelude::*;
+use dioxus_sdk::time::use_timeout;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Delay before reconnecting after the connection to the server was lost
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+type Listeners = Vec<(usize, Callback<ChangeEvent>)>;
+
+/// Registry of the components listening for changes pushed by the server
+#[derive(Clone, Default)]
+pub struct LiveUpdates {
+    listeners: Rc<RefCell<Listeners>>,
+    next_id: Rc<Cell<usize>>,
+}
+
+impl LiveUpdates {
+    fn register(&self, listener: Callback<ChangeEvent>) -> usize {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        self.listeners.borrow_mut().push((id, listener));
+        id
+    }
+
+    fn unregister(&self, id: usize) {
+        self.listeners
+            .borrow_mut()
+            .retain(|(listener_id, _)| *listener_id != id);
+    }
+
+    fn dispatch(&self, change: &ChangeEvent) {
+        // Copied, so listeners may mount or unmount components while handling the change
+        let listeners: Vec<_> = self
+            .listeners
+            .borrow()
+            .iter()
+            .map(|(_, listener)| *listener)
+            .collect();
+        for listener in listeners {
+            listener.call(change.clone());
+        }
+    }
+}
+
+/// Keeps a connection to the live update stream of the logged in user and forwards changes to [`use_live_updates`]
+#[component]
+pub fn LiveUpdatesProvider(children: Element) -> Element {
+    let auth = use_auth();
+    let live_updates = use_context_provider(LiveUpdates::default);
+    let mut was_connected = use_signal(|| false);
+
+    let mut connection = use_resource(move || {
+        let live_updates = live_updates.clone();
+        async move {
+            if auth.user.read().is_none() {
+                return;
+            }
+            let Ok(mut changes) = subscribe_to_changes().await else {
+                return;
+            };
+            if *was_connected.peek() {
+                // Changes made while disconnected are unknown
+                live_updates.dispatch(&ChangeEvent::Resync);
+            }
+            was_connected.set(true);
+            while let Some(Ok(change)) = changes.recv().await {
+                live_updates.dispatch(&change);
+            }
+        }
+    });
+
+    let reconnect = use_timeout(RECONNECT_DELAY, move |()| {
+        connection.restart();
+    });
+
+    use_effect(move || {
+        if connection.finished() && auth.user.read().is_some() {
+            reconnect.action(());
+        }
+    });
+
+    rsx! {
+        {children}
+    }
+}
+
+/// Calls the handler for every change pushed by the server while the component is mounted
+///
+/// #Example
+/// ```ignore
+/// use_live_updates(move |change| {
+///     if let ChangeEvent::GroupChanged { group_id: changed } = change && changed == group_id {
+///         group.restart();
+///     }
+/// });
+/// ```
+pub fn use_live_updates(handler: impl FnMut(ChangeEvent) + 'static) {
+    let handler = use_callback(handler);
+    let live_updates = try_use_context::<LiveUpdates>();
+    let id = use_hook({
+        let live_updates = live_updates.clone();
+        move || live_updates.map(|live_updates| live_updates.register(handler))
+    });
+    use_drop(move || {
+        if let (Some(live_updates), Some(id)) = (live_updates, id) {
+            live_updates.unregister(id);
+        }
+    });
+}
@@ -1,5 +1,7 @@
 mod app_config_provider;
 mod auth_provider;
+mod live_updates_provider;
 
 pub use app_config_provider::{AppConfigProvider, use_app_config};
 pub use auth_provider::{AuthGuard, AuthProvider, AuthState, use_auth};
+pub use live_updates_provider::{LiveUpdates, LiveUpdatesProvider, use_live_updates};
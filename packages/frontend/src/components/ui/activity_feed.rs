@@ -0,0 +1,94 @@
+use crate::components::ui::button::{Button, ButtonVariant};
+use crate::components::ui::card::{Card, CardBody, CardTitle};
+use crate::components::ui::list::{List, ListDetails, ListRow};
+use api::routes::activity::{list_group_activity, list_todo_list_activity};
+use dioxus::prelude::*;
+use time::UtcOffset;
+use time::macros::format_description;
+
+/// Group or To-Do list whose activities are shown
+#[derive(Clone, Copy, PartialEq)]
+pub enum ActivitySource {
+    Group(i32),
+    TodoList(i32),
+}
+
+#[component]
+pub fn ActivityFeed(source: ActivitySource, #[props(default)] class: String) -> Element {
+    let mut page = use_signal(|| 1u64);
+    let activities = use_resource(move || {
+        let page = page();
+        async move {
+            match source {
+                ActivitySource::Group(group_id) => list_group_activity(group_id, Some(page)).await,
+                ActivitySource::TodoList(todo_list_id) => {
+                    list_todo_list_activity(todo_list_id, Some(page)).await
+                }
+            }
+        }
+    });
+    let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+
+    rsx! {
+        Card { class,
+            CardBody {
+                CardTitle { "Activity" }
+                match activities.read().as_ref() {
+                    Some(Ok(activities)) => rsx! {
+                        List { header: "",
+                            if activities.items.is_empty() {
+                                ListRow {
+                                    ListDetails { title: "Nothing happened yet" }
+                                }
+                            }
+                            for activity in activities.items.iter() {
+                                ListRow { key: "{activity.id}",
+                                    ListDetails { title: activity.message(),
+                                        {
+                                            activity
+                                                .created_at
+                                                .to_offset(offset)
+                                                .format(format_description!("[day].[month].[year] [hour]:[minute]"))
+                                                .unwrap_or_default()
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        if activities.total_pages > 1 {
+                            div { class: "join justify-center",
+                                Button {
+                                    class: "join-item btn-sm",
+                                    variant: ButtonVariant::None,
+                                    disabled: page() <= 1,
+                                    onclick: move |_| page -= 1,
+                                    "«"
+                                }
+                                span { class: "join-item btn btn-sm btn-disabled",
+                                    "{activities.page} / {activities.total_pages}"
+                                }
+                                Button {
+                                    class: "join-item btn-sm",
+                                    variant: ButtonVariant::None,
+                                    disabled: page() >= activities.total_pages,
+                                    onclick: move |_| page += 1,
+                                    "»"
+                                }
+                            }
+                        }
+                    },
+                    Some(Err(_)) => rsx! {
+                        List { header: "",
+                            ListRow {
+                                ListDetails { title: "Failed to load activities" }
+                            }
+                        }
+                    },
+                    None => rsx! {
+                        div { class: "skeleton h-32 w-full" }
+                    },
+                }
+            }
+        }
+    }
+}
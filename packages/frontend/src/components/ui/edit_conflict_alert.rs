@@ -0,0 +1,38 @@
+use crate::components::ui::button::{Button, ButtonVariant};
+use dioxus::prelude::*;
+
+/// Shown by editors when the edited object was saved by someone else in the meantime.
+/// The user decides whether their changes overwrite the other ones or are discarded.
+///
+/// Has to be placed inside the form of the editor, the overwrite button submits it after calling `onoverwrite`
+#[component]
+pub fn EditConflictAlert(
+    /// Name of the edited object, e.g. "event"
+    name: String,
+    /// Bases the changes of the user on the current version
+    onoverwrite: EventHandler<()>,
+    /// Replaces the values of the form with the current version
+    onreload: EventHandler<()>,
+) -> Element {
+    rsx! {
+        div { class: "alert alert-warning flex flex-col items-start gap-2", role: "alert",
+            p { "This {name} was changed by someone else while you were editing it." }
+            div { class: "flex flex-wrap gap-2",
+                Button {
+                    r#type: "button",
+                    variant: ButtonVariant::Secondary,
+                    class: "btn-sm",
+                    onclick: move |_| onreload.call(()),
+                    "Load their changes"
+                }
+                Button {
+                    r#type: "submit",
+                    variant: ButtonVariant::Warning,
+                    class: "btn-sm",
+                    onclick: move |_| onoverwrite.call(()),
+                    "Overwrite with my changes"
+                }
+            }
+        }
+    }
+}
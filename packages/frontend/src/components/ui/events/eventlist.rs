@@ -1,3 +1,4 @@
+use crate::components::contexts::use_live_updates;
 use crate::components::ui::{
     events::eventlistentry::{EventListEntry, SharedEventRow},
     list::List,
@@ -8,6 +9,7 @@ use api::routes::events::{
     {delete_event, leave_event, list_events, remove_event_from_group},
 };
 use api::routes::groups::retrieve_group;
+use api::routes::live_updates::ChangeEvent;
 use dioxus::prelude::*;
 use roommates::message_from_captured_error;
 use time::Date;
@@ -16,6 +18,13 @@ use time::Date;
 pub fn EventList(date: Option<Date>) -> Element {
     let mut events = use_loader(move || async move { list_events(date, date).await })?;
     let mut delete_event = use_action(delete_event);
+    use_live_updates(move |change| match change {
+        ChangeEvent::EventDeleted { event_id } => {
+            events.write().retain(|event| event.id != event_id);
+        }
+        ChangeEvent::EventChanged { .. } | ChangeEvent::Resync => events.restart(),
+        _ => {}
+    });
     let mut toaster = use_toaster();
     let ondelete = move |event_id: i32| async move {
         delete_event.call(event_id).await;
@@ -53,6 +62,16 @@ pub fn EventList(date: Option<Date>) -> Element {
 pub fn EventListGroups(group_id: i32) -> Element {
     let mut group = use_loader(move || async move { retrieve_group(group_id).await })?;
     let mut remove_event_from_group = use_action(remove_event_from_group);
+    use_live_updates(move |change| match change {
+        ChangeEvent::GroupChanged { group_id: changed } if changed == group_id => group.restart(),
+        ChangeEvent::EventChanged { event_id } | ChangeEvent::EventDeleted { event_id }
+            if group.read().events.iter().any(|event| event.id == event_id) =>
+        {
+            group.restart();
+        }
+        ChangeEvent::Resync => group.restart(),
+        _ => {}
+    });
     let mut toaster = use_toaster();
 
     let ondelete = move |event_id: i32| async move {
@@ -95,6 +114,13 @@ pub fn EventListGroups(group_id: i32) -> Element {
 pub fn SharedEventList() -> Element {
     let mut shared_events = use_loader(move || async move { list_shared_friend_events().await })?;
     let mut leave_event = use_action(leave_event);
+    use_live_updates(move |change| match change {
+        ChangeEvent::EventDeleted { event_id } => {
+            shared_events.write().retain(|event| event.id != event_id);
+        }
+        ChangeEvent::EventChanged { .. } | ChangeEvent::Resync => shared_events.restart(),
+        _ => {}
+    });
 
     let mut toaster = use_toaster();
 
@@ -1,3 +1,4 @@
+pub mod activity_feed;
 pub mod button;
 pub mod collapse;
 pub mod navbar;
@@ -8,6 +9,7 @@ pub mod calendar_small;
 pub mod card;
 pub mod dialog;
 pub mod dock;
+pub mod edit_conflict_alert;
 pub mod events;
 pub mod fieldset;
 pub mod footer;
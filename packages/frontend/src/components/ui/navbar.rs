@@ -53,9 +53,12 @@ pub fn Navbar() -> Element {
                             li {
                                 button {
                                     onclick: move |_| async move {
-                                        let _ = logout().await;
+                                        let redirect_url = logout().await.ok().and_then(|response| response.redirect_url);
                                         auth_state.logout();
-                                        nav.push(Route::Home {});
+                                        match redirect_url {
+                                            Some(url) => nav.push(NavigationTarget::External(url)),
+                                            None => nav.push(Route::Home {}),
+                                        };
                                     },
                                     "Logout"
                                 }
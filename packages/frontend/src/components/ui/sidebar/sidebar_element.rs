@@ -4,7 +4,9 @@ use crate::components::ui::theme_controller::ThemeController;
 use dioxus::prelude::*;
 use dioxus_free_icons::Icon;
 use dioxus_free_icons::IconShape;
-use dioxus_free_icons::icons::ld_icons::{LdCalendar, LdHome, LdListTodo, LdMail, LdUsers};
+use dioxus_free_icons::icons::ld_icons::{
+    LdCalendar, LdHome, LdListTodo, LdMail, LdTrash, LdUsers,
+};
 
 #[component]
 pub fn Sidebar() -> Element {
@@ -40,6 +42,11 @@ pub fn Sidebar() -> Element {
                         icon: LdMail,
                         to: Route::ListInviteView {},
                     }
+                    SidebarItem {
+                        title: "Trash",
+                        icon: LdTrash,
+                        to: Route::TrashView {},
+                    }
                 }
                 div { class: "md:hidden w-full is-drawer-close:hidden",
                     ThemeController { dropdown_top: true, id_extra: "sidebar" }
@@ -1,30 +1,45 @@
 use std::fmt::{Display, Formatter};
 
+use api::error::ApiError;
 use dioxus::CapturedError;
-use dioxus::fullstack::RequestError;
 use dioxus::prelude::*;
 use dioxus::router::FromQueryArgument;
-use serde::Deserialize;
-
-// This is an extremely dirty way to retrieve the actual error message from the server. For some reason, the Server functions return `ServerFnError::Request(RequestError::Status {message, ..})`
-// where the message is a stringified JSON of the actual error.
-// Example of the message:  {"message":"error running server function: Cannot invite owner! (details: None)","code":400,"data":{"ServerError":{"message":"Cannot invite owner!","code":400}}}
-#[derive(Deserialize, Debug)]
-struct ServerError {
-    message: String,
-    #[serde(default)]
-    data: Option<ServerFnError>,
+use form_hooks::use_form::FormState;
+use serde::de::DeserializeOwned;
+
+/// Returns the [`ApiError`] of a failed server function call.
+///
+/// Errors of loaders that already failed during server side rendering only keep their message
+/// after hydration. In this case `None` is returned.
+#[must_use]
+pub fn api_error_from_captured_error(error: &CapturedError) -> Option<ApiError> {
+    if let Some(err) = error.downcast_ref::<ApiError>() {
+        return Some(err.clone());
+    }
+    if let Some(err) = error.downcast_ref::<ServerFnError>() {
+        return Some(ApiError::from(err.clone()));
+    }
+    error
+        .downcast_ref::<HttpError>()
+        .map(|err| ApiError::from(err.clone()))
+}
+
+/// Returns the current state of the object if an update failed with an [`ApiError::EditConflict`],
+/// i.e. someone else changed it since it was loaded.
+#[must_use]
+pub fn edit_conflict_from_captured_error<T: DeserializeOwned>(error: &CapturedError) -> Option<T> {
+    api_error_from_captured_error(error)?.current_state()
 }
 
 /// Converts a captured error into a user-friendly message.
-/// It checks for specific error types and returns appropriate messages based on the error content.
-/// If the error type is not recognized, it returns a generic error message.
+/// For errors returned by server functions this is the message of the [`ApiError`].
 ///
 /// # Example
 /// ```
-/// # use dioxus::{prelude::ServerFnError, CapturedError};
+/// # use api::error::ApiError;
+/// # use dioxus::CapturedError;
 /// # use roommates::message_from_captured_error;
-/// # let error: CapturedError = anyhow::anyhow!(ServerFnError::ServerError { message: "Internal Server Error".to_string(), code: 500, details: None }).into();
+/// # let error: CapturedError = ApiError::internal("Internal Server Error").into();
 /// let message = message_from_captured_error(&error);
 /// // This message can be used to display a user-friendly error message in the UI.
 /// assert_eq!(
@@ -34,36 +49,30 @@ struct ServerError {
 /// ```
 #[must_use]
 pub fn message_from_captured_error(error: &CapturedError) -> String {
-    if let Some(err) = error.downcast_ref::<ServerFnError>() {
-        return match err {
-            ServerFnError::ServerError { message, .. } => message.clone(),
-            ServerFnError::Request(RequestError::Status(message, _)) => serde_json::from_str::<
-                ServerError,
-            >(message)
-            .map(|server_error| {
-                if let Some(ServerFnError::ServerError { message, .. }) = server_error.data {
-                    message
-                } else {
-                    server_error.message
-                }
-            })
-            .unwrap_or(message.clone()),
-            _ => "An unknown error occurred".to_string(),
-        };
+    if let Some(err) = api_error_from_captured_error(error) {
+        return err.message().to_string();
     }
 
     if let Some(err) = error.downcast_ref::<StatusCode>() {
         return format!("An error occurred with status code: {err}");
     }
 
-    if let Some(err) = error.downcast_ref::<HttpError>() {
-        return err.message.clone().unwrap_or(format!(
-            "An error occurred with status code: {}",
-            err.status
-        ));
-    }
+    error.to_string()
+}
 
-    "An unknown error occurred".to_string()
+/// Shows the field errors of an [`ApiError::Validation`] on the matching fields of the form.
+///
+/// Returns the messages which could not be assigned to a field and should be shown for the
+/// whole form instead.
+pub fn apply_field_errors(form: &mut FormState, error: &CapturedError) -> Vec<String> {
+    match api_error_from_captured_error(error) {
+        Some(ApiError::Validation { fields, .. }) if !fields.is_empty() => fields
+            .into_iter()
+            .filter(|field| !form.add_field_error(&field.field, field.message.clone()))
+            .map(|field| field.message)
+            .collect(),
+        _ => vec![message_from_captured_error(error)],
+    }
 }
 
 /// The number of days since the first weekday of current date
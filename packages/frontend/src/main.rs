@@ -5,7 +5,8 @@ use crate::views::event_views::DateQueryParam;
 use dioxus::prelude::*;
 use roommates::OptionalIntQueryParam;
 use views::{
-    Home, LoginPage, NotFound, Profile, SignupView,
+    ForgotPasswordView, Home, LoginPage, NotFound, Profile, ResetPasswordView, SignupView,
+    TrashView, VerifyEmailView,
     event_views::{AddEventView, EditEventView, EventCalendarView, ListEventView},
     groups::{EditGroup, GroupView},
     invitation_views::{ListInviteView, SendInvite},
@@ -16,6 +17,7 @@ mod components;
 mod hooks;
 mod layouts;
 mod views;
+mod webauthn;
 
 const TAILWIND_CSS: Asset = asset!("/assets/dist/tailwind.css");
 const FAVICON: Asset = asset!("/assets/favicon.ico");
@@ -32,6 +34,12 @@ enum Route {
         LoginPage {},
         #[route("/signup")]
         SignupView {},
+        #[route("/forgot-password")]
+        ForgotPasswordView {},
+        #[route("/reset-password?:token")]
+        ResetPasswordView { token: String },
+        #[route("/verify-email?:token")]
+        VerifyEmailView { token: String },
 
         #[nest("/todo")]
             #[route("/")]
@@ -72,12 +80,18 @@ enum Route {
         #[route("/profile")]
         Profile {},
 
+        #[route("/trash")]
+        TrashView {},
+
         #[route("/:..segments")]
         NotFound { segments: Vec<String> },
 }
 fn main() {
     #[cfg(feature = "server")]
-    dioxus::serve(|| async move { api::server::setup_api(App).await });
+    {
+        api::server::logging::init();
+        dioxus::serve(|| async move { api::server::setup_api(App).await });
+    }
 
     #[cfg(not(debug_assertions))]
     #[cfg(all(not(feature = "server"), any(feature = "desktop", feature = "mobile")))]
@@ -1,5 +1,6 @@
 use crate::Route;
 use crate::components::ui::card::CardTitle;
+use crate::components::ui::edit_conflict_alert::EditConflictAlert;
 use crate::components::ui::{
     card::{Card, CardActions, CardBody},
     form::checkbox::Checkbox,
@@ -14,29 +15,34 @@ use entity::event::PartialEventModel;
 use form_hooks::use_form::{use_form, use_on_submit};
 use form_hooks::use_form_field::{FormField, use_form_field};
 use form_hooks::validators;
-use roommates::OptionalIntQueryParam;
+use roommates::{OptionalIntQueryParam, edit_conflict_from_captured_error};
 use time::OffsetDateTime;
 
 #[component]
 pub fn EditEventView(event_id: i32, group_id: OptionalIntQueryParam) -> Element {
     let event = use_loader(move || async move { retrieve_event(event_id).await })?;
     let mut form_state = use_form();
-    let mut update_action: Action<(i32, PartialEventModel), entity::event::Model> =
+    let mut update_action: Action<(i32, i32, PartialEventModel), entity::event::Model> =
         use_action(update_event);
     let mut form_errors = use_signal(Vec::<String>::new);
 
     let event_clone = event();
+    // Version of the event the form values are based on
+    let mut version = use_signal(|| event_clone.version);
+    // Current event if it was changed by someone else while editing
+    let mut conflict = use_signal(|| None::<entity::event::Model>);
 
-    let title: FormField<String> = use_form_field("title", event_clone.title)
+    let mut title: FormField<String> = use_form_field("title", event_clone.title)
         .with_validator(validators::required("event needs a title"));
-    let reocurring: FormField<bool> = use_form_field("reoccurring", event_clone.reoccurring);
-    let private: FormField<bool> = use_form_field("private", event_clone.private);
-    let desc: FormField<Option<String>> = use_form_field("description", event_clone.description);
-    let loc: FormField<Option<String>> = use_form_field("location", event_clone.location);
-    let date = use_form_field("date", event_clone.date);
-    let start = use_form_field("start_time", event_clone.start_time);
-    let end = use_form_field("end_time", event_clone.end_time);
-    let weekday = use_form_field("weekday", event_clone.weekday);
+    let mut reocurring: FormField<bool> = use_form_field("reoccurring", event_clone.reoccurring);
+    let mut private: FormField<bool> = use_form_field("private", event_clone.private);
+    let mut desc: FormField<Option<String>> =
+        use_form_field("description", event_clone.description);
+    let mut loc: FormField<Option<String>> = use_form_field("location", event_clone.location);
+    let mut date = use_form_field("date", event_clone.date);
+    let mut start = use_form_field("start_time", event_clone.start_time);
+    let mut end = use_form_field("end_time", event_clone.end_time);
+    let mut weekday = use_form_field("weekday", event_clone.weekday);
 
     form_state.register_field(&title);
     form_state.register_field(&reocurring);
@@ -51,6 +57,24 @@ pub fn EditEventView(event_id: i32, group_id: OptionalIntQueryParam) -> Element
     form_state.revalidate();
     let reoccurring_value = reocurring.value;
 
+    let mut reloaded_form_state = form_state.clone();
+    let load_current = move |()| {
+        let Some(current) = conflict.take() else {
+            return;
+        };
+        title.value.set(current.title);
+        reocurring.value.set(current.reoccurring);
+        private.value.set(current.private);
+        desc.value.set(current.description);
+        loc.value.set(current.location);
+        date.value.set(current.date);
+        start.value.set(current.start_time);
+        end.value.set(current.end_time);
+        weekday.value.set(current.weekday);
+        reloaded_form_state.mark_clean();
+        version.set(current.version);
+    };
+
     let nav = use_navigator();
 
     let onsubmit = use_on_submit(&form_state, move |submit_state| async move {
@@ -59,7 +83,9 @@ pub fn EditEventView(event_id: i32, group_id: OptionalIntQueryParam) -> Element
         let reoccurring = form_data.reoccurring;
         let weekday = form_data.weekday;
 
-        update_action.call(event.read().id, form_data).await;
+        update_action
+            .call(event.read().id, version(), form_data)
+            .await;
 
         match update_action.value() {
             Some(Ok(_)) => {
@@ -80,7 +106,11 @@ pub fn EditEventView(event_id: i32, group_id: OptionalIntQueryParam) -> Element
                 }
             }
             Some(Err(error)) => {
-                form_errors.push(error.to_string());
+                if let Some(current) = edit_conflict_from_captured_error(&error) {
+                    conflict.set(Some(current));
+                } else {
+                    form_errors.push(error.to_string());
+                }
             }
             None => {
                 warn!("Error creating event. API call did not complete")
@@ -109,6 +139,17 @@ pub fn EditEventView(event_id: i32, group_id: OptionalIntQueryParam) -> Element
                                     }
                                 }
                             }
+                            if let Some(current) = conflict() {
+                                EditConflictAlert {
+                                    name: "event",
+                                    // The overwrite button submits the form again, now based on the current version
+                                    onoverwrite: move |()| {
+                                        version.set(current.version);
+                                        conflict.set(None);
+                                    },
+                                    onreload: load_current,
+                                }
+                            }
                             Input::<String> { field: title, label: "Title" }
                             Checkbox { label: "Reocurring", field: reocurring }
                             Checkbox { label: "Private", field: private }
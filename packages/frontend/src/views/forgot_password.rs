@@ -0,0 +1,115 @@
+use crate::components::ui::card::{Card, CardActions, CardBody, CardTitle};
+use crate::components::ui::form::input::Input;
+use crate::components::ui::form::submit_button::SubmitButton;
+use crate::{ICON, Route};
+use api::routes::users::EMAIL_REGEX;
+use api::routes::users::password_reset::request_password_reset;
+use dioxus::prelude::*;
+use dioxus_free_icons::Icon;
+use dioxus_free_icons::icons::ld_icons::{LdCircleCheck, LdCircleX, LdMail};
+use form_hooks::use_form::{use_form, use_on_submit};
+use form_hooks::use_form_field::use_form_field;
+use form_hooks::validators;
+use regex::Regex;
+use roommates::message_from_captured_error;
+
+#[derive(Clone, serde::Deserialize)]
+struct ForgotPasswordFormData {
+    email: String,
+}
+
+#[component]
+pub fn ForgotPasswordView() -> Element {
+    let mut request_action = use_action(request_password_reset);
+    let mut form_errors = use_signal(Vec::<String>::new);
+    let mut email_sent = use_signal(|| false);
+
+    let mut form_state = use_form();
+    let email = use_form_field("email", String::new())
+        .with_validator(validators::required("Email is required!"))
+        .with_validator(validators::pattern(
+            Regex::new(EMAIL_REGEX)?,
+            "Email must be a valid email",
+        ));
+
+    form_state.register_field(&email);
+    form_state.revalidate();
+
+    let onsubmit = use_on_submit(&form_state, move |form| async move {
+        form_errors.clear();
+        let form_data: ForgotPasswordFormData = form.parsed_values().unwrap();
+        request_action.call(form_data.email).await;
+        match request_action.value() {
+            Some(Ok(_)) => {
+                email_sent.set(true);
+            }
+            Some(Err(error)) => {
+                form_errors.push(message_from_captured_error(&error));
+            }
+            None => {
+                warn!("Requesting password reset did not finish!");
+            }
+        }
+    });
+
+    rsx! {
+        div { class: "flex flex-col justify-center items-center w-full h-[90vh]",
+            div { class: "w-full lg:w-1/2",
+                Card {
+                    CardBody { class: "items-center text-center",
+                        img { src: ICON, class: "aspect-square w-20" }
+                        CardTitle { class: "lg:mb-8", "Forgot Password" }
+
+                        if email_sent() {
+                            div {
+                                class: "alert alert-success mb-4 text-left",
+                                role: "alert",
+                                Icon { icon: LdCircleCheck }
+                                span {
+                                    "If an account with this email exists, we sent you a link to reset your password."
+                                }
+                            }
+                        } else {
+                            form { onsubmit, class: "w-full text-left",
+                                if form_errors.len() > 0 {
+                                    div {
+                                        class: "alert alert-error mb-4",
+                                        role: "alert",
+                                        Icon { icon: LdCircleX }
+                                        ul {
+                                            for error in form_errors.read().iter() {
+                                                li { key: "{error}", "{error}" }
+                                            }
+                                        }
+                                    }
+                                }
+                                Input {
+                                    field: email,
+                                    label: "Email",
+                                    r#type: "email",
+                                    icon: {
+                                        rsx! {
+                                            Icon { icon: LdMail }
+                                        }
+                                    },
+                                }
+                                CardActions {
+                                    SubmitButton {
+                                        form: form_state.clone(),
+                                        class: "w-full",
+                                        label: "Send Reset Link",
+                                        submitting_label: "Sending...",
+                                    }
+                                }
+                            }
+                        }
+                        p {
+                            "Remembered your password? "
+                            Link { to: Route::LoginPage {}, class: "link", "Log In" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
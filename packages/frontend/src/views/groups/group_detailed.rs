@@ -1,5 +1,7 @@
 use crate::Route;
+use crate::components::contexts::use_live_updates;
 use crate::components::ui::{
+    activity_feed::{ActivityFeed, ActivitySource},
     button::{Button, ButtonShape, ButtonVariant},
     card::{Card, CardActions, CardBody, CardTitle},
     dialog::{Dialog, DialogAction, DialogContent, DialogTrigger, use_dialog},
@@ -14,6 +16,7 @@ use api::routes::{
         add_user_to_group, change_group_name, delete_group, leave_group, remove_user_from_group,
         retrieve_group,
     },
+    live_updates::ChangeEvent,
     users::{EMAIL_REGEX, UserInfo},
 };
 use dioxus::prelude::*;
@@ -45,6 +48,14 @@ pub fn EditGroup(group_id: i32) -> Element {
     let update_group = move |_| {
         group.restart();
     };
+    use_live_updates(move |change| match change {
+        ChangeEvent::GroupChanged { group_id: changed } if changed == group_id => group.restart(),
+        ChangeEvent::GroupDeleted { group_id: changed } if changed == group_id => {
+            navigator().replace(Route::GroupView {});
+        }
+        ChangeEvent::Resync => group.restart(),
+        _ => {}
+    });
     let mut toaster = use_toaster();
 
     let mut change_group_name = use_action(change_group_name);
@@ -139,6 +150,10 @@ pub fn EditGroup(group_id: i32) -> Element {
                             }
                         }
                     }
+                    ActivityFeed {
+                        source: ActivitySource::Group(group_id),
+                        class: "w-full mt-2 mb-32 md:mb-0",
+                    }
                 }
                 div { class: "relative flex flex-col md:w-1/6 overflow-y-auto w-full pb-32",
                     ComplexList {
@@ -188,6 +203,7 @@ pub fn EditGroup(group_id: i32) -> Element {
                             Icon { icon: LdTrash }
                         }
                         DialogContent { title: "Do you want to delete this group?",
+                            "The group is moved to the trash. It can be restored from there until it is deleted permanently."
                             DeleteGroup { group_id }
                         }
                     }
@@ -369,7 +385,7 @@ pub fn DeleteGroup(group_id: i32) -> Element {
                                 }),
                             );
                     } else {
-                        toaster.success("Deleted group successfully!", ToastOptions::new());
+                        toaster.success("Moved group to the trash!", ToastOptions::new());
                         nav.push(Route::GroupView {});
                     }
                 },
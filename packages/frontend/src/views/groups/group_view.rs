@@ -1,4 +1,5 @@
 use crate::Route;
+use crate::components::contexts::use_live_updates;
 use crate::components::ui::{
     button::{Button, ButtonShape, ButtonVariant},
     dialog::{Dialog, DialogAction, DialogContent, DialogTrigger, use_dialog},
@@ -7,6 +8,7 @@ use crate::components::ui::{
     toaster::{ToastOptions, use_toaster},
 };
 use api::routes::groups::{create_group, list_groups};
+use api::routes::live_updates::ChangeEvent;
 use dioxus::prelude::*;
 use dioxus_free_icons::{
     Icon,
@@ -26,7 +28,17 @@ struct NewGroupName {
 
 #[component]
 pub fn GroupView() -> Element {
-    let groups = use_server_future(move || async move { list_groups().await })?;
+    let mut groups = use_server_future(move || async move { list_groups().await })?;
+    use_live_updates(move |change| {
+        if matches!(
+            change,
+            ChangeEvent::GroupChanged { .. }
+                | ChangeEvent::GroupDeleted { .. }
+                | ChangeEvent::Resync
+        ) {
+            groups.restart();
+        }
+    });
 
     rsx! {
         div {
@@ -1,17 +1,18 @@
 use crate::Route;
-use crate::components::contexts::{AuthGuard, AuthState};
+use crate::components::contexts::{AuthGuard, AuthState, use_live_updates};
 use crate::components::ui::button::{Button, ButtonShape, ButtonVariant};
 use crate::components::ui::calendar_small::CalendarDashview;
 use crate::components::ui::list::{ComplexListDetails, List, ListDetails, ListRow};
 use crate::components::ui::toaster::{ToastOptions, use_toaster};
 use api::routes::events::list_events;
+use api::routes::live_updates::ChangeEvent;
 use api::routes::todos::list_todos;
 use api::routes::todos::update_todo;
 use dioxus::prelude::*;
 use dioxus_free_icons::Icon;
 use dioxus_free_icons::icons::ld_icons::{LdCircle, LdCircleCheckBig};
 use entity::todo::UpdateToDo;
-use roommates::message_from_captured_error;
+use roommates::{edit_conflict_from_captured_error, message_from_captured_error};
 
 #[component]
 pub fn Home() -> Element {
@@ -46,10 +47,22 @@ fn Dashboard() -> Element {
 
     let mut todos = use_loader(move || async move { list_todos(Some(false), Some(true)).await })?;
 
-    let on_todo_update = move |id| {
+    let mut on_todo_update = move |id| {
         todos.write().retain(|list| list.id != id);
     };
 
+    use_live_updates(move |change| match change {
+        ChangeEvent::EventChanged { .. } | ChangeEvent::EventDeleted { .. } => events.restart(),
+        ChangeEvent::TodoSaved(todo) if todo.completed => on_todo_update(todo.id),
+        ChangeEvent::TodoDeleted { todo_id, .. } => on_todo_update(todo_id),
+        ChangeEvent::TodoSaved(_) | ChangeEvent::TodoListChanged { .. } => todos.restart(),
+        ChangeEvent::Resync => {
+            events.restart();
+            todos.restart();
+        }
+        _ => {}
+    });
+
     rsx! {
         div {
             h1 { class: "text-3xl font-bold",
@@ -80,6 +93,7 @@ fn Dashboard() -> Element {
                                     key: "{todo.id}",
                                     todo: todo.clone(),
                                     onupdate: on_todo_update,
+                                    onconflict: move |()| todos.restart(),
                                 }
                             }
                         }
@@ -94,12 +108,14 @@ fn Dashboard() -> Element {
 fn DashboardTodoEntry(
     todo: entity::todo::TodoWithPermission,
     onupdate: EventHandler<i32>,
+    onconflict: EventHandler<()>,
 ) -> Element {
     let mut toaster = use_toaster();
 
     let mut update_completed = use_action(move |completed| async move {
         update_todo(
             todo.id,
+            todo.version,
             UpdateToDo {
                 completed: Some(completed),
                 ..Default::default()
@@ -120,6 +136,19 @@ fn DashboardTodoEntry(
                             toaster.success("Todo completed!", ToastOptions::new());
                             onupdate.call(todo.peek().id);
                         }
+                        Some(Err(error))
+                            if edit_conflict_from_captured_error::<entity::todo::Model>(&error)
+                                .is_some() =>
+                        {
+                            toaster
+                                .warning(
+                                    "Todo was changed by someone else",
+                                    ToastOptions::new().description(rsx! {
+                                        span { "The task was reloaded, please try again." }
+                                    }),
+                                );
+                            onconflict.call(());
+                        }
                         Some(Err(error)) => {
                             toaster
                                 .error(
@@ -1,9 +1,11 @@
+use crate::components::contexts::use_live_updates;
 use crate::components::tooltip::Tooltip;
 use crate::components::ui::button::{Button, ButtonVariant};
 use crate::components::ui::list::{ComplexListDetails, List, ListRow};
 use crate::components::ui::toaster::{ToastOptions, use_toaster};
 use api::routes::events::invitations::{accept_invite, decline_invite, list_received_invites};
 use api::routes::events::retrieve_event;
+use api::routes::live_updates::ChangeEvent;
 use api::routes::todo_list::invite::{
     accept_todo_list_invite, decline_todo_list_invite, list_todo_invites,
 };
@@ -31,6 +33,16 @@ pub fn ListInviteView() -> Element {
             .retain(|invite| invite.todo_list_id != todo_id && invite.receiving_user_id != user_id);
     };
 
+    use_live_updates(move |change| {
+        if matches!(
+            change,
+            ChangeEvent::InvitationsChanged | ChangeEvent::Resync
+        ) {
+            invites.restart();
+            todo_invites.restart();
+        }
+    });
+
     rsx! {
 
         List { header: "Your Inbox",
@@ -2,15 +2,21 @@ use crate::components::contexts::{use_app_config, use_auth};
 use crate::components::ui::card::{Card, CardActions, CardBody, CardTitle};
 use crate::components::ui::form::input::Input;
 use crate::components::ui::form::submit_button::SubmitButton;
-use crate::{ICON, Route};
-use api::routes::users::{EMAIL_REGEX, get_me, login};
+use crate::components::ui::toaster::{ToastOptions, use_toaster};
+use crate::{ICON, Route, webauthn};
+use api::error::ApiError;
+use api::routes::users::email_verification::resend_verification_email;
+use api::routes::users::passkeys::{finish_passkey_login, start_passkey_login};
+use api::routes::users::totp::complete_totp_login;
+use api::routes::users::{EMAIL_REGEX, LoginResponse, get_me, login};
 use dioxus::prelude::*;
 use dioxus_free_icons::Icon;
-use dioxus_free_icons::icons::ld_icons::{LdCircleX, LdKey, LdMail};
+use dioxus_free_icons::icons::ld_icons::{LdCircleX, LdFingerprint, LdKey, LdMail, LdShieldCheck};
 use form_hooks::use_form::{use_form, use_on_submit};
 use form_hooks::use_form_field::use_form_field;
 use form_hooks::validators;
 use regex::Regex;
+use roommates::{api_error_from_captured_error, message_from_captured_error};
 use serde::Deserialize;
 
 #[derive(Clone, Deserialize)]
@@ -25,7 +31,12 @@ pub fn LoginPage() -> Element {
 
     let mut login_action = use_action(login);
     let mut get_me = use_action(get_me);
+    let mut resend_action = use_action(resend_verification_email);
+    let mut passkey_start_action = use_action(start_passkey_login);
+    let mut passkey_finish_action = use_action(finish_passkey_login);
+    let mut toaster = use_toaster();
     let mut form_errors = use_signal(Vec::<String>::new);
+    let mut totp_challenge = use_signal(|| None::<String>);
 
     let mut auth_state = use_auth();
 
@@ -51,12 +62,16 @@ pub fn LoginPage() -> Element {
 
     form_state.revalidate();
 
-    let onsubmit = use_on_submit(&form_state, move |form| async move {
+    let onsubmit = use_on_submit(&form_state, move |mut form| async move {
         form_errors.set(Vec::new());
         let form_data: LoginFormData = form.parsed_values().unwrap();
         login_action.call(form_data.email, form_data.password).await;
         match login_action.value() {
-            Some(Ok(_)) => {
+            Some(Ok(response)) => {
+                if let LoginResponse::TotpRequired { challenge } = response.peek().clone() {
+                    totp_challenge.set(Some(challenge));
+                    return;
+                }
                 get_me.call().await;
                 match get_me.value() {
                     Some(Ok(_fetched_user)) => {
@@ -71,6 +86,13 @@ pub fn LoginPage() -> Element {
             }
             Some(Err(error)) => {
                 debug!("Failed to log in with error {:?}", error);
+                if let Some(ApiError::Unauthorized { message }) =
+                    api_error_from_captured_error(&error)
+                {
+                    form.add_field_error("password", message);
+                } else {
+                    form_errors.push(message_from_captured_error(&error));
+                }
             }
             None => {
                 debug!("No value present!");
@@ -78,6 +100,68 @@ pub fn LoginPage() -> Element {
         }
     });
 
+    let on_passkey_login = move |_| async move {
+        form_errors.set(Vec::new());
+        passkey_start_action.call().await;
+        let options = match passkey_start_action.value() {
+            Some(Ok(options)) => options.peek().clone(),
+            Some(Err(error)) => {
+                form_errors.push(message_from_captured_error(&error));
+                return;
+            }
+            None => {
+                debug!("No value present!");
+                return;
+            }
+        };
+        let credential = match webauthn::get_passkey(options).await {
+            Ok(credential) => credential,
+            Err(message) => {
+                debug!("Passkey ceremony failed: {message}");
+                form_errors.push("Passkey login was cancelled or failed".into());
+                return;
+            }
+        };
+        passkey_finish_action.call(credential).await;
+        match passkey_finish_action.value() {
+            Some(Ok(user)) => {
+                auth_state.user.set(Some(user.peek().clone()));
+                navigator().push(Route::Home {});
+            }
+            Some(Err(error)) => {
+                form_errors.push(message_from_captured_error(&error));
+            }
+            None => {
+                debug!("No value present!");
+            }
+        }
+    };
+
+    let on_resend = move |_| async move {
+        let email_value = email.value.peek().clone();
+        if email_value.is_empty() {
+            form_errors.set(vec![
+                "Enter your email to resend the verification link".into(),
+            ]);
+            return;
+        }
+        resend_action.call(email_value).await;
+        match resend_action.value() {
+            Some(Ok(_)) => {
+                toaster.info(
+                    "If this account is unverified, we sent you a new verification link.",
+                    ToastOptions::new(),
+                );
+            }
+            Some(Err(error)) => {
+                toaster.error(&message_from_captured_error(&error), ToastOptions::new());
+            }
+            None => {
+                warn!("Resending verification email did not finish!");
+            }
+        }
+    };
+
     rsx! {
         div { class: "flex flex-col justify-center items-center w-full h-[90vh]",
             div { class: "w-full lg:w-1/2",
@@ -86,64 +170,94 @@ pub fn LoginPage() -> Element {
                         img { src: ICON, class: "aspect-square w-20" }
                         CardTitle { class: "lg:mb-8", "RoomMates" }
 
-                        form { onsubmit, class: "w-full text-left",
-                            if form_errors.len() > 0 {
-                                div {
-                                    class: "alert alert-error mb-4",
-                                    role: "alert",
-                                    Icon { icon: LdCircleX }
-                                    ul {
-                                        for error in form_errors.read().iter() {
-                                            li { key: "{error}", "{error}" }
+                        if let Some(challenge) = totp_challenge() {
+                            TotpLoginForm {
+                                challenge,
+                                oncancel: move |_| totp_challenge.set(None),
+                            }
+                        } else {
+                            form { onsubmit, class: "w-full text-left",
+                                if form_errors.len() > 0 {
+                                    div {
+                                        class: "alert alert-error mb-4",
+                                        role: "alert",
+                                        Icon { icon: LdCircleX }
+                                        ul {
+                                            for error in form_errors.read().iter() {
+                                                li { key: "{error}", "{error}" }
+                                            }
                                         }
                                     }
                                 }
-                            }
-                            Input {
-                                field: email,
-                                label: "Email",
-                                r#type: "email",
-                                icon: {
-                                    rsx! {
-                                        Icon { icon: LdMail }
-                                    }
-                                },
-                            }
-                            Input {
-                                field: password,
-                                label: "Password",
-                                r#type: "password",
-                                icon: {
-                                    rsx! {
-                                        Icon { icon: LdKey }
+                                Input {
+                                    field: email,
+                                    label: "Email",
+                                    r#type: "email",
+                                    icon: {
+                                        rsx! {
+                                            Icon { icon: LdMail }
+                                        }
+                                    },
+                                }
+                                Input {
+                                    field: password,
+                                    label: "Password",
+                                    r#type: "password",
+                                    icon: {
+                                        rsx! {
+                                            Icon { icon: LdKey }
+                                        }
+                                    },
+                                }
+                                p { class: "text-right mb-2",
+                                    Link {
+                                        to: Route::ForgotPasswordView {},
+                                        class: "link text-sm",
+                                        "Forgot your password?"
                                     }
-                                },
-                            }
-                            CardActions {
-                                SubmitButton {
-                                    form: form_state.clone(),
-                                    class: "w-full",
-                                    label: "Login",
-                                    submitting_label: "Logging in...",
                                 }
-                                if app_config.oidc_enabled {
-                                    a {
-                                        href: "/api/oidc/login",
-                                        class: "btn btn-primary grow w-full",
-                                        if let Some(name) = app_config.oidc_provider_name {
-                                            "Login with {name}"
-                                        } else {
-                                            "Login using SSO"
+                                if app_config.email_verification_required {
+                                    p { class: "text-right mb-2",
+                                        button {
+                                            r#type: "button",
+                                            class: "link text-sm",
+                                            disabled: resend_action.pending(),
+                                            onclick: on_resend,
+                                            "Resend verification email"
                                         }
                                     }
                                 }
-                                if !app_config.signup_enabled {
-                                    p {
-                                        "Dont have an account? "
-                                        Link {
-                                            to: Route::SignupView {},
-                                            class: "link",
-                                            "Sign Up"
+                                CardActions {
+                                    SubmitButton {
+                                        form: form_state.clone(),
+                                        class: "w-full",
+                                        label: "Login",
+                                        submitting_label: "Logging in...",
+                                    }
+                                    button {
+                                        r#type: "button",
+                                        class: "btn btn-primary btn-outline grow w-full",
+                                        disabled: passkey_start_action.pending() || passkey_finish_action.pending(),
+                                        onclick: on_passkey_login,
+                                        Icon { icon: LdFingerprint }
+                                        "Login with Passkey"
+                                    }
+                                    for provider in app_config.oidc_providers.iter() {
+                                        a {
+                                            key: "{provider.id}",
+                                            href: "{provider.login_url}",
+                                            class: "btn btn-primary grow w-full",
+                                            "Login with {provider.name}"
+                                        }
+                                    }
+                                    if !app_config.signup_enabled {
+                                        p {
+                                            "Dont have an account? "
+                                            Link {
+                                                to: Route::SignupView {},
+                                                class: "link",
+                                                "Sign Up"
+                                            }
                                         }
                                     }
                                 }
@@ -155,3 +269,91 @@ pub fn LoginPage() -> Element {
         }
     }
 }
+
+#[derive(Clone, Deserialize)]
+struct TotpFormData {
+    code: String,
+}
+
+/// Second login step for users with two-factor authentication
+#[component]
+fn TotpLoginForm(challenge: String, oncancel: EventHandler<()>) -> Element {
+    let mut auth_state = use_auth();
+    let mut totp_action = use_action(complete_totp_login);
+    let mut form_errors = use_signal(Vec::<String>::new);
+
+    let mut form_state = use_form();
+    let code = use_form_field("code", String::new())
+        .with_validator(validators::required("Code is required!"));
+    form_state.register_field(&code);
+    form_state.revalidate();
+
+    let onsubmit = use_on_submit(&form_state, move |mut form| {
+        let challenge = challenge.clone();
+        async move {
+            form_errors.set(Vec::new());
+            let form_data: TotpFormData = form.parsed_values().unwrap();
+            totp_action.call(challenge, form_data.code).await;
+            match totp_action.value() {
+                Some(Ok(user)) => {
+                    auth_state.user.set(Some(user.peek().clone()));
+                    navigator().push(Route::Home {});
+                }
+                Some(Err(error)) => {
+                    if let Some(ApiError::Unauthorized { message }) =
+                        api_error_from_captured_error(&error)
+                    {
+                        form.add_field_error("code", message);
+                    } else {
+                        form_errors.push(message_from_captured_error(&error));
+                    }
+                }
+                None => {
+                    debug!("No value present!");
+                }
+            }
+        }
+    });
+
+    rsx! {
+        form { onsubmit, class: "w-full text-left",
+            if form_errors.len() > 0 {
+                div { class: "alert alert-error mb-4", role: "alert",
+                    Icon { icon: LdCircleX }
+                    ul {
+                        for error in form_errors.read().iter() {
+                            li { key: "{error}", "{error}" }
+                        }
+                    }
+                }
+            }
+            p { class: "mb-2",
+                "Enter the code from your authenticator app or one of your recovery codes."
+            }
+            Input {
+                field: code,
+                label: "Authentication Code",
+                r#type: "text",
+                icon: {
+                    rsx! {
+                        Icon { icon: LdShieldCheck }
+                    }
+                },
+            }
+            CardActions {
+                SubmitButton {
+                    form: form_state.clone(),
+                    class: "w-full",
+                    label: "Verify",
+                    submitting_label: "Verifying...",
+                }
+                button {
+                    r#type: "button",
+                    class: "btn btn-ghost w-full",
+                    onclick: move |_| oncancel.call(()),
+                    "Back"
+                }
+            }
+        }
+    }
+}
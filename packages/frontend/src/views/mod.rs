@@ -4,6 +4,12 @@ mod not_found;
 pub use not_found::NotFound;
 mod login;
 pub use login::LoginPage;
+mod forgot_password;
+pub use forgot_password::ForgotPasswordView;
+mod reset_password;
+pub use reset_password::ResetPasswordView;
+mod verify_email;
+pub use verify_email::VerifyEmailView;
 pub mod groups;
 mod sign_up;
 pub mod todo;
@@ -12,3 +18,5 @@ pub mod event_views;
 mod profile;
 pub use profile::Profile;
 pub mod invitation_views;
+mod trash;
+pub use trash::TrashView;
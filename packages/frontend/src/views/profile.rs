@@ -1,19 +1,42 @@
+use crate::components::contexts::{use_app_config, use_auth};
 use crate::components::ui::button;
 use crate::components::ui::card::{Card, CardActions, CardTitle};
 use crate::components::ui::fieldset::Fieldset;
+use crate::components::ui::form::checkbox::Checkbox;
 use crate::components::ui::form::input::Input;
 use crate::components::ui::form::submit_button::SubmitButton;
+use crate::components::ui::form::vectorselect::VectorSelect;
+use crate::components::ui::list::{List, ListRow};
 use crate::components::ui::toaster::{ToastOptions, use_toaster};
-use crate::{Route, components::ui::button::Button};
+use crate::{Route, components::ui::button::Button, webauthn};
+use api::error::ApiError;
 use api::routes::users::EMAIL_REGEX;
+use api::routes::users::account_deletion::{
+    EventOutcome, GroupOutcome, TodoListOutcome, delete_account, preview_account_deletion,
+};
+use api::routes::users::api_tokens::{
+    ApiScope, MAX_API_TOKEN_LIFETIME_DAYS, create_api_token, list_api_tokens, revoke_api_token,
+};
+use api::routes::users::email_verification::resend_verification_email;
+use api::routes::users::export::ExportFormat;
 use api::routes::users::get_me;
+use api::routes::users::oidc_identities::{list_oidc_identities, unlink_oidc_identity};
+use api::routes::users::passkeys::{
+    delete_passkey, finish_passkey_registration, list_passkeys, start_passkey_registration,
+};
+use api::routes::users::totp::{
+    TotpEnrolment, confirm_totp_enrolment, disable_totp, regenerate_totp_recovery_codes,
+    start_totp_enrolment, totp_status,
+};
 use api::routes::users::{UserInfo, change_password, change_user_info};
 use dioxus::prelude::*;
 use form_hooks::use_form::{use_form, use_on_submit};
 use form_hooks::use_form_field::use_form_field;
 use form_hooks::validators;
 use regex::Regex;
+use roommates::{api_error_from_captured_error, apply_field_errors, message_from_captured_error};
 use std::rc::Rc;
+use time::macros::format_description;
 
 #[derive(Clone, serde::Deserialize)]
 struct UpdateFormData {
@@ -24,6 +47,7 @@ struct UpdateFormData {
 
 #[component]
 pub fn Profile() -> Element {
+    let app_config = use_app_config();
     let mut user = use_loader(move || async move { get_me().await })?;
     let real_user = user.read().clone();
 
@@ -32,6 +56,7 @@ pub fn Profile() -> Element {
         write_user.email = new.email;
         write_user.first_name = new.first_name;
         write_user.last_name = new.last_name;
+        write_user.email_verified = new.email_verified;
     };
     rsx! {
         div { class: "flex flex-col items-center gap-4 justify-center h-full",
@@ -58,7 +83,15 @@ pub fn Profile() -> Element {
             ListInfoDisplay { real_user, onupdate }
             if !user().is_oidc_user {
                 PasswordDisplay {}
+                TwoFactorDisplay {}
+                PasskeyDisplay {}
             }
+            if app_config.oidc_enabled() {
+                LinkedAccountsDisplay {}
+            }
+            ApiTokenDisplay {}
+            DataExportDisplay {}
+            DeleteAccountDisplay { is_oidc_user: user().is_oidc_user }
         }
     }
 }
@@ -82,19 +115,55 @@ pub fn ListInfoDisplay(real_user: UserInfo, onupdate: EventHandler<UserInfo>) ->
 
     form_state.revalidate();
     let mut toaster = use_toaster();
+    let mut resend_action = use_action(resend_verification_email);
+    let current_email = real_user.email.clone();
+
+    let on_resend = move |_| {
+        let current_email = current_email.clone();
+        async move {
+            resend_action.call(current_email).await;
+            match resend_action.value() {
+                Some(Ok(_)) => {
+                    toaster.info("Verification link sent!", ToastOptions::new());
+                }
+                Some(Err(_)) => {
+                    toaster.error("Failed to send verification link!", ToastOptions::new());
+                }
+                None => {
+                    warn!("Error resending verification email")
+                }
+            }
+        }
+    };
 
     let onsubmit = use_on_submit(&form_state, move |mut form| async move {
         let form_data: UpdateFormData = form.parsed_values().unwrap();
+        let requested_email = form_data.email.trim().to_lowercase();
         update_action.call(form_data).await;
 
         match update_action.value() {
             Some(Ok(new_user)) => {
-                onupdate.call(new_user.read().clone());
+                let new_user = new_user.read().clone();
+                let email_pending = new_user.email != requested_email;
+                onupdate.call(new_user);
                 toaster.success("Successfully changed user info!", ToastOptions::new());
+                if email_pending {
+                    toaster.info(
+                        &format!("We sent a verification link to {requested_email}"),
+                        ToastOptions::new(),
+                    );
+                }
                 form.mark_clean();
             }
-            Some(Err(_)) => {
-                toaster.error("Failed to update user info!", ToastOptions::new());
+            Some(Err(error)) => {
+                if let Some(ApiError::Conflict { message }) = api_error_from_captured_error(&error)
+                {
+                    form.add_field_error("email", message);
+                } else {
+                    for message in apply_field_errors(&mut form, &error) {
+                        toaster.error(&message, ToastOptions::new());
+                    }
+                }
             }
             None => {
                 warn!("Error changing user info")
@@ -123,7 +192,19 @@ pub fn ListInfoDisplay(real_user: UserInfo, onupdate: EventHandler<UserInfo>) ->
                 }
 
                 Fieldset {
-                    p { "Email: {real_user.email}" }
+                    p {
+                        "Email: {real_user.email} "
+                        if !real_user.email_verified {
+                            span { class: "badge badge-warning badge-sm", "Not verified" }
+                            button {
+                                r#type: "button",
+                                class: "link text-sm ml-2",
+                                disabled: resend_action.pending(),
+                                onclick: on_resend,
+                                "Resend verification link"
+                            }
+                        }
+                    }
                     Input {
                         label: "Set Email",
                         field: email,
@@ -212,3 +293,689 @@ pub fn PasswordDisplay() -> Element {
         }
     }
 }
+
+#[component]
+pub fn TwoFactorDisplay() -> Element {
+    let mut status = use_loader(move || async move { totp_status().await })?;
+    let mut enrolment = use_signal(|| None::<TotpEnrolment>);
+    let mut recovery_codes = use_signal(Vec::<String>::new);
+    let mut toaster = use_toaster();
+
+    let mut start_action = use_action(start_totp_enrolment);
+    let mut confirm_action = use_action(confirm_totp_enrolment);
+    let mut regenerate_action = use_action(regenerate_totp_recovery_codes);
+    let mut disable_action = use_action(disable_totp);
+
+    let mut code_state = use_form();
+    let totp_code = use_form_field("code", String::new()).with_validator(validators::required(
+        "Enter a code from your authenticator app",
+    ));
+    code_state.register_field(&totp_code);
+    code_state.revalidate();
+
+    let mut password_state = use_form();
+    let password = use_form_field("password", String::new())
+        .with_validator(validators::required("Enter your password"));
+    password_state.register_field(&password);
+    password_state.revalidate();
+
+    let on_start = move |_| async move {
+        start_action.call().await;
+        match start_action.value() {
+            Some(Ok(new_enrolment)) => {
+                enrolment.set(Some(new_enrolment.peek().clone()));
+            }
+            Some(Err(_)) => {
+                toaster.error("Failed to start two-factor setup!", ToastOptions::new());
+            }
+            None => {
+                warn!("Request did not finish!");
+            }
+        }
+    };
+
+    let on_code_submit = use_on_submit(&code_state, move |mut code_state| async move {
+        let code_value = totp_code.value.peek().clone();
+        let enabled = status.read().enabled;
+
+        if enabled {
+            regenerate_action.call(code_value).await;
+        } else {
+            confirm_action.call(code_value).await;
+        }
+        let result = if enabled {
+            regenerate_action.value()
+        } else {
+            confirm_action.value()
+        };
+        match result {
+            Some(Ok(codes)) => {
+                recovery_codes.set(codes.peek().clone());
+                enrolment.set(None);
+                let mut status = status.write();
+                status.enabled = true;
+                status.remaining_recovery_codes = codes.peek().len() as u64;
+                toaster.success("Saved new recovery codes!", ToastOptions::new());
+                code_state.reset();
+            }
+            Some(Err(error)) => {
+                for message in apply_field_errors(&mut code_state, &error) {
+                    toaster.error(&message, ToastOptions::new());
+                }
+            }
+            None => {
+                warn!("Request did not finish!");
+            }
+        }
+    });
+
+    let on_disable = use_on_submit(&password_state, move |mut password_state| async move {
+        let password_value = password.value.peek().clone();
+
+        disable_action.call(password_value).await;
+        match disable_action.value() {
+            Some(Ok(_)) => {
+                let mut status = status.write();
+                status.enabled = false;
+                status.remaining_recovery_codes = 0;
+                recovery_codes.clear();
+                toaster.success("Disabled two-factor authentication!", ToastOptions::new());
+                password_state.reset();
+            }
+            Some(Err(_)) => {
+                toaster.error(
+                    "Failed to disable two-factor authentication!",
+                    ToastOptions::new(),
+                );
+            }
+            None => {
+                warn!("Request did not finish!");
+            }
+        }
+    });
+
+    let code_label = if status().enabled {
+        "Regenerate Recovery Codes"
+    } else {
+        "Enable Two-Factor Authentication"
+    };
+
+    rsx! {
+        Card {
+            Fieldset {
+                p { "Two-factor authentication" }
+                if status().enabled {
+                    p { class: "text-sm",
+                        "Enabled. {status().remaining_recovery_codes} recovery codes left."
+                    }
+                } else if let Some(enrolment) = enrolment() {
+                    p { class: "text-sm",
+                        "Scan the QR code with your authenticator app or enter the secret manually, then confirm with a code from the app."
+                    }
+                    img {
+                        class: "w-48 aspect-square bg-white p-2 rounded",
+                        src: "data:image/png;base64,{enrolment.qr_code}",
+                        alt: "{enrolment.otpauth_url}",
+                    }
+                    code { class: "break-all", "{enrolment.secret}" }
+                } else {
+                    p { class: "text-sm", "Protect your account with codes from an authenticator app." }
+                    button {
+                        r#type: "button",
+                        class: "btn btn-primary w-fit",
+                        disabled: start_action.pending(),
+                        onclick: on_start,
+                        "Set Up Two-Factor Authentication"
+                    }
+                }
+                if !recovery_codes.read().is_empty() {
+                    div { class: "alert alert-warning", role: "alert",
+                        div {
+                            p { "Store these recovery codes somewhere safe. Each can be used once if you lose your authenticator." }
+                            ul { class: "font-mono",
+                                for recovery_code in recovery_codes.read().iter() {
+                                    li { key: "{recovery_code}", "{recovery_code}" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if status().enabled || enrolment.read().is_some() {
+                form { onsubmit: on_code_submit,
+                    Fieldset {
+                        Input {
+                            field: totp_code,
+                            label: "Authentication Code",
+                            r#type: "text",
+                        }
+                    }
+                    CardActions {
+                        SubmitButton {
+                            form: code_state.clone(),
+                            label: code_label,
+                        }
+                    }
+                }
+            }
+            if status().enabled {
+                form { onsubmit: on_disable,
+                    Fieldset {
+                        Input {
+                            field: password,
+                            label: "Current Password",
+                            r#type: "password",
+                        }
+                    }
+                    CardActions {
+                        SubmitButton {
+                            form: password_state.clone(),
+                            label: "Disable Two-Factor Authentication",
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn PasskeyDisplay() -> Element {
+    let mut passkeys = use_loader(move || async move { list_passkeys().await })?;
+    let mut toaster = use_toaster();
+
+    let mut start_action = use_action(start_passkey_registration);
+    let mut finish_action = use_action(finish_passkey_registration);
+    let mut delete_action = use_action(delete_passkey);
+
+    let mut passkey_state = use_form();
+    let name = use_form_field("name", String::new()).with_validator(validators::required(
+        "Name your passkey, e.g. after its device",
+    ));
+    passkey_state.register_field(&name);
+    passkey_state.revalidate();
+
+    let onsubmit = use_on_submit(&passkey_state, move |mut passkey_state| async move {
+        let name_value = name.value.peek().clone();
+
+        start_action.call().await;
+        let options = match start_action.value() {
+            Some(Ok(options)) => options.peek().clone(),
+            Some(Err(_)) => {
+                toaster.error("Failed to start passkey registration!", ToastOptions::new());
+                return;
+            }
+            None => {
+                warn!("Request did not finish!");
+                return;
+            }
+        };
+        let credential = match webauthn::create_passkey(options).await {
+            Ok(credential) => credential,
+            Err(message) => {
+                debug!("Passkey ceremony failed: {message}");
+                toaster.error("Passkey creation was cancelled!", ToastOptions::new());
+                return;
+            }
+        };
+
+        finish_action.call(name_value, credential).await;
+        match finish_action.value() {
+            Some(Ok(passkey)) => {
+                passkeys.write().push(passkey.peek().clone());
+                toaster.success("Added passkey!", ToastOptions::new());
+                passkey_state.reset();
+            }
+            Some(Err(error)) => {
+                if !apply_field_errors(&mut passkey_state, &error).is_empty() {
+                    toaster.error("Failed to add passkey!", ToastOptions::new());
+                }
+            }
+            None => {
+                warn!("Request did not finish!");
+            }
+        }
+    });
+
+    let on_delete = move |passkey_id: i32| async move {
+        delete_action.call(passkey_id).await;
+        match delete_action.value() {
+            Some(Ok(_)) => {
+                passkeys.write().retain(|passkey| passkey.id != passkey_id);
+                toaster.success("Removed passkey!", ToastOptions::new());
+            }
+            Some(Err(_)) => {
+                toaster.error("Failed to remove passkey!", ToastOptions::new());
+            }
+            None => {
+                warn!("Request did not finish!");
+            }
+        }
+    };
+
+    rsx! {
+        Card {
+            Fieldset {
+                p { "Passkeys" }
+                List { header: "Log in without a password using your device",
+                    for passkey in passkeys() {
+                        ListRow { key: "{passkey.id}",
+                            div {
+                                div { "{passkey.name}" }
+                                div { class: "text-xs opacity-60",
+                                    "Added on "
+                                    {
+                                        passkey
+                                            .created_at
+                                            .format(format_description!("[day].[month].[year]"))
+                                            .unwrap_or_default()
+                                    }
+                                }
+                            }
+                            button {
+                                r#type: "button",
+                                class: "btn btn-ghost btn-sm text-error",
+                                disabled: delete_action.pending(),
+                                onclick: move |_| on_delete(passkey.id),
+                                "Remove"
+                            }
+                        }
+                    }
+                }
+            }
+            form { onsubmit,
+                Fieldset {
+                    Input { field: name, label: "Passkey Name", r#type: "text" }
+                }
+                CardActions {
+                    SubmitButton {
+                        form: passkey_state.clone(),
+                        label: "Add Passkey",
+                        submitting_label: "Waiting for authenticator...",
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn DataExportDisplay() -> Element {
+    rsx! {
+        Card {
+            Fieldset {
+                p { "Your Data" }
+                p { class: "text-sm opacity-60",
+                    "Download a copy of your profile, events, groups, To-Do lists, invitations and sessions. The ZIP archive additionally contains your events as calendar file and your tasks as spreadsheet."
+                }
+            }
+            CardActions {
+                a {
+                    href: ExportFormat::Json.url(),
+                    download: "",
+                    class: "btn btn-secondary",
+                    "Download JSON"
+                }
+                a {
+                    href: ExportFormat::Zip.url(),
+                    download: "",
+                    class: "btn btn-primary",
+                    "Download ZIP"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn DeleteAccountDisplay(is_oidc_user: bool) -> Element {
+    let plan = use_loader(move || async move { preview_account_deletion().await })?;
+    let mut auth_state = use_auth();
+    let nav = navigator();
+    let mut toaster = use_toaster();
+    let mut delete_action = use_action(delete_account);
+
+    let mut delete_state = use_form();
+    let confirmation_email = use_form_field("confirmation_email", String::new())
+        .with_validator(validators::required("Enter your email address"));
+    let password = use_form_field("password", String::new())
+        .with_validator(validators::required("Enter your password"));
+    delete_state.register_field(&confirmation_email);
+    if !is_oidc_user {
+        delete_state.register_field(&password);
+    }
+    delete_state.revalidate();
+
+    let on_delete = use_on_submit(&delete_state, move |mut delete_state| async move {
+        let password_value = (!is_oidc_user).then(|| password.value.peek().clone());
+        let email_value = confirmation_email.value.peek().clone();
+
+        delete_action.call(password_value, email_value).await;
+        match delete_action.value() {
+            Some(Ok(_)) => {
+                auth_state.logout();
+                toaster.success("Deleted your account!", ToastOptions::new());
+                nav.push(Route::Home {});
+            }
+            Some(Err(error)) => {
+                for message in apply_field_errors(&mut delete_state, &error) {
+                    toaster.error(&message, ToastOptions::new());
+                }
+            }
+            None => {
+                warn!("Request did not finish!");
+            }
+        }
+    });
+
+    let plan = plan.read();
+    rsx! {
+        Card {
+            Fieldset {
+                p { "Delete Account" }
+                p { class: "text-sm opacity-60",
+                    "Deleting your account cannot be undone. Everything you share with others is handed over to them as listed below, everything else is deleted."
+                }
+                ul { class: "list-disc list-inside text-sm",
+                    for event in plan.events.iter() {
+                        li { key: "event-{event.event_id}",
+                            match &event.outcome {
+                                EventOutcome::TransferredTo(successor) => rsx! {
+                                    "Event {event.title} is handed over to {successor.first_name} {successor.last_name}"
+                                },
+                                EventOutcome::Deleted => rsx! { "Event {event.title} is deleted" },
+                            }
+                        }
+                    }
+                    for todo_list in plan.todo_lists.iter() {
+                        li { key: "todo-list-{todo_list.todo_list_id}",
+                            match &todo_list.outcome {
+                                TodoListOutcome::Kept { successor, promoted: true } => rsx! {
+                                    "{successor.first_name} {successor.last_name} becomes admin of the To-Do list {todo_list.title} and takes over your tasks"
+                                },
+                                TodoListOutcome::Kept { successor, promoted: false } => rsx! {
+                                    "{successor.first_name} {successor.last_name} takes over your tasks in the To-Do list {todo_list.title}"
+                                },
+                                TodoListOutcome::Deleted => rsx! { "To-Do list {todo_list.title} is deleted" },
+                            }
+                        }
+                    }
+                    for group in plan.groups.iter() {
+                        li { key: "group-{group.group_id}",
+                            match group.outcome {
+                                GroupOutcome::Left => rsx! { "You leave the group {group.name}" },
+                                GroupOutcome::Deleted => rsx! { "Group {group.name} is deleted" },
+                            }
+                        }
+                    }
+                }
+            }
+            form { onsubmit: on_delete,
+                Fieldset {
+                    Input {
+                        field: confirmation_email,
+                        label: "Confirm with your email address",
+                        r#type: "email",
+                    }
+                    if is_oidc_user {
+                        p { class: "text-sm opacity-60",
+                            "Log in at your identity provider again right before deleting your account."
+                        }
+                    } else {
+                        Input {
+                            field: password,
+                            label: "Current Password",
+                            r#type: "password",
+                        }
+                    }
+                }
+                CardActions {
+                    if is_oidc_user {
+                        a {
+                            href: "/api/oidc/reauthenticate",
+                            class: "btn btn-secondary",
+                            "Log in again"
+                        }
+                    }
+                    SubmitButton {
+                        form: delete_state.clone(),
+                        label: "Delete Account",
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn LinkedAccountsDisplay() -> Element {
+    let app_config = use_app_config();
+    let mut identities = use_loader(move || async move { list_oidc_identities().await })?;
+    let mut toaster = use_toaster();
+
+    let mut unlink_action = use_action(unlink_oidc_identity);
+
+    let on_unlink = move |identity_id: i32| async move {
+        unlink_action.call(identity_id).await;
+        match unlink_action.value() {
+            Some(Ok(_)) => {
+                identities
+                    .write()
+                    .retain(|identity| identity.id != identity_id);
+                toaster.success("Unlinked account!", ToastOptions::new());
+            }
+            Some(Err(error)) => {
+                toaster.error(&message_from_captured_error(&error), ToastOptions::new());
+            }
+            None => {
+                warn!("Request did not finish!");
+            }
+        }
+    };
+
+    rsx! {
+        Card {
+            Fieldset {
+                p { "Linked Accounts" }
+                List { header: "Log in with an account of your identity provider",
+                    for identity in identities() {
+                        ListRow { key: "{identity.id}",
+                            div {
+                                div { {identity.email.clone().unwrap_or(identity.issuer.clone())} }
+                                div { class: "text-xs opacity-60",
+                                    "Linked on "
+                                    {
+                                        identity
+                                            .created_at
+                                            .format(format_description!("[day].[month].[year]"))
+                                            .unwrap_or_default()
+                                    }
+                                    if let Some(last_login_at) = identity.last_login_at {
+                                        ", last login on "
+                                        {
+                                            last_login_at
+                                                .format(format_description!("[day].[month].[year]"))
+                                                .unwrap_or_default()
+                                        }
+                                    }
+                                }
+                            }
+                            button {
+                                r#type: "button",
+                                class: "btn btn-ghost btn-sm text-error",
+                                disabled: unlink_action.pending(),
+                                onclick: move |_| on_unlink(identity.id),
+                                "Unlink"
+                            }
+                        }
+                    }
+                }
+            }
+            CardActions {
+                for provider in app_config.oidc_providers.iter() {
+                    a {
+                        key: "{provider.id}",
+                        href: "{provider.link_url}",
+                        class: "btn btn-primary",
+                        "Link {provider.name} Account"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn ApiTokenDisplay() -> Element {
+    let mut tokens = use_loader(move || async move { list_api_tokens().await })?;
+    let mut toaster = use_toaster();
+    let mut created_token = use_signal(|| None::<String>);
+
+    let mut create_action = use_action(create_api_token);
+    let mut revoke_action = use_action(revoke_api_token);
+
+    let mut token_state = use_form();
+    let name = use_form_field("name", String::new()).with_validator(validators::required(
+        "Name your token, e.g. after the script using it",
+    ));
+    let scope_fields = ApiScope::ALL.map(|scope| use_form_field(scope.as_str(), false));
+    let lifetime = use_form_field("lifetime_days", 90);
+    token_state.register_field(&name);
+    for scope_field in &scope_fields {
+        token_state.register_field(scope_field);
+    }
+    token_state.register_field(&lifetime);
+    token_state.revalidate();
+
+    let scope_values = scope_fields.each_ref().map(|field| field.value);
+    let lifetime_options = vec![
+        (30, "30 days".to_string()),
+        (90, "90 days".to_string()),
+        (
+            MAX_API_TOKEN_LIFETIME_DAYS,
+            format!("{MAX_API_TOKEN_LIFETIME_DAYS} days"),
+        ),
+    ];
+
+    let onsubmit = use_on_submit(&token_state, move |mut token_state| async move {
+        let scopes: Vec<ApiScope> = ApiScope::ALL
+            .into_iter()
+            .zip(scope_values)
+            .filter(|(_, selected)| *selected.peek())
+            .map(|(scope, _)| scope)
+            .collect();
+        if scopes.is_empty() {
+            toaster.error("Select at least one scope!", ToastOptions::new());
+            return;
+        }
+
+        create_action
+            .call(name.value.peek().clone(), scopes, *lifetime.value.peek())
+            .await;
+        match create_action.value() {
+            Some(Ok(created)) => {
+                let created = created.peek().clone();
+                tokens.write().push(created.info);
+                created_token.set(Some(created.token));
+                toaster.success("Created API token!", ToastOptions::new());
+                token_state.reset();
+            }
+            Some(Err(error)) => {
+                for message in apply_field_errors(&mut token_state, &error) {
+                    toaster.error(&message, ToastOptions::new());
+                }
+            }
+            None => {
+                warn!("Request did not finish!");
+            }
+        }
+    });
+
+    let on_revoke = move |token_id: i32| async move {
+        revoke_action.call(token_id).await;
+        match revoke_action.value() {
+            Some(Ok(_)) => {
+                tokens.write().retain(|token| token.id != token_id);
+                toaster.success("Revoked API token!", ToastOptions::new());
+            }
+            Some(Err(_)) => {
+                toaster.error("Failed to revoke API token!", ToastOptions::new());
+            }
+            None => {
+                warn!("Request did not finish!");
+            }
+        }
+    };
+
+    rsx! {
+        Card {
+            Fieldset {
+                p { "API Tokens" }
+                List { header: "Access your events, todos and groups from scripts",
+                    for token in tokens() {
+                        ListRow { key: "{token.id}",
+                            div {
+                                div { "{token.name} (…{token.token_hint})" }
+                                div { class: "flex flex-wrap gap-1",
+                                    for scope in token.scopes.iter() {
+                                        span { class: "badge badge-outline badge-info badge-sm", "{scope}" }
+                                    }
+                                }
+                                div { class: "text-xs opacity-60",
+                                    "Expires on "
+                                    {
+                                        token
+                                            .expires_at
+                                            .format(format_description!("[day].[month].[year]"))
+                                            .unwrap_or_default()
+                                    }
+                                    if let Some(last_used_at) = token.last_used_at {
+                                        ", last used on "
+                                        {
+                                            last_used_at
+                                                .format(format_description!("[day].[month].[year]"))
+                                                .unwrap_or_default()
+                                        }
+                                    } else {
+                                        ", never used"
+                                    }
+                                }
+                            }
+                            button {
+                                r#type: "button",
+                                class: "btn btn-ghost btn-sm text-error",
+                                disabled: revoke_action.pending(),
+                                onclick: move |_| on_revoke(token.id),
+                                "Revoke"
+                            }
+                        }
+                    }
+                }
+                if let Some(token) = created_token() {
+                    div { class: "alert alert-warning", role: "alert",
+                        div {
+                            p { "Copy your new token now. It will not be shown again." }
+                            code { class: "break-all", "{token}" }
+                        }
+                    }
+                }
+            }
+            form { onsubmit,
+                Fieldset {
+                    Input { field: name, label: "Token Name", r#type: "text" }
+                    for (scope, field) in ApiScope::ALL.into_iter().zip(scope_fields) {
+                        Checkbox { key: "{scope}", label: scope.to_string(), field }
+                    }
+                    VectorSelect {
+                        label: Some("Expires after".into()),
+                        field: lifetime.clone(),
+                        options: lifetime_options,
+                    }
+                }
+                CardActions {
+                    SubmitButton { form: token_state.clone(), label: "Create API Token" }
+                }
+            }
+        }
+    }
+}
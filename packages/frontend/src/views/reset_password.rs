@@ -0,0 +1,120 @@
+use std::rc::Rc;
+
+use crate::components::ui::card::{Card, CardActions, CardBody, CardTitle};
+use crate::components::ui::form::input::Input;
+use crate::components::ui::form::submit_button::SubmitButton;
+use crate::components::ui::toaster::{ToastOptions, use_toaster};
+use crate::{ICON, Route};
+use api::routes::users::password_reset::reset_password;
+use dioxus::prelude::*;
+use dioxus_free_icons::Icon;
+use dioxus_free_icons::icons::ld_icons::{LdCircleX, LdKeyRound};
+use form_hooks::use_form::{use_form, use_on_submit};
+use form_hooks::use_form_field::use_form_field;
+use form_hooks::validators;
+use roommates::message_from_captured_error;
+
+#[component]
+pub fn ResetPasswordView(token: String) -> Element {
+    let nav = use_navigator();
+    let mut toaster = use_toaster();
+    let mut reset_action = use_action(reset_password);
+    let mut form_errors = use_signal(Vec::<String>::new);
+
+    let mut form_state = use_form();
+    let password = use_form_field("password", String::new())
+        .with_validator(validators::required("Password is required!"));
+
+    let password_repeat_func = Rc::new(move |value: &String| {
+        let password_value = password.value.peek();
+        if *value != *password_value || value.is_empty() {
+            Err("Passwords do not match!".to_string())
+        } else {
+            Ok(())
+        }
+    });
+
+    let password_repeat = use_form_field("password_repeat", String::new())
+        .with_validator(validators::custom(password_repeat_func));
+
+    form_state.register_field(&password);
+    form_state.register_field(&password_repeat);
+    form_state.revalidate();
+
+    let onsubmit = use_on_submit(&form_state, move |_| {
+        let token = token.clone();
+        async move {
+            form_errors.clear();
+            let password_value = password.value.peek().clone();
+            reset_action.call(token, password_value).await;
+            match reset_action.value() {
+                Some(Ok(_)) => {
+                    toaster.success("Password changed! You can now log in.", ToastOptions::new());
+                    nav.push(Route::LoginPage {});
+                }
+                Some(Err(error)) => {
+                    form_errors.push(message_from_captured_error(&error));
+                }
+                None => {
+                    warn!("Resetting password did not finish!");
+                }
+            }
+        }
+    });
+
+    rsx! {
+        div { class: "flex flex-col justify-center items-center w-full h-[90vh]",
+            div { class: "w-full lg:w-1/2",
+                Card {
+                    CardBody { class: "items-center text-center",
+                        img { src: ICON, class: "aspect-square w-20" }
+                        CardTitle { class: "lg:mb-8", "Choose a New Password" }
+
+                        form { onsubmit, class: "w-full text-left",
+                            if form_errors.len() > 0 {
+                                div {
+                                    class: "alert alert-error mb-4",
+                                    role: "alert",
+                                    Icon { icon: LdCircleX }
+                                    ul {
+                                        for error in form_errors.read().iter() {
+                                            li { key: "{error}", "{error}" }
+                                        }
+                                    }
+                                }
+                            }
+                            Input {
+                                field: password,
+                                label: "New Password",
+                                r#type: "password",
+                                icon: {
+                                    rsx! {
+                                        Icon { icon: LdKeyRound }
+                                    }
+                                },
+                            }
+                            Input {
+                                field: password_repeat,
+                                label: "Repeat New Password",
+                                r#type: "password",
+                                icon: {
+                                    rsx! {
+                                        Icon { icon: LdKeyRound }
+                                    }
+                                },
+                            }
+                            CardActions {
+                                SubmitButton {
+                                    form: form_state.clone(),
+                                    class: "w-full",
+                                    label: "Reset Password",
+                                    submitting_label: "Resetting password...",
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
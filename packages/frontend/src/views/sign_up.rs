@@ -4,7 +4,9 @@ use crate::components::contexts::{AuthState, use_app_config};
 use crate::components::ui::card::{Card, CardActions, CardBody, CardTitle};
 use crate::components::ui::form::input::Input;
 use crate::components::ui::form::submit_button::SubmitButton;
+use crate::components::ui::toaster::{ToastOptions, use_toaster};
 use crate::{ICON, Route};
+use api::error::ApiError;
 use api::routes::users::{EMAIL_REGEX, sign_up};
 use dioxus::prelude::*;
 use dioxus_free_icons::Icon;
@@ -13,6 +15,7 @@ use form_hooks::use_form::{use_form, use_on_submit};
 use form_hooks::use_form_field::use_form_field;
 use form_hooks::validators;
 use regex::Regex;
+use roommates::{api_error_from_captured_error, apply_field_errors};
 
 #[derive(Clone, serde::Deserialize)]
 struct SignupFormData {
@@ -26,6 +29,7 @@ struct SignupFormData {
 pub fn SignupView() -> Element {
     let app_config = use_app_config();
     let nav = use_navigator();
+    let mut toaster = use_toaster();
 
     let mut form_errors = use_signal(Vec::<String>::new);
     let mut sign_up_action = use_action(move |form_data: SignupFormData| async move {
@@ -80,16 +84,25 @@ pub fn SignupView() -> Element {
     form_state.register_field(&password_repeat);
     form_state.revalidate();
 
-    let onsubmit = use_on_submit(&form_state, move |form_state| async move {
+    let onsubmit = use_on_submit(&form_state, move |mut form_state| async move {
         form_errors.clear();
         let form_data: SignupFormData = form_state.parsed_values().unwrap();
         sign_up_action.call(form_data).await;
         match sign_up_action.value() {
             Some(Ok(_)) => {
+                toaster.info(
+                    "Account created! Check your inbox to verify your email.",
+                    ToastOptions::new(),
+                );
                 nav.push(Route::LoginPage {});
             }
             Some(Err(error)) => {
-                form_errors.push(error.to_string());
+                if let Some(ApiError::Conflict { message }) = api_error_from_captured_error(&error)
+                {
+                    form_state.add_field_error("email", message);
+                } else {
+                    form_errors.set(apply_field_errors(&mut form_state, &error));
+                }
             }
             None => {
                 warn!("Error signing up user. API call did not complete")
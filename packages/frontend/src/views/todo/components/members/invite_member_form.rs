@@ -14,7 +14,7 @@ use entity::todo_list_invitation::InvitationPermission;
 use form_hooks::prelude::{use_form, use_form_field, use_on_submit};
 use form_hooks::validators;
 use regex::Regex;
-use roommates::message_from_captured_error;
+use roommates::apply_field_errors;
 
 #[component]
 pub fn InviteMemberForm(onmemberinvited: EventHandler<()>) -> Element {
@@ -53,8 +53,8 @@ pub fn InviteMemberForm(onmemberinvited: EventHandler<()>) -> Element {
                 onmemberinvited.call(());
             }
             Some(Err(error)) => {
-                let message = message_from_captured_error(error);
-                form_error.set(Some(message));
+                let messages = apply_field_errors(&mut form_state, error);
+                form_error.set(messages.into_iter().next());
             }
             None => {
                 warn!("Invite user request did not finish!");
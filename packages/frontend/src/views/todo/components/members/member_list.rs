@@ -1,8 +1,10 @@
 use super::{InviteMemberForm, MemberEntry};
+use crate::components::contexts::use_live_updates;
 use crate::components::ui::card::{Card, CardBody, CardTitle};
 use crate::components::ui::dialog::{Dialog, DialogContent, DialogTrigger};
 use crate::components::ui::list::{List, ListDetails, ListRow};
 use crate::views::todo::todos_group::use_todo_list;
+use api::routes::live_updates::ChangeEvent;
 use api::routes::todo_list::list_todo_list_members;
 use dioxus::prelude::*;
 use dioxus_free_icons::Icon;
@@ -51,14 +53,22 @@ pub fn MemberList() -> Element {
         timeout.action(());
     });
 
-    let onmemberchange = move || {
+    let mut onmemberchange = move || {
         members.restart();
         show_skeleton.set(false);
         timeout.action(());
     };
 
+    use_live_updates(move |change| match change {
+        ChangeEvent::TodoListChanged {
+            todo_list_id: changed,
+        } if changed == todo_list_id => onmemberchange(),
+        ChangeEvent::Resync => onmemberchange(),
+        _ => {}
+    });
+
     rsx! {
-        Card { class: "shrink-0 w-full lg:w-1/2 xl:w-1/3",
+        Card { class: "w-full",
             CardBody {
                 CardTitle { class: "flex items-center justify-between",
                     "Members"
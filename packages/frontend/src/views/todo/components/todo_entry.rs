@@ -8,7 +8,7 @@ use dioxus_free_icons::Icon;
 use dioxus_free_icons::icons::ld_icons::{LdCircle, LdCircleCheckBig, LdTrash};
 use entity::todo::UpdateToDo;
 use entity::todo_list_invitation::InvitationPermission;
-use roommates::message_from_captured_error;
+use roommates::{edit_conflict_from_captured_error, message_from_captured_error};
 
 #[component]
 pub fn TodoEntry(
@@ -25,6 +25,7 @@ pub fn TodoEntry(
     let mut update_completed = use_action(move |completed: bool| async move {
         update_todo(
             todo.id,
+            todo.version,
             UpdateToDo {
                 completed: Some(completed),
                 ..Default::default()
@@ -48,6 +49,19 @@ pub fn TodoEntry(
                             Some(Ok(_)) => {
                                 onupdate.call(());
                             }
+                            Some(Err(error))
+                                if edit_conflict_from_captured_error::<entity::todo::Model>(&error)
+                                    .is_some() =>
+                            {
+                                toaster
+                                    .warning(
+                                        &format!("{title_clone} was changed by someone else"),
+                                        ToastOptions::new().description(rsx! {
+                                            span { "The task was reloaded, please try again." }
+                                        }),
+                                    );
+                                onupdate.call(());
+                            }
                             Some(Err(error)) => {
                                 toaster
                                     .error(
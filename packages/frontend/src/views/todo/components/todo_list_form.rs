@@ -1,5 +1,6 @@
 use crate::components::ui::button::{Button, ButtonVariant};
 use crate::components::ui::card::CardTitle;
+use crate::components::ui::edit_conflict_alert::EditConflictAlert;
 use crate::components::ui::form::input::Input;
 use crate::components::ui::form::submit_button::SubmitButton;
 use crate::components::ui::form::textarea::Textarea;
@@ -12,7 +13,7 @@ use dioxus_free_icons::icons::ld_icons::{LdPen, LdX};
 use entity::todo_list::{TodoListWithPermission, UpdateTodoList};
 use form_hooks::prelude::{use_form_signal, use_on_submit};
 use form_hooks::use_form_field::use_form_field;
-use roommates::message_from_captured_error;
+use roommates::{edit_conflict_from_captured_error, message_from_captured_error};
 
 #[component]
 pub fn TodoListForm() -> Element {
@@ -23,7 +24,13 @@ pub fn TodoListForm() -> Element {
     let todo_list_id = todo_list.id;
 
     let mut show_edit = use_signal(|| false);
-    let mut edit_todo_list = use_action(move |data| update_todo_list(todo_list_id, data));
+    // Version of the list the form values are based on
+    let mut version = use_signal(|| todo_list.version);
+    // Current list if it was changed by someone else while editing
+    let mut conflict = use_signal(|| None::<entity::todo_list::Model>);
+    let mut edit_todo_list = use_action(move |version: i32, data: UpdateTodoList| {
+        update_todo_list(todo_list_id, version, data)
+    });
 
     let form_state_signal = use_form_signal();
     let mut form_state = form_state_signal();
@@ -31,10 +38,12 @@ pub fn TodoListForm() -> Element {
     let description_field = use_form_field("description", todo_list.description.clone());
     form_state.register_field(&name_field);
     form_state.register_field(&description_field);
+    let mut title_value = name_field.value;
+    let mut description_value = description_field.value;
 
     let onsubmit = use_on_submit(&form_state, move |mut form_state| async move {
         let data: UpdateTodoList = form_state.parsed_values().unwrap();
-        edit_todo_list.call(data).await;
+        edit_todo_list.call(version(), data).await;
         match edit_todo_list.value() {
             Some(Ok(new_todo_list)) => {
                 let new_todo_list = new_todo_list.read();
@@ -42,12 +51,19 @@ pub fn TodoListForm() -> Element {
                     id: new_todo_list.id,
                     title: new_todo_list.title.clone(),
                     description: new_todo_list.description.clone(),
+                    version: new_todo_list.version,
                     invitation: todo_list.invitation,
                 });
+                version.set(new_todo_list.version);
+                conflict.set(None);
                 form_state.mark_clean();
                 show_edit.set(false);
             }
             Some(Err(error)) => {
+                if let Some(current) = edit_conflict_from_captured_error(&error) {
+                    conflict.set(Some(current));
+                    return;
+                }
                 toaster.error(
                     "Failed to update To-Do List",
                     ToastOptions::new().description(rsx! {
@@ -61,11 +77,39 @@ pub fn TodoListForm() -> Element {
         }
     });
 
+    let load_current = move |()| {
+        let Some(current) = conflict.take() else {
+            return;
+        };
+        title_value.set(current.title.clone());
+        description_value.set(current.description.clone());
+        form_state_signal().mark_clean();
+        version.set(current.version);
+        todo_list_context.set_todo_list(TodoListWithPermission {
+            id: current.id,
+            title: current.title,
+            description: current.description,
+            version: current.version,
+            invitation: todo_list.invitation,
+        });
+    };
+
     form_state.revalidate();
 
     rsx! {
         if *show_edit.read() {
             form { onsubmit,
+                if let Some(current) = conflict() {
+                    EditConflictAlert {
+                        name: "To-Do List",
+                        // The overwrite button submits the form again, now based on the current version
+                        onoverwrite: move |()| {
+                            version.set(current.version);
+                            conflict.set(None);
+                        },
+                        onreload: load_current,
+                    }
+                }
                 div { class: "flex justify-between items-center gap-2",
                     Input { label: "Title", field: name_field }
                     div { class: "flex gap-2",
@@ -111,7 +155,10 @@ pub fn TodoListForm() -> Element {
                 if todo_list_context.permission().can_write() {
                     Button {
                         variant: ButtonVariant::Primary,
-                        onclick: move |_| show_edit.set(true),
+                        onclick: move |_| {
+                            version.set(todo_list_context.todo_list().version);
+                            show_edit.set(true);
+                        },
                         Icon { icon: LdPen }
                     }
                 }
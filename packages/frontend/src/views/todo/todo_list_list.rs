@@ -1,9 +1,11 @@
 use crate::Route;
+use crate::components::contexts::use_live_updates;
 use crate::components::ui::button::{Button, ButtonShape, ButtonVariant};
 use crate::components::ui::dialog::{Dialog, DialogAction, DialogContent, DialogTrigger};
 use crate::components::ui::list::{ComplexListDetails, List, ListDetails, ListRow};
 use crate::components::ui::loader::{Loader, LoaderSize};
 use crate::components::ui::toaster::{ToastOptions, use_toaster};
+use api::routes::live_updates::ChangeEvent;
 use api::routes::todo_list::invite::update_my_todo_list_invitation;
 use api::routes::todo_list::{delete_todo_list, list_todo_lists};
 use dioxus::prelude::*;
@@ -17,7 +19,7 @@ use std::default::Default;
 pub fn TodoListListView() -> Element {
     let mut todo_lists = use_loader(list_todo_lists)?;
 
-    let ondelete = move |id: i32| {
+    let mut ondelete = move |id: i32| {
         let mut lists_write = todo_lists.write();
         lists_write.retain(|list| list.id != id);
     };
@@ -36,6 +38,12 @@ pub fn TodoListListView() -> Element {
         }
     };
 
+    use_live_updates(move |change| match change {
+        ChangeEvent::TodoListDeleted { todo_list_id } => ondelete(todo_list_id),
+        ChangeEvent::TodoListChanged { .. } | ChangeEvent::Resync => todo_lists.restart(),
+        _ => {}
+    });
+
     rsx! {
         List { header: "Your Todo Lists",
             if todo_lists.read().is_empty() {
@@ -144,7 +152,7 @@ pub fn TodoListEntry(
                             Icon { icon: LdTrash }
                         }
                         DialogContent { title: "Do you want to delete {title.clone()}?",
-                            "This action cannot be undone! All members will be kicked and all To-Dos are lost!"
+                            "The To-Do List is moved to the trash. It can be restored from there until it is deleted permanently."
                             form { method: "dialog",
                                 DialogAction {
                                     Button { variant: ButtonVariant::Secondary, "Cancel" }
@@ -158,7 +166,7 @@ pub fn TodoListEntry(
                                                         toaster
 
                                                             .success(
-                                                                &format!("Deleted {title_clone} successfully!"),
+                                                                &format!("Moved {title_clone} to the trash!"),
                                                                 ToastOptions::new(),
                                                             );
                                                         ondelete.call(todo_list.id);
@@ -1,8 +1,11 @@
 use super::components::{MemberList, TodoCreateForm, TodoEntry, TodoListForm};
+use crate::components::contexts::use_live_updates;
+use crate::components::ui::activity_feed::{ActivityFeed, ActivitySource};
 use crate::components::ui::button::{ButtonShape, ButtonVariant};
 use crate::components::ui::card::{Card, CardBody};
 use crate::components::ui::dialog::{Dialog, DialogTrigger};
 use crate::components::ui::list::{List, ListDetails, ListRow};
+use api::routes::live_updates::ChangeEvent;
 use api::routes::todo_list::retrieve_todo_list;
 use api::routes::todos::list_todo;
 use dioxus::prelude::*;
@@ -99,6 +102,36 @@ pub fn TodosGroupView(todo_list_id: i32) -> Element {
         todos.restart();
     };
 
+    use_live_updates(move |change| match change {
+        ChangeEvent::TodoSaved(todo) if todo.todo_list_id == todo_list_id => {
+            let mut todos_write = todos.write();
+            if let Some(existing) = todos_write.iter_mut().find(|t| t.id == todo.id) {
+                *existing = todo;
+            } else {
+                todos_write.push(todo);
+            }
+        }
+        ChangeEvent::TodoDeleted {
+            todo_list_id: changed,
+            todo_id,
+        } if changed == todo_list_id => {
+            todos.write().retain(|todo| todo.id != todo_id);
+        }
+        ChangeEvent::TodoListChanged {
+            todo_list_id: changed,
+        }
+        | ChangeEvent::TodoListDeleted {
+            todo_list_id: changed,
+        } if changed == todo_list_id => {
+            todo_list.restart();
+        }
+        ChangeEvent::Resync => {
+            todo_list.restart();
+            todos.restart();
+        }
+        _ => {}
+    });
+
     rsx! {
         div { class: "flex gap-2 flex-col lg:flex-row mb-16 lg:mb-0",
             Card { class: "grow w-full",
@@ -139,7 +172,13 @@ pub fn TodosGroupView(todo_list_id: i32) -> Element {
                     }
                 }
             }
-            MemberList {}
+            div { class: "flex flex-col gap-2 shrink-0 w-full lg:w-1/2 xl:w-1/3",
+                MemberList {}
+                ActivityFeed {
+                    source: ActivitySource::TodoList(todo_list_id),
+                    class: "w-full",
+                }
+            }
         }
 
         if user_permission.can_write() {
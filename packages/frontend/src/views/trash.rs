@@ -0,0 +1,111 @@
+use crate::components::contexts::use_live_updates;
+use crate::components::ui::button::{Button, ButtonVariant};
+use crate::components::ui::list::{List, ListDetails, ListRow};
+use crate::components::ui::toaster::{ToastOptions, use_toaster};
+use api::routes::live_updates::ChangeEvent;
+use api::routes::trash::{TrashItem, TrashKind, list_trash, restore_group, restore_todo_list};
+use dioxus::prelude::*;
+use roommates::message_from_captured_error;
+use time::macros::format_description;
+
+#[component]
+pub fn TrashView() -> Element {
+    let mut items = use_loader(list_trash)?;
+
+    let onrestore = move |(kind, id): (TrashKind, i32)| {
+        items
+            .write()
+            .retain(|item| item.kind != kind || item.id != id);
+    };
+
+    use_live_updates(move |change| {
+        if matches!(
+            change,
+            ChangeEvent::TodoListDeleted { .. }
+                | ChangeEvent::TodoListChanged { .. }
+                | ChangeEvent::GroupDeleted { .. }
+                | ChangeEvent::GroupChanged { .. }
+                | ChangeEvent::Resync
+        ) {
+            items.restart();
+        }
+    });
+
+    rsx! {
+        List { header: "Trash",
+            if items.read().is_empty() {
+                ListRow {
+                    ListDetails { title: "The trash is empty" }
+                }
+            }
+            for item in items.iter() {
+                TrashEntry {
+                    key: "{item.kind:?}-{item.id}",
+                    item: item.clone(),
+                    onrestore,
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn TrashEntry(item: TrashItem, onrestore: EventHandler<(TrashKind, i32)>) -> Element {
+    let mut toaster = use_toaster();
+    let mut restore_todo_list = use_action(restore_todo_list);
+    let mut restore_group = use_action(restore_group);
+
+    let kind = match item.kind {
+        TrashKind::TodoList => "To-Do List",
+        TrashKind::Group => "Group",
+    };
+    let date_format = format_description!("[day].[month].[year]");
+    let deleted_at = item.deleted_at.format(date_format).unwrap();
+    let purge_at = item.purge_at.format(date_format).unwrap();
+
+    rsx! {
+        ListRow {
+            ListDetails { title: "{item.name}",
+                "{kind}, deleted on {deleted_at}. Will be deleted permanently on {purge_at}."
+            }
+            Button {
+                variant: ButtonVariant::Secondary,
+                class: "btn-sm",
+                onclick: move |_| {
+                    let name = item.name.clone();
+                    async move {
+                        let result = match item.kind {
+                            TrashKind::TodoList => {
+                                restore_todo_list.call(item.id).await;
+                                restore_todo_list.value()
+                            }
+                            TrashKind::Group => {
+                                restore_group.call(item.id).await;
+                                restore_group.value()
+                            }
+                        };
+                        match result {
+                            Some(Ok(_)) => {
+                                toaster.success(&format!("Restored {name}!"), ToastOptions::new());
+                                onrestore.call((item.kind, item.id));
+                            }
+                            Some(Err(error)) => {
+                                toaster
+                                    .error(
+                                        &format!("Failed to restore {name}!"),
+                                        ToastOptions::new().description(rsx! {
+                                            span { "{message_from_captured_error(&error)}" }
+                                        }),
+                                    );
+                            }
+                            None => {
+                                warn!("Request did not finish!");
+                            }
+                        }
+                    }
+                },
+                "Restore"
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::components::contexts::use_auth;
+use crate::components::ui::card::{Card, CardActions, CardBody, CardTitle};
+use crate::{ICON, Route};
+use api::routes::users::email_verification::verify_email;
+use dioxus::prelude::*;
+use dioxus_free_icons::Icon;
+use dioxus_free_icons::icons::ld_icons::{LdCircleCheck, LdCircleX};
+use roommates::message_from_captured_error;
+
+/// Confirms the email address from a verification link.
+/// Verification only happens on click, so link scanners of mail providers cannot consume the token.
+#[component]
+pub fn VerifyEmailView(token: String) -> Element {
+    let mut auth_state = use_auth();
+    let mut verify_action = use_action(verify_email);
+    let mut error_message = use_signal(|| None::<String>);
+    let mut verified_email = use_signal(|| None::<String>);
+
+    let on_verify = move |_| {
+        let token = token.clone();
+        async move {
+            error_message.set(None);
+            verify_action.call(token).await;
+            match verify_action.value() {
+                Some(Ok(user)) => {
+                    let user = user.peek().clone();
+                    verified_email.set(Some(user.email.clone()));
+                    // Keep the profile in sync if the link was opened while logged in
+                    if auth_state
+                        .user
+                        .peek()
+                        .as_ref()
+                        .is_some_and(|u| u.id == user.id)
+                    {
+                        auth_state.user.set(Some(user));
+                    }
+                }
+                Some(Err(error)) => {
+                    error_message.set(Some(message_from_captured_error(&error)));
+                }
+                None => {
+                    warn!("Verifying email did not finish!");
+                }
+            }
+        }
+    };
+
+    rsx! {
+        div { class: "flex flex-col justify-center items-center w-full h-[90vh]",
+            div { class: "w-full lg:w-1/2",
+                Card {
+                    CardBody { class: "items-center text-center",
+                        img { src: ICON, class: "aspect-square w-20" }
+                        CardTitle { class: "lg:mb-8", "Verify Email" }
+
+                        if let Some(email) = verified_email() {
+                            div {
+                                class: "alert alert-success mb-4 text-left w-full",
+                                role: "alert",
+                                Icon { icon: LdCircleCheck }
+                                span { "{email} has been verified." }
+                            }
+                            Link { to: Route::LoginPage {}, class: "btn btn-primary w-full", "Continue" }
+                        } else {
+                            if let Some(error) = error_message() {
+                                div {
+                                    class: "alert alert-error mb-4 text-left w-full",
+                                    role: "alert",
+                                    Icon { icon: LdCircleX }
+                                    span { "{error}" }
+                                }
+                            }
+                            p { class: "mb-4", "Click the button below to confirm your email address." }
+                            CardActions { class: "w-full",
+                                button {
+                                    class: "btn btn-primary w-full",
+                                    disabled: verify_action.pending(),
+                                    onclick: on_verify,
+                                    if verify_action.pending() {
+                                        "Verifying..."
+                                    } else {
+                                        "Verify Email"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
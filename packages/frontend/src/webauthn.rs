@@ -0,0 +1,66 @@
+//! Bridges the WebAuthn browser API, which is only reachable through JavaScript.
+//! Options and credentials are exchanged in the JSON format understood by the server.
+
+use dioxus::prelude::*;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum CeremonyResult {
+    Ok(serde_json::Value),
+    Error(String),
+}
+
+async fn run_ceremony(
+    script: &str,
+    options: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let mut eval = document::eval(script);
+    eval.send(options).map_err(|e| e.to_string())?;
+    match eval
+        .recv::<CeremonyResult>()
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        CeremonyResult::Ok(credential) => Ok(credential),
+        CeremonyResult::Error(message) => Err(message),
+    }
+}
+
+/// Asks the authenticator to create a new passkey using the creation options from the server
+pub async fn create_passkey(options: serde_json::Value) -> Result<serde_json::Value, String> {
+    run_ceremony(
+        r#"
+            const options = await dioxus.recv();
+            try {
+                const credential = await navigator.credentials.create({
+                    publicKey: PublicKeyCredential.parseCreationOptionsFromJSON(options.publicKey),
+                });
+                dioxus.send({ ok: credential.toJSON() });
+            } catch (error) {
+                dioxus.send({ error: error.message ?? "Passkey creation failed" });
+            }
+        "#,
+        options,
+    )
+    .await
+}
+
+/// Asks the authenticator to sign the login challenge from the server with one of its passkeys
+pub async fn get_passkey(options: serde_json::Value) -> Result<serde_json::Value, String> {
+    run_ceremony(
+        r#"
+            const options = await dioxus.recv();
+            try {
+                const credential = await navigator.credentials.get({
+                    publicKey: PublicKeyCredential.parseRequestOptionsFromJSON(options.publicKey),
+                });
+                dioxus.send({ ok: credential.toJSON() });
+            } catch (error) {
+                dioxus.send({ error: error.message ?? "Passkey login failed" });
+            }
+        "#,
+        options,
+    )
+    .await
+}